@@ -1,3 +1,9 @@
+//! Compares the throughput of this crate with `sha1sum` and `ring`.
+//!
+//! Usage: `bench [--json] [file]`, reading from stdin if no file is given.
+//! With `--json` a single JSON object with the input size and the time and
+//! throughput of every implementation is printed instead of the report.
+
 extern crate ring;
 extern crate sha1_smol as sha1;
 
@@ -7,69 +13,114 @@ use std::io::{Read, Write};
 use std::process::{Command, Stdio};
 use std::time::{Duration, Instant};
 
-fn time<F, FMT>(desc: &str, f: F, fmt: FMT)
-where
-    F: Fn(),
-    FMT: Fn(Duration) -> String,
-{
-    let start = Instant::now();
-    f();
-    let duration = Instant::now() - start;
-    println!("{}: {}", desc, fmt(duration));
+struct Bench {
+    json: bool,
+    bytes: usize,
+    results: Vec<(&'static str, Duration)>,
+}
+
+impl Bench {
+    fn time<F>(&mut self, desc: &'static str, f: F)
+    where
+        F: Fn(),
+    {
+        let start = Instant::now();
+        f();
+        let duration = Instant::now() - start;
+        if !self.json {
+            println!("{}: {:.2} MB/s", desc, self.throughput(duration));
+        }
+        self.results.push((desc, duration));
+    }
+
+    fn throughput(&self, duration: Duration) -> f64 {
+        let s = duration.as_secs() as f64;
+        let ns = duration.subsec_nanos() as f64 / 1000000000.0;
+        self.bytes as f64 / (s + ns) / 1000000.0
+    }
+
+    /// Prints all results as a single JSON object.
+    fn print_json(&self) {
+        let results: Vec<String> = self
+            .results
+            .iter()
+            .map(|&(desc, duration)| {
+                format!(
+                    "{{\"implementation\":\"{}\",\"seconds\":{:.9},\"mb_per_sec\":{:.2}}}",
+                    desc,
+                    duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1000000000.0,
+                    self.throughput(duration)
+                )
+            })
+            .collect();
+        println!(
+            "{{\"input_bytes\":{},\"results\":[{}]}}",
+            self.bytes,
+            results.join(",")
+        );
+    }
 }
 
 fn main() {
-    let args: Vec<_> = env::args().collect();
+    let mut args: Vec<_> = env::args().skip(1).collect();
+    let json = if let Some(pos) = args.iter().position(|arg| arg == "--json") {
+        args.remove(pos);
+        true
+    } else {
+        false
+    };
     let mut out = Vec::<u8>::new();
 
-    if args.len() == 1 {
+    if args.is_empty() {
         std::io::stdin().read_to_end(&mut out).unwrap();
-    } else if args.len() == 2 {
-        let mut f = fs::File::open(&args[1]).unwrap();
+    } else if args.len() == 1 {
+        let mut f = fs::File::open(&args[0]).unwrap();
         f.read_to_end(&mut out).unwrap();
     } else {
-        panic!("wrong argument count");
+        panic!("usage: bench [--json] [file]");
     }
 
-    let throughput = |duration: Duration| {
-        let s = duration.as_secs() as f64;
-        let ns = duration.subsec_nanos() as f64 / 1000000000.0;
-        format!("{:.2} MB/s", out.len() as f64 / (s + ns) / 1000000.0)
+    let mut bench = Bench {
+        json,
+        bytes: out.len(),
+        results: Vec::new(),
     };
 
     if env::var("WITHOUT_SHA1SUM") != Ok("1".into()) {
-        time(
-            "sha1sum program",
-            || {
-                let mut child = Command::new("sha1sum")
-                    .stdin(Stdio::piped())
-                    .spawn()
-                    .unwrap();
-                if let Some(ref mut stdin) = child.stdin {
-                    stdin.write(&out).unwrap();
-                }
-                child.wait().unwrap();
-            },
-            &throughput,
-        );
+        bench.time("sha1sum program", || {
+            let mut child = Command::new("sha1sum")
+                .stdin(Stdio::piped())
+                .stdout(if json {
+                    Stdio::null()
+                } else {
+                    Stdio::inherit()
+                })
+                .spawn()
+                .unwrap();
+            if let Some(ref mut stdin) = child.stdin {
+                stdin.write_all(&out).unwrap();
+            }
+            child.wait().unwrap();
+        });
     }
 
-    time(
-        "sha1 crate",
-        || {
-            let mut sha1 = sha1::Sha1::new();
-            sha1.update(&out);
-            println!("{}", sha1.digest());
-        },
-        &throughput,
-    );
+    bench.time("sha1 crate", || {
+        let mut sha1 = sha1::Sha1::new();
+        sha1.update(&out);
+        let digest = sha1.digest();
+        if !json {
+            println!("{}", digest);
+        }
+    });
 
-    time(
-        "ring crate",
-        || {
-            let digest = ring::digest::digest(&ring::digest::SHA1, &out);
+    bench.time("ring crate", || {
+        let digest = ring::digest::digest(&ring::digest::SHA1, &out);
+        if !json {
             println!("{:?}", digest);
-        },
-        &throughput,
-    );
+        }
+    });
+
+    if json {
+        bench.print_json();
+    }
 }
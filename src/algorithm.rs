@@ -0,0 +1,79 @@
+//! A self-describing descriptor of the hash algorithm.
+//!
+//! Applications that support several hash functions can use this to look
+//! up the properties of SHA-1 by name or object identifier instead of
+//! hard-coding them:
+//!
+//! ```
+//! use sha1_smol::algorithm::Algorithm;
+//!
+//! let alg = Algorithm::lookup("sha1").unwrap();
+//! assert_eq!(alg.name, "SHA-1");
+//! assert_eq!(alg.output_len, 20);
+//! assert_eq!(Algorithm::lookup("1.3.14.3.2.26"), Some(alg));
+//! ```
+
+use crate::der::SHA1_OID;
+use crate::DIGEST_LENGTH;
+
+/// Describes a hash algorithm.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Algorithm {
+    /// The canonical name of the algorithm.
+    pub name: &'static str,
+    /// Alternative names the algorithm is known by.
+    pub aliases: &'static [&'static str],
+    /// The length of the digest in bytes.
+    pub output_len: usize,
+    /// The length of the input block in bytes.
+    pub block_len: usize,
+    /// The object identifier in dotted notation.
+    pub oid: &'static str,
+    /// The DER encoded object identifier (without tag and length).
+    pub oid_der: &'static [u8],
+}
+
+/// The descriptor of SHA-1.
+pub const SHA1: Algorithm = Algorithm {
+    name: "SHA-1",
+    aliases: &["SHA1", "SHA"],
+    output_len: DIGEST_LENGTH,
+    block_len: 64,
+    oid: "1.3.14.3.2.26",
+    oid_der: &SHA1_OID,
+};
+
+/// All algorithms implemented by this crate.
+pub static ALGORITHMS: &[Algorithm] = &[SHA1];
+
+impl Algorithm {
+    /// Looks up an algorithm by name, alias or dotted object identifier.
+    ///
+    /// Names are compared ASCII case-insensitively.
+    pub fn lookup(name: &str) -> Option<&'static Algorithm> {
+        ALGORITHMS.iter().find(|alg| {
+            alg.oid == name
+                || alg.name.eq_ignore_ascii_case(name)
+                || alg.aliases.iter().any(|a| a.eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// Looks up an algorithm by its DER encoded object identifier.
+    pub fn lookup_oid_der(oid_der: &[u8]) -> Option<&'static Algorithm> {
+        ALGORITHMS.iter().find(|alg| alg.oid_der == oid_der)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        for name in ["SHA-1", "sha-1", "sha1", "SHA", "1.3.14.3.2.26"].iter() {
+            assert_eq!(Algorithm::lookup(name), Some(&SHA1));
+        }
+        assert_eq!(Algorithm::lookup("sha256"), None);
+        assert_eq!(Algorithm::lookup_oid_der(&SHA1_OID), Some(&SHA1));
+    }
+}
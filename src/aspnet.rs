@@ -0,0 +1,102 @@
+//! The SHA1 password format of the classic ASP.NET membership provider.
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+//!
+//! With `passwordFormat="Hashed"` and `hashAlgorithmType="SHA1"` the
+//! `SqlMembershipProvider` stores `Base64(SHA1(salt + UTF-16LE(password)))`
+//! in the `Password` column and the base64 encoded salt, usually 16 random
+//! bytes, in the `PasswordSalt` column.
+//!
+//! ```
+//! use sha1_smol::aspnet;
+//!
+//! let salt = "vT6ZcmKCSdY+6XxdD6Q2Dg==";
+//! let hash = aspnet::hash_password("P@ssw0rd", salt).unwrap();
+//! assert_eq!(hash, "7P0Il4/tYF3t7VokbMVjHMG3e0Q=");
+//! assert!(aspnet::verify_password("P@ssw0rd", salt, &hash));
+//! ```
+
+use alloc::string::String;
+
+use crate::encoding::{decode_b64, decode_b64_into, encode_b64, Alphabet};
+use crate::hmac::ct_eq;
+use crate::{Sha1, DIGEST_LENGTH};
+
+fn digest(password: &str, salt: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut sha1 = Sha1::from(salt);
+    for unit in password.encode_utf16() {
+        sha1.update(&unit.to_le_bytes());
+    }
+    sha1.digest().bytes()
+}
+
+/// Returns the base64 encoded hash of `password` with the raw `salt`.
+pub fn hash_password_raw(password: &str, salt: &[u8]) -> String {
+    encode_b64(&digest(password, salt), Alphabet::Standard, true)
+}
+
+/// Returns the base64 encoded hash of `password` with a base64 encoded
+/// salt as stored in the `PasswordSalt` column.
+///
+/// Returns `None` if the salt is not valid base64.
+pub fn hash_password(password: &str, salt: &str) -> Option<String> {
+    let salt = decode_b64(salt.as_bytes(), Alphabet::Standard)?;
+    Some(hash_password_raw(password, &salt))
+}
+
+/// Verifies `password` against the stored base64 encoded salt and hash.
+///
+/// The digests are compared in constant time.  Malformed salts or hashes
+/// never verify.
+pub fn verify_password(password: &str, salt: &str, hash: &str) -> bool {
+    let salt = match decode_b64(salt.as_bytes(), Alphabet::Standard) {
+        Some(salt) => salt,
+        None => return false,
+    };
+    let mut expected = [0u8; DIGEST_LENGTH];
+    match decode_b64_into(hash.as_bytes(), Alphabet::Standard, &mut expected) {
+        Some(DIGEST_LENGTH) => ct_eq(&digest(password, &salt), &expected),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // computed with python hashlib over `salt + password.encode("utf-16le")`
+    const SALT: &str = "vT6ZcmKCSdY+6XxdD6Q2Dg==";
+
+    #[test]
+    fn test_hash_password() {
+        assert_eq!(
+            hash_password("P@ssw0rd", SALT).as_deref(),
+            Some("7P0Il4/tYF3t7VokbMVjHMG3e0Q=")
+        );
+        let raw = decode_b64(SALT.as_bytes(), Alphabet::Standard).unwrap();
+        assert_eq!(
+            hash_password_raw("P@ssw0rd", &raw),
+            "7P0Il4/tYF3t7VokbMVjHMG3e0Q="
+        );
+        assert_eq!(hash_password("P@ssw0rd", "not base64!"), None);
+    }
+
+    #[test]
+    fn test_verify_password() {
+        let hash = "7P0Il4/tYF3t7VokbMVjHMG3e0Q=";
+        assert!(verify_password("P@ssw0rd", SALT, hash));
+        assert!(!verify_password("p@ssw0rd", SALT, hash));
+        assert!(!verify_password(
+            "P@ssw0rd",
+            "AAAAAAAAAAAAAAAAAAAAAA==",
+            hash
+        ));
+        assert!(!verify_password("P@ssw0rd", "not base64!", hash));
+        assert!(!verify_password(
+            "P@ssw0rd",
+            SALT,
+            "7P0Il4/tYF3t7VokbMVjHMG3"
+        ));
+        assert!(!verify_password("P@ssw0rd", SALT, ""));
+    }
+}
@@ -0,0 +1,320 @@
+//! Information about the SHA-1 compression function in use.
+//!
+//! This crate currently ships a single, portable implementation of the
+//! compression function.  It performs the same sequence of operations for
+//! every input and has no data-dependent shortcuts, so digests (and the work
+//! done to compute them) are fully reproducible across platforms and builds.
+//!
+//! Code that must stay on that path even if accelerated implementations are
+//! added in the future, such as reproducible-build tooling or differential
+//! tests comparing hardware engines against a reference, should use
+//! [`Deterministic`].
+//!
+//! ```
+//! use sha1_smol::backend::{self, Deterministic};
+//!
+//! let reference = Deterministic::from("Hello World!").digest();
+//! assert_eq!(reference, sha1_smol::Sha1::from("Hello World!").digest());
+//! assert_eq!(backend::active(), "portable");
+//! ```
+//!
+//! [`capabilities`], which is also exported at the crate root, reports the
+//! backend together with the crate features the build was compiled with.
+//!
+//! Alternative implementations, such as hardware hashing engines on
+//! embedded targets, can implement [`Sha1Engine`] and validate themselves
+//! against this crate's test vectors with
+//! [`check_impl`](crate::test_support::check_impl).
+//!
+//! Such engines can fail while producing the digest.  They report this
+//! through [`Sha1Engine::try_finalize`], which code generic over engines
+//! should call through [`finalize`] so failures are logged when the
+//! `tracing` feature is enabled.  The software implementation never fails
+//! and keeps its infallible API.
+
+use core::fmt;
+
+use crate::{Digest, Sha1, Sha1State};
+
+/// Indicates that an engine failed to produce a digest.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+#[non_exhaustive]
+pub enum EngineError {
+    /// The engine is in use by someone else.
+    Busy,
+    /// The engine did not complete the operation in time.
+    Timeout,
+    /// The engine reported a hardware or driver fault.
+    Fault,
+}
+
+impl EngineError {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            EngineError::Busy => "sha1 engine busy",
+            EngineError::Timeout => "sha1 engine timed out",
+            EngineError::Fault => "sha1 engine fault",
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EngineError {
+    fn description(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// The operations of an incremental SHA-1 implementation.
+pub trait Sha1Engine {
+    /// Feeds `data` into the hash.
+    fn update(&mut self, data: &[u8]);
+
+    /// Returns the digest of everything fed in so far.
+    ///
+    /// This must not change the state, more data can be fed in afterwards.
+    fn digest(&self) -> Digest;
+
+    /// Resets the engine to the initial state.
+    fn reset(&mut self);
+
+    /// Returns the digest of everything fed in so far or the reason the
+    /// engine could not compute it.
+    ///
+    /// The default implementation forwards to [`digest`](Self::digest) and
+    /// never fails.  After an error the engine should be reset before it is
+    /// used again.
+    fn try_finalize(&mut self) -> Result<Digest, EngineError> {
+        Ok(self.digest())
+    }
+}
+
+impl Sha1Engine for Sha1 {
+    fn update(&mut self, data: &[u8]) {
+        Sha1::update(self, data);
+    }
+
+    fn digest(&self) -> Digest {
+        Sha1::digest(self)
+    }
+
+    fn reset(&mut self) {
+        Sha1::reset(self);
+    }
+}
+
+/// Finalizes `engine`, tracing failures if the `tracing` feature is enabled.
+pub fn finalize<E: Sha1Engine + ?Sized>(engine: &mut E) -> Result<Digest, EngineError> {
+    let rv = engine.try_finalize();
+    #[cfg(feature = "tracing")]
+    {
+        if let Err(ref err) = rv {
+            tracing::warn!(error = %err, "sha1 engine failed to finalize");
+        }
+    }
+    rv
+}
+
+/// A hasher that is guaranteed to use the portable compression function.
+///
+/// The digests are the same as those of [`crate::Sha1`], but unlike
+/// `Sha1` this never dispatches to another backend, regardless of the
+/// target or crate features enabled.
+#[derive(Clone, Default)]
+pub struct Deterministic(Sha1);
+
+impl Deterministic {
+    /// Creates a fresh hasher.
+    pub fn new() -> Deterministic {
+        Deterministic(Sha1::new())
+    }
+
+    /// Shortcut to create a hasher from some bytes.
+    pub fn from<D: AsRef<[u8]>>(data: D) -> Deterministic {
+        let mut rv = Deterministic::new();
+        rv.update(data.as_ref());
+        rv
+    }
+
+    /// Update hash with input data.
+    pub fn update(&mut self, data: &[u8]) {
+        self.0
+            .update_using(data, |_, _| {}, Sha1State::process_portable)
+    }
+
+    /// Retrieve digest result.
+    pub fn digest(&self) -> Digest {
+        self.0.digest_using(Sha1State::process_portable)
+    }
+
+    /// Resets the hasher to its initial state.
+    pub fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+impl Sha1Engine for Deterministic {
+    fn update(&mut self, data: &[u8]) {
+        Deterministic::update(self, data);
+    }
+
+    fn digest(&self) -> Digest {
+        Deterministic::digest(self)
+    }
+
+    fn reset(&mut self) {
+        Deterministic::reset(self);
+    }
+}
+
+/// Returns the name of the compression backend used by [`crate::Sha1`].
+pub fn active() -> &'static str {
+    "portable"
+}
+
+/// The features this build of the crate was compiled with.
+///
+/// Applications can log this or refuse to start if the build does not
+/// match their expectations.  New fields may be added when new features
+/// are added, so this can't be constructed outside of the crate.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// The name of the compression backend, see [`active`].
+    pub backend: &'static str,
+    /// Whether the `std` feature is enabled.
+    pub std: bool,
+    /// Whether the `alloc` feature is enabled.
+    pub alloc: bool,
+    /// Whether HMAC-SHA1 is available.  This is always the case.
+    pub hmac: bool,
+    /// Whether parallel derivation through the `rayon` feature is enabled.
+    pub parallel: bool,
+    /// Whether the `serde` feature is enabled.
+    pub serde: bool,
+    /// Whether the `arbitrary` feature is enabled.
+    pub arbitrary: bool,
+    /// Whether the SHACAL-1 block cipher (`shacal1` feature) is available.
+    pub shacal1: bool,
+    /// Whether SHA-1DC (`collision-detection` feature) is available.
+    pub collision_detection: bool,
+    /// Whether the `tracing` feature is enabled.
+    pub tracing: bool,
+    /// Whether the `unicode-normalization` feature is enabled.
+    pub unicode_normalization: bool,
+}
+
+/// Returns the features this build of the crate was compiled with.
+///
+/// ```
+/// let caps = sha1_smol::capabilities();
+/// assert_eq!(caps.backend, "portable");
+/// assert!(caps.hmac);
+/// assert_eq!(caps.std, cfg!(feature = "std"));
+/// ```
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        backend: active(),
+        std: cfg!(feature = "std"),
+        alloc: cfg!(feature = "alloc"),
+        hmac: true,
+        parallel: cfg!(feature = "rayon"),
+        serde: cfg!(feature = "serde"),
+        arbitrary: cfg!(feature = "arbitrary"),
+        shacal1: cfg!(feature = "shacal1"),
+        collision_detection: cfg!(feature = "collision-detection"),
+        tracing: cfg!(feature = "tracing"),
+        unicode_normalization: cfg!(feature = "unicode-normalization"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Flaky {
+        inner: Sha1,
+        fail: bool,
+    }
+
+    impl Sha1Engine for Flaky {
+        fn update(&mut self, data: &[u8]) {
+            self.inner.update(data);
+        }
+
+        fn digest(&self) -> Digest {
+            self.inner.digest()
+        }
+
+        fn reset(&mut self) {
+            self.inner.reset();
+            self.fail = false;
+        }
+
+        fn try_finalize(&mut self) -> Result<Digest, EngineError> {
+            if self.fail {
+                Err(EngineError::Timeout)
+            } else {
+                Ok(self.inner.digest())
+            }
+        }
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let caps = capabilities();
+        assert_eq!(caps.backend, active());
+        assert_eq!(caps.alloc, cfg!(feature = "alloc"));
+        assert!(!caps.std || caps.alloc);
+        assert_eq!(caps.parallel, cfg!(feature = "rayon"));
+        assert_eq!(caps.serde, cfg!(feature = "serde"));
+        assert_eq!(caps.arbitrary, cfg!(feature = "arbitrary"));
+        assert_eq!(caps.shacal1, cfg!(feature = "shacal1"));
+        assert_eq!(
+            caps.collision_detection,
+            cfg!(feature = "collision-detection")
+        );
+        assert_eq!(caps.tracing, cfg!(feature = "tracing"));
+        assert_eq!(
+            caps.unicode_normalization,
+            cfg!(feature = "unicode-normalization")
+        );
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let data = [0x42u8; 300];
+        for len in 0..data.len() {
+            let expected = Sha1::from(&data[..len]).digest();
+            assert_eq!(Deterministic::from(&data[..len]).digest(), expected);
+        }
+        let mut m = Deterministic::default();
+        for chunk in data.chunks(7) {
+            m.update(chunk);
+        }
+        assert_eq!(m.digest(), Sha1::from(&data[..]).digest());
+        m.reset();
+        assert_eq!(m.digest(), Sha1::new().digest());
+    }
+
+    #[test]
+    fn test_finalize() {
+        let mut sha1 = Sha1::from("abc");
+        assert_eq!(finalize(&mut sha1), Ok(sha1.digest()));
+
+        let mut engine = Flaky {
+            inner: Sha1::from("abc"),
+            fail: true,
+        };
+        assert_eq!(finalize(&mut engine), Err(EngineError::Timeout));
+        engine.reset();
+        engine.update(b"abc");
+        assert_eq!(finalize(&mut engine), Ok(sha1.digest()));
+    }
+}
@@ -0,0 +1,108 @@
+//! Bloom filter bucket indices derived from a single SHA-1.
+//!
+//! Kirsch and Mitzenmacher showed that the `k` hash functions of a Bloom
+//! filter can be simulated with two: the `i`-th index is
+//! `(h1 + i * h2) mod m` for a filter of `m` bits.  Here `h1` and `h2` are
+//! the first and second big endian 32 bit words of the digest, so one
+//! SHA-1 per item is enough regardless of `k`.
+//!
+//! ```
+//! use sha1_smol::{bloom, Sha1};
+//!
+//! let digest = Sha1::from("item").digest();
+//! let mut filter = [false; 1024];
+//! for idx in bloom::indices(&digest, 7, filter.len()) {
+//!     filter[idx] = true;
+//! }
+//! assert!(bloom::indices(&digest, 7, filter.len()).all(|idx| filter[idx]));
+//! ```
+
+use crate::Digest;
+
+/// An iterator over the bucket indices of a digest.
+///
+/// Created by [`indices`].
+#[derive(Clone, Debug)]
+pub struct Indices {
+    h1: u64,
+    h2: u64,
+    m: u64,
+    i: u64,
+    k: u64,
+}
+
+impl Iterator for Indices {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.i == self.k {
+            return None;
+        }
+        let rv = (self.h1 + self.i * self.h2) % self.m;
+        self.i += 1;
+        Some(rv as usize)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.k - self.i) as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Indices {}
+
+/// Returns the `k` bucket indices of `digest` in a filter of `m` buckets.
+///
+/// The indices are not necessarily distinct.  This panics if `m` is zero
+/// or does not fit into 32 bits.
+pub fn indices(digest: &Digest, k: u32, m: usize) -> Indices {
+    assert!(
+        m > 0 && m as u64 <= u32::MAX as u64 + 1,
+        "invalid bloom filter size"
+    );
+    let bytes = digest.bytes();
+    Indices {
+        h1: u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64,
+        h2: u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as u64,
+        m: m as u64,
+        i: 0,
+        k: k as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha1;
+
+    #[test]
+    fn test_indices() {
+        // the sha1 of "Hello World!" starts with 2ef7bde6 08ce5404
+        let digest = Sha1::from("Hello World!").digest();
+        let (h1, h2) = (0x2ef7bde6u64, 0x08ce5404u64);
+        let mut iter = indices(&digest, 4, 1000);
+        assert_eq!(iter.len(), 4);
+        for i in 0..4 {
+            assert_eq!(iter.next(), Some(((h1 + i * h2) % 1000) as usize));
+        }
+        assert_eq!(iter.next(), None);
+        assert_eq!(indices(&digest, 0, 1000).count(), 0);
+        assert!(indices(&digest, 100, 1).all(|idx| idx == 0));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_no_overflow() {
+        let digest: Digest = "ffffffffffffffff000000000000000000000000".parse().unwrap();
+        let mut iter = indices(&digest, 3, u32::MAX as usize + 1);
+        assert_eq!(iter.next(), Some(0xffffffff));
+        assert_eq!(iter.next(), Some(0xfffffffe));
+        assert_eq!(iter.next(), Some(0xfffffffd));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid bloom filter size")]
+    fn test_empty_filter() {
+        indices(&Sha1::new().digest(), 1, 0);
+    }
+}
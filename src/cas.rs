@@ -0,0 +1,132 @@
+//! Content addressable storage keys.
+//!
+//! Build caches and artifact stores commonly keep objects under their
+//! SHA-1, sharded into 256 directories by the first byte the same way the
+//! loose objects of git are stored:
+//!
+//! ```
+//! use sha1_smol::cas;
+//!
+//! let digest = sha1_smol::Sha1::from("Hello World!").digest();
+//! let mut buf = [0u8; cas::SHARDED_PATH_LENGTH];
+//! let path = cas::sharded_path(&digest, &mut buf);
+//! assert_eq!(path, "2e/f7bde608ce5404e97d5f042f95f89f1c232871");
+//! assert_eq!(cas::parse_sharded_path(path), Some(digest));
+//! ```
+
+use crate::encoding::{decode_hex_into, encode_hex_into};
+use crate::{Digest, DIGEST_LENGTH};
+
+/// The length of a sharded path.
+pub const SHARDED_PATH_LENGTH: usize = DIGEST_LENGTH * 2 + 1;
+
+/// Writes the sharded relative path (`ab/cdef...`) of `digest` into a
+/// buffer and returns it as string slice.
+pub fn sharded_path<'a>(digest: &Digest, out: &'a mut [u8; SHARDED_PATH_LENGTH]) -> &'a str {
+    let mut hex = [0u8; DIGEST_LENGTH * 2];
+    encode_hex_into(&digest.bytes(), &mut hex);
+    out[..2].copy_from_slice(&hex[..2]);
+    out[2] = b'/';
+    out[3..].copy_from_slice(&hex[2..]);
+    core::str::from_utf8(out).unwrap()
+}
+
+/// Parses a sharded path back into a digest.
+///
+/// Only the last two components of `path` are considered so paths
+/// including the store root are accepted as well.  Both `/` and `\` are
+/// separators and hex of either case is accepted.  Returns `None` if the
+/// path does not end in a sharded digest.
+pub fn parse_sharded_path(path: &str) -> Option<Digest> {
+    let path = path.as_bytes();
+    if path.len() < SHARDED_PATH_LENGTH {
+        return None;
+    }
+    let tail = &path[path.len() - SHARDED_PATH_LENGTH..];
+    if tail[2] != b'/' && tail[2] != b'\\' {
+        return None;
+    }
+    if path.len() > SHARDED_PATH_LENGTH {
+        let c = path[path.len() - SHARDED_PATH_LENGTH - 1];
+        if c != b'/' && c != b'\\' {
+            return None;
+        }
+    }
+    let mut hex = [0u8; DIGEST_LENGTH * 2];
+    hex[..2].copy_from_slice(&tail[..2]);
+    hex[2..].copy_from_slice(&tail[3..]);
+    let mut bytes = [0u8; DIGEST_LENGTH];
+    decode_hex_into(&hex, &mut bytes)?;
+    Some(Digest::from_bytes(&bytes))
+}
+
+/// Hashes everything that can be read from `reader` and returns the key
+/// to store it under.
+///
+/// (This function is only available if the `std` feature is enabled)
+#[cfg(feature = "std")]
+pub fn store_key<R: std::io::Read>(reader: R) -> std::io::Result<Digest> {
+    crate::io::hash_reader(reader)
+}
+
+/// Returns the path of the object with `digest` in the store at `root`.
+///
+/// (This function is only available if the `std` feature is enabled)
+#[cfg(feature = "std")]
+pub fn store_path<P: AsRef<std::path::Path>>(root: P, digest: &Digest) -> std::path::PathBuf {
+    let mut hex = [0u8; DIGEST_LENGTH * 2];
+    let hex = encode_hex_into(&digest.bytes(), &mut hex);
+    root.as_ref().join(&hex[..2]).join(&hex[2..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha1;
+
+    #[test]
+    fn test_sharded_path() {
+        let digest = Sha1::new().digest();
+        let mut buf = [0u8; SHARDED_PATH_LENGTH];
+        assert_eq!(
+            sharded_path(&digest, &mut buf),
+            "da/39a3ee5e6b4b0d3255bfef95601890afd80709"
+        );
+    }
+
+    #[test]
+    fn test_parse_sharded_path() {
+        let digest = Some(Sha1::new().digest());
+        for path in &[
+            "da/39a3ee5e6b4b0d3255bfef95601890afd80709",
+            "DA\\39A3EE5E6B4B0D3255BFEF95601890AFD80709",
+            "/var/cache/objects/da/39a3ee5e6b4b0d3255bfef95601890afd80709",
+        ] {
+            assert_eq!(parse_sharded_path(path), digest, "{}", path);
+        }
+        for path in &[
+            "da39a3ee5e6b4b0d3255bfef95601890afd80709",
+            "d/a39a3ee5e6b4b0d3255bfef95601890afd80709",
+            "da/39a3ee5e6b4b0d3255bfef95601890afd8070",
+            "xda/39a3ee5e6b4b0d3255bfef95601890afd80709",
+            "da/39a3ee5e6b4b0d3255bfef95601890afd8070z",
+        ] {
+            assert_eq!(parse_sharded_path(path), None, "{}", path);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_store() {
+        let digest = store_key(&b"Hello World!"[..]).unwrap();
+        assert_eq!(digest, Sha1::from("Hello World!").digest());
+        let path = store_path("cache", &digest);
+        assert_eq!(
+            path,
+            std::path::Path::new("cache")
+                .join("2e")
+                .join("f7bde608ce5404e97d5f042f95f89f1c232871")
+        );
+        assert_eq!(parse_sharded_path(path.to_str().unwrap()), Some(digest));
+    }
+}
@@ -0,0 +1,153 @@
+//! Arithmetic on the ring of 160 bit identifiers.
+//!
+//! Chord and similar overlays place nodes and keys on a ring of size
+//! `2^160` by their SHA-1 and treat digests as big endian integers modulo
+//! `2^160`.  The successor of a key is the first node clockwise from it
+//! and a node's finger table points at the successors of `n + 2^k`.
+//!
+//! ```
+//! use sha1_smol::{chord, Sha1};
+//!
+//! let node = Sha1::from("node").digest();
+//! let finger = chord::add_pow2(&node, 159);
+//! assert_eq!(chord::sub(&finger, &node), chord::add_pow2(&Default::default(), 159));
+//! assert!(chord::in_interval(&finger, &node, &finger));
+//! ```
+
+use crate::{Digest, DIGEST_LENGTH};
+
+/// The number of bits of an identifier.
+pub const BITS: u32 = DIGEST_LENGTH as u32 * 8;
+
+/// Returns `a + b` modulo `2^160`.
+pub fn add(a: &Digest, b: &Digest) -> Digest {
+    let a = a.bytes();
+    let b = b.bytes();
+    let mut rv = [0u8; DIGEST_LENGTH];
+    let mut carry = 0u16;
+    for idx in (0..DIGEST_LENGTH).rev() {
+        let sum = a[idx] as u16 + b[idx] as u16 + carry;
+        rv[idx] = sum as u8;
+        carry = sum >> 8;
+    }
+    Digest::from_bytes(&rv)
+}
+
+/// Returns `a - b` modulo `2^160`.
+///
+/// This is the clockwise distance from `b` to `a`.
+pub fn sub(a: &Digest, b: &Digest) -> Digest {
+    let a = a.bytes();
+    let b = b.bytes();
+    let mut rv = [0u8; DIGEST_LENGTH];
+    let mut borrow = 0i16;
+    for idx in (0..DIGEST_LENGTH).rev() {
+        let diff = a[idx] as i16 - b[idx] as i16 - borrow;
+        rv[idx] = diff as u8;
+        borrow = (diff < 0) as i16;
+    }
+    Digest::from_bytes(&rv)
+}
+
+/// Returns `a + 2^k` modulo `2^160`, the start of the `k`-th finger.
+///
+/// This panics if `k` is not smaller than [`BITS`].
+pub fn add_pow2(a: &Digest, k: u32) -> Digest {
+    assert!(k < BITS, "exponent out of range");
+    let mut offset = [0u8; DIGEST_LENGTH];
+    offset[DIGEST_LENGTH - 1 - (k / 8) as usize] = 1 << (k % 8);
+    add(a, &Digest::from_bytes(&offset))
+}
+
+/// Checks whether `x` lies in the half open interval `(a, b]` going
+/// clockwise from `a`.
+///
+/// If `a` and `b` are equal the interval spans the whole ring.
+pub fn in_interval(x: &Digest, a: &Digest, b: &Digest) -> bool {
+    a == b || sub(x, a) <= sub(b, a) && x != a
+}
+
+/// Checks whether `x` lies in the open interval `(a, b)` going clockwise
+/// from `a`.
+///
+/// If `a` and `b` are equal the interval is the whole ring except `a`.
+pub fn in_open_interval(x: &Digest, a: &Digest, b: &Digest) -> bool {
+    x != a && (a == b || sub(x, a) < sub(b, a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(hex: &str) -> Digest {
+        hex.parse().unwrap()
+    }
+
+    const ZERO: &str = "0000000000000000000000000000000000000000";
+    const ONE: &str = "0000000000000000000000000000000000000001";
+    const MAX: &str = "ffffffffffffffffffffffffffffffffffffffff";
+    const HALF: &str = "8000000000000000000000000000000000000000";
+
+    #[test]
+    fn test_add_sub() {
+        assert_eq!(add(&id(MAX), &id(ONE)), id(ZERO));
+        assert_eq!(sub(&id(ZERO), &id(ONE)), id(MAX));
+        assert_eq!(add(&id(HALF), &id(HALF)), id(ZERO));
+        assert_eq!(
+            add(&id("00000000000000000000000000000000ffffffff"), &id(ONE)),
+            id("0000000000000000000000000000000100000000")
+        );
+        assert_eq!(
+            sub(&id("0000000000000000000000000000000100000000"), &id(ONE)),
+            id("00000000000000000000000000000000ffffffff")
+        );
+
+        let a = crate::Sha1::from("a").digest();
+        let b = crate::Sha1::from("b").digest();
+        assert_eq!(sub(&add(&a, &b), &b), a);
+        assert_eq!(add(&sub(&a, &b), &b), a);
+    }
+
+    #[test]
+    fn test_add_pow2() {
+        assert_eq!(add_pow2(&id(ZERO), 0), id(ONE));
+        assert_eq!(add_pow2(&id(ZERO), 159), id(HALF));
+        assert_eq!(
+            add_pow2(&id(ZERO), 9),
+            id("0000000000000000000000000000000000000200")
+        );
+        assert_eq!(add_pow2(&id(MAX), 0), id(ZERO));
+    }
+
+    #[test]
+    #[should_panic(expected = "exponent out of range")]
+    fn test_add_pow2_out_of_range() {
+        add_pow2(&id(ZERO), 160);
+    }
+
+    #[test]
+    fn test_intervals() {
+        let (zero, one, half, max) = (id(ZERO), id(ONE), id(HALF), id(MAX));
+
+        assert!(in_interval(&one, &zero, &half));
+        assert!(in_interval(&half, &zero, &half));
+        assert!(!in_interval(&zero, &zero, &half));
+        assert!(!in_interval(&max, &zero, &half));
+
+        // wrapping around zero
+        assert!(in_interval(&max, &half, &one));
+        assert!(in_interval(&zero, &half, &one));
+        assert!(in_interval(&one, &half, &one));
+        assert!(!in_interval(&half, &half, &one));
+
+        assert!(in_interval(&half, &one, &one));
+        assert!(in_interval(&one, &one, &one));
+
+        assert!(in_open_interval(&one, &zero, &half));
+        assert!(!in_open_interval(&half, &zero, &half));
+        assert!(in_open_interval(&zero, &max, &one));
+        assert!(!in_open_interval(&one, &max, &one));
+        assert!(in_open_interval(&half, &one, &one));
+        assert!(!in_open_interval(&one, &one, &one));
+    }
+}
@@ -0,0 +1,335 @@
+//! Content defined chunking keyed by SHA-1.
+//!
+//! This splits data into variable sized chunks with a FastCDC style gear
+//! hash so that an insertion or deletion only changes the chunks around the
+//! edit.  Every chunk is identified by the SHA-1 of its contents which makes
+//! the records directly usable for deduplication.
+//!
+//! ```
+//! let data = vec![42u8; 100_000];
+//! let chunks: Vec<_> = sha1_smol::chunking::Chunker::new(&data).collect();
+//! assert_eq!(chunks.iter().map(|c| c.len).sum::<usize>(), data.len());
+//! ```
+
+use core::cmp;
+
+use crate::{Digest, Sha1};
+
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+/// The default minimum chunk size.
+pub const DEFAULT_MIN_SIZE: usize = 2 * 1024;
+/// The default average chunk size.
+pub const DEFAULT_AVG_SIZE: usize = 8 * 1024;
+/// The default maximum chunk size.
+pub const DEFAULT_MAX_SIZE: usize = 64 * 1024;
+
+/// A single chunk produced by the chunker.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Chunk {
+    /// The offset of the chunk in the input.
+    pub offset: u64,
+    /// The length of the chunk in bytes.
+    pub len: usize,
+    /// The SHA-1 of the chunk contents.
+    pub digest: Digest,
+}
+
+/// The chunk size parameters.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+struct Params {
+    min: usize,
+    avg: usize,
+    max: usize,
+    mask_s: u64,
+    mask_l: u64,
+}
+
+impl Params {
+    fn new(min: usize, avg: usize, max: usize) -> Params {
+        assert!(min > 0, "minimum chunk size must not be zero");
+        assert!(min <= avg && avg <= max, "chunk sizes must be ordered");
+        // the stricter mask needs two bits more than the average size
+        assert!(
+            avg as u64 <= 1 << 62,
+            "average chunk size must not exceed 2^62"
+        );
+        let bits = cmp::max(avg.next_power_of_two().trailing_zeros(), 3);
+        // Normalized chunking: before we reach the average size we use a
+        // stricter mask, afterwards a more lenient one.
+        Params {
+            min,
+            avg,
+            max,
+            mask_s: !0u64 << (64 - (bits + 2)),
+            mask_l: !0u64 << (64 - (bits - 2)),
+        }
+    }
+
+    /// Returns the length of the next chunk at the start of `data`.
+    fn cut_point(&self, data: &[u8]) -> usize {
+        if data.len() <= self.min {
+            return data.len();
+        }
+        let end = cmp::min(data.len(), self.max);
+        let normal = cmp::min(end, self.avg);
+        let mut fp = 0u64;
+        let mut idx = self.min;
+        while idx < normal {
+            fp = (fp << 1).wrapping_add(GEAR[data[idx] as usize]);
+            if fp & self.mask_s == 0 {
+                return idx + 1;
+            }
+            idx += 1;
+        }
+        while idx < end {
+            fp = (fp << 1).wrapping_add(GEAR[data[idx] as usize]);
+            if fp & self.mask_l == 0 {
+                return idx + 1;
+            }
+            idx += 1;
+        }
+        end
+    }
+}
+
+/// An iterator over the content defined chunks of a byte slice.
+#[derive(Clone, Debug)]
+pub struct Chunker<'a> {
+    data: &'a [u8],
+    offset: usize,
+    params: Params,
+}
+
+impl<'a> Chunker<'a> {
+    /// Creates a chunker with the default chunk sizes.
+    pub fn new(data: &'a [u8]) -> Chunker<'a> {
+        Chunker::with_sizes(data, DEFAULT_MIN_SIZE, DEFAULT_AVG_SIZE, DEFAULT_MAX_SIZE)
+    }
+
+    /// Creates a chunker with custom minimum, average and maximum sizes.
+    ///
+    /// This panics if the sizes are not ordered, the minimum is zero or the
+    /// average is larger than `2^62`.
+    pub fn with_sizes(data: &'a [u8], min: usize, avg: usize, max: usize) -> Chunker<'a> {
+        Chunker {
+            data,
+            offset: 0,
+            params: Params::new(min, avg, max),
+        }
+    }
+}
+
+impl<'a> Iterator for Chunker<'a> {
+    type Item = Chunk;
+
+    fn next(&mut self) -> Option<Chunk> {
+        let rest = &self.data[self.offset..];
+        if rest.is_empty() {
+            return None;
+        }
+        let len = self.params.cut_point(rest);
+        let chunk = Chunk {
+            offset: self.offset as u64,
+            len,
+            digest: Sha1::from(&rest[..len]).digest(),
+        };
+        self.offset += len;
+        Some(chunk)
+    }
+}
+
+/// Chunks everything that can be read from `reader`.
+///
+/// This produces the same chunks as [`Chunker::with_sizes`] would over the
+/// complete input while only buffering up to `max` bytes, and panics for
+/// the same sizes.
+///
+/// (The function is only available if the `std` feature is enabled)
+#[cfg(feature = "std")]
+pub fn chunk_reader<R: Read>(
+    mut reader: R,
+    min: usize,
+    avg: usize,
+    max: usize,
+) -> io::Result<Vec<Chunk>> {
+    let params = Params::new(min, avg, max);
+    let mut rv = Vec::new();
+    let mut buf = Vec::with_capacity(max);
+    let mut offset = 0u64;
+    let mut eof = false;
+    loop {
+        while !eof && buf.len() < max {
+            let old_len = buf.len();
+            buf.resize(max, 0);
+            match reader.read(&mut buf[old_len..]) {
+                Ok(n) => {
+                    buf.truncate(old_len + n);
+                    eof = n == 0;
+                }
+                Err(err) => {
+                    buf.truncate(old_len);
+                    if err.kind() != io::ErrorKind::Interrupted {
+                        return Err(err);
+                    }
+                }
+            }
+        }
+        if buf.is_empty() {
+            return Ok(rv);
+        }
+        let len = params.cut_point(&buf);
+        rv.push(Chunk {
+            offset,
+            len,
+            digest: Sha1::from(&buf[..len]).digest(),
+        });
+        offset += len as u64;
+        buf.drain(..len);
+    }
+}
+
+/// The gear table, `splitmix64(i)` for every byte value `i`.
+#[rustfmt::skip]
+static GEAR: [u64; 256] = [
+    0xe220a8397b1dcdaf, 0x910a2dec89025cc1, 0x975835de1c9756ce, 0x1d0b14e4db018fed,
+    0x6e73e372e2338aca, 0x63033b0ca389c35a, 0xbd64a5d9adefe000, 0x63cbe1e459320dd7,
+    0x9e5651b0ef953636, 0xaeaf52febe706064, 0x088712be8a582fca, 0x50f5647d2380309d,
+    0x943ff9fc99de8f03, 0xc4ca37b7f8ad8aff, 0x6aa9d61435dbe63e, 0x875b9307abf55005,
+    0x5de186dcba779207, 0x808475f02ee37363, 0x1120b3d00955f032, 0xbc4075f2ef431a44,
+    0x362259904816818c, 0x06ca0a95b7e825c7, 0xc80de0f9d4d60e0a, 0xe8d7da001b0181d6,
+    0xaac8c00000a81e44, 0xa208c12cf0c7b709, 0xc3b7f4e80f554dda, 0x974e35325981068a,
+    0x905c768ad49f146c, 0xbb7b49ab8801cf70, 0xa8ee577af2720dce, 0xd7599677879feaea,
+    0xeaeb7f27b54e2401, 0x2c0e0fedbe2218a8, 0x89242d2dd9d4a40d, 0x4d5cb825dfaab05b,
+    0xe9b316290724ba1b, 0xc7ab56057c8da907, 0xeb01cfaf16b47eb0, 0xce6a57a6e3ccdeec,
+    0x369eae0b0ca19112, 0x118e846ea93bc949, 0xbdd732262feb6e95, 0xba69ec90eb4fef88,
+    0xfb452912299a5453, 0xf7e9f3f88cc04ad6, 0xbaee56f32e223007, 0x7bb3c45c597cdb85,
+    0x040a2076f607ff23, 0x1c4a97a6edc2a958, 0xbb0802c412d354cb, 0x5ddad83b4e874068,
+    0xf9b44ecd07b4404a, 0xc85e84f460206f76, 0xbc46b610e9d3f375, 0x6e1351b2349f331c,
+    0x9d189ecfff7b2147, 0x36057413850f2a31, 0x7daf7bd7b0085fd2, 0x9691c38b30d2b83c,
+    0xbb0af0f391997767, 0x417ffd1301eaa631, 0x322f69afa8a70bea, 0x8c741196acc47e35,
+    0xd6967248fbe68cc3, 0x3706970b052f16b1, 0x2c1c719d2c17b759, 0xe5aeaa7575ad84c4,
+    0x051c9d6dbc0a79ca, 0x5351ebfc8b302867, 0x6707e8333343cb0f, 0xd0b1b125e467daaf,
+    0x8e64bfa85c308c9a, 0xd08f003850439a4b, 0x49f64e7a9b76929b, 0x8b425770edf587a4,
+    0xe5f50758d870dbb2, 0x6258cbe07c1ff081, 0x5709ba31dfe2649c, 0x8795cc503eda4f23,
+    0xbd9e8145f2fa917b, 0x9192105c8367ccf5, 0x0de7f33488454a0c, 0x0981d32ae0dff915,
+    0xcf64624732260342, 0x6e159ae8300518f8, 0xd34711b5dbaac9b5, 0xf4c4962e561469c2,
+    0x3d9b64451af7f223, 0xd0f8252577628d86, 0xef96e022e649cec6, 0xf85507da4c69f9d2,
+    0x9d589011dcd0f76f, 0xfb43e48309ca2db7, 0xf5d716b2f98bf127, 0xfb761138e1e0a78c,
+    0x6232969000262121, 0x4f5da978776a9db1, 0xf0eea8c8a0607d15, 0x42f3a9364c476be3,
+    0x23259b94f13cf544, 0xd1024a5fad64d717, 0x11402f2430284f2c, 0x19e7518defa0f0b0,
+    0x47784b983a872ed6, 0xbba2aa66f998b193, 0x225ac33c6d679b92, 0x230275d791e422cc,
+    0xcd19f198025ce714, 0xb32f0f46fbfbccde, 0x9089f05fbf88f8e7, 0xf9364c1f89270349,
+    0x738032f6d1fb2e3a, 0xaea0b05615e97b6c, 0x66bbe75c01e83091, 0x4eff0b08f92f0f6c,
+    0xdabe0bd2457bff71, 0x4b1bbcd2f7f4f4be, 0x730543a4c9f46231, 0x2a8e80d0bea7e5b7,
+    0x02f590758a6d2936, 0x28bf8a80bc3eab52, 0x1882c195e4347c74, 0xb4dc9bd462de412b,
+    0xd1b89df7e5b467f9, 0xca87cb278bbed782, 0xb41d1166f0fa7f73, 0x3fadb6bde9285e98,
+    0xabf42acc138fedbe, 0x96403e918bdbd015, 0x740b401493c72543, 0x8e48a85c3260d9d0,
+    0xb24ce50ea345fe83, 0x03ef5b87efea98e8, 0xe85c7afb627bf59e, 0x8d9bd6ca69c6b7f2,
+    0x3d3f9d6d24164931, 0x6a0c02bb9152575b, 0xad4f6e1945b4c6be, 0x325c64dbdc5bbde3,
+    0xe6877908a812dea1, 0xb6e5f089ef37d1dc, 0xaafb638a996df051, 0xaab6e406556a6437,
+    0x6855ce5643eec108, 0x596cb256d7ae9dd7, 0x35e76b2eb1a96a71, 0x3d8af2aebb01ee14,
+    0x23a09496ea34e97f, 0x2ac8b3c337332480, 0xf8c632ff416764a8, 0xb2d51ec8c64e3969,
+    0xf40299ce8c16ea32, 0x4d1c44a4856af5a4, 0x90ef8da332b2a7e7, 0xfba1c308dec50480,
+    0x96a2c66144306401, 0x746c9d6c2485f666, 0x3c59c8e067cb9788, 0x653c0bfecead92ab,
+    0x911b6c48e11c7f00, 0x98d2848e685e5be6, 0x13fb6953ab52fe41, 0xc565002c28b5b3f4,
+    0xc09b8405b020a230, 0xfbe4b4262f00e647, 0xc5c137f9c4107517, 0xb08a3cc9efb60979,
+    0x5bc7c7d9c168bf6d, 0xeb9f627fe00ea9ed, 0x4f3c532b3a1decba, 0xca23f2d88d65a602,
+    0x57c4944fbf781144, 0x049f1a060b263a12, 0x6847cd16adb241e1, 0xbb095a08560e6dd0,
+    0x68b4754b9c83e91f, 0x73d34e79ab6661bd, 0x97656cc335c029f4, 0x06cb111d65a5c1bf,
+    0xae6f10cfefb4ae24, 0x28bdf54bc25a42b5, 0x7f8f57525c9d03d5, 0x3b7a1aa8445aa94f,
+    0x219f5093119cdcf6, 0x3280126b73eee4e6, 0x02dc423fe038d768, 0x8108096be4efdfdb,
+    0x8f86331cd7693666, 0xd04d1b94b2e33d31, 0x13e6fb952b8ea245, 0xfd87a3030fcf5e15,
+    0xf7bb2b266558a3d8, 0x2871f5925412e4cd, 0xb6eeecbe4105bbb4, 0x5bd22369c07a0949,
+    0x01165b69a81b14c7, 0xc4079edf0591a9f9, 0x92a006731b9a5046, 0x174a42cc14886cb5,
+    0x3f13f4e3c8c592c9, 0xa7a6d3b21ecb97ec, 0xe699e345278de898, 0xb5145f14d921c666,
+    0x172b3485e05703ad, 0xa92d4e8a72a6344c, 0x6bc265fe18354c64, 0x82770a37d755ceeb,
+    0x4e04e2c49ec8dad0, 0xa6b897d8d30eac80, 0xe8cb9afac767b109, 0xed3e2b670029c22b,
+    0x209c26f8211bd185, 0x96519dd7d49df4ae, 0x38e7c4b2e028a229, 0xe65f073cd2135179,
+    0xc9a246f21baecb68, 0xd3a8d03c36640b2f, 0x36491d4aa365d567, 0xdda4f3b0b9e43fd1,
+    0x08400fcc2ffa9314, 0x64b1e39568402827, 0x86e6b7951c608613, 0xeaaf4fbac1d16892,
+    0x23ad6bad6f5c8c81, 0x8eaff63463a306f7, 0x9c52a3eb4fcdc34d, 0x70ea2dfd1920444c,
+    0x84dcc1c7b6773c56, 0x45f7d13d91b9c52f, 0xaf30118ddcc3ca47, 0x6931b9e1122a75b2,
+    0x6bb5ba8e4c5a049f, 0x2eb67620ec65912f, 0xa3f6365bd15b8991, 0x5fa4f116a892767a,
+    0x0f56e2e9b5aa2e5b, 0xf0afa51599d0c810, 0xbb5fe8e953e15117, 0xb7646777da99a215,
+    0xbeab1149a28edcde, 0xe9966f076d4f365c, 0x71f1f1d4ce980385, 0x3cd2167a91a41946,
+    0x679737c796055769, 0x437d563c56393be5, 0x5564a7f784a78404, 0x6ba8674fe24001fb,
+    0xb6986e3027ef2bf4, 0xe1efefb5d14f69ea, 0xd257429b2b5ffe4c, 0x7e5423c80b38522d,
+    0x4997cf2cbecdfc4e, 0x31d6e6002586bf15, 0xda2e5ee5a7779c01, 0x338c507146283fb4,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pseudo_random() -> [u8; 50000] {
+        let mut data = [0u8; 50000];
+        let mut x = 0x2545f491u32;
+        for byte in data.iter_mut() {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            *byte = x as u8;
+        }
+        data
+    }
+
+    #[test]
+    fn test_chunks_cover_input() {
+        let data = pseudo_random();
+        let mut expected_offset = 0;
+        for chunk in Chunker::with_sizes(&data, 256, 1024, 4096) {
+            assert_eq!(chunk.offset, expected_offset);
+            assert!(chunk.len <= 4096);
+            let start = chunk.offset as usize;
+            assert_eq!(
+                chunk.digest,
+                Sha1::from(&data[start..start + chunk.len]).digest()
+            );
+            expected_offset += chunk.len as u64;
+        }
+        assert_eq!(expected_offset, 50000);
+    }
+
+    #[test]
+    fn test_chunks_resynchronize() {
+        let data = pseudo_random();
+        let mut shifted = [0u8; 50001];
+        shifted[0] = 0xff;
+        shifted[1..].copy_from_slice(&data[..]);
+        let a = Chunker::with_sizes(&data, 256, 1024, 4096).map(|c| c.digest);
+        let b = Chunker::with_sizes(&shifted, 256, 1024, 4096).map(|c| c.digest);
+        let last_a = a.last().unwrap();
+        let last_b = b.last().unwrap();
+        assert_eq!(last_a, last_b);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_largest_average() {
+        let params = Params::new(1, 1 << 62, 1 << 62);
+        assert_eq!(params.mask_s, !0);
+        assert_eq!(params.mask_l, !0 << 4);
+        let data = pseudo_random();
+        assert_eq!(Chunker::with_sizes(&data, 1, 1 << 62, 1 << 62).count(), 1);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    #[should_panic(expected = "average chunk size must not exceed 2^62")]
+    fn test_average_too_large() {
+        Params::new(1, (1 << 62) + 1, usize::MAX);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_chunk_reader() {
+        let data = pseudo_random();
+        let from_reader = chunk_reader(&data[..], 256, 1024, 4096).unwrap();
+        let from_slice: Vec<_> = Chunker::with_sizes(&data, 256, 1024, 4096).collect();
+        assert_eq!(from_reader, from_slice);
+    }
+}
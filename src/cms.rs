@@ -0,0 +1,115 @@
+//! Hashing helpers for CMS / PKCS#7 `SignedData` with SHA-1.
+//!
+//! A CMS signer does not sign the content directly.  It records the digest
+//! of the encapsulated content in the `messageDigest` signed attribute and
+//! signs the DER encoding of the signed attributes, re-tagged from the
+//! `[0] IMPLICIT` form found in `SignerInfo` to an explicit `SET OF`
+//! (RFC 5652 section 5.4).
+
+use core::fmt;
+
+use crate::{Digest, Sha1, DIGEST_LENGTH};
+
+const TAG_SET: u8 = 0x31;
+const TAG_IMPLICIT_0: u8 = 0xa0;
+
+/// Indicates that signed attributes are not valid DER.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct SignedAttrsError(());
+
+impl fmt::Display for SignedAttrsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "not valid DER signed attributes")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SignedAttrsError {
+    fn description(&self) -> &str {
+        "not valid DER signed attributes"
+    }
+}
+
+/// Returns the DER encoded value of the `messageDigest` attribute.
+///
+/// This is the `OCTET STRING` holding the SHA-1 of the encapsulated
+/// content (`eContent`), ready to compare with the attribute value found in
+/// a signature.
+pub fn message_digest_attribute(econtent: &[u8]) -> [u8; DIGEST_LENGTH + 2] {
+    let mut rv = [0u8; DIGEST_LENGTH + 2];
+    rv[0] = 0x04;
+    rv[1] = DIGEST_LENGTH as u8;
+    rv[2..].copy_from_slice(&Sha1::from(econtent).digest().bytes());
+    rv
+}
+
+/// Returns the length of the DER header and contents of a single TLV.
+fn tlv_lengths(der: &[u8]) -> Option<(usize, usize)> {
+    let first = *der.get(1)? as usize;
+    if first < 0x80 {
+        return Some((2, first));
+    }
+    let count = first & 0x7f;
+    if count == 0 || count > 4 || der.len() < 2 + count {
+        return None;
+    }
+    let mut len = 0usize;
+    for &byte in &der[2..2 + count] {
+        len = (len << 8) | byte as usize;
+    }
+    // DER requires the shortest length encoding
+    if len < 0x80 || der[2] == 0 {
+        return None;
+    }
+    Some((2 + count, len))
+}
+
+/// Computes the digest that is signed for the given signed attributes.
+///
+/// `signed_attrs` is the complete DER encoding of the attributes, either as
+/// it appears in `SignerInfo` (tagged `[0] IMPLICIT`) or already tagged as
+/// `SET OF`.  The tag is replaced with the `SET OF` tag before hashing.
+pub fn signed_attrs_digest(signed_attrs: &[u8]) -> Result<Digest, SignedAttrsError> {
+    match signed_attrs.first() {
+        Some(&TAG_SET) | Some(&TAG_IMPLICIT_0) => {}
+        _ => return Err(SignedAttrsError(())),
+    }
+    match tlv_lengths(signed_attrs) {
+        Some((header, len)) if header.checked_add(len) == Some(signed_attrs.len()) => {}
+        _ => return Err(SignedAttrsError(())),
+    }
+    let mut m = Sha1::new();
+    m.update(&[TAG_SET]);
+    m.update(&signed_attrs[1..]);
+    Ok(m.digest())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_digest_attribute() {
+        let attr = message_digest_attribute(b"abc");
+        assert_eq!(attr[..2], [0x04, 0x14]);
+        assert_eq!(attr[2..], Sha1::from("abc").digest().bytes());
+    }
+
+    #[test]
+    fn test_signed_attrs_digest() {
+        let implicit = [0xa0, 0x03, 0x02, 0x01, 0x05];
+        let explicit = [0x31, 0x03, 0x02, 0x01, 0x05];
+        let expected = Sha1::from(&explicit[..]).digest();
+        assert_eq!(signed_attrs_digest(&implicit), Ok(expected));
+        assert_eq!(signed_attrs_digest(&explicit), Ok(expected));
+        assert!(signed_attrs_digest(&implicit[..4]).is_err());
+        assert!(signed_attrs_digest(&[0x30, 0x00]).is_err());
+
+        let mut long = [0u8; 3 + 200];
+        long[..3].copy_from_slice(&[0xa0, 0x81, 200]);
+        assert!(signed_attrs_digest(&long).is_ok());
+        assert!(signed_attrs_digest(&[0xa0, 0x81, 0x01, 0x00]).is_err());
+        // the header plus this length overflows a 32 bit usize
+        assert!(signed_attrs_digest(&[0x31, 0x84, 0xff, 0xff, 0xff, 0xfe]).is_err());
+    }
+}
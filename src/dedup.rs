@@ -0,0 +1,128 @@
+//! Finding items with identical contents.
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+//!
+//! [`scan`] hashes every item as it is produced and only keeps the
+//! identifiers, so the contents do not need to be in memory at the same
+//! time.  [`scan_by_length`] first collects the items and only hashes those
+//! that share their length with another one, which skips most of the
+//! hashing when lengths are diverse.
+//!
+//! ```
+//! use sha1_smol::dedup;
+//!
+//! let items = vec![(1, "foo"), (2, "bar"), (3, "foo"), (4, "baz")];
+//! let groups = dedup::scan(items);
+//! assert_eq!(groups.len(), 1);
+//! assert_eq!(groups[0].ids, vec![1, 3]);
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::{Digest, Sha1};
+
+/// A set of items with identical contents.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Group<K> {
+    /// The SHA-1 of the contents.
+    pub digest: Digest,
+    /// The length of the contents in bytes.
+    pub len: usize,
+    /// The identifiers of the items in the order they were produced.
+    pub ids: Vec<K>,
+}
+
+fn duplicates<K>(groups: BTreeMap<Digest, (usize, Vec<K>)>) -> Vec<Group<K>> {
+    groups
+        .into_iter()
+        .filter(|(_, (_, ids))| ids.len() > 1)
+        .map(|(digest, (len, ids))| Group { digest, len, ids })
+        .collect()
+}
+
+/// Groups items with identical contents.
+///
+/// Only groups with at least two items are returned, ordered by digest.
+pub fn scan<K, B, I>(items: I) -> Vec<Group<K>>
+where
+    B: AsRef<[u8]>,
+    I: IntoIterator<Item = (K, B)>,
+{
+    let mut groups = BTreeMap::new();
+    for (id, data) in items {
+        let data = data.as_ref();
+        groups
+            .entry(Sha1::from(data).digest())
+            .or_insert_with(|| (data.len(), Vec::new()))
+            .1
+            .push(id);
+    }
+    duplicates(groups)
+}
+
+/// Groups items with identical contents, hashing only items whose length
+/// is shared with another item.
+///
+/// The result is the same as the one of [`scan`].
+pub fn scan_by_length<K, B, I>(items: I) -> Vec<Group<K>>
+where
+    B: AsRef<[u8]>,
+    I: IntoIterator<Item = (K, B)>,
+{
+    let mut by_len: BTreeMap<usize, Vec<(K, B)>> = BTreeMap::new();
+    for (id, data) in items {
+        by_len
+            .entry(data.as_ref().len())
+            .or_default()
+            .push((id, data));
+    }
+    let mut rv: Vec<Group<K>> = by_len
+        .into_iter()
+        .filter(|(_, items)| items.len() > 1)
+        .flat_map(|(_, items)| scan(items))
+        .collect();
+    rv.sort_by_key(|group| group.digest);
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn items() -> Vec<(usize, &'static [u8])> {
+        vec![
+            (0, &b"alpha"[..]),
+            (1, &b"beta"[..]),
+            (2, &b"alpha"[..]),
+            (3, &b"gamma"[..]),
+            (4, &b""[..]),
+            (5, &b"beta"[..]),
+            (6, &b"alpha"[..]),
+            (7, &b""[..]),
+            (8, &b"delta"[..]),
+        ]
+    }
+
+    #[test]
+    fn test_scan() {
+        let groups = scan(items());
+        assert_eq!(groups.len(), 3);
+        let mut ids: Vec<_> = groups.iter().map(|g| g.ids.clone()).collect();
+        ids.sort();
+        assert_eq!(ids, vec![vec![0, 2, 6], vec![1, 5], vec![4, 7]]);
+        for group in &groups {
+            let (_, data) = items()[group.ids[0]];
+            assert_eq!(group.digest, Sha1::from(data).digest());
+            assert_eq!(group.len, data.len());
+        }
+        assert!(groups.windows(2).all(|w| w[0].digest < w[1].digest));
+    }
+
+    #[test]
+    fn test_scan_by_length() {
+        assert_eq!(scan_by_length(items()), scan(items()));
+        assert!(scan_by_length(vec![(0, "a"), (1, "bb")]).is_empty());
+    }
+}
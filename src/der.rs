@@ -0,0 +1,171 @@
+//! DER `DigestInfo` handling for PKCS#1 v1.5 signatures.
+//!
+//! RSA PKCS#1 v1.5 signatures do not sign the bare digest but a DER encoded
+//! `DigestInfo` structure naming the hash algorithm:
+//!
+//! ```text
+//! DigestInfo ::= SEQUENCE {
+//!     digestAlgorithm AlgorithmIdentifier,  -- id-sha1 (1.3.14.3.2.26)
+//!     digest OCTET STRING
+//! }
+//! ```
+
+use core::fmt;
+
+use crate::{Digest, DIGEST_LENGTH};
+
+/// The DER encoding of the SHA-1 object identifier (1.3.14.3.2.26).
+pub const SHA1_OID: [u8; 5] = [0x2b, 0x0e, 0x03, 0x02, 0x1a];
+
+/// The DER prefix of a SHA-1 `DigestInfo` with NULL parameters.
+///
+/// This is the value listed in RFC 8017 section 9.2, the encoded
+/// `DigestInfo` is this prefix followed by the 20 digest bytes.
+pub const DIGEST_INFO_PREFIX: [u8; 15] = [
+    TAG_SEQUENCE,
+    0x21,
+    TAG_SEQUENCE,
+    0x09,
+    TAG_OID,
+    0x05,
+    SHA1_OID[0],
+    SHA1_OID[1],
+    SHA1_OID[2],
+    SHA1_OID[3],
+    SHA1_OID[4],
+    TAG_NULL,
+    0x00,
+    TAG_OCTET_STRING,
+    0x14,
+];
+
+/// The length of an encoded SHA-1 `DigestInfo`.
+pub const DIGEST_INFO_LENGTH: usize = DIGEST_INFO_PREFIX.len() + DIGEST_LENGTH;
+
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_OID: u8 = 0x06;
+const TAG_NULL: u8 = 0x05;
+const TAG_OCTET_STRING: u8 = 0x04;
+
+/// Indicates that a `DigestInfo` couldn't be parsed.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct DigestInfoError(());
+
+impl fmt::Display for DigestInfoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "not a valid sha1 DigestInfo")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DigestInfoError {
+    fn description(&self) -> &str {
+        "not a valid sha1 DigestInfo"
+    }
+}
+
+/// Encodes `digest` as DER `DigestInfo` for PKCS#1 v1.5 signatures.
+///
+/// The parameters of the algorithm are encoded as NULL as required by
+/// RFC 8017.  The result is what gets padded with EMSA-PKCS1-v1_5 and
+/// passed to the raw RSA operation.
+pub fn encode_digest_info(digest: &Digest) -> [u8; DIGEST_INFO_LENGTH] {
+    let mut rv = [0u8; DIGEST_INFO_LENGTH];
+    rv[..DIGEST_INFO_PREFIX.len()].copy_from_slice(&DIGEST_INFO_PREFIX);
+    rv[DIGEST_INFO_PREFIX.len()..].copy_from_slice(&digest.bytes());
+    rv
+}
+
+/// Splits a DER TLV with the given tag off the front of `input`.
+///
+/// Only the short length form is accepted: everything in a SHA-1
+/// `DigestInfo` is shorter than 128 bytes and DER requires the shortest
+/// encoding.
+fn take_tlv(input: &[u8], tag: u8) -> Result<(&[u8], &[u8]), DigestInfoError> {
+    match input {
+        [t, len, rest @ ..] if *t == tag && *len < 0x80 && rest.len() >= *len as usize => {
+            Ok(rest.split_at(*len as usize))
+        }
+        _ => Err(DigestInfoError(())),
+    }
+}
+
+/// Parses a DER encoded SHA-1 `DigestInfo` and returns the digest.
+///
+/// The parser is strict: the algorithm has to be id-sha1 with either NULL
+/// or absent parameters, the digest has to be exactly 20 bytes and no data
+/// may follow the structure.
+pub fn parse_digest_info(der: &[u8]) -> Result<Digest, DigestInfoError> {
+    let (body, rest) = take_tlv(der, TAG_SEQUENCE)?;
+    if !rest.is_empty() {
+        return Err(DigestInfoError(()));
+    }
+    let (algorithm, body) = take_tlv(body, TAG_SEQUENCE)?;
+    let (oid, params) = take_tlv(algorithm, TAG_OID)?;
+    if oid != SHA1_OID || (!params.is_empty() && params != [TAG_NULL, 0]) {
+        return Err(DigestInfoError(()));
+    }
+    let (digest, rest) = take_tlv(body, TAG_OCTET_STRING)?;
+    if !rest.is_empty() || digest.len() != DIGEST_LENGTH {
+        return Err(DigestInfoError(()));
+    }
+    let mut bytes = [0u8; DIGEST_LENGTH];
+    bytes.copy_from_slice(digest);
+    Ok(Digest::from_bytes(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha1;
+
+    const PREFIX: [u8; 15] = [
+        0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00, 0x04, 0x14,
+    ];
+
+    fn encoded(prefix: &[u8], digest: &Digest) -> ([u8; 64], usize) {
+        let mut buf = [0u8; 64];
+        buf[..prefix.len()].copy_from_slice(prefix);
+        buf[prefix.len()..prefix.len() + 20].copy_from_slice(&digest.bytes());
+        (buf, prefix.len() + 20)
+    }
+
+    #[test]
+    fn test_parse_digest_info() {
+        let digest = Sha1::from("abc").digest();
+        let (buf, len) = encoded(&PREFIX, &digest);
+        assert_eq!(parse_digest_info(&buf[..len]), Ok(digest));
+
+        // absent parameters
+        let (buf, len) = encoded(
+            &[
+                0x30, 0x1f, 0x30, 0x07, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x04, 0x14,
+            ],
+            &digest,
+        );
+        assert_eq!(parse_digest_info(&buf[..len]), Ok(digest));
+    }
+
+    #[test]
+    fn test_encode_digest_info() {
+        assert_eq!(DIGEST_INFO_PREFIX, PREFIX);
+        let digest = Sha1::from("abc").digest();
+        let (buf, len) = encoded(&PREFIX, &digest);
+        let der = encode_digest_info(&digest);
+        assert_eq!(&der[..], &buf[..len]);
+        assert_eq!(parse_digest_info(&der), Ok(digest));
+    }
+
+    #[test]
+    fn test_parse_digest_info_rejects() {
+        let digest = Sha1::from("abc").digest();
+        let (buf, len) = encoded(&PREFIX, &digest);
+        assert!(parse_digest_info(&buf[..len + 1]).is_err());
+        assert!(parse_digest_info(&buf[..len - 1]).is_err());
+
+        let mut wrong_oid = PREFIX;
+        wrong_oid[10] = 0x1b;
+        let (buf, len) = encoded(&wrong_oid, &digest);
+        assert!(parse_digest_info(&buf[..len]).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! Kademlia style XOR distances between digests.
+//!
+//! Kademlia and the BitTorrent DHT identify nodes and values by 160 bit
+//! ids and measure the distance between two ids as their XOR interpreted
+//! as big endian integer.  Digests compare in that same order, so the
+//! distance is returned as a [`Digest`] again.
+//!
+//! A routing table keeps one bucket per bit: bucket `i` holds the nodes at
+//! a distance in `[2^i, 2^(i + 1))` from the local node.
+//!
+//! ```
+//! use sha1_smol::{dht, Sha1};
+//!
+//! let local = Sha1::from("local").digest();
+//! let mut nodes = [
+//!     Sha1::from("a").digest(),
+//!     Sha1::from("b").digest(),
+//!     Sha1::from("c").digest(),
+//! ];
+//! dht::sort_by_distance(&local, &mut nodes);
+//! assert!(dht::distance(&local, &nodes[0]) < dht::distance(&local, &nodes[1]));
+//! assert_eq!(dht::bucket_index(&local, &local), None);
+//! ```
+
+use core::cmp::Ordering;
+
+use crate::{Digest, DIGEST_LENGTH};
+
+/// The number of buckets of a routing table.
+pub const BUCKET_COUNT: usize = DIGEST_LENGTH * 8;
+
+/// Returns the XOR distance between `a` and `b`.
+pub fn distance(a: &Digest, b: &Digest) -> Digest {
+    let mut bytes = a.bytes();
+    for (x, y) in bytes.iter_mut().zip(b.bytes().iter()) {
+        *x ^= y;
+    }
+    Digest::from_bytes(&bytes)
+}
+
+/// Returns the number of leading zero bits of the distance between `a`
+/// and `b`, which is the length of their common prefix.
+pub fn common_prefix_len(a: &Digest, b: &Digest) -> usize {
+    let distance = distance(a, b).bytes();
+    match distance.iter().position(|&x| x != 0) {
+        Some(idx) => idx * 8 + distance[idx].leading_zeros() as usize,
+        None => BUCKET_COUNT,
+    }
+}
+
+/// Returns the index of the bucket `node` belongs into in the routing
+/// table of `local`.
+///
+/// This is the base 2 logarithm of the distance, rounded down, so it is
+/// smaller than [`BUCKET_COUNT`].  Returns `None` if both ids are equal.
+pub fn bucket_index(local: &Digest, node: &Digest) -> Option<usize> {
+    match common_prefix_len(local, node) {
+        BUCKET_COUNT => None,
+        len => Some(BUCKET_COUNT - 1 - len),
+    }
+}
+
+/// Compares `a` and `b` by their distance to `target`, closest first.
+pub fn cmp_distance(target: &Digest, a: &Digest, b: &Digest) -> Ordering {
+    distance(target, a).cmp(&distance(target, b))
+}
+
+/// Sorts `nodes` by their distance to `target`, closest first.
+///
+/// Distinct ids never have the same distance to a target, so the order is
+/// fully determined even though the sort is not stable.
+pub fn sort_by_distance(target: &Digest, nodes: &mut [Digest]) {
+    nodes.sort_unstable_by_key(|node| distance(target, node));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha1;
+
+    fn id(first: u8, last: u8) -> Digest {
+        let mut bytes = [0u8; DIGEST_LENGTH];
+        bytes[0] = first;
+        bytes[DIGEST_LENGTH - 1] = last;
+        Digest::from_bytes(&bytes)
+    }
+
+    #[test]
+    fn test_distance() {
+        let a = Sha1::from("a").digest();
+        let b = Sha1::from("b").digest();
+        assert_eq!(distance(&a, &a), Digest::default());
+        assert_eq!(distance(&a, &b), distance(&b, &a));
+        assert_eq!(distance(&id(0xf0, 1), &id(0x0f, 3)), id(0xff, 2));
+    }
+
+    #[test]
+    fn test_bucket_index() {
+        let zero = Digest::default();
+        assert_eq!(bucket_index(&zero, &zero), None);
+        assert_eq!(bucket_index(&zero, &id(0, 1)), Some(0));
+        assert_eq!(bucket_index(&zero, &id(0, 0xff)), Some(7));
+        assert_eq!(bucket_index(&zero, &id(0x80, 0)), Some(159));
+        assert_eq!(bucket_index(&id(0x80, 0), &id(0xc0, 0)), Some(158));
+        assert_eq!(common_prefix_len(&id(0x80, 0), &id(0xc0, 0)), 1);
+        assert_eq!(common_prefix_len(&zero, &zero), BUCKET_COUNT);
+    }
+
+    #[test]
+    fn test_sort_by_distance() {
+        let target = id(0x10, 0);
+        let mut nodes = [id(0x00, 0), id(0x11, 0), id(0x10, 5), id(0xff, 0)];
+        sort_by_distance(&target, &mut nodes);
+        assert_eq!(nodes, [id(0x10, 5), id(0x11, 0), id(0x00, 0), id(0xff, 0)]);
+        assert_eq!(cmp_distance(&target, &nodes[0], &nodes[1]), Ordering::Less);
+        assert_eq!(cmp_distance(&target, &nodes[3], &nodes[3]), Ordering::Equal);
+    }
+}
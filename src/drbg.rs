@@ -0,0 +1,233 @@
+//! HMAC_DRBG with SHA-1 (NIST SP 800-90A section 10.1.2).
+//!
+//! This is a deterministic random bit generator: the same entropy input,
+//! nonce and personalization string always produce the same output, which
+//! is what reproducing vectors of existing devices requires.  The caller is
+//! responsible for supplying real entropy when it is used as a generator.
+//!
+//! ```
+//! use sha1_smol::drbg::HmacDrbgSha1;
+//!
+//! # let (entropy, nonce) = ([0u8; 32], [0u8; 16]);
+//! let mut drbg = HmacDrbgSha1::new(&entropy, &nonce, b"my app");
+//! let mut key = [0u8; 32];
+//! drbg.generate(&mut key, b"").unwrap();
+//! ```
+
+use core::fmt;
+
+use crate::hmac::HmacSha1;
+use crate::DIGEST_LENGTH;
+
+/// The maximum number of bytes a single `generate` call may request.
+///
+/// SP 800-90A limits requests to `2^19` bits for HMAC_DRBG.
+pub const MAX_REQUEST_LENGTH: usize = (1 << 19) / 8;
+
+/// The number of requests after which a reseed is required.
+const RESEED_INTERVAL: u64 = 1 << 48;
+
+/// Indicates that the generator could not produce output.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum DrbgError {
+    /// More than [`MAX_REQUEST_LENGTH`] bytes were requested at once.
+    RequestTooLarge,
+    /// The reseed interval was reached and [`HmacDrbgSha1::reseed`] has to
+    /// be called before more output can be generated.
+    ReseedRequired,
+}
+
+impl DrbgError {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            DrbgError::RequestTooLarge => "drbg request too large",
+            DrbgError::ReseedRequired => "drbg reseed required",
+        }
+    }
+}
+
+impl fmt::Display for DrbgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DrbgError {
+    fn description(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// An HMAC_DRBG instance using HMAC-SHA1.
+#[derive(Clone)]
+pub struct HmacDrbgSha1 {
+    key: [u8; DIGEST_LENGTH],
+    value: [u8; DIGEST_LENGTH],
+    reseed_counter: u64,
+}
+
+impl HmacDrbgSha1 {
+    /// Instantiates the generator.
+    ///
+    /// The personalization string may be empty.
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> HmacDrbgSha1 {
+        let mut rv = HmacDrbgSha1 {
+            key: [0x00; DIGEST_LENGTH],
+            value: [0x01; DIGEST_LENGTH],
+            reseed_counter: 1,
+        };
+        rv.update(&[entropy, nonce, personalization]);
+        rv
+    }
+
+    /// Mixes fresh entropy and optional additional input into the state.
+    pub fn reseed(&mut self, entropy: &[u8], additional: &[u8]) {
+        self.update(&[entropy, additional]);
+        self.reseed_counter = 1;
+    }
+
+    /// Fills `out` with pseudo random bytes.
+    ///
+    /// `additional` is optional additional input and may be empty.
+    pub fn generate(&mut self, out: &mut [u8], additional: &[u8]) -> Result<(), DrbgError> {
+        if out.len() > MAX_REQUEST_LENGTH {
+            return Err(DrbgError::RequestTooLarge);
+        }
+        if self.reseed_counter > RESEED_INTERVAL {
+            return Err(DrbgError::ReseedRequired);
+        }
+        if !additional.is_empty() {
+            self.update(&[additional]);
+        }
+        for chunk in out.chunks_mut(DIGEST_LENGTH) {
+            self.value = self.hmac(&[&self.value]);
+            chunk.copy_from_slice(&self.value[..chunk.len()]);
+        }
+        self.update(&[additional]);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    fn hmac(&self, parts: &[&[u8]]) -> [u8; DIGEST_LENGTH] {
+        let mut m = HmacSha1::new(&self.key);
+        for part in parts {
+            m.update(part);
+        }
+        m.digest().bytes()
+    }
+
+    /// The `HMAC_DRBG_Update` function over the concatenation of `data`.
+    fn update(&mut self, data: &[&[u8]]) {
+        let provided = data.iter().any(|part| !part.is_empty());
+        for &round in &[0x00u8, 0x01] {
+            if round == 0x01 && !provided {
+                break;
+            }
+            let mut m = HmacSha1::new(&self.key);
+            m.update(&self.value);
+            m.update(&[round]);
+            for part in data {
+                m.update(part);
+            }
+            self.key = m.digest().bytes();
+            self.value = self.hmac(&[&self.value]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::unhex;
+
+    // the CAVP tests generate twice and check the output of the second
+    // call, which requests 640 bits with SHA-1
+    fn check(drbg: &mut HmacDrbgSha1, additional: [&[u8]; 2], expected: &str) {
+        let mut out = [0u8; 80];
+        for add in additional.iter() {
+            drbg.generate(&mut out, add).unwrap();
+        }
+        assert_eq!(out, unhex::<80>(expected));
+    }
+
+    // the first SHA-1 vectors of HMAC_DRBG.rsp of the NIST CAVP DRBG test
+    // vectors (CAVS 14.3), from drbgvectors_no_reseed and
+    // drbgvectors_pr_false
+
+    #[test]
+    fn test_cavp_no_reseed() {
+        let mut drbg = HmacDrbgSha1::new(
+            &unhex::<16>("e91b63309e93d1d08e30e8d556906875"),
+            &unhex::<8>("f59747c468b0d0da"),
+            b"",
+        );
+        check(
+            &mut drbg,
+            [b"", b""],
+            "b7928f9503a417110788f9d0c2585f8aee6fb73b220a626b3ab9825b7a9facc7\
+             9723d7e1ba9255e40e65c249b6082a7bc5e3f129d3d8f69b04ed1183419d6c4f\
+             2a13b304d2c5743f41c8b0ee73225347",
+        );
+    }
+
+    #[test]
+    fn test_cavp_additional_input() {
+        let mut drbg = HmacDrbgSha1::new(
+            &unhex::<16>("c27f80b1d085dd15cb163f0336d07745"),
+            &unhex::<8>("7ecb3f32a90242f7"),
+            &unhex::<16>("4deb622a31b4c530348b5f08008fb7ee"),
+        );
+        check(
+            &mut drbg,
+            [
+                &unhex::<16>("5a84f94804e2d04ead773d2a324b34d6"),
+                &unhex::<16>("226d9f4d720f580c2be44d4eaf2ec8db"),
+            ],
+            "6db76a0a003a64dec6801dd3271fae8a43aa8ce2e0d205e3830e267072abe28d\
+             2a6f707494d15638559fa4282843760daa90eec5d2865ea11e836e60345160d5\
+             112445ab1754b578b55471a1d9caf275",
+        );
+    }
+
+    #[test]
+    fn test_cavp_reseed() {
+        let mut drbg = HmacDrbgSha1::new(
+            &unhex::<16>("03e7b41c95818eb0b667bfa8a175a824"),
+            &unhex::<8>("66a1e417a9b6b92f"),
+            &unhex::<16>("126dded5eb0bc81be37c10bcd9d5f793"),
+        );
+        drbg.reseed(
+            &unhex::<16>("d17e98c2e50ee0db00d25c3364451e95"),
+            &unhex::<16>("dc596d188e2343802240bc7f5cc60516"),
+        );
+        check(
+            &mut drbg,
+            [
+                &unhex::<16>("14c8ec10f5bdde6b9e75898d7f9f03d0"),
+                &unhex::<16>("31aa842afcc1daa94098241a87d6ddfc"),
+            ],
+            "4739b1bcf87404a2290829bd7a61f0b391a794c71c055c7cc513b28dcb5fdc88\
+             645bc9cb490f41fab134c6b33ce9336571762754343961de671b02a47960b4b4\
+             e23c5bfb87dcc19b260b3bcb921ae325",
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut drbg = HmacDrbgSha1::new(b"entropy", b"nonce", b"");
+        let mut out = [0u8; MAX_REQUEST_LENGTH + 1];
+        assert_eq!(
+            drbg.generate(&mut out, b""),
+            Err(DrbgError::RequestTooLarge)
+        );
+        assert_eq!(drbg.generate(&mut out[..MAX_REQUEST_LENGTH], b""), Ok(()));
+        drbg.reseed_counter = RESEED_INTERVAL + 1;
+        assert_eq!(
+            drbg.generate(&mut out[..1], b""),
+            Err(DrbgError::ReseedRequired)
+        );
+        drbg.reseed(b"more entropy", b"");
+        assert_eq!(drbg.generate(&mut out[..1], b""), Ok(()));
+    }
+}
@@ -0,0 +1,431 @@
+//! Allocation free hex and base64 encoding.
+//!
+//! Formatting a digest (or a truncated tag) for output does not require
+//! pulling in separate `hex` or `base64` crates.  The encoders write into a
+//! caller provided buffer and return the written part as a string slice:
+//!
+//! ```
+//! use sha1_smol::encoding::{encode_b64_into, encode_hex_into, Alphabet};
+//!
+//! let digest = sha1_smol::Sha1::from("Hello World!").digest().bytes();
+//! let mut buf = [0u8; 40];
+//! assert_eq!(encode_hex_into(&digest, &mut buf), "2ef7bde608ce5404e97d5f042f95f89f1c232871");
+//! let mut buf = [0u8; 28];
+//! assert_eq!(encode_b64_into(&digest, Alphabet::Standard, true, &mut buf),
+//!            "Lve95gjOVATpfV8EL5X4nxwjKHE=");
+//! ```
+//!
+//! The hex encoder and the base64 encoder and decoder do not use lookup
+//! tables indexed by the data and do not branch on it, as they also handle
+//! secrets such as salts and authentication proofs.
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+
+/// The base64 alphabets supported by [`encode_b64_into`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Alphabet {
+    /// The standard alphabet with `+` and `/` (RFC 4648 section 4).
+    Standard,
+    /// The URL and filename safe alphabet with `-` and `_` (RFC 4648
+    /// section 5).
+    UrlSafe,
+}
+
+impl Alphabet {
+    /// Returns the characters for the values 62 and 63.
+    fn extra_chars(self) -> (u8, u8) {
+        match self {
+            Alphabet::Standard => (b'+', b'/'),
+            Alphabet::UrlSafe => (b'-', b'_'),
+        }
+    }
+}
+
+/// Returns the written prefix of `out` as a string.
+fn ascii_str(out: &[u8]) -> &str {
+    // the encoders only ever produce ascii
+    core::str::from_utf8(out).unwrap()
+}
+
+/// Encodes `input` as lowercase hex into the front of `out`.
+///
+/// Returns the encoded string.  This panics if `out` is shorter than twice
+/// the length of `input`.
+pub fn encode_hex_into<'a>(input: &[u8], out: &'a mut [u8]) -> &'a str {
+    let len = input.len() * 2;
+    assert!(out.len() >= len, "output buffer too small");
+    for (pair, &byte) in out.chunks_mut(2).zip(input.iter()) {
+        pair[0] = hex_char(byte >> 4);
+        pair[1] = hex_char(byte & 0xf);
+    }
+    ascii_str(&out[..len])
+}
+
+/// Maps a nibble to its lowercase hex digit without branching on it.
+fn hex_char(nibble: u8) -> u8 {
+    // 0xff if the nibble is above 9, zero otherwise
+    let above_nine = ((9u8.wrapping_sub(nibble) as i8) >> 7) as u8;
+    b'0' + nibble + (above_nine & (b'a' - b'0' - 10))
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Decodes hex of either case into `out` which must be exactly half as long.
+pub(crate) fn decode_hex_into(input: &[u8], out: &mut [u8]) -> Option<()> {
+    if input.len() != out.len() * 2 {
+        return None;
+    }
+    for (byte, pair) in out.iter_mut().zip(input.chunks(2)) {
+        *byte = (hex_value(pair[0])? << 4) | hex_value(pair[1])?;
+    }
+    Some(())
+}
+
+/// Decodes a hex test vector into an array.
+#[cfg(test)]
+pub(crate) fn unhex<const N: usize>(hex: &str) -> [u8; N] {
+    let mut rv = [0u8; N];
+    decode_hex_into(hex.as_bytes(), &mut rv).expect("malformed hex");
+    rv
+}
+
+/// Returns the length of the base64 encoding of `len` input bytes.
+pub fn encoded_b64_len(len: usize, pad: bool) -> usize {
+    if pad {
+        (len + 2) / 3 * 4
+    } else {
+        (len * 4 + 2) / 3
+    }
+}
+
+/// Encodes `input` as base64 into the front of `out`.
+///
+/// Returns the encoded string.  This panics if `out` is shorter than
+/// [`encoded_b64_len`].
+pub fn encode_b64_into<'a>(
+    input: &[u8],
+    alphabet: Alphabet,
+    pad: bool,
+    out: &'a mut [u8],
+) -> &'a str {
+    assert!(
+        out.len() >= encoded_b64_len(input.len(), pad),
+        "output buffer too small"
+    );
+    let mut idx = 0;
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        let chars = [
+            b64_char(alphabet, (n >> 18) as u8 & 63),
+            b64_char(alphabet, (n >> 12) as u8 & 63),
+            b64_char(alphabet, (n >> 6) as u8 & 63),
+            b64_char(alphabet, n as u8 & 63),
+        ];
+        let used = chunk.len() + 1;
+        out[idx..idx + used].copy_from_slice(&chars[..used]);
+        idx += used;
+        if pad {
+            for c in out[idx..idx + 4 - used].iter_mut() {
+                *c = b'=';
+            }
+            idx += 4 - used;
+        }
+    }
+    ascii_str(&out[..idx])
+}
+
+/// Encodes `input` as base64 into a newly allocated string.
+#[cfg(feature = "alloc")]
+pub(crate) fn encode_b64(input: &[u8], alphabet: Alphabet, pad: bool) -> String {
+    let mut out = alloc::vec![0u8; encoded_b64_len(input.len(), pad)];
+    encode_b64_into(input, alphabet, pad, &mut out);
+    // the alphabets are plain ascii
+    String::from_utf8(out).unwrap()
+}
+
+/// Returns 0xff if `lo <= x < hi` and zero otherwise, without branching.
+fn range_mask(x: u8, lo: u8, hi: u8) -> u8 {
+    let below = |a: u8, b: u8| ((a as i16 - b as i16) >> 8) as u8;
+    !below(x, lo) & below(x, hi)
+}
+
+/// Maps a sextet to its base64 character without branching on it.
+fn b64_char(alphabet: Alphabet, value: u8) -> u8 {
+    let (c62, c63) = alphabet.extra_chars();
+    (range_mask(value, 0, 26) & value.wrapping_add(b'A'))
+        | (range_mask(value, 26, 52) & value.wrapping_add(b'a' - 26))
+        | (range_mask(value, 52, 62) & value.wrapping_sub(52 - b'0'))
+        | (range_mask(value, 62, 63) & c62)
+        | (range_mask(value, 63, 64) & c63)
+}
+
+/// Maps a base64 character to its sextet without branching on it.
+fn b64_value(alphabet: Alphabet, c: u8) -> Option<u32> {
+    let (c62, c63) = alphabet.extra_chars();
+    let upper = range_mask(c, b'A', b'Z' + 1);
+    let lower = range_mask(c, b'a', b'z' + 1);
+    let digit = range_mask(c, b'0', b'9' + 1);
+    let v62 = range_mask(c, c62, c62 + 1);
+    let v63 = range_mask(c, c63, c63 + 1);
+    let value = (upper & c.wrapping_sub(b'A'))
+        | (lower & c.wrapping_sub(b'a' - 26))
+        | (digit & c.wrapping_add(52 - b'0'))
+        | (v62 & 62)
+        | (v63 & 63);
+    if upper | lower | digit | v62 | v63 == 0 {
+        None
+    } else {
+        Some(value as u32)
+    }
+}
+
+/// Returns the maximum decoded length of a base64 input of `len` bytes.
+#[cfg(feature = "alloc")]
+pub(crate) fn decoded_b64_len(len: usize) -> usize {
+    len / 4 * 3 + (len % 4 * 3 / 4)
+}
+
+/// Decodes base64 `input` into `out` which must be large enough.
+///
+/// Padding is optional but has to be correct if present.  Non canonical
+/// encodings (with stray bits in the last character) are rejected.  Returns
+/// the number of bytes written.
+pub(crate) fn decode_b64_into(input: &[u8], alphabet: Alphabet, out: &mut [u8]) -> Option<usize> {
+    let mut input = input;
+    if !input.is_empty() && input.len() % 4 == 0 {
+        if input.ends_with(b"==") {
+            input = &input[..input.len() - 2];
+        } else if input.ends_with(b"=") {
+            input = &input[..input.len() - 1];
+        }
+    }
+    if input.len() % 4 == 1 {
+        return None;
+    }
+    let mut idx = 0;
+    for chunk in input.chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            n |= b64_value(alphabet, c)? << (18 - 6 * i);
+        }
+        let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        let produced = chunk.len() - 1;
+        if n & (0xffffff >> (8 * produced)) != 0 {
+            return None;
+        }
+        out.get_mut(idx..idx + produced)?
+            .copy_from_slice(&bytes[..produced]);
+        idx += produced;
+    }
+    Some(idx)
+}
+
+/// Decodes base64 `input` into a newly allocated buffer.
+#[cfg(feature = "alloc")]
+pub(crate) fn decode_b64(input: &[u8], alphabet: Alphabet) -> Option<Vec<u8>> {
+    let mut out = alloc::vec![0u8; decoded_b64_len(input.len())];
+    let len = decode_b64_into(input, alphabet, &mut out)?;
+    out.truncate(len);
+    Some(out)
+}
+
+/// The base32 alphabet (RFC 4648 section 6).
+pub(crate) const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Encodes `input` as uppercase base32 without padding.
+#[cfg(feature = "alloc")]
+pub(crate) fn encode_b32(input: &[u8]) -> String {
+    let mut rv = String::with_capacity((input.len() * 8 + 4) / 5);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &b in input {
+        buffer = (buffer << 8) | b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            rv.push(BASE32[(buffer >> bits) as usize & 31] as char);
+        }
+    }
+    if bits > 0 {
+        rv.push(BASE32[(buffer << (5 - bits)) as usize & 31] as char);
+    }
+    rv
+}
+
+/// Decodes base32 with optional padding, ignoring the case of letters.
+///
+/// Unlike with base64 the unused bits of the last character are ignored as
+/// secrets from authenticator setups are not always canonically encoded.
+#[cfg(feature = "alloc")]
+pub(crate) fn decode_b32(input: &[u8]) -> Option<Vec<u8>> {
+    let mut input = input;
+    while let Some((&b'=', rest)) = input.split_last() {
+        input = rest;
+    }
+    let mut rv = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &c in input {
+        let c = c.to_ascii_uppercase();
+        let value = BASE32.iter().position(|&x| x == c)? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            rv.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    if bits >= 5 {
+        return None;
+    }
+    Some(rv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_hex_into() {
+        let mut buf = [0u8; 34];
+        let input: [u8; 16] = [
+            0x00, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0x0f, 0xf0, 0x9a, 0xa9, 0xff,
+            0x10, 0x7e,
+        ];
+        assert_eq!(
+            encode_hex_into(&input, &mut buf),
+            "000123456789abcdef0ff09aa9ff107e"
+        );
+        assert_eq!(encode_hex_into(b"", &mut buf), "");
+    }
+
+    #[test]
+    fn test_encode_b64_into() {
+        let mut buf = [0u8; 8];
+        assert_eq!(
+            encode_b64_into(b"\xfb\xff", Alphabet::Standard, true, &mut buf),
+            "+/8="
+        );
+        assert_eq!(
+            encode_b64_into(b"\xfb\xff", Alphabet::UrlSafe, false, &mut buf),
+            "-_8"
+        );
+        assert_eq!(encoded_b64_len(2, false), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "output buffer too small")]
+    fn test_encode_hex_into_too_small() {
+        encode_hex_into(b"abc", &mut [0u8; 5]);
+    }
+
+    #[test]
+    fn test_hex_decode() {
+        let mut buf = [0u8; 3];
+        assert_eq!(decode_hex_into(b"00fFa1", &mut buf), Some(()));
+        assert_eq!(buf, [0x00, 0xff, 0xa1]);
+        assert_eq!(decode_hex_into(b"00fFa", &mut buf), None);
+        assert_eq!(decode_hex_into(b"+0fFa1", &mut buf), None);
+    }
+
+    #[test]
+    fn test_b64_roundtrip() {
+        let vectors: [(&[u8], &[u8]); 7] = [
+            (b"", b""),
+            (b"f", b"Zg=="),
+            (b"fo", b"Zm8="),
+            (b"foo", b"Zm9v"),
+            (b"foob", b"Zm9vYg=="),
+            (b"fooba", b"Zm9vYmE="),
+            (b"foobar", b"Zm9vYmFy"),
+        ];
+        for &(raw, encoded) in vectors.iter() {
+            let mut buf = [0u8; 8];
+            assert_eq!(encoded_b64_len(raw.len(), true), encoded.len());
+            let n = encode_b64_into(raw, Alphabet::Standard, true, &mut buf).len();
+            assert_eq!(&buf[..n], encoded);
+            let mut buf = [0u8; 8];
+            let n = decode_b64_into(encoded, Alphabet::Standard, &mut buf).unwrap();
+            assert_eq!(&buf[..n], raw);
+        }
+    }
+
+    #[test]
+    fn test_b64_chars() {
+        let standard = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        for (value, &c) in standard.iter().enumerate() {
+            assert_eq!(b64_char(Alphabet::Standard, value as u8), c);
+            assert_eq!(b64_value(Alphabet::Standard, c), Some(value as u32));
+        }
+        assert_eq!(b64_char(Alphabet::UrlSafe, 62), b'-');
+        assert_eq!(b64_char(Alphabet::UrlSafe, 63), b'_');
+        assert_eq!(b64_value(Alphabet::UrlSafe, b'-'), Some(62));
+        assert_eq!(b64_value(Alphabet::UrlSafe, b'_'), Some(63));
+        for c in 0..=255u8 {
+            if !standard.contains(&c) {
+                assert_eq!(b64_value(Alphabet::Standard, c), None, "{}", c);
+            }
+        }
+        assert_eq!(b64_value(Alphabet::UrlSafe, b'+'), None);
+    }
+
+    #[test]
+    fn test_b64_rejects_invalid() {
+        let mut buf = [0u8; 8];
+        assert_eq!(decode_b64_into(b"Zh==", Alphabet::Standard, &mut buf), None);
+        assert_eq!(decode_b64_into(b"Z", Alphabet::Standard, &mut buf), None);
+        assert_eq!(
+            decode_b64_into(b"Zm9v!", Alphabet::Standard, &mut buf),
+            None
+        );
+        assert_eq!(
+            decode_b64_into(b"Zm8", Alphabet::Standard, &mut buf),
+            Some(2)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_b32_roundtrip() {
+        let vectors: [(&[u8], &str); 7] = [
+            (b"", ""),
+            (b"f", "MY"),
+            (b"fo", "MZXQ"),
+            (b"foo", "MZXW6"),
+            (b"foob", "MZXW6YQ"),
+            (b"fooba", "MZXW6YTB"),
+            (b"foobar", "MZXW6YTBOI"),
+        ];
+        for &(raw, encoded) in vectors.iter() {
+            assert_eq!(encode_b32(raw), encoded);
+            assert_eq!(decode_b32(encoded.as_bytes()).unwrap(), raw);
+        }
+        assert_eq!(decode_b32(b"mzxw6ytboi").unwrap(), b"foobar");
+        assert_eq!(decode_b32(b"MZXW6YQ=").unwrap(), b"foob");
+        // the unused low bits of the last character are ignored
+        assert_eq!(decode_b32(b"MZ").unwrap(), b"f");
+        assert_eq!(decode_b32(b"MZXR").unwrap(), b"fo");
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_b32_rejects_invalid() {
+        assert_eq!(decode_b32(b"M"), None);
+        assert_eq!(decode_b32(b"MZXW6Y"), None);
+        assert_eq!(decode_b32(b"MZXW1"), None);
+    }
+}
@@ -0,0 +1,117 @@
+//! Git object ids.
+//!
+//! Git identifies an object by the SHA-1 of a `"<type> <len>\0"` header
+//! followed by the object contents, which is what `git hash-object`
+//! prints:
+//!
+//! ```
+//! use sha1_smol::git::{hash_object, ObjectKind};
+//!
+//! let id = hash_object(ObjectKind::Blob, b"hello world\n");
+//! assert_eq!(id.to_string(), "3b18e512dba79e4c8300dd08aeb37f8e728b8dad");
+//! ```
+
+use core::fmt;
+
+use crate::{Digest, Sha1};
+
+/// The type of a git object.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ObjectKind {
+    /// File contents.
+    Blob,
+    /// A directory listing.
+    Tree,
+    /// A commit.
+    Commit,
+    /// An annotated tag.
+    Tag,
+}
+
+impl ObjectKind {
+    /// Returns the name of the type as used in the object header.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ObjectKind::Blob => "blob",
+            ObjectKind::Tree => "tree",
+            ObjectKind::Commit => "commit",
+            ObjectKind::Tag => "tag",
+        }
+    }
+}
+
+impl fmt::Display for ObjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Returns a hasher that already contains the header of an object of `len`
+/// bytes.
+///
+/// This allows hashing large objects incrementally.  Exactly `len` bytes of
+/// contents have to be fed in afterwards or the id will be wrong.
+pub fn hasher(kind: ObjectKind, len: u64) -> Sha1 {
+    let mut m = Sha1::new();
+    m.update(kind.as_str().as_bytes());
+    m.update(b" ");
+    let mut digits = [0u8; 20];
+    let mut idx = digits.len();
+    let mut len = len;
+    loop {
+        idx -= 1;
+        digits[idx] = b'0' + (len % 10) as u8;
+        len /= 10;
+        if len == 0 {
+            break;
+        }
+    }
+    m.update(&digits[idx..]);
+    m.update(b"\0");
+    m
+}
+
+/// Computes the id of an object with the given contents.
+pub fn hash_object(kind: ObjectKind, data: &[u8]) -> Digest {
+    let mut m = hasher(kind, data.len() as u64);
+    m.update(data);
+    m.digest()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_object() {
+        // reference values from `git hash-object`
+        assert_eq!(
+            hash_object(ObjectKind::Blob, b""),
+            "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391".parse().unwrap()
+        );
+        assert_eq!(
+            hash_object(ObjectKind::Tree, b""),
+            "4b825dc642cb6eb9a060e54bf8d69288fbee4904".parse().unwrap()
+        );
+        assert_eq!(
+            hash_object(ObjectKind::Blob, b"what is up, doc?"),
+            "bd9dbf5aae1a3862dd1526723246b20206e5fc37".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hasher() {
+        let data = [b'x'; 1234];
+        let mut m = hasher(ObjectKind::Commit, data.len() as u64);
+        for chunk in data.chunks(100) {
+            m.update(chunk);
+        }
+        let mut expected = Sha1::from("commit 1234\0");
+        expected.update(&data);
+        assert_eq!(m.digest(), expected.digest());
+        assert_eq!(
+            hasher(ObjectKind::Tag, u64::MAX).digest(),
+            Sha1::from("tag 18446744073709551615\0").digest()
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! A consistent hashing ring keyed by SHA-1.
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+//!
+//! Every node is placed on a ring of `u32` points at a number of virtual
+//! node positions and a key belongs to the first node clockwise from the
+//! key's position.  Adding or removing a node only moves the keys between
+//! that node and its neighbours.
+//!
+//! The positions of a node are derived by hashing its name with a `-<n>`
+//! suffix for `n = 0, 1, 2, ...` and every digest yields five positions as
+//! big endian `u32`s, taken until there are as many as virtual nodes were
+//! requested.  The position of a key is the first four bytes of its SHA-1.
+//! This layout is not compatible with libketama, which uses MD5.
+//!
+//! ```
+//! use sha1_smol::hashring::HashRing;
+//!
+//! let mut ring = HashRing::new(100);
+//! ring.add("cache-1:11211");
+//! ring.add("cache-2:11211");
+//! let node = *ring.get("user:42").unwrap();
+//! ring.add("cache-3:11211");
+//! let moved = ring.get("user:42").unwrap();
+//! assert!(*moved == node || *moved == "cache-3:11211");
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::vec::Vec;
+
+use crate::Sha1;
+
+/// The number of virtual nodes of a [`HashRing`] created with `default`.
+pub const DEFAULT_VNODES: usize = 160;
+
+/// Returns the first four bytes of the SHA-1 of `data` as big endian `u32`.
+fn position(data: &[u8]) -> u32 {
+    let bytes = Sha1::from(data).digest().bytes();
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// A consistent hashing ring of nodes of type `T`.
+///
+/// Nodes are identified by their bytes.  If two nodes land on the same
+/// position the one with the smaller bytes gets it, so lookups do not
+/// depend on the order nodes were added in.
+#[derive(Clone, Debug)]
+pub struct HashRing<T> {
+    vnodes: usize,
+    nodes: Vec<T>,
+    ring: BTreeMap<u32, usize>,
+}
+
+impl<T: AsRef<[u8]>> Default for HashRing<T> {
+    fn default() -> HashRing<T> {
+        HashRing::new(DEFAULT_VNODES)
+    }
+}
+
+impl<T: AsRef<[u8]>> HashRing<T> {
+    /// Creates an empty ring placing every node at `vnodes` positions.
+    ///
+    /// This panics if `vnodes` is zero.
+    pub fn new(vnodes: usize) -> HashRing<T> {
+        assert!(vnodes > 0, "at least one virtual node is required");
+        HashRing {
+            vnodes,
+            nodes: Vec::new(),
+            ring: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of virtual nodes per node.
+    pub fn vnodes(&self) -> usize {
+        self.vnodes
+    }
+
+    /// Returns the number of nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if the ring has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Iterates over the nodes in the order they were added.
+    pub fn nodes(&self) -> impl Iterator<Item = &T> + '_ {
+        self.nodes.iter()
+    }
+
+    fn place(&mut self, idx: usize) {
+        let name = self.nodes[idx].as_ref();
+        let mut placed = 0;
+        for group in 0.. {
+            let mut sha1 = Sha1::from(name);
+            sha1.update(format!("-{}", group).as_bytes());
+            for point in sha1.digest().bytes().chunks(4) {
+                if placed == self.vnodes {
+                    return;
+                }
+                let point = u32::from_be_bytes([point[0], point[1], point[2], point[3]]);
+                let nodes = &self.nodes;
+                self.ring
+                    .entry(point)
+                    .and_modify(|owner| {
+                        if nodes[idx].as_ref() < nodes[*owner].as_ref() {
+                            *owner = idx;
+                        }
+                    })
+                    .or_insert(idx);
+                placed += 1;
+            }
+        }
+    }
+
+    /// Adds a node to the ring.
+    ///
+    /// Returns `false` and leaves the ring unchanged if a node with the
+    /// same bytes is already present.
+    pub fn add(&mut self, node: T) -> bool {
+        if self.nodes.iter().any(|n| n.as_ref() == node.as_ref()) {
+            return false;
+        }
+        self.nodes.push(node);
+        self.place(self.nodes.len() - 1);
+        true
+    }
+
+    /// Removes the node with the given bytes and returns it.
+    pub fn remove<N: AsRef<[u8]>>(&mut self, node: N) -> Option<T> {
+        let idx = self
+            .nodes
+            .iter()
+            .position(|n| n.as_ref() == node.as_ref())?;
+        let rv = self.nodes.remove(idx);
+        // positions another node lost to the removed one have to be
+        // recomputed, so rebuild the ring.
+        self.ring.clear();
+        for idx in 0..self.nodes.len() {
+            self.place(idx);
+        }
+        Some(rv)
+    }
+
+    /// Returns the node `key` belongs to or `None` if the ring is empty.
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Option<&T> {
+        let pos = position(key.as_ref());
+        let (_, &idx) = self
+            .ring
+            .range(pos..)
+            .next()
+            .or_else(|| self.ring.iter().next())?;
+        Some(&self.nodes[idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NODES: [&str; 4] = ["a:11211", "b:11211", "c:11211", "d:11211"];
+
+    fn keys() -> impl Iterator<Item = [u8; 4]> {
+        (0u32..2000).map(|i| i.to_be_bytes())
+    }
+
+    #[test]
+    fn test_distribution() {
+        let mut ring = HashRing::default();
+        for node in NODES.iter() {
+            assert!(ring.add(*node));
+        }
+        assert!(!ring.add("a:11211"));
+        assert_eq!(ring.len(), 4);
+        assert_eq!(ring.ring.len(), 4 * DEFAULT_VNODES);
+
+        let mut counts = [0usize; 4];
+        for key in keys() {
+            let node = ring.get(key).unwrap();
+            counts[NODES.iter().position(|n| n == node).unwrap()] += 1;
+        }
+        for count in counts.iter() {
+            assert!(*count > 300 && *count < 700, "{:?}", counts);
+        }
+    }
+
+    #[test]
+    fn test_add_remove() {
+        let mut ring = HashRing::new(50);
+        assert_eq!(ring.get("key"), None);
+        for node in NODES[..3].iter() {
+            ring.add(*node);
+        }
+        let before: Vec<_> = keys().map(|key| *ring.get(key).unwrap()).collect();
+
+        // keys only ever move to the new node
+        ring.add(NODES[3]);
+        for (key, old) in keys().zip(before.iter()) {
+            let new = *ring.get(key).unwrap();
+            assert!(new == *old || new == NODES[3]);
+        }
+
+        // and back once it is gone
+        assert_eq!(ring.remove(NODES[3]), Some(NODES[3]));
+        assert_eq!(ring.remove(NODES[3]), None);
+        let after: Vec<_> = keys().map(|key| *ring.get(key).unwrap()).collect();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn test_insertion_order() {
+        let mut a = HashRing::new(10);
+        let mut b = HashRing::new(10);
+        for node in NODES.iter() {
+            a.add(*node);
+        }
+        for node in NODES.iter().rev() {
+            b.add(*node);
+        }
+        assert!(keys().all(|key| a.get(key) == b.get(key)));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one virtual node is required")]
+    fn test_no_vnodes() {
+        HashRing::<&str>::new(0);
+    }
+}
@@ -0,0 +1,62 @@
+//! Mercurial node ids.
+//!
+//! Mercurial identifies every revision of a file, manifest or changeset by
+//! the SHA-1 of the two parent node ids followed by the revision text.  The
+//! parents are hashed in sorted order so that swapping them does not change
+//! the id, a missing parent is [`NULL_ID`]:
+//!
+//! ```
+//! use sha1_smol::hg::{nodeid, NULL_ID};
+//!
+//! let id = nodeid(&NULL_ID, &NULL_ID, b"");
+//! assert_eq!(id.to_string(), "b80de5d138758541c5f05265ad144ab9fa86d1db");
+//! ```
+
+use crate::{Digest, Sha1};
+
+/// The id of the null revision, used for missing parents.
+pub const NULL_ID: Digest = Digest {
+    data: crate::Sha1State { state: [0; 5] },
+};
+
+/// Returns a hasher that already contains the sorted parents.
+///
+/// The revision text has to be fed in afterwards.
+pub fn hasher(parent1: &Digest, parent2: &Digest) -> Sha1 {
+    let (a, b) = (parent1.bytes(), parent2.bytes());
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    let mut m = Sha1::new();
+    m.update(&lo);
+    m.update(&hi);
+    m
+}
+
+/// Computes the node id of a revision with the given parents and text.
+pub fn nodeid(parent1: &Digest, parent2: &Digest, text: &[u8]) -> Digest {
+    let mut m = hasher(parent1, parent2);
+    m.update(text);
+    m.digest()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nodeid() {
+        // reference values computed with python's hashlib
+        let empty = nodeid(&NULL_ID, &NULL_ID, b"");
+        assert_eq!(
+            empty,
+            "b80de5d138758541c5f05265ad144ab9fa86d1db".parse().unwrap()
+        );
+        let other: Digest = "2ef7bde608ce5404e97d5f042f95f89f1c232871".parse().unwrap();
+        let expected: Digest = "25fc1f87e340ae72851bdeacd8be29465224cde5".parse().unwrap();
+        assert_eq!(nodeid(&empty, &other, b"hello\n"), expected);
+        assert_eq!(nodeid(&other, &empty, b"hello\n"), expected);
+        assert_eq!(
+            nodeid(&empty, &NULL_ID, b"hello\n"),
+            "267498132eb58c3c2c2495d598c5146d3d2ce7ab".parse().unwrap()
+        );
+    }
+}
@@ -0,0 +1,379 @@
+//! HMAC-SHA1 (RFC 2104).
+//!
+//! The `HmacSha1` type mirrors the `Sha1` API:
+//!
+//! ```rust
+//! let mut m = sha1_smol::hmac::HmacSha1::new(b"Jefe");
+//! m.update(b"what do ya want ");
+//! m.update(b"for nothing?");
+//! assert_eq!(m.digest().to_string(),
+//!            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79");
+//! ```
+
+use core::fmt;
+
+use crate::{Digest, Sha1, DIGEST_LENGTH};
+
+const BLOCK_LENGTH: usize = 64;
+
+/// The shortest truncated tag `verify_truncated` accepts.
+const MIN_TRUNCATED_LENGTH: usize = 4;
+
+/// A key with precomputed HMAC-SHA1 pad states.
+///
+/// Deriving the inner and outer pad states costs two compressions (three
+/// for long keys).  When many tags are computed with the same key, create
+/// an `HmacKey` once and stamp out fresh `HmacSha1` objects from it:
+///
+/// ```
+/// use sha1_smol::hmac::HmacKey;
+///
+/// let key = HmacKey::new(b"Jefe");
+/// let mut m = key.hmac();
+/// m.update(b"what do ya want for nothing?");
+/// assert_eq!(m.digest().to_string(), "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79");
+/// ```
+#[derive(Clone)]
+pub struct HmacKey {
+    inner: Sha1,
+    outer: Sha1,
+}
+
+impl HmacKey {
+    /// Precomputes the pad states for `key`.
+    ///
+    /// Keys longer than the 64 byte block size are hashed first as required
+    /// by RFC 2104.
+    pub fn new(key: &[u8]) -> HmacKey {
+        let mut block = [0u8; BLOCK_LENGTH];
+        if key.len() > BLOCK_LENGTH {
+            block[..20].copy_from_slice(&Sha1::from(key).digest().bytes());
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+
+        let mut pad = [0u8; BLOCK_LENGTH];
+        for (p, k) in pad.iter_mut().zip(block.iter()) {
+            *p = k ^ 0x36;
+        }
+        let mut inner = Sha1::new();
+        inner.update(&pad);
+        for (p, k) in pad.iter_mut().zip(block.iter()) {
+            *p = k ^ 0x5c;
+        }
+        let mut outer = Sha1::new();
+        outer.update(&pad);
+
+        HmacKey { inner, outer }
+    }
+
+    /// Creates a fresh HMAC object for this key.
+    pub fn hmac(&self) -> HmacSha1 {
+        HmacSha1 {
+            key: self.clone(),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// Represents an HMAC-SHA1 object in memory.
+#[derive(Clone)]
+pub struct HmacSha1 {
+    key: HmacKey,
+    inner: Sha1,
+}
+
+impl HmacSha1 {
+    /// Creates an HMAC for the given key.
+    ///
+    /// Keys longer than the 64 byte block size are hashed first as required
+    /// by RFC 2104.
+    pub fn new(key: &[u8]) -> HmacSha1 {
+        HmacKey::new(key).hmac()
+    }
+
+    /// Resets the HMAC object to its initial state, keeping the key.
+    pub fn reset(&mut self) {
+        self.inner = self.key.inner.clone();
+    }
+
+    /// Update the HMAC with input data.
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    /// Update the HMAC with formatted text without an intermediate buffer.
+    ///
+    /// This is handy for string-to-sign constructions:
+    ///
+    /// ```
+    /// let mut m = sha1_smol::hmac::HmacSha1::new(b"key");
+    /// let (method, expires, path) = ("GET", 1234, "/path");
+    /// m.update_fmt(format_args!("{}\n{}\n{}", method, expires, path));
+    /// assert_eq!(m.digest(), {
+    ///     let mut m = sha1_smol::hmac::HmacSha1::new(b"key");
+    ///     m.update(b"GET\n1234\n/path");
+    ///     m.digest()
+    /// });
+    /// ```
+    pub fn update_fmt(&mut self, args: fmt::Arguments) {
+        // writing into the hmac is infallible
+        let _ = fmt::Write::write_fmt(self, args);
+    }
+
+    /// Retrieve the authentication tag.
+    pub fn digest(&self) -> Digest {
+        let mut outer = self.key.outer.clone();
+        outer.update(&self.inner.digest().bytes());
+        outer.digest()
+    }
+
+    /// Retrieve the authentication tag truncated to its first `N` bytes.
+    ///
+    /// Protocols like IPsec, SRTP or SNMP use tags truncated to 96 bits
+    /// (`N = 12`), 80 bits (`N = 10`) or even 32 bits (`N = 4`).  This panics
+    /// if `N` is larger than 20.
+    ///
+    /// ```
+    /// let mut m = sha1_smol::hmac::HmacSha1::new(b"key");
+    /// m.update(b"packet");
+    /// let tag: [u8; 12] = m.digest_truncated();
+    /// assert!(m.verify_truncated(&tag));
+    /// ```
+    pub fn digest_truncated<const N: usize>(&self) -> [u8; N] {
+        assert!(N <= DIGEST_LENGTH, "truncated tag longer than the digest");
+        let mut rv = [0u8; N];
+        rv.copy_from_slice(&self.digest().bytes()[..N]);
+        rv
+    }
+
+    /// Verifies a possibly truncated tag in constant time.
+    ///
+    /// The tag length determines the truncation.  Tags shorter than 4 bytes
+    /// (or longer than 20) are always rejected so an empty or tiny tag can
+    /// never pass verification.
+    pub fn verify_truncated(&self, tag: &[u8]) -> bool {
+        if tag.len() < MIN_TRUNCATED_LENGTH || tag.len() > DIGEST_LENGTH {
+            return false;
+        }
+        ct_eq(&self.digest().bytes()[..tag.len()], tag)
+    }
+
+    /// Retrieve the authentication tag as hex string directly.
+    ///
+    /// (The function is only available if the `alloc` feature is enabled)
+    #[cfg(feature = "alloc")]
+    pub fn hexdigest(&self) -> alloc::string::String {
+        use alloc::string::ToString;
+        self.digest().to_string()
+    }
+}
+
+/// Feeds formatted text into the HMAC so it works with `write!`.
+impl fmt::Write for HmacSha1 {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.update(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Feeds written data into the HMAC so it works with `std::io::copy`.
+///
+/// (The implementation is only available if the `std` feature is enabled)
+#[cfg(feature = "std")]
+impl std::io::Write for HmacSha1 {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Computes the HMAC-SHA1 of `msg` with `key` in one go.
+pub fn hmac_sha1(key: &[u8], msg: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut mac = HmacSha1::new(key);
+    mac.update(msg);
+    mac.digest().bytes()
+}
+
+/// Verifies that `tag` is the HMAC-SHA1 of `msg` with `key`.
+///
+/// The tags are compared in constant time.
+///
+/// ```
+/// use sha1_smol::hmac;
+///
+/// let tag = hmac::hmac_sha1(b"secret", b"payload");
+/// assert!(hmac::verify(b"secret", b"payload", &tag));
+/// assert!(!hmac::verify(b"secret", b"tampered", &tag));
+/// ```
+pub fn verify(key: &[u8], msg: &[u8], tag: &[u8]) -> bool {
+    ct_eq(&hmac_sha1(key, msg), tag)
+}
+
+/// Verifies `tag` for `msg` against several candidate keys.
+///
+/// Returns the index of the key that produced the tag.  Every key is
+/// checked in constant time and all keys are always checked, so neither
+/// the comparison nor the position of the matching key leaks through
+/// timing.  This makes key rotation possible without downtime: accept
+/// tags made with both the old and the new key until all senders switched.
+///
+/// ```
+/// let keys: [&[u8]; 2] = [b"new key", b"old key"];
+/// let tag = sha1_smol::hmac::hmac_sha1(b"old key", b"payload");
+/// assert_eq!(sha1_smol::hmac_verify_any(&keys, b"payload", &tag), Some(1));
+/// ```
+pub fn hmac_verify_any<K: AsRef<[u8]>>(keys: &[K], msg: &[u8], tag: &[u8]) -> Option<usize> {
+    let mut rv = None;
+    for (idx, key) in keys.iter().enumerate() {
+        if verify(key.as_ref(), msg, tag) && rv.is_none() {
+            rv = Some(idx);
+        }
+    }
+    rv
+}
+
+/// Compares two byte strings in constant time (for equal length inputs).
+pub(crate) fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut acc = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        acc |= x ^ y;
+    }
+    // keep the optimizer from turning the loop into an early exit
+    unsafe { core::ptr::read_volatile(&acc) == 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use self::std::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn test_rfc2202_vectors() {
+        let m = HmacSha1::new(&[0x0b; 20]);
+        let mut m2 = m.clone();
+        m2.update(b"Hi There");
+        assert_eq!(
+            m2.digest().to_string(),
+            "b617318655057264e28bc0b6fb378c8ef146be00"
+        );
+
+        let mut m = HmacSha1::new(&[0xaa; 80]);
+        m.update(b"Test Using Larger Than Block-Size Key - Hash Key First");
+        assert_eq!(
+            m.digest().to_string(),
+            "aa4ae5e15272d00e95705637ce8a3b55ed402112"
+        );
+    }
+
+    #[test]
+    fn test_one_shot() {
+        let tag = hmac_sha1(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(tag[..4], [0xef, 0xfc, 0xdf, 0x6a]);
+        assert!(verify(b"Jefe", b"what do ya want for nothing?", &tag));
+        assert!(!verify(b"Jefe", b"what do ya want for nothing!", &tag));
+        assert!(!verify(
+            b"Jefe",
+            b"what do ya want for nothing?",
+            &tag[..10]
+        ));
+    }
+
+    #[test]
+    fn test_truncated() {
+        // RFC 2202 test case 5
+        let mut m = HmacSha1::new(&[0x0c; 20]);
+        m.update(b"Test With Truncation");
+        let tag: [u8; 12] = m.digest_truncated();
+        assert_eq!(
+            tag,
+            [0x4c, 0x1a, 0x03, 0x42, 0x4b, 0x55, 0xe0, 0x7f, 0xe7, 0xf2, 0x7b, 0xe1]
+        );
+        assert!(m.verify_truncated(&tag));
+        assert!(m.verify_truncated(&tag[..10]));
+        assert!(!m.verify_truncated(&tag[..3]));
+        assert!(!m.verify_truncated(&[]));
+        let mut bad = tag;
+        bad[11] ^= 1;
+        assert!(!m.verify_truncated(&bad));
+    }
+
+    #[test]
+    fn test_hmac_key() {
+        let key = HmacKey::new(&[0xaa; 80]);
+        for _ in 0..2 {
+            let mut m = key.hmac();
+            m.update(b"Test Using Larger Than Block-Size Key - Hash Key First");
+            assert_eq!(
+                m.digest().to_string(),
+                "aa4ae5e15272d00e95705637ce8a3b55ed402112"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_io_write() {
+        let mut m = HmacSha1::new(b"Jefe");
+        let mut reader = &b"what do ya want for nothing?"[..];
+        std::io::copy(&mut reader, &mut m).unwrap();
+        assert_eq!(
+            m.digest().to_string(),
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+    }
+
+    #[test]
+    fn test_fmt_write() {
+        use core::fmt::Write;
+
+        let (what, which) = ("want", "nothing");
+        let mut m = HmacSha1::new(b"Jefe");
+        write!(m, "what do ya {} for", what).unwrap();
+        m.update_fmt(format_args!(" {}?", which));
+        assert_eq!(
+            m.digest().to_string(),
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut m = HmacSha1::new(b"Jefe");
+        m.update(b"garbage");
+        m.reset();
+        m.update(b"what do ya want for nothing?");
+        assert_eq!(
+            m.digest().to_string(),
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+    }
+
+    #[test]
+    fn test_verify_any() {
+        let keys: [&[u8]; 3] = [b"k1", b"k2", b"k3"];
+        let mut m = HmacSha1::new(b"k2");
+        m.update(b"msg");
+        let tag = m.digest().bytes();
+        assert_eq!(hmac_verify_any(&keys, b"msg", &tag), Some(1));
+        assert_eq!(hmac_verify_any(&keys, b"other", &tag), None);
+        assert_eq!(hmac_verify_any(&keys, b"msg", &tag[..19]), None);
+    }
+
+    #[test]
+    fn test_ct_eq() {
+        assert!(ct_eq(b"abc", b"abc"));
+        assert!(!ct_eq(b"abc", b"abd"));
+        assert!(!ct_eq(b"abc", b"ab"));
+    }
+}
@@ -0,0 +1,204 @@
+//! Apache `htpasswd` files with `{SHA}` entries.
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+//!
+//! Every line of an `htpasswd` file is a user name and a password hash
+//! separated by a colon.  The `{SHA}` scheme (`htpasswd -s`) stores the
+//! base64 encoded SHA-1 of the password without a salt.  Entries of the
+//! other schemes Apache understands are parsed so they can be told apart,
+//! but only `{SHA}` entries can be verified.
+//!
+//! ```
+//! use sha1_smol::htpasswd::{self, Scheme};
+//!
+//! let line = htpasswd::entry("alice", b"secret");
+//! assert_eq!(line, "alice:{SHA}5en6G6MezRroT3XKqkdPOmY/BfQ=");
+//!
+//! let entries = htpasswd::parse(&line).unwrap();
+//! assert_eq!(entries[0].user, "alice");
+//! assert_eq!(entries[0].scheme(), Scheme::Sha);
+//! assert!(entries[0].verify(b"secret"));
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::hmac::ct_eq;
+use crate::ldap;
+
+/// Indicates that an `htpasswd` file couldn't be parsed.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct HtpasswdError(usize);
+
+impl HtpasswdError {
+    /// Returns the one based number of the offending line.
+    pub fn line(&self) -> usize {
+        self.0
+    }
+}
+
+impl fmt::Display for HtpasswdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "malformed htpasswd line {}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HtpasswdError {
+    fn description(&self) -> &str {
+        "malformed htpasswd line"
+    }
+}
+
+/// The password hash scheme of an entry.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum Scheme {
+    /// Base64 of the unsalted SHA-1 (`{SHA}`).
+    Sha,
+    /// The Apache specific MD5 variant (`$apr1$`).
+    Md5,
+    /// bcrypt (`$2y$`, `$2a$` or `$2b$`).
+    Bcrypt,
+    /// Anything else, which Apache passes to the system's `crypt(3)`.
+    Crypt,
+}
+
+/// A single entry of an `htpasswd` file.
+#[derive(Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct Entry {
+    /// The user name.
+    pub user: String,
+    /// The password hash including its scheme prefix.
+    pub hash: String,
+}
+
+impl Entry {
+    /// Returns the scheme of the password hash.
+    pub fn scheme(&self) -> Scheme {
+        let hash = &self.hash;
+        if hash.starts_with(ldap::SHA_SCHEME) {
+            Scheme::Sha
+        } else if hash.starts_with("$apr1$") {
+            Scheme::Md5
+        } else if hash.starts_with("$2y$") || hash.starts_with("$2a$") || hash.starts_with("$2b$") {
+            Scheme::Bcrypt
+        } else {
+            Scheme::Crypt
+        }
+    }
+
+    /// Verifies `password` against the entry.
+    ///
+    /// The hash is compared in constant time.  Always returns `false` for
+    /// entries that are not of the [`Scheme::Sha`] scheme.
+    pub fn verify(&self, password: &[u8]) -> bool {
+        self.scheme() == Scheme::Sha && ct_eq(ldap::sha(password).as_bytes(), self.hash.as_bytes())
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.user, self.hash)
+    }
+}
+
+/// Returns an `htpasswd` line for `user` with the `{SHA}` hash of
+/// `password`, without line terminator.
+///
+/// This panics if the user name contains a colon or a line break.
+pub fn entry(user: &str, password: &[u8]) -> String {
+    assert!(
+        !user.contains(|c| c == ':' || c == '\n' || c == '\r'),
+        "invalid htpasswd user name"
+    );
+    let mut rv = String::from(user);
+    rv.push(':');
+    rv.push_str(&ldap::sha(password));
+    rv
+}
+
+/// Parses a single `htpasswd` line.
+///
+/// Returns `None` if the line has no colon or an empty user name.
+pub fn parse_line(line: &str) -> Option<Entry> {
+    let idx = line.find(':')?;
+    if idx == 0 {
+        return None;
+    }
+    Some(Entry {
+        user: String::from(&line[..idx]),
+        hash: String::from(line[idx + 1..].trim_end()),
+    })
+}
+
+/// Parses a whole `htpasswd` file.
+///
+/// Empty lines and comments starting with `#` are skipped, the same way
+/// Apache does.
+pub fn parse(contents: &str) -> Result<Vec<Entry>, HtpasswdError> {
+    let mut rv = Vec::new();
+    for (idx, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        rv.push(parse_line(line).ok_or(HtpasswdError(idx + 1))?);
+    }
+    Ok(rv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    // hashes of "secret" from python hashlib, `openssl passwd -apr1` and
+    // python crypt; the bcrypt hash only needs to have the right shape
+    const FILE: &str = "# managed by hand\n\
+                        alice:{SHA}5en6G6MezRroT3XKqkdPOmY/BfQ=\n\
+                        \n\
+                        bob:$apr1$J4wLn7Ec$Y6Gd1fuDIOuNKXFr1fymy1\r\n\
+                        carol:$2y$05$zVxFoleisLTe0ImQaqBVGe1mVkvqK6bS9yGNNCSZz.5AaFoxR4ZFS\n\
+                        dave:qDF4DuTgmMFHM\n";
+
+    #[test]
+    fn test_parse() {
+        let entries = parse(FILE).unwrap();
+        let users: Vec<_> = entries.iter().map(|e| (&*e.user, e.scheme())).collect();
+        assert_eq!(
+            users,
+            [
+                ("alice", Scheme::Sha),
+                ("bob", Scheme::Md5),
+                ("carol", Scheme::Bcrypt),
+                ("dave", Scheme::Crypt),
+            ]
+        );
+        assert_eq!(entries[1].hash, "$apr1$J4wLn7Ec$Y6Gd1fuDIOuNKXFr1fymy1");
+        assert_eq!(entries[0].to_string(), entry("alice", b"secret"));
+
+        assert_eq!(parse("alice:x\nbob\n"), Err(HtpasswdError(2)));
+        assert_eq!(
+            parse(":{SHA}5en6G6MezRroT3XKqkdPOmY/BfQ=")
+                .unwrap_err()
+                .line(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let entries = parse(FILE).unwrap();
+        assert!(entries[0].verify(b"secret"));
+        assert!(!entries[0].verify(b"Secret"));
+        assert!(!entries[1].verify(b"secret"));
+        let entry = parse_line("eve:{SHA}2jmj7l5rSw0yVb/vlWAYkK/YBwk=").unwrap();
+        assert!(entry.verify(b""));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid htpasswd user name")]
+    fn test_entry_invalid_user() {
+        entry("mallory:x", b"secret");
+    }
+}
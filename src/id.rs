@@ -0,0 +1,59 @@
+//! Stable identifiers for composite keys.
+//!
+//! Concatenating the parts of a key before hashing makes `("ab", "c")` and
+//! `("a", "bc")` collide.  The functions in this module use an unambiguous
+//! framing instead which is fixed and safe to reimplement elsewhere:
+//!
+//! ```text
+//! SHA1(u64be(number of parts) || for each part: u64be(len(part)) || part)
+//! ```
+//!
+//! ```
+//! let id = sha1_smol::id::stable_id(&[b"tenant-1", b"order", b"42"]);
+//! assert_ne!(id, sha1_smol::id::stable_id(&[b"tenant-1", b"order42"]));
+//! ```
+
+use crate::{Digest, Sha1};
+
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+
+/// Returns the stable identifier for a key made of `parts`.
+pub fn stable_id(parts: &[&[u8]]) -> Digest {
+    let mut m = Sha1::new();
+    m.update(&(parts.len() as u64).to_be_bytes());
+    for part in parts {
+        m.update(&(part.len() as u64).to_be_bytes());
+        m.update(part);
+    }
+    m.digest()
+}
+
+/// Returns the stable identifier for `parts` as hex string.
+///
+/// (The function is only available if the `alloc` feature is enabled)
+#[cfg(feature = "alloc")]
+pub fn stable_id_hex(parts: &[&[u8]]) -> String {
+    stable_id(parts).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_id_framing() {
+        assert_ne!(stable_id(&[b"ab", b"c"]), stable_id(&[b"a", b"bc"]));
+        assert_ne!(stable_id(&[b"a"]), stable_id(&[b"a", b""]));
+        assert_ne!(stable_id(&[]), stable_id(&[b""]));
+    }
+
+    #[test]
+    fn test_stable_id_value() {
+        let mut framed = [0u8; 8 + 8 + 3];
+        framed[7] = 1;
+        framed[15] = 3;
+        framed[16..].copy_from_slice(b"abc");
+        assert_eq!(stable_id(&[b"abc"]), Sha1::from(&framed[..]).digest());
+    }
+}
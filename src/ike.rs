@@ -0,0 +1,245 @@
+//! IKEv1 (RFC 2409) key derivation with `PRF_HMAC_SHA1`.
+//!
+//! Phase 1 first computes `SKEYID` in a way that depends on the
+//! authentication method, then derives `SKEYID_d`, `SKEYID_a` and
+//! `SKEYID_e` from it.  `Ni_b` / `Nr_b` are the bodies of the nonce
+//! payloads and `CKY-I` / `CKY-R` the initiator and responder cookies:
+//!
+//! ```
+//! use sha1_smol::ike;
+//!
+//! # let (ni, nr, g_xy) = (b"ni", b"nr", b"g^xy");
+//! # let (cky_i, cky_r) = ([1u8; 8], [2u8; 8]);
+//! let skeyid = ike::skeyid_psk(b"shared secret", ni, nr);
+//! let keys = ike::derive_keys(&skeyid, g_xy, &cky_i, &cky_r);
+//! let mut cipher_key = [0u8; 32];
+//! ike::expand_cipher_key(&keys.e, &mut cipher_key);
+//! ```
+
+use crate::hmac::{hmac_sha1, HmacKey};
+use crate::{Sha1, DIGEST_LENGTH};
+
+/// `SKEYID` for (DSS or RSA) signature authentication.
+///
+/// `SKEYID = prf(Ni_b | Nr_b, g^xy)`
+pub fn skeyid_signature(ni: &[u8], nr: &[u8], g_xy: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let len = ni.len() + nr.len();
+    if len > 64 {
+        // HMAC hashes keys longer than a block anyway
+        let mut key = Sha1::new();
+        key.update(ni);
+        key.update(nr);
+        return hmac_sha1(&key.digest().bytes(), g_xy);
+    }
+    let mut key = [0u8; 64];
+    key[..ni.len()].copy_from_slice(ni);
+    key[ni.len()..len].copy_from_slice(nr);
+    hmac_sha1(&key[..len], g_xy)
+}
+
+/// `SKEYID` for pre-shared key authentication.
+///
+/// `SKEYID = prf(pre-shared-key, Ni_b | Nr_b)`
+pub fn skeyid_psk(psk: &[u8], ni: &[u8], nr: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut m = HmacKey::new(psk).hmac();
+    m.update(ni);
+    m.update(nr);
+    m.digest().bytes()
+}
+
+/// `SKEYID` for public key encryption authentication.
+///
+/// `SKEYID = prf(hash(Ni_b | Nr_b), CKY-I | CKY-R)`
+pub fn skeyid_public_key(
+    ni: &[u8],
+    nr: &[u8],
+    cky_i: &[u8; 8],
+    cky_r: &[u8; 8],
+) -> [u8; DIGEST_LENGTH] {
+    let mut h = Sha1::new();
+    h.update(ni);
+    h.update(nr);
+    let mut m = HmacKey::new(&h.digest().bytes()).hmac();
+    m.update(cky_i);
+    m.update(cky_r);
+    m.digest().bytes()
+}
+
+/// The keying material derived from `SKEYID`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Keys {
+    /// `SKEYID_d`, used to derive keying material for IPsec SAs.
+    pub d: [u8; DIGEST_LENGTH],
+    /// `SKEYID_a`, used to authenticate ISAKMP messages.
+    pub a: [u8; DIGEST_LENGTH],
+    /// `SKEYID_e`, used to encrypt ISAKMP messages.
+    pub e: [u8; DIGEST_LENGTH],
+}
+
+/// Derives `SKEYID_d`, `SKEYID_a` and `SKEYID_e`.
+///
+/// ```text
+/// SKEYID_d = prf(SKEYID, g^xy | CKY-I | CKY-R | 0)
+/// SKEYID_a = prf(SKEYID, SKEYID_d | g^xy | CKY-I | CKY-R | 1)
+/// SKEYID_e = prf(SKEYID, SKEYID_a | g^xy | CKY-I | CKY-R | 2)
+/// ```
+pub fn derive_keys(
+    skeyid: &[u8; DIGEST_LENGTH],
+    g_xy: &[u8],
+    cky_i: &[u8; 8],
+    cky_r: &[u8; 8],
+) -> Keys {
+    let prf = HmacKey::new(skeyid);
+    let step = |prev: &[u8], idx: u8| {
+        let mut m = prf.hmac();
+        m.update(prev);
+        m.update(g_xy);
+        m.update(cky_i);
+        m.update(cky_r);
+        m.update(&[idx]);
+        m.digest().bytes()
+    };
+    let d = step(&[], 0);
+    let a = step(&d, 1);
+    let e = step(&a, 2);
+    Keys { d, a, e }
+}
+
+/// Derives the ISAKMP cipher key from `SKEYID_e` (RFC 2409 appendix B).
+///
+/// Keys of up to 20 bytes are the leading bytes of `SKEYID_e`.  Longer ones
+/// are `K1 | K2 | ...` with `K1 = prf(SKEYID_e, 0)` and
+/// `Kn = prf(SKEYID_e, Kn-1)`.
+pub fn expand_cipher_key(skeyid_e: &[u8; DIGEST_LENGTH], out: &mut [u8]) {
+    if out.len() <= DIGEST_LENGTH {
+        out.copy_from_slice(&skeyid_e[..out.len()]);
+        return;
+    }
+    let prf = HmacKey::new(skeyid_e);
+    let mut k = [0u8; DIGEST_LENGTH];
+    for (idx, chunk) in out.chunks_mut(DIGEST_LENGTH).enumerate() {
+        let mut m = prf.hmac();
+        if idx == 0 {
+            m.update(&[0]);
+        } else {
+            m.update(&k);
+        }
+        k = m.digest().bytes();
+        chunk.copy_from_slice(&k[..chunk.len()]);
+    }
+}
+
+/// Derives quick mode `KEYMAT` for an IPsec SA.
+///
+/// `pfs_g_xy` is the quick mode Diffie-Hellman shared secret when perfect
+/// forward secrecy is used.  The material is expanded as
+///
+/// ```text
+/// K1 = prf(SKEYID_d, [ g(qm)^xy | ] protocol | SPI | Ni_b | Nr_b)
+/// Kn = prf(SKEYID_d, Kn-1 | [ g(qm)^xy | ] protocol | SPI | Ni_b | Nr_b)
+/// ```
+pub fn keymat(
+    skeyid_d: &[u8; DIGEST_LENGTH],
+    pfs_g_xy: Option<&[u8]>,
+    protocol: u8,
+    spi: &[u8],
+    ni: &[u8],
+    nr: &[u8],
+    out: &mut [u8],
+) {
+    let prf = HmacKey::new(skeyid_d);
+    let mut k = [0u8; DIGEST_LENGTH];
+    for (idx, chunk) in out.chunks_mut(DIGEST_LENGTH).enumerate() {
+        let mut m = prf.hmac();
+        if idx > 0 {
+            m.update(&k);
+        }
+        if let Some(g_xy) = pfs_g_xy {
+            m.update(g_xy);
+        }
+        m.update(&[protocol]);
+        m.update(spi);
+        m.update(ni);
+        m.update(nr);
+        k = m.digest().bytes();
+        chunk.copy_from_slice(&k[..chunk.len()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::unhex;
+
+    const NI: &[u8] = b"initiator nonce!";
+    const NR: &[u8] = b"responder nonce!";
+    const CKY_I: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+    const CKY_R: [u8; 8] = [0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18];
+
+    fn g_xy() -> [u8; 32] {
+        let mut rv = [0u8; 32];
+        for (idx, byte) in rv.iter_mut().enumerate() {
+            *byte = idx as u8;
+        }
+        rv
+    }
+
+    // reference values computed with Python's hmac module following RFC 2409
+
+    #[test]
+    fn test_skeyid() {
+        assert_eq!(
+            skeyid_psk(b"shared secret", NI, NR),
+            unhex("80a86288dcb749d1174286cb5079f20c27a4fa03")
+        );
+        assert_eq!(
+            skeyid_signature(NI, NR, &g_xy()),
+            unhex("54e05f38e93a2aa2864dafdd9a99e7c00aafec3b")
+        );
+        assert_eq!(
+            skeyid_signature(&[1; 40], &[2; 40], &g_xy()),
+            unhex("0642ecb897dbd2dd92578950eea347f699a70ce9")
+        );
+        assert_eq!(
+            skeyid_public_key(NI, NR, &CKY_I, &CKY_R),
+            unhex("7de616db24834e25b6c1d270210afb188f340626")
+        );
+    }
+
+    #[test]
+    fn test_derive_keys() {
+        let skeyid = skeyid_psk(b"shared secret", NI, NR);
+        let keys = derive_keys(&skeyid, &g_xy(), &CKY_I, &CKY_R);
+        assert_eq!(keys.d, unhex("618cdd9c9c28a502946c32fff7560d5a1cc303cc"));
+        assert_eq!(keys.a, unhex("59dd2e0fbdca9ed6470556e993f60fecf62a6a5f"));
+        assert_eq!(keys.e, unhex("6bb92031fdaa32531aae901a6d35ee35f7f392dc"));
+
+        let mut short = [0u8; 16];
+        expand_cipher_key(&keys.e, &mut short);
+        assert_eq!(short[..], keys.e[..16]);
+        let mut long = [0u8; 32];
+        expand_cipher_key(&keys.e, &mut long);
+        assert_eq!(
+            long,
+            unhex("75351c3616d38efe3d775f25561c59d4bc0c63d93f0a4c24fa9e9733070b9d12")
+        );
+    }
+
+    #[test]
+    fn test_keymat() {
+        let d = unhex("618cdd9c9c28a502946c32fff7560d5a1cc303cc");
+        let spi = [0xde, 0xad, 0xbe, 0xef];
+        let mut out = [0u8; 48];
+        keymat(&d, None, 3, &spi, NI, NR, &mut out);
+        assert_eq!(
+            out,
+            unhex(
+                "4e2bf8c2e87efa829a43b583a6f8f1482583f41b12d90b78\
+                 624f40ddd2ce3b4aba173cf0bfa1b23fa2c8e73ff0d5e714"
+            )
+        );
+        let mut out = [0u8; 16];
+        keymat(&d, Some(b"qmdh"), 3, &spi, NI, NR, &mut out);
+        assert_eq!(out, unhex("bf3740c0170e5e3434296e3fb8231181"));
+    }
+}
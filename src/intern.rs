@@ -0,0 +1,109 @@
+//! Interning of digests into small handles.
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+//!
+//! Graph and deduplication algorithms that reference millions of objects by
+//! their SHA-1 can store a 4 byte handle per reference instead of the 20
+//! byte digest and translate back when needed.
+//!
+//! ```
+//! use sha1_smol::{intern::Interner, Sha1};
+//!
+//! let mut interner = Interner::new();
+//! let a = interner.intern(Sha1::from("a").digest());
+//! let b = interner.intern(Sha1::from("b").digest());
+//! assert_eq!(interner.intern(Sha1::from("a").digest()), a);
+//! assert_ne!(a, b);
+//! assert_eq!(interner.resolve(b), Some(Sha1::from("b").digest()));
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::Digest;
+
+/// Maps digests to dense `u32` handles and back.
+///
+/// Handles are assigned in insertion order starting at zero, so they can be
+/// used directly as indexes into side tables.
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    handles: BTreeMap<Digest, u32>,
+    digests: Vec<Digest>,
+}
+
+impl Interner {
+    /// Creates an empty interner.
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    /// Returns the handle for `digest`, assigning a new one if necessary.
+    ///
+    /// This panics if more than `u32::MAX + 1` digests are interned.
+    pub fn intern(&mut self, digest: Digest) -> u32 {
+        if let Some(&handle) = self.handles.get(&digest) {
+            return handle;
+        }
+        assert!(
+            self.digests.len() <= u32::MAX as usize,
+            "too many interned digests"
+        );
+        let handle = self.digests.len() as u32;
+        self.digests.push(digest);
+        self.handles.insert(digest, handle);
+        handle
+    }
+
+    /// Returns the handle of `digest` if it was interned before.
+    pub fn get(&self, digest: &Digest) -> Option<u32> {
+        self.handles.get(digest).copied()
+    }
+
+    /// Returns the digest for a handle.
+    pub fn resolve(&self, handle: u32) -> Option<Digest> {
+        self.digests.get(handle as usize).copied()
+    }
+
+    /// Returns the number of interned digests.
+    pub fn len(&self) -> usize {
+        self.digests.len()
+    }
+
+    /// Returns `true` if nothing was interned yet.
+    pub fn is_empty(&self) -> bool {
+        self.digests.is_empty()
+    }
+
+    /// Iterates over all `(handle, digest)` pairs in handle order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, Digest)> + '_ {
+        self.digests
+            .iter()
+            .enumerate()
+            .map(|(idx, digest)| (idx as u32, *digest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha1;
+
+    #[test]
+    fn test_interner() {
+        let mut interner = Interner::new();
+        assert!(interner.is_empty());
+        for round in 0..2 {
+            for i in 0u32..100 {
+                let digest = Sha1::from(i.to_be_bytes()).digest();
+                assert_eq!(interner.intern(digest), i);
+                assert_eq!(interner.get(&digest), Some(i));
+                assert_eq!(interner.resolve(i), Some(digest));
+            }
+            assert_eq!(interner.len(), 100, "round {}", round);
+        }
+        assert_eq!(interner.resolve(100), None);
+        assert_eq!(interner.get(&Sha1::from("missing").digest()), None);
+        assert!(interner.iter().all(|(h, d)| interner.get(&d) == Some(h)));
+    }
+}
@@ -0,0 +1,236 @@
+//! Hasher state interoperability with other SHA-1 implementations.
+//!
+//! These functions convert the state of a running `Sha1` from and to the
+//! layouts other implementations use, so hashing can start elsewhere and
+//! be finished with this crate (or the other way round) without hashing
+//! the data again.
+
+use crate::{Blocks, Sha1, Sha1State, StateError};
+
+/// The in-memory layout of OpenSSL's `SHA_CTX`.
+///
+/// This is `#[repr(C)]` and can be passed to and from C code directly on
+/// platforms where `SHA_LONG` is 32 bits wide (all platforms OpenSSL
+/// supports today).  `data` holds the buffered message bytes in memory
+/// order, `Nl`/`Nh` are the low and high words of the message length in
+/// bits and `num` is the number of buffered bytes.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[allow(non_snake_case)]
+pub struct ShaCtx {
+    /// The first chaining word.
+    pub h0: u32,
+    /// The second chaining word.
+    pub h1: u32,
+    /// The third chaining word.
+    pub h2: u32,
+    /// The fourth chaining word.
+    pub h3: u32,
+    /// The fifth chaining word.
+    pub h4: u32,
+    /// The low word of the message length in bits.
+    pub Nl: u32,
+    /// The high word of the message length in bits.
+    pub Nh: u32,
+    /// The buffered message bytes.
+    pub data: [u32; 16],
+    /// The number of buffered bytes.
+    pub num: u32,
+}
+
+/// Converts the state of `m` into an OpenSSL `SHA_CTX`.
+pub fn to_openssl_ctx(m: &Sha1) -> ShaCtx {
+    let bits = (m.len + m.blocks.len as u64) * 8;
+    let mut data = [0u32; 16];
+    for (word, chunk) in data.iter_mut().zip(m.blocks.block.chunks(4)) {
+        *word = u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    let [h0, h1, h2, h3, h4] = m.state.state;
+    ShaCtx {
+        h0,
+        h1,
+        h2,
+        h3,
+        h4,
+        Nl: bits as u32,
+        Nh: (bits >> 32) as u32,
+        data,
+        num: m.blocks.len,
+    }
+}
+
+/// Converts an OpenSSL `SHA_CTX` into a `Sha1`.
+///
+/// Fails if the context does not describe a whole number of bytes or the
+/// buffer length is inconsistent with the message length.
+pub fn from_openssl_ctx(ctx: &ShaCtx) -> Result<Sha1, StateError> {
+    let bits = ((ctx.Nh as u64) << 32) | ctx.Nl as u64;
+    let total = bits / 8;
+    if bits % 8 != 0 || ctx.num as u64 != total % 64 {
+        return Err(StateError(()));
+    }
+    let mut block = [0u8; 64];
+    for (chunk, word) in block.chunks_mut(4).zip(ctx.data.iter()) {
+        chunk.copy_from_slice(&word.to_ne_bytes());
+    }
+    // OpenSSL does not clear the buffer, keep only the meaningful bytes
+    for byte in block[ctx.num as usize..].iter_mut() {
+        *byte = 0;
+    }
+    Ok(Sha1 {
+        state: Sha1State {
+            state: [ctx.h0, ctx.h1, ctx.h2, ctx.h3, ctx.h4],
+        },
+        len: total - ctx.num as u64,
+        blocks: Blocks {
+            len: ctx.num,
+            block,
+        },
+    })
+}
+
+/// The length of a serialized Java SHA-1 engine state in bytes.
+pub const JAVA_STATE_LENGTH: usize = 96;
+
+/// Exports the state in a layout built from the JDK SHA-1 engine fields.
+///
+/// The JDK does not serialize `MessageDigest` objects itself.  This is
+/// what a JVM service gets when it writes the fields of the SUN provider's
+/// `sun.security.provider.SHA` engine with a `DataOutputStream` in this
+/// order: the five `int`s of `state`, then the `long` `bytesProcessed`,
+/// the `int` `bufOfs` and the 64 byte `buffer` of its `DigestBase` parent.
+/// All integers are big endian.
+pub fn export_java_state(m: &Sha1) -> [u8; JAVA_STATE_LENGTH] {
+    let mut rv = [0u8; JAVA_STATE_LENGTH];
+    for (chunk, word) in rv[..20].chunks_mut(4).zip(m.state.state.iter()) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    let total = m.len + m.blocks.len as u64;
+    rv[20..28].copy_from_slice(&total.to_be_bytes());
+    rv[28..32].copy_from_slice(&m.blocks.len.to_be_bytes());
+    let blocklen = m.blocks.len as usize;
+    rv[32..32 + blocklen].copy_from_slice(&m.blocks.block[..blocklen]);
+    rv
+}
+
+/// Imports a state exported in the layout of the JDK SHA-1 engine.
+///
+/// See [`export_java_state`] for the layout.  The buffer offset has to be
+/// consistent with the number of bytes processed.  The JDK does not clear
+/// the buffer past the offset, so those bytes are ignored.
+pub fn import_java_state(state: &[u8]) -> Result<Sha1, StateError> {
+    if state.len() != JAVA_STATE_LENGTH {
+        return Err(StateError(()));
+    }
+    let mut words = [0u32; 5];
+    for (word, chunk) in words.iter_mut().zip(state[..20].chunks(4)) {
+        *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    let mut total = [0u8; 8];
+    total.copy_from_slice(&state[20..28]);
+    let total = u64::from_be_bytes(total);
+    let offset = u32::from_be_bytes([state[28], state[29], state[30], state[31]]);
+    if total > u64::MAX / 8 || offset as u64 != total % 64 {
+        return Err(StateError(()));
+    }
+    let mut block = [0u8; 64];
+    block[..offset as usize].copy_from_slice(&state[32..32 + offset as usize]);
+    Ok(Sha1 {
+        state: Sha1State { state: words },
+        len: total - offset as u64,
+        blocks: Blocks { len: offset, block },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::unhex;
+
+    #[test]
+    fn test_java_state_roundtrip() {
+        let mut m = Sha1::new();
+        m.update(&[b'x'; 100]);
+        let state = export_java_state(&m);
+        assert_eq!(state[20..28], 100u64.to_be_bytes());
+        assert_eq!(state[28..32], 36u32.to_be_bytes());
+        let mut resumed = import_java_state(&state).unwrap();
+        m.update(b"tail");
+        resumed.update(b"tail");
+        assert_eq!(resumed.digest(), m.digest());
+    }
+
+    #[test]
+    fn test_java_state_from_jvm() {
+        // the fields of the engine behind MessageDigest.getInstance("SHA-1",
+        // "SUN") on OpenJDK 17 after updating with 50 'x' and 50 'y' bytes,
+        // read with reflection and written with a DataOutputStream
+        let state: [u8; JAVA_STATE_LENGTH] = unhex(
+            "29ecb3cbd4340ffa3177ff70f6dac325d5b8d9ef000000000000006400000024\
+             7979797979797979797979797979797979797979797979797979797979797979\
+             7979797978787878787878787878787878787979797979797979797979797979",
+        );
+        let mut m = Sha1::new();
+        m.update(&[b'x'; 50]);
+        m.update(&[b'y'; 50]);
+        assert_eq!(export_java_state(&m)[..68], state[..68]);
+
+        // the JVM continued with "tail" and reported this digest
+        let mut resumed = import_java_state(&state).unwrap();
+        resumed.update(b"tail");
+        assert_eq!(
+            resumed.digest().bytes(),
+            unhex::<20>("475aa95ce31d61484ed52308668fcc316dd2e278")
+        );
+    }
+
+    #[test]
+    fn test_java_state_inconsistent() {
+        let mut state = export_java_state(&Sha1::from(&[b'x'; 100][..]));
+        state[31] = 35;
+        assert!(import_java_state(&state).is_err());
+        assert!(import_java_state(&state[..95]).is_err());
+    }
+
+    #[test]
+    fn test_openssl_ctx_roundtrip() {
+        let mut m = Sha1::new();
+        m.update(&[b'x'; 70]);
+        let ctx = to_openssl_ctx(&m);
+        assert_eq!((ctx.Nl, ctx.Nh, ctx.num), (560, 0, 6));
+        assert_eq!(ctx.data[0].to_ne_bytes(), *b"xxxx");
+        let resumed = from_openssl_ctx(&ctx).unwrap();
+        assert_eq!(resumed.digest(), m.digest());
+
+        let mut bad = ctx;
+        bad.Nl += 1;
+        assert!(from_openssl_ctx(&bad).is_err());
+    }
+
+    #[test]
+    fn test_openssl_ctx_from_openssl() {
+        extern "C" {
+            fn SHA1_Init(c: *mut ShaCtx) -> i32;
+            fn SHA1_Update(c: *mut ShaCtx, data: *const u8, len: usize) -> i32;
+        }
+        let mut ctx = ShaCtx {
+            h0: 0,
+            h1: 0,
+            h2: 0,
+            h3: 0,
+            h4: 0,
+            Nl: 0,
+            Nh: 0,
+            data: [0; 16],
+            num: 0,
+        };
+        let data = [b'y'; 150];
+        openssl::init();
+        unsafe {
+            SHA1_Init(&mut ctx);
+            SHA1_Update(&mut ctx, data.as_ptr(), data.len());
+        }
+        let resumed = from_openssl_ctx(&ctx).unwrap();
+        assert_eq!(resumed.digest(), Sha1::from(&data[..]).digest());
+    }
+}
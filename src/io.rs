@@ -0,0 +1,431 @@
+//! Helpers to hash data from readers and files.
+//!
+//! (This module is only available if the `std` feature is enabled)
+//!
+//! If the `tracing` feature is enabled as well, every helper runs inside a
+//! `sha1.hash` span and emits an event with the number of bytes hashed, the
+//! backend used and the time it took once the input is exhausted.
+
+use std::cmp;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{Digest, Sha1, DIGEST_LENGTH, STATE_LENGTH};
+
+/// Feeds everything from `reader` into `m` until EOF.
+///
+/// Returns the number of bytes that were read.
+pub(crate) fn update_reader<R: Read>(m: &mut Sha1, mut reader: R) -> io::Result<u64> {
+    let mut buf = [0u8; 8192];
+    let mut total = 0u64;
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(total),
+            Ok(n) => n,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        m.update(&buf[..n]);
+        total += n as u64;
+    }
+}
+
+/// Runs a hashing operation, tracing it if the `tracing` feature is enabled.
+///
+/// The closure returns the digest and the number of bytes that were hashed.
+fn traced<F>(op: &'static str, f: F) -> io::Result<Digest>
+where
+    F: FnOnce() -> io::Result<(Digest, u64)>,
+{
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::debug_span!("sha1.hash", op, backend = crate::backend::active());
+        let _guard = span.enter();
+        let start = std::time::Instant::now();
+        let rv = f();
+        let duration_us = start.elapsed().as_micros() as u64;
+        match rv {
+            Ok((ref digest, bytes)) => {
+                tracing::debug!(bytes, duration_us, digest = %digest, "hashing finished")
+            }
+            Err(ref err) => tracing::debug!(duration_us, error = %err, "hashing failed"),
+        }
+        rv.map(|(digest, _)| digest)
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = op;
+        f().map(|(digest, _)| digest)
+    }
+}
+
+/// Hashes everything that can be read from `reader`.
+pub fn hash_reader<R: Read>(reader: R) -> io::Result<Digest> {
+    traced("reader", || {
+        let mut m = Sha1::new();
+        let bytes = update_reader(&mut m, reader)?;
+        Ok((m.digest(), bytes))
+    })
+}
+
+/// Hashes the contents of the file at `path`.
+pub fn hash_file<P: AsRef<Path>>(path: P) -> io::Result<Digest> {
+    traced("file", || {
+        let mut m = Sha1::new();
+        let bytes = update_reader(&mut m, fs::File::open(path)?)?;
+        Ok((m.digest(), bytes))
+    })
+}
+
+const SIDECAR_MAGIC: &[u8; 8] = b"sha1rsm1";
+const SIDECAR_LENGTH: usize = 8 + DIGEST_LENGTH + STATE_LENGTH;
+
+/// Verifies a download against an expected digest across restarts.
+///
+/// The verifier persists the hasher state together with the number of bytes
+/// verified so far into a small sidecar file.  When a download is
+/// interrupted it can be reopened, the transfer continued from `offset` and
+/// only the missing bytes need to be hashed.
+///
+/// ```no_run
+/// # fn download(_from: u64) -> Vec<u8> { vec![] }
+/// # fn main() -> std::io::Result<()> {
+/// use sha1_smol::io::ResumableVerifier;
+///
+/// let expected = "2ef7bde608ce5404e97d5f042f95f89f1c232871".parse().unwrap();
+/// let mut verifier = ResumableVerifier::open("download.part.sha1", expected)?;
+/// let data = download(verifier.offset());
+/// verifier.update(&data);
+/// verifier.checkpoint()?;
+/// assert!(verifier.finish()?);
+/// # Ok(()) }
+/// ```
+pub struct ResumableVerifier {
+    hasher: Sha1,
+    expected: Digest,
+    sidecar: PathBuf,
+}
+
+impl ResumableVerifier {
+    /// Opens a verifier for `expected` that persists into `sidecar`.
+    ///
+    /// If the sidecar exists and was created for the same expected digest
+    /// verification resumes from the stored state, otherwise it starts from
+    /// the beginning.  A corrupted sidecar is reported as an
+    /// `InvalidData` error.
+    pub fn open<P: Into<PathBuf>>(sidecar: P, expected: Digest) -> io::Result<ResumableVerifier> {
+        let sidecar = sidecar.into();
+        let hasher = match fs::read(&sidecar) {
+            Ok(contents) => {
+                if contents.len() != SIDECAR_LENGTH || &contents[..8] != SIDECAR_MAGIC {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "not a valid sha1 sidecar file",
+                    ));
+                }
+                if contents[8..8 + DIGEST_LENGTH] == expected.bytes()[..] {
+                    Sha1::import_state(&contents[8 + DIGEST_LENGTH..])
+                        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?
+                } else {
+                    Sha1::new()
+                }
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Sha1::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(ResumableVerifier {
+            hasher,
+            expected,
+            sidecar,
+        })
+    }
+
+    /// Returns the number of bytes verified so far.
+    ///
+    /// This is the offset at which the download needs to continue.
+    pub fn offset(&self) -> u64 {
+        self.hasher.len + self.hasher.blocks.len as u64
+    }
+
+    /// Feeds the next downloaded bytes into the verifier.
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+
+    /// Persists the current state into the sidecar file.
+    ///
+    /// The file is written next to the sidecar first and then renamed so
+    /// that a crash never leaves a truncated sidecar behind.
+    pub fn checkpoint(&self) -> io::Result<()> {
+        let mut contents = [0u8; SIDECAR_LENGTH];
+        contents[..8].copy_from_slice(SIDECAR_MAGIC);
+        contents[8..8 + DIGEST_LENGTH].copy_from_slice(&self.expected.bytes());
+        contents[8 + DIGEST_LENGTH..].copy_from_slice(&self.hasher.export_state());
+        let mut tmp = self.sidecar.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        {
+            let mut f = fs::File::create(&tmp)?;
+            f.write_all(&contents)?;
+            f.sync_all()?;
+        }
+        fs::rename(&tmp, &self.sidecar)
+    }
+
+    /// Finishes verification and removes the sidecar file.
+    ///
+    /// Returns `true` if the hashed data matches the expected digest.
+    pub fn finish(self) -> io::Result<bool> {
+        match fs::remove_file(&self.sidecar) {
+            Ok(()) => {}
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        Ok(self.hasher.digest() == self.expected)
+    }
+}
+
+/// The size of the read buffer of [`TrailerReader`].
+const TRAILER_BUFFER: usize = 8192;
+
+/// A reader for data followed by a SHA-1 of everything before it.
+///
+/// Git packfiles and index files end in such a trailer.  The reader passes
+/// the data through without the trailing 20 bytes and checks them against
+/// the digest of the data once the inner reader is exhausted.  A mismatch
+/// is reported as an `InvalidData` error, input shorter than the trailer as
+/// `UnexpectedEof`.  Data is only verified after the last byte was read, so
+/// it must not be trusted before `read` returned `Ok(0)`.
+///
+/// ```
+/// use std::io::{Read, Write};
+/// use sha1_smol::io::{TrailerReader, TrailerWriter};
+///
+/// let mut writer = TrailerWriter::new(Vec::new());
+/// writer.write_all(b"index contents").unwrap();
+/// let file = writer.finish().unwrap();
+///
+/// let mut contents = Vec::new();
+/// TrailerReader::new(&file[..]).read_to_end(&mut contents).unwrap();
+/// assert_eq!(contents, b"index contents");
+/// ```
+pub struct TrailerReader<R> {
+    inner: R,
+    hasher: Sha1,
+    buf: [u8; TRAILER_BUFFER],
+    pos: usize,
+    len: usize,
+    eof: bool,
+}
+
+impl<R: Read> TrailerReader<R> {
+    /// Wraps `inner`.
+    pub fn new(inner: R) -> TrailerReader<R> {
+        TrailerReader {
+            inner,
+            hasher: Sha1::new(),
+            buf: [0; TRAILER_BUFFER],
+            pos: 0,
+            len: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn verify(&self) -> io::Result<()> {
+        if self.len - self.pos < DIGEST_LENGTH {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "missing sha1 trailer",
+            ));
+        }
+        if self.buf[self.pos..self.len] != self.hasher.digest().bytes()[..] {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "sha1 trailer mismatch",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for TrailerReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let avail = self.len - self.pos;
+            if avail > DIGEST_LENGTH {
+                let n = cmp::min(avail - DIGEST_LENGTH, out.len());
+                out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+                self.hasher.update(&out[..n]);
+                self.pos += n;
+                return Ok(n);
+            }
+            // only the trailer is left, so it can be checked
+            if self.eof {
+                self.verify()?;
+                return Ok(0);
+            }
+            self.buf.copy_within(self.pos..self.len, 0);
+            self.len -= self.pos;
+            self.pos = 0;
+            match self.inner.read(&mut self.buf[self.len..])? {
+                0 => self.eof = true,
+                n => self.len += n,
+            }
+        }
+    }
+}
+
+/// A writer that appends the SHA-1 of everything written to it.
+///
+/// The trailer is written by [`finish`](TrailerWriter::finish), dropping
+/// the writer without calling it leaves the output without a trailer.
+pub struct TrailerWriter<W> {
+    inner: W,
+    hasher: Sha1,
+}
+
+impl<W: Write> TrailerWriter<W> {
+    /// Wraps `inner`.
+    pub fn new(inner: W) -> TrailerWriter<W> {
+        TrailerWriter {
+            inner,
+            hasher: Sha1::new(),
+        }
+    }
+
+    /// Returns the digest of the data written so far.
+    pub fn digest(&self) -> Digest {
+        self.hasher.digest()
+    }
+
+    /// Writes the trailer, flushes and returns the wrapped writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.inner.write_all(&self.hasher.digest().bytes())?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for TrailerWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_reader() {
+        let data = [b'x'; 20000];
+        let digest = hash_reader(&data[..]).unwrap();
+        assert_eq!(digest, Sha1::from(&data[..]).digest());
+    }
+
+    #[test]
+    fn test_resumable_verifier() {
+        let sidecar =
+            std::env::temp_dir().join(std::format!("sha1-smol-test-{}.sha1", std::process::id()));
+        let expected = Sha1::from("Hello World!").digest();
+
+        let mut verifier = ResumableVerifier::open(&sidecar, expected).unwrap();
+        assert_eq!(verifier.offset(), 0);
+        verifier.update(b"Hello ");
+        verifier.checkpoint().unwrap();
+        drop(verifier);
+
+        let mut verifier = ResumableVerifier::open(&sidecar, expected).unwrap();
+        assert_eq!(verifier.offset(), 6);
+        verifier.update(b"World!");
+        assert!(verifier.finish().unwrap());
+        assert!(!sidecar.exists());
+    }
+
+    /// Yields at most `self.1` bytes per read.
+    struct Trickle<'a>(&'a [u8], usize);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            let n = cmp::min(cmp::min(self.1, out.len()), self.0.len());
+            out[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_trailer_roundtrip() {
+        let data: std::vec::Vec<u8> = (0..20000u32).map(|x| (x % 253) as u8).collect();
+        for &len in &[0, 1, 19, 20, 21, 8191, 8192, 20000] {
+            let mut writer = TrailerWriter::new(std::vec::Vec::new());
+            writer.write_all(&data[..len]).unwrap();
+            assert_eq!(writer.digest(), Sha1::from(&data[..len]).digest());
+            let file = writer.finish().unwrap();
+            assert_eq!(file.len(), len + DIGEST_LENGTH);
+
+            for &chunk in &[1, 7, 100000] {
+                let mut out = std::vec::Vec::new();
+                TrailerReader::new(Trickle(&file, chunk))
+                    .read_to_end(&mut out)
+                    .unwrap();
+                assert_eq!(out, &data[..len], "len {} chunk {}", len, chunk);
+            }
+        }
+    }
+
+    #[test]
+    fn test_trailer_empty_reads() {
+        let data = [0x42u8; 100];
+        let mut writer = TrailerWriter::new(std::vec::Vec::new());
+        writer.write_all(&data).unwrap();
+        let file = writer.finish().unwrap();
+
+        let mut reader = TrailerReader::new(&file[..]);
+        let mut buf = [0u8; 10];
+        assert_eq!(reader.read(&mut []).unwrap(), 0);
+        assert_eq!(reader.read(&mut buf).unwrap(), 10);
+        // the whole file is buffered, an empty read must not verify yet
+        reader.eof = true;
+        assert_eq!(reader.read(&mut []).unwrap(), 0);
+        let mut out = std::vec::Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, &data[10..]);
+        assert_eq!(reader.read(&mut []).unwrap(), 0);
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_trailer_errors() {
+        let mut file = TrailerWriter::new(std::vec::Vec::new());
+        file.write_all(b"packfile").unwrap();
+        let mut file = file.finish().unwrap();
+        let last = file.len() - 1;
+        file[last] ^= 1;
+        let mut out = std::vec::Vec::new();
+        let err = TrailerReader::new(&file[..])
+            .read_to_end(&mut out)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = TrailerReader::new(&file[..10])
+            .read_to_end(&mut out)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,245 @@
+//! SHA-1 digests in JAR manifests and signature files.
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+//!
+//! A signed JAR carries the base64 encoded SHA-1 of every entry in
+//! `META-INF/MANIFEST.MF` and the SHA-1 of every manifest section in the
+//! signature file (`META-INF/*.SF`).  Section digests are computed over the
+//! raw bytes of the section as they appear in the manifest, including the
+//! blank line terminating it, so they only match if the manifest is written
+//! exactly like `jarsigner` does: `CRLF` line endings and lines of at most
+//! 72 bytes, continued on the next line after a single space.
+//!
+//! ```
+//! use sha1_smol::jar;
+//!
+//! let section = jar::entry_section("hello.txt", b"Hello World!");
+//! assert_eq!(
+//!     section,
+//!     "Name: hello.txt\r\nSHA1-Digest: Lve95gjOVATpfV8EL5X4nxwjKHE=\r\n\r\n"
+//! );
+//! // the value of the corresponding section in the signature file
+//! let sf_digest = jar::section_digest(section.as_bytes());
+//! # let _ = sf_digest;
+//! ```
+//!
+//! Older tools name the attribute `SHA1-Digest`, current versions of
+//! `jarsigner` name it `SHA-1-Digest`.  Java accepts both.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::encoding::{encode_b64, Alphabet};
+use crate::Sha1;
+
+/// The digest attribute name written by older tools.
+pub const SHA1_DIGEST: &str = "SHA1-Digest";
+
+/// The digest attribute name written by current versions of `jarsigner`.
+pub const SHA_1_DIGEST: &str = "SHA-1-Digest";
+
+/// The maximum length of a manifest line in bytes, without the line end.
+const MAX_LINE: usize = 72;
+
+/// Returns the base64 encoded SHA-1 of `data`.
+pub fn digest(data: &[u8]) -> String {
+    encode_b64(&Sha1::from(data).digest().bytes(), Alphabet::Standard, true)
+}
+
+/// Appends a `name: value` attribute line to `out`, wrapped to 72 bytes.
+///
+/// Lines are only broken between characters, never inside the UTF-8
+/// encoding of one.
+pub fn write_attribute(out: &mut String, name: &str, value: &str) {
+    let mut line_len = 0;
+    let mut first = true;
+    for part in [name, ": ", value].iter() {
+        for c in part.chars() {
+            let limit = if first { MAX_LINE } else { MAX_LINE - 1 };
+            if line_len + c.len_utf8() > limit {
+                out.push_str("\r\n ");
+                line_len = 0;
+                first = false;
+            }
+            out.push(c);
+            line_len += c.len_utf8();
+        }
+    }
+    out.push_str("\r\n");
+}
+
+/// Builds a manifest section for the entry `name` with the given
+/// attributes, terminated by a blank line.
+pub fn section(name: &str, attributes: &[(&str, &str)]) -> String {
+    let mut rv = String::new();
+    write_attribute(&mut rv, "Name", name);
+    for (attr, value) in attributes {
+        write_attribute(&mut rv, attr, value);
+    }
+    rv.push_str("\r\n");
+    rv
+}
+
+/// Builds the manifest section of a JAR entry with its `SHA1-Digest`.
+pub fn entry_section(name: &str, data: &[u8]) -> String {
+    section(name, &[(SHA1_DIGEST, &digest(data))])
+}
+
+/// Returns the digest of a raw manifest section for the signature file.
+///
+/// This is also the value of `SHA1-Digest-Manifest` when passed the whole
+/// manifest and of `SHA1-Digest-Manifest-Main-Attributes` when passed the
+/// main section.
+pub fn section_digest(section: &[u8]) -> String {
+    digest(section)
+}
+
+/// Splits a raw manifest into its sections.
+///
+/// Every section keeps its line ends and the blank line terminating it so
+/// it can be passed to [`section_digest`].  The first section is the main
+/// section.  `\r\n`, `\n` and `\r` are all accepted as line ends.
+pub fn split_sections(manifest: &[u8]) -> Vec<&[u8]> {
+    let mut rv = Vec::new();
+    let mut start = 0;
+    let mut pos = 0;
+    let mut line_start = 0;
+    while pos < manifest.len() {
+        let end = match manifest[pos] {
+            b'\r' if manifest.get(pos + 1) == Some(&b'\n') => pos + 2,
+            b'\r' | b'\n' => pos + 1,
+            _ => {
+                pos += 1;
+                continue;
+            }
+        };
+        if pos == line_start {
+            rv.push(&manifest[start..end]);
+            start = end;
+        }
+        pos = end;
+        line_start = end;
+    }
+    if start < manifest.len() {
+        rv.push(&manifest[start..]);
+    }
+    rv
+}
+
+/// Looks up an attribute in a raw manifest section.
+///
+/// Continuation lines are joined and the name is compared ignoring ASCII
+/// case like Java does.  Returns `None` if the attribute is missing or the
+/// section is not valid UTF-8.
+pub fn attribute(section: &[u8], name: &str) -> Option<String> {
+    let section = core::str::from_utf8(section).ok()?;
+    let mut lines = section
+        .split(|c| c == '\n' || c == '\r')
+        .filter(|line| !line.is_empty())
+        .peekable();
+    while let Some(line) = lines.next() {
+        let (attr, value) = match line.find(": ") {
+            Some(idx) => (&line[..idx], &line[idx + 2..]),
+            None => continue,
+        };
+        if !attr.eq_ignore_ascii_case(name) {
+            continue;
+        }
+        let mut rv = String::from(value);
+        while let Some(cont) = lines.peek().and_then(|line| line.strip_prefix(' ')) {
+            rv.push_str(cont);
+            lines.next();
+        }
+        return Some(rv);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // written by `jarsigner -digestalg SHA-1` of OpenJDK 17
+    const MANIFEST: &str = "Manifest-Version: 1.0\r\n\
+                            Created-By: 17.0.15 (Debian)\r\n\
+                            \r\n\
+                            Name: hello.txt\r\n\
+                            SHA-1-Digest: Lve95gjOVATpfV8EL5X4nxwjKHE=\r\n\
+                            \r\n\
+                            Name: a_very_long_entry_name_that_definitely_needs_to_be_wrapped_over_mu\r\n \
+                            ltiple_lines.txt\r\n\
+                            SHA-1-Digest: UOSDaQ7EgfSvf2+1JLK5nrFxZWU=\r\n\
+                            \r\n";
+
+    const LONG_NAME: &str =
+        "a_very_long_entry_name_that_definitely_needs_to_be_wrapped_over_multiple_lines.txt";
+
+    #[test]
+    fn test_sections_match_jarsigner() {
+        let sections = split_sections(MANIFEST.as_bytes());
+        assert_eq!(sections.len(), 3);
+        assert_eq!(
+            sections[1],
+            section("hello.txt", &[(SHA_1_DIGEST, &digest(b"Hello World!"))]).as_bytes()
+        );
+        assert_eq!(
+            sections[2],
+            section(LONG_NAME, &[(SHA_1_DIGEST, &digest(&[b'x'; 100]))]).as_bytes()
+        );
+
+        // values from the signature file written by jarsigner
+        assert_eq!(
+            section_digest(MANIFEST.as_bytes()),
+            "k6ZQWSnOth9puDmNw4fOVBM6F9Q="
+        );
+        assert_eq!(section_digest(sections[0]), "B1nSgcGs5iG2EwhES6UAU3DogLY=");
+        assert_eq!(section_digest(sections[1]), "RfLp0FfKYS3j8QlzqmBLrbAZWLc=");
+        assert_eq!(section_digest(sections[2]), "fQiL3yDEPTvO4RS4/2GL1J3DDvU=");
+    }
+
+    #[test]
+    fn test_attribute() {
+        let sections = split_sections(MANIFEST.as_bytes());
+        assert_eq!(attribute(sections[2], "name").as_deref(), Some(LONG_NAME));
+        assert_eq!(
+            attribute(sections[2], SHA_1_DIGEST).as_deref(),
+            Some("UOSDaQ7EgfSvf2+1JLK5nrFxZWU=")
+        );
+        assert_eq!(attribute(sections[2], SHA1_DIGEST), None);
+        assert_eq!(
+            attribute(sections[0], "Manifest-Version").as_deref(),
+            Some("1.0")
+        );
+    }
+
+    #[test]
+    fn test_write_attribute() {
+        let mut out = String::new();
+        let value = "ü".repeat(40);
+        write_attribute(&mut out, "Name", &value);
+        let lines: Vec<&str> = out.split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].len(), 72);
+        assert!(lines[1].starts_with(' ') && lines[1].len() <= 72);
+        assert_eq!(lines[2], "");
+        let section = section(&value, &[]);
+        assert_eq!(attribute(section.as_bytes(), "Name"), Some(value));
+    }
+
+    #[test]
+    fn test_split_sections() {
+        assert_eq!(
+            split_sections(b"A: 1\n\nName: a\nB: 2\n\nName: b\r\r"),
+            alloc::vec![
+                &b"A: 1\n\n"[..],
+                &b"Name: a\nB: 2\n\n"[..],
+                &b"Name: b\r\r"[..]
+            ]
+        );
+        assert_eq!(
+            split_sections(b"A: 1\r\n\r\nName: a"),
+            alloc::vec![&b"A: 1\r\n\r\n"[..], &b"Name: a"[..]]
+        );
+        assert!(split_sections(b"").is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+//! Counter based key derivation functions built on SHA-1.
+//!
+//! ```
+//! use sha1_smol::kdf::{concat, sp800_108_counter, x963, CounterEncoding};
+//!
+//! let mut key = [0u8; 32];
+//! sp800_108_counter(b"key", b"label", b"context", CounterEncoding::default(), &mut key);
+//! x963(b"shared secret", b"shared info", &mut key);
+//! concat(b"shared secret", b"other info", &mut key);
+//! ```
+
+use crate::hmac::HmacKey;
+use crate::{Sha1, DIGEST_LENGTH};
+
+/// Describes how the fixed input of [`sp800_108_counter`] is encoded.
+///
+/// Every block is computed as
+/// `HMAC(key, [i] || label || 0x00 || context || [L])` where `[i]` is the
+/// big-endian block counter starting at one and `[L]` the big-endian output
+/// length in bits.  The default matches the common 32 bit counter, 32 bit
+/// length layout with separator.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CounterEncoding {
+    /// The width of `[i]` in bytes (1 to 4).
+    pub counter_len: usize,
+    /// The width of `[L]` in bytes (0 to 4, zero omits the length).
+    pub length_len: usize,
+    /// Whether the zero byte between label and context is included.
+    pub separator: bool,
+}
+
+impl Default for CounterEncoding {
+    fn default() -> CounterEncoding {
+        CounterEncoding {
+            counter_len: 4,
+            length_len: 4,
+            separator: true,
+        }
+    }
+}
+
+/// Derives `out` with the NIST SP 800-108 KDF in counter mode using
+/// HMAC-SHA1 as PRF.
+///
+/// This panics if the encoding widths are out of range, or if the length of
+/// `out` cannot be represented in the configured `[i]` or `[L]` fields.
+pub fn sp800_108_counter(
+    key: &[u8],
+    label: &[u8],
+    context: &[u8],
+    encoding: CounterEncoding,
+    out: &mut [u8],
+) {
+    assert!(
+        encoding.counter_len >= 1 && encoding.counter_len <= 4,
+        "counter width must be between 1 and 4 bytes"
+    );
+    assert!(
+        encoding.length_len <= 4,
+        "length width must be at most 4 bytes"
+    );
+    let bits = out.len() as u64 * 8;
+    assert!(
+        bits >> (encoding.length_len * 8) == 0 || encoding.length_len == 0,
+        "derived key too long for length field"
+    );
+    let blocks = (out.len() as u64 + DIGEST_LENGTH as u64 - 1) / DIGEST_LENGTH as u64;
+    assert!(
+        blocks >> (encoding.counter_len * 8) == 0,
+        "derived key too long for counter field"
+    );
+
+    let prf = HmacKey::new(key);
+    let length = (bits as u32).to_be_bytes();
+    for (idx, chunk) in out.chunks_mut(DIGEST_LENGTH).enumerate() {
+        let counter = (idx as u32 + 1).to_be_bytes();
+        let mut m = prf.hmac();
+        m.update(&counter[4 - encoding.counter_len..]);
+        m.update(label);
+        if encoding.separator {
+            m.update(&[0]);
+        }
+        m.update(context);
+        m.update(&length[4 - encoding.length_len..]);
+        chunk.copy_from_slice(&m.digest().bytes()[..chunk.len()]);
+    }
+}
+
+/// Derives `out` with the ANSI X9.63 KDF using SHA-1.
+///
+/// Every block is computed as `SHA1(shared_secret || [i] || shared_info)`
+/// with `[i]` a 32 bit big-endian counter starting at one, as used by ECIES
+/// (SEC 1 section 3.6.1).
+///
+/// This panics if `out` is longer than `(2^32 - 1) * 20` bytes.
+pub fn x963(shared_secret: &[u8], shared_info: &[u8], out: &mut [u8]) {
+    assert!(
+        (out.len() as u64) <= u32::MAX as u64 * DIGEST_LENGTH as u64,
+        "derived key too long"
+    );
+    for (idx, chunk) in out.chunks_mut(DIGEST_LENGTH).enumerate() {
+        let mut m = Sha1::new();
+        m.update(shared_secret);
+        m.update(&(idx as u32 + 1).to_be_bytes());
+        m.update(shared_info);
+        chunk.copy_from_slice(&m.digest().bytes()[..chunk.len()]);
+    }
+}
+
+/// Derives `out` with the NIST SP 800-56A concatenation KDF using SHA-1.
+///
+/// Every block is computed as `SHA1([i] || z || other_info)` with `[i]` a
+/// 32 bit big-endian counter starting at one.  This is the same as [`x963`]
+/// except that the counter comes before the shared secret.
+///
+/// This panics if `out` is longer than `(2^32 - 1) * 20` bytes.
+pub fn concat(z: &[u8], other_info: &[u8], out: &mut [u8]) {
+    assert!(
+        (out.len() as u64) <= u32::MAX as u64 * DIGEST_LENGTH as u64,
+        "derived key too long"
+    );
+    for (idx, chunk) in out.chunks_mut(DIGEST_LENGTH).enumerate() {
+        let mut m = Sha1::new();
+        m.update(&(idx as u32 + 1).to_be_bytes());
+        m.update(z);
+        m.update(other_info);
+        chunk.copy_from_slice(&m.digest().bytes()[..chunk.len()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::unhex;
+
+    fn check<const N: usize>(encoding: CounterEncoding, expected: &str) {
+        let mut out = [0u8; N];
+        sp800_108_counter(b"secret key", b"label", b"context", encoding, &mut out);
+        assert_eq!(out, unhex::<N>(expected));
+    }
+
+    #[test]
+    fn test_sp800_108_counter() {
+        // reference values computed with pyca/cryptography's KBKDFHMAC and
+        // Python's hmac module
+        check::<42>(
+            CounterEncoding::default(),
+            "dcb3cc2f25f4a5c25af3bc2cd81b02a356c7180cef523ad651813a0a88f652033cb144d6cfe31ca40961",
+        );
+        check::<16>(
+            CounterEncoding {
+                counter_len: 1,
+                length_len: 2,
+                separator: true,
+            },
+            "a6d0ac33f06dd11dffe303e7fb3b2d82",
+        );
+        check::<30>(
+            CounterEncoding {
+                counter_len: 2,
+                length_len: 0,
+                separator: false,
+            },
+            "ed864551bdda6acac73b3417298226dc3602f95ff5e4c0d4d1db31e539e2",
+        );
+    }
+
+    #[test]
+    fn test_x963() {
+        // reference values computed with pyca/cryptography's X963KDF
+        let secret: [u8; 24] = unhex("1c7d7b5f0597b03d06a018466ed1a93e30ed4b04dc64ccdd");
+        let mut out = [0u8; 32];
+        x963(&secret, b"", &mut out);
+        assert_eq!(
+            out,
+            unhex::<32>("bf71dffd8f4d99223936beb46fee8ccc60439b7e52f12b8933fe82ef964ba775")
+        );
+
+        let mut out = [0u8; 50];
+        x963(b"shared secret", b"shared info", &mut out);
+        assert_eq!(
+            out,
+            unhex::<50>(
+                "1f411d3187d9ecc7ddb295f32e6535bfc31ad32a3574b787b16265bfa4350e07\
+                 51db9c254fad1342bb3ce8f3151cc2aa1b82"
+            )
+        );
+    }
+
+    #[test]
+    fn test_concat() {
+        // reference values computed with pyca/cryptography's ConcatKDFHash
+        let mut out = [0u8; 50];
+        concat(b"shared secret", b"other info", &mut out);
+        assert_eq!(
+            out,
+            unhex::<50>(
+                "375f7bd7297ebbee98a925048f14e9c09fd3b576ebc47e590074fc21a34815b1\
+                 76d2bfe9d885e3b43ce330d0548b64ac3cad"
+            )
+        );
+
+        let mut out = [0u8; 16];
+        concat(b"shared secret", b"", &mut out);
+        assert_eq!(out, unhex::<16>("067bc2586b6ab462186b07d090ef0a5d"));
+    }
+
+    #[test]
+    #[should_panic(expected = "derived key too long for counter field")]
+    fn test_counter_overflow() {
+        let encoding = CounterEncoding {
+            counter_len: 1,
+            length_len: 0,
+            separator: false,
+        };
+        sp800_108_counter(b"k", b"", b"", encoding, &mut [0u8; 256 * 20]);
+    }
+}
@@ -0,0 +1,151 @@
+//! RFC 2307 `{SHA}` and `{SSHA}` userPassword values.
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+//!
+//! LDAP directories store passwords as the scheme in braces followed by
+//! base64.  `{SHA}` is the plain SHA-1 of the password, `{SSHA}` the SHA-1
+//! of the password followed by a salt with the salt appended to the digest.
+//! The salt has to be generated by the caller, OpenLDAP uses 4 random
+//! bytes, newer tools 8 or more.
+//!
+//! ```
+//! use sha1_smol::ldap;
+//!
+//! let stored = ldap::ssha(b"secret", b"salt");
+//! assert_eq!(stored, "{SSHA}gVK8WC9YyFT1gMsQHTGCgT3sSv5zYWx0");
+//! assert!(ldap::verify(&stored, b"secret"));
+//! assert!(!ldap::verify(&stored, b"wrong"));
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::encoding::{decode_b64, encode_b64, Alphabet};
+use crate::hmac::ct_eq;
+use crate::{Sha1, DIGEST_LENGTH};
+
+/// The scheme prefix of unsalted values.
+pub const SHA_SCHEME: &str = "{SHA}";
+
+/// The scheme prefix of salted values.
+pub const SSHA_SCHEME: &str = "{SSHA}";
+
+/// Strips `scheme` off `stored` ignoring ASCII case.
+fn strip_scheme<'a>(stored: &'a str, scheme: &str) -> Option<&'a str> {
+    if stored.len() >= scheme.len()
+        && stored.as_bytes()[..scheme.len()].eq_ignore_ascii_case(scheme.as_bytes())
+    {
+        Some(&stored[scheme.len()..])
+    } else {
+        None
+    }
+}
+
+/// Returns the `{SHA}` value of `password`.
+pub fn sha(password: &[u8]) -> String {
+    let mut rv = String::from(SHA_SCHEME);
+    rv.push_str(&encode_b64(
+        &Sha1::from(password).digest().bytes(),
+        Alphabet::Standard,
+        true,
+    ));
+    rv
+}
+
+/// Returns the `{SSHA}` value of `password` with `salt`.
+pub fn ssha(password: &[u8], salt: &[u8]) -> String {
+    let mut sha1 = Sha1::from(password);
+    sha1.update(salt);
+    let mut blob = Vec::with_capacity(DIGEST_LENGTH + salt.len());
+    blob.extend_from_slice(&sha1.digest().bytes());
+    blob.extend_from_slice(salt);
+    let mut rv = String::from(SSHA_SCHEME);
+    rv.push_str(&encode_b64(&blob, Alphabet::Standard, true));
+    rv
+}
+
+/// Extracts the salt from a `{SSHA}` value.
+///
+/// Returns `None` if `stored` is not a well formed `{SSHA}` value.
+pub fn salt(stored: &str) -> Option<Vec<u8>> {
+    let blob = decode_b64(
+        strip_scheme(stored, SSHA_SCHEME)?.as_bytes(),
+        Alphabet::Standard,
+    )?;
+    if blob.len() <= DIGEST_LENGTH {
+        return None;
+    }
+    Some(blob[DIGEST_LENGTH..].to_vec())
+}
+
+/// Verifies `password` against a stored `{SHA}` or `{SSHA}` value.
+///
+/// The scheme is matched ignoring case and the digest is compared in
+/// constant time.  Values of other schemes and malformed values never
+/// verify.
+pub fn verify(stored: &str, password: &[u8]) -> bool {
+    let (encoded, salted) = if let Some(encoded) = strip_scheme(stored, SHA_SCHEME) {
+        (encoded, false)
+    } else if let Some(encoded) = strip_scheme(stored, SSHA_SCHEME) {
+        (encoded, true)
+    } else {
+        return false;
+    };
+    let blob = match decode_b64(encoded.as_bytes(), Alphabet::Standard) {
+        Some(blob) => blob,
+        None => return false,
+    };
+    if salted && blob.len() <= DIGEST_LENGTH || !salted && blob.len() != DIGEST_LENGTH {
+        return false;
+    }
+    let (expected, salt) = blob.split_at(DIGEST_LENGTH);
+    let mut sha1 = Sha1::from(password);
+    sha1.update(salt);
+    ct_eq(&sha1.digest().bytes(), expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // expected values computed with python hashlib and base64
+
+    #[test]
+    fn test_sha() {
+        assert_eq!(sha(b"secret"), "{SHA}5en6G6MezRroT3XKqkdPOmY/BfQ=");
+        assert!(verify("{SHA}5en6G6MezRroT3XKqkdPOmY/BfQ=", b"secret"));
+        assert!(verify("{sha}5en6G6MezRroT3XKqkdPOmY/BfQ=", b"secret"));
+        assert!(!verify("{SHA}5en6G6MezRroT3XKqkdPOmY/BfQ=", b"Secret"));
+        assert_eq!(salt("{SHA}5en6G6MezRroT3XKqkdPOmY/BfQ="), None);
+    }
+
+    #[test]
+    fn test_ssha() {
+        let stored = ssha(b"secret", b"salt");
+        assert_eq!(stored, "{SSHA}gVK8WC9YyFT1gMsQHTGCgT3sSv5zYWx0");
+        assert_eq!(salt(&stored).as_deref(), Some(&b"salt"[..]));
+        assert_eq!(
+            salt("{ssha}gVK8WC9YyFT1gMsQHTGCgT3sSv5zYWx0").as_deref(),
+            Some(&b"salt"[..])
+        );
+        assert!(verify(&stored, b"secret"));
+        assert!(!verify(&stored, b"secretsalt"));
+        assert!(verify(&ssha(b"", &[0xff; 16]), b""));
+    }
+
+    #[test]
+    fn test_malformed() {
+        for stored in &[
+            "",
+            "5en6G6MezRroT3XKqkdPOmY/BfQ=",
+            "{MD5}5en6G6MezRroT3XKqkdPOmY/BfQ=",
+            "{SHA}5en6G6MezRroT3XKqkdPOmY/Bf",
+            "{SHA}gVK8WC9YyFT1gMsQHTGCgT3sSv5zYWx0",
+            "{SSHA}5en6G6MezRroT3XKqkdPOmY/BfQ=",
+            "{SSHA}not base64!",
+        ] {
+            assert!(!verify(stored, b"secret"), "{}", stored);
+        }
+        assert_eq!(salt("{SSHA}5en6G6MezRroT3XKqkdPOmY/BfQ="), None);
+    }
+}
@@ -4,8 +4,21 @@
 //! following features are available and can be optionally enabled:
 //!
 //! * ``serde``: when enabled the `Digest` type can be serialized.
+//! * ``arbitrary``: when enabled `Sha1` implements `arbitrary::Arbitrary` so
+//!   fuzzers can construct hashers in arbitrary mid-stream states.
 //! * ``std``: when enabled errors from this library implement `std::error::Error`
-//!   and the `hexdigest` shortcut becomes available.
+//!   and the `hexdigest` shortcut becomes available.  This also enables the
+//!   `io` module with helpers to hash readers and files.
+//! * ``tracing``: when enabled together with ``std`` the `io` helpers emit
+//!   `tracing` spans and events describing the hashing work.
+//! * ``rayon``: when enabled `pbkdf2::derive_parallel` derives multi-block
+//!   PBKDF2 outputs on the `rayon` thread pool.
+//! * ``unicode-normalization``: when enabled the `paths` module can hash
+//!   paths in Unicode normalization form C.
+//! * ``shacal1``: when enabled the `shacal1` module exposes the SHACAL-1 block
+//!   cipher defined by the compression function.
+//! * ``collision-detection``: when enabled the `sha1dc` module provides a
+//!   hasher that detects inputs crafted with known SHA-1 collision attacks.
 //!
 //! ## Example
 //!
@@ -27,6 +40,14 @@
 //!            "2ef7bde608ce5404e97d5f042f95f89f1c232871");
 //! # }
 //! ```
+//!
+//! HMAC-SHA1 is available through [`hmac::HmacSha1`] which follows the same
+//! API.
+//!
+//! This crate used to be published as `sha1`.  The final `sha1` 0.6 release
+//! is a shim re-exporting this crate (its source lives in `legacy-shim/` in
+//! the repository), so projects on that release already use this code and
+//! can switch by changing `sha1::` imports to `sha1_smol::`.
 
 #![no_std]
 #![deny(missing_docs)]
@@ -41,18 +62,114 @@ use core::str;
 
 mod simd;
 use crate::simd::*;
+#[cfg(feature = "collision-detection")]
+mod ubc;
+
+pub mod algorithm;
+#[cfg(feature = "alloc")]
+pub mod aspnet;
+pub mod backend;
+pub mod bloom;
+pub mod cas;
+pub mod chord;
+pub mod chunking;
+pub mod cms;
+#[cfg(feature = "alloc")]
+pub mod dedup;
+pub mod der;
+pub mod dht;
+pub mod drbg;
+pub mod encoding;
+pub mod git;
+#[cfg(feature = "alloc")]
+pub mod hashring;
+pub mod hg;
+pub mod hmac;
+#[cfg(feature = "alloc")]
+pub mod htpasswd;
+pub mod id;
+pub mod ike;
+#[cfg(feature = "alloc")]
+pub mod intern;
+pub mod interop;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "alloc")]
+pub mod jar;
+pub mod kdf;
+#[cfg(feature = "alloc")]
+pub mod ldap;
+pub mod magnet;
+#[cfg(feature = "alloc")]
+pub mod manifest;
+pub mod maven;
+#[cfg(feature = "alloc")]
+pub mod merkle;
+pub mod mssql;
+pub mod multihash;
+pub mod mysql;
+#[cfg(feature = "alloc")]
+pub mod oauth1;
+pub mod openpgp;
+pub mod otp;
+#[cfg(feature = "alloc")]
+pub mod otpauth;
+pub mod paths;
+pub mod pbkdf1;
+pub mod pbkdf2;
+#[cfg(feature = "alloc")]
+pub mod pkcs12;
+pub mod prf;
+#[cfg(feature = "alloc")]
+pub mod query;
+pub mod rendezvous;
+pub mod rfc6979;
+pub mod sampling;
+#[cfg(feature = "alloc")]
+pub mod scram;
+pub mod segmented;
+#[cfg(feature = "collision-detection")]
+pub mod sha1dc;
+#[cfg(feature = "shacal1")]
+pub mod shacal1;
+pub mod skey;
+pub mod snmp;
+pub mod sri;
+pub mod srp;
+pub mod test_support;
+pub mod tls;
+#[cfg(feature = "alloc")]
+pub mod token;
+pub mod torrent;
+pub mod transcript;
+pub mod truncated;
+pub mod webhook;
+pub mod websocket;
+pub mod x509;
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+pub use crate::backend::{capabilities, Capabilities};
+pub use crate::hmac::hmac_verify_any;
+
 #[cfg(feature = "std")]
 extern crate std;
 
 /// The length of a SHA1 digest in bytes
 pub const DIGEST_LENGTH: usize = 20;
 
+/// The length of an exported `Sha1` state in bytes
+pub const STATE_LENGTH: usize = 92;
+
 /// Represents a Sha1 hash object in memory.
-#[derive(Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
+///
+/// A hasher is a fixed size value that owns no heap memory, so cloning it
+/// (or using [`Sha1::fork`]) is a constant time copy of about a hundred
+/// bytes.  This makes it cheap to hash a common prefix once and fork off
+/// hashers for every message sharing it.  Creating one with [`Sha1::new`]
+/// is just as cheap, so there is no need to pool hashers.
+#[derive(PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub struct Sha1 {
     state: Sha1State,
     blocks: Blocks,
@@ -128,6 +245,27 @@ impl Sha1 {
         rv
     }
 
+    /// Returns an independent hasher continuing from the current state.
+    ///
+    /// This is the same as `clone` but spells out the intent: the returned
+    /// hasher shares nothing with `self`, feeding more data into one does not
+    /// affect the other, and forking is O(1) regardless of how much data was
+    /// hashed so far.
+    ///
+    /// ```
+    /// let mut prefix = sha1_smol::Sha1::new();
+    /// prefix.update(b"Hello ");
+    /// let mut a = prefix.fork();
+    /// a.update(b"World!");
+    /// let mut b = prefix.fork();
+    /// b.update(b"there!");
+    /// assert_eq!(a.digest(), sha1_smol::Sha1::from("Hello World!").digest());
+    /// assert_eq!(b.digest(), sha1_smol::Sha1::from("Hello there!").digest());
+    /// ```
+    pub fn fork(&self) -> Sha1 {
+        self.clone()
+    }
+
     /// Resets the hash object to it's initial state.
     pub fn reset(&mut self) {
         self.state = DEFAULT_STATE;
@@ -137,56 +275,153 @@ impl Sha1 {
 
     /// Update hash with input data.
     pub fn update(&mut self, data: &[u8]) {
+        self.update_with(data, |_, _| {})
+    }
+
+    /// Update hash with input data and observe every completed block.
+    ///
+    /// `on_block` is called with the stream offset and the contents of each
+    /// 64 byte block right before it is compressed.  Code layering framing
+    /// or encryption on top of the hash can reuse the block accumulation of
+    /// the hasher this way instead of buffering the data a second time.
+    ///
+    /// ```
+    /// let mut m = sha1_smol::Sha1::new();
+    /// let mut offsets = vec![];
+    /// m.update_with(&[0; 100], |offset, _block| offsets.push(offset));
+    /// m.update_with(&[0; 100], |offset, _block| offsets.push(offset));
+    /// assert_eq!(offsets, [0, 64, 128]);
+    /// ```
+    pub fn update_with<F>(&mut self, data: &[u8], on_block: F)
+    where
+        F: FnMut(u64, &[u8; 64]),
+    {
+        self.update_using(data, on_block, Sha1State::process)
+    }
+
+    /// The update path shared with [`backend::Deterministic`], which passes
+    /// a different compression function.
+    fn update_using<F, C>(&mut self, data: &[u8], mut on_block: F, compress: C)
+    where
+        F: FnMut(u64, &[u8; 64]),
+        C: Fn(&mut Sha1State, &[u8; 64]),
+    {
         let len = &mut self.len;
         let state = &mut self.state;
         self.blocks.input(data, |block| {
+            on_block(*len, block);
             *len += block.len() as u64;
-            state.process(block);
+            compress(state, block);
         })
     }
 
+    /// Update hash with input data while running at most `max_blocks`
+    /// invocations of the compression function.
+    ///
+    /// Returns the number of bytes of `data` that were consumed.  This is
+    /// all of `data` unless the budget ran out, in which case the caller is
+    /// expected to continue with the remainder later.  This allows hashing
+    /// large inputs cooperatively on single threaded event loops without
+    /// blocking them for too long.
+    ///
+    /// ```
+    /// let data = [0u8; 1000];
+    /// let mut m = sha1_smol::Sha1::new();
+    /// let mut pos = 0;
+    /// while pos < data.len() {
+    ///     pos += m.update_budgeted(&data[pos..], 4);
+    ///     // yield to the event loop here
+    /// }
+    /// assert_eq!(m.digest(), sha1_smol::Sha1::from(&data[..]).digest());
+    /// ```
+    pub fn update_budgeted(&mut self, data: &[u8], max_blocks: usize) -> usize {
+        // bytes that can be taken in before block `max_blocks + 1` is full
+        let limit = max_blocks
+            .saturating_add(1)
+            .saturating_mul(64)
+            .saturating_sub(1 + self.blocks.len as usize);
+        let consumed = cmp::min(data.len(), limit);
+        self.update(&data[..consumed]);
+        consumed
+    }
+
     /// Retrieve digest result.
     pub fn digest(&self) -> Digest {
+        self.digest_using(Sha1State::process)
+    }
+
+    /// The finalization shared with [`backend::Deterministic`].
+    fn digest_using<C>(&self, compress: C) -> Digest
+    where
+        C: Fn(&mut Sha1State, &[u8; 64]),
+    {
         let mut state = self.state;
-        let bits = (self.len + (self.blocks.len as u64)) * 8;
-        let extra = [
-            (bits >> 56) as u8,
-            (bits >> 48) as u8,
-            (bits >> 40) as u8,
-            (bits >> 32) as u8,
-            (bits >> 24) as u8,
-            (bits >> 16) as u8,
-            (bits >> 8) as u8,
-            (bits >> 0) as u8,
-        ];
-        let mut last = [0; 128];
-        let blocklen = self.blocks.len as usize;
-        last[..blocklen].clone_from_slice(&self.blocks.block[..blocklen]);
-        last[blocklen] = 0x80;
+        self.blocks
+            .finish(self.len, |block| compress(&mut state, block));
+        Digest { data: state }
+    }
 
-        if blocklen < 56 {
-            last[56..64].clone_from_slice(&extra);
-            state.process(as_block(&last[0..64]));
-        } else {
-            last[120..128].clone_from_slice(&extra);
-            state.process(as_block(&last[0..64]));
-            state.process(as_block(&last[64..128]));
+    /// Exports the internal state of the hash object.
+    ///
+    /// The state can later be restored with `import_state` to continue
+    /// hashing where it left off.  The format is stable: the five chaining
+    /// words and the total number of bytes hashed so far (all big endian),
+    /// followed by the 64 byte block buffer of which only the first
+    /// `total % 64` bytes are used (the rest is zeroed).
+    pub fn export_state(&self) -> [u8; STATE_LENGTH] {
+        let mut rv = [0u8; STATE_LENGTH];
+        for (chunk, word) in rv[..20].chunks_mut(4).zip(self.state.state.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
         }
+        let total = self.len + self.blocks.len as u64;
+        rv[20..28].copy_from_slice(&total.to_be_bytes());
+        let blocklen = self.blocks.len as usize;
+        rv[28..28 + blocklen].copy_from_slice(&self.blocks.block[..blocklen]);
+        rv
+    }
 
-        Digest { data: state }
+    /// Restores a hash object from a state created by `export_state`.
+    pub fn import_state(state: &[u8]) -> Result<Sha1, StateError> {
+        if state.len() != STATE_LENGTH {
+            return Err(StateError(()));
+        }
+        let mut rv = Sha1::new();
+        for (word, chunk) in rv.state.state.iter_mut().zip(state[..20].chunks(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        let mut total = [0u8; 8];
+        total.copy_from_slice(&state[20..28]);
+        let total = u64::from_be_bytes(total);
+        if total > u64::MAX / 8 {
+            return Err(StateError(()));
+        }
+        let blocklen = (total % 64) as usize;
+        rv.len = total - blocklen as u64;
+        rv.blocks.len = blocklen as u32;
+        rv.blocks.block[..blocklen].copy_from_slice(&state[28..28 + blocklen]);
+        Ok(rv)
     }
 
     /// Retrieve the digest result as hex string directly.
     ///
     /// (The function is only available if the `alloc` feature is enabled)
     #[cfg(feature = "alloc")]
-    pub fn hexdigest(&self) -> std::string::String {
-        use std::string::ToString;
+    pub fn hexdigest(&self) -> alloc::string::String {
+        use alloc::string::ToString;
         self.digest().to_string()
     }
 }
 
 impl Digest {
+    /// Creates a digest from its 20 byte representation.
+    pub(crate) fn from_bytes(bytes: &[u8; DIGEST_LENGTH]) -> Digest {
+        let mut rv = Digest::default();
+        for (word, chunk) in rv.data.state.iter_mut().zip(bytes.chunks(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        rv
+    }
+
     /// Returns the 160 bit (20 byte) digest as a byte array.
     pub fn bytes(&self) -> [u8; DIGEST_LENGTH] {
         [
@@ -212,6 +447,93 @@ impl Digest {
             (self.data.state[4] >> 0) as u8,
         ]
     }
+
+    /// Returns the five digest words serialized as little endian.
+    ///
+    /// **This is not the SHA-1 digest.**  The standard byte order is big
+    /// endian (see `bytes`).  Some legacy firmware and vendor protocols
+    /// store the state words in native little endian order though and this
+    /// produces that layout for interoperability.
+    pub fn bytes_le_words(&self) -> [u8; DIGEST_LENGTH] {
+        let mut rv = [0u8; DIGEST_LENGTH];
+        for (chunk, word) in rv.chunks_mut(4).zip(self.data.state.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        rv
+    }
+
+    /// Reduces the digest modulo `n`.
+    ///
+    /// The whole digest is interpreted as a 160 bit big endian integer and
+    /// reduced.  Taking all 160 bits into account keeps the bias of the
+    /// result below `n / 2^160` (so below `2^-96` for any `u64`), unlike
+    /// truncating the digest to 64 bits first which is noticeably biased for
+    /// large `n` that are not a power of two.
+    ///
+    /// This panics if `n` is zero.
+    ///
+    /// ```
+    /// let digest = sha1_smol::Sha1::from("user:42").digest();
+    /// let shard = digest.mod_u64(12);
+    /// assert!(shard < 12);
+    /// ```
+    pub fn mod_u64(&self, n: u64) -> u64 {
+        assert!(n != 0, "modulus must not be zero");
+        let n = n as u128;
+        let mut r = 0u128;
+        for &word in self.data.state.iter() {
+            r = ((r << 32) | word as u128) % n;
+        }
+        r as u64
+    }
+
+    /// Reduces the digest modulo `n`, see [`Digest::mod_u64`].
+    ///
+    /// This panics if `n` is zero.
+    pub fn mod_usize(&self, n: usize) -> usize {
+        self.mod_u64(n as u64) as usize
+    }
+
+    /// Reads a hex encoded digest from a reader.
+    ///
+    /// Whitespace (including newlines) anywhere in the input is ignored so
+    /// this can be used directly on digest files or HTTP response bodies.
+    /// Anything other than exactly 40 hex digits fails with an error of kind
+    /// `InvalidData`.
+    ///
+    /// (This function is only available if the `std` feature is enabled)
+    #[cfg(feature = "std")]
+    pub fn from_hex_reader<R: std::io::Read>(mut reader: R) -> std::io::Result<Digest> {
+        use std::io;
+
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, DigestParseError(()));
+        let mut hex = [0u8; DIGEST_LENGTH * 2];
+        let mut hex_len = 0;
+        let mut buf = [0u8; 512];
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            for &c in &buf[..n] {
+                if c.is_ascii_whitespace() {
+                    continue;
+                }
+                if hex_len == hex.len() {
+                    return Err(invalid());
+                }
+                hex[hex_len] = c;
+                hex_len += 1;
+            }
+        }
+        let mut bytes = [0u8; DIGEST_LENGTH];
+        if hex_len != hex.len() || encoding::decode_hex_into(&hex, &mut bytes).is_none() {
+            return Err(invalid());
+        }
+        Ok(Digest::from_bytes(&bytes))
+    }
 }
 
 impl Blocks {
@@ -242,6 +564,38 @@ impl Blocks {
             }
         }
     }
+
+    /// Feeds the padded final blocks of a message to `f`, given the length
+    /// of the message without the buffered bytes.
+    fn finish<F>(&self, len: u64, mut f: F)
+    where
+        F: FnMut(&[u8; 64]),
+    {
+        let bits = (len + (self.len as u64)) * 8;
+        let extra = [
+            (bits >> 56) as u8,
+            (bits >> 48) as u8,
+            (bits >> 40) as u8,
+            (bits >> 32) as u8,
+            (bits >> 24) as u8,
+            (bits >> 16) as u8,
+            (bits >> 8) as u8,
+            (bits >> 0) as u8,
+        ];
+        let mut last = [0; 128];
+        let blocklen = self.len as usize;
+        last[..blocklen].clone_from_slice(&self.block[..blocklen]);
+        last[blocklen] = 0x80;
+
+        if blocklen < 56 {
+            last[56..64].clone_from_slice(&extra);
+            f(as_block(&last[0..64]));
+        } else {
+            last[120..128].clone_from_slice(&extra);
+            f(as_block(&last[0..64]));
+            f(as_block(&last[64..128]));
+        }
+    }
 }
 
 // Round key constants
@@ -424,7 +778,11 @@ fn sha1rnds4m(abcd: u32x4, msg: u32x4) -> u32x4 {
 }
 
 impl Sha1State {
-    fn process(&mut self, block: &[u8; 64]) {
+    /// Runs the 80 rounds over `block` starting from the state and returns
+    /// the working variables without adding them to the state.  This is the
+    /// SHACAL-1 encryption of the state under the key `block`.
+    #[inline(always)]
+    fn rounds(&self, block: &[u8; 64]) -> [u32; 5] {
         let mut words = [0u32; 16];
         for (i, word) in words.iter_mut().enumerate() {
             let off = i * 4;
@@ -496,7 +854,19 @@ impl Sha1State {
 
         let e = sha1_first(h1).rotate_left(30);
         let u32x4(a, b, c, d) = h0;
+        [a, b, c, d, e]
+    }
+
+    fn process(&mut self, block: &[u8; 64]) {
+        self.process_portable(block);
+    }
 
+    /// The portable compression function.  [`backend::Deterministic`]
+    /// calls this directly so it is unaffected by the backend `process`
+    /// dispatches to.
+    #[inline(always)]
+    fn process_portable(&mut self, block: &[u8; 64]) {
+        let [a, b, c, d, e] = self.rounds(block);
         self.state[0] = self.state[0].wrapping_add(a);
         self.state[1] = self.state[1].wrapping_add(b);
         self.state[2] = self.state[2].wrapping_add(c);
@@ -536,6 +906,32 @@ impl Clone for Blocks {
     fn clone(&self) -> Blocks {
         Blocks { ..*self }
     }
+
+    fn clone_from(&mut self, source: &Blocks) {
+        self.len = source.len;
+        self.block.copy_from_slice(&source.block);
+    }
+}
+
+impl Clone for Sha1 {
+    fn clone(&self) -> Sha1 {
+        Sha1 {
+            state: self.state,
+            blocks: self.blocks.clone(),
+            len: self.len,
+        }
+    }
+
+    /// Copies the state of `source` into `self` in place.
+    ///
+    /// Unlike `*self = source.clone()` this does not construct a temporary
+    /// hasher which makes it the cheapest way to rewind a hasher to a
+    /// previously saved prefix in a loop.
+    fn clone_from(&mut self, source: &Sha1) {
+        self.state = source.state;
+        self.blocks.clone_from(&source.blocks);
+        self.len = source.len;
+    }
 }
 
 /// Indicates that a digest couldn't be parsed.
@@ -555,6 +951,23 @@ impl std::error::Error for DigestParseError {
     }
 }
 
+/// Indicates that an exported hasher state couldn't be imported.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct StateError(());
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "not a valid sha1 state")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StateError {
+    fn description(&self) -> &str {
+        "not a valid sha1 state"
+    }
+}
+
 impl str::FromStr for Digest {
     type Err = DigestParseError;
 
@@ -640,6 +1053,32 @@ impl<'de> serde::de::Deserialize<'de> for Digest {
     }
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Sha1 {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Sha1> {
+        let mut state = [0u32; 5];
+        for word in state.iter_mut() {
+            *word = u.arbitrary()?;
+        }
+        // only whole blocks are counted in `len` and the total length in
+        // bits has to fit into the u64 that is appended on finalization.
+        // Half of that range is left free for the data a fuzzer feeds in
+        // afterwards.
+        let processed_blocks = u.int_in_range(0..=(u64::MAX / 8 / 64 / 2))?;
+        let buffered = u.int_in_range(0..=63u32)?;
+        let mut block = [0; 64];
+        u.fill_buffer(&mut block[..buffered as usize])?;
+        Ok(Sha1 {
+            state: Sha1State { state },
+            len: processed_blocks * 64,
+            blocks: Blocks {
+                len: buffered,
+                block,
+            },
+        })
+    }
+}
+
 #[rustfmt::skip]
 #[cfg(test)]
 mod tests {
@@ -653,6 +1092,7 @@ mod tests {
     use crate::Sha1;
 
     #[test]
+    #[allow(clippy::needless_borrow)]
     fn test_simple() {
         let mut m = Sha1::new();
 
@@ -698,6 +1138,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::borrow_deref_ref)]
     fn test_multiple_updates() {
         let mut m = Sha1::new();
 
@@ -751,6 +1192,118 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_mod_u64() {
+        // 0x2ef7bde608ce5404e97d5f042f95f89f1c232871 reduced with python
+        let d = Sha1::from("Hello World!").digest();
+        assert_eq!(d.mod_u64(1), 0);
+        assert_eq!(d.mod_u64(1000), 665);
+        assert_eq!(d.mod_u64(u64::MAX), 4063457031935771995);
+        assert_eq!(d.mod_u64(1 << 32), 0x1c232871);
+        assert_eq!(d.mod_usize(1000), 665);
+    }
+
+    #[test]
+    fn test_update_with() {
+        let data: std::vec::Vec<u8> = (0..300u32).map(|x| x as u8).collect();
+        let mut m = Sha1::new();
+        let mut seen = std::vec::Vec::new();
+        for chunk in data.chunks(37) {
+            m.update_with(chunk, |offset, block| {
+                assert_eq!(offset as usize, seen.len());
+                seen.extend_from_slice(block);
+            });
+        }
+        assert_eq!(seen[..], data[..256]);
+        assert_eq!(m.digest(), Sha1::from(&data).digest());
+    }
+
+    #[test]
+    fn test_update_budgeted() {
+        let data: std::vec::Vec<u8> = (0..1000u32).map(|x| x as u8).collect();
+        for &budget in &[0, 1, 2, 7] {
+            let mut m = Sha1::new();
+            m.update(&data[..10]);
+            let mut pos = 10;
+            let mut rounds = 0;
+            while pos < data.len() {
+                let before = m.len;
+                let n = m.update_budgeted(&data[pos..], budget);
+                assert!((m.len - before) / 64 <= budget as u64);
+                pos += n;
+                rounds += 1;
+                if budget == 0 {
+                    assert_eq!(n, 53);
+                    break;
+                }
+            }
+            if budget > 0 {
+                assert!(rounds > 1);
+                assert_eq!(m.digest(), Sha1::from(&data).digest());
+            }
+        }
+    }
+
+    #[test]
+    fn test_fork_and_clone_from() {
+        let mut prefix = Sha1::new();
+        prefix.update(&[b'p'; 100]);
+        let mut m = Sha1::new();
+        m.update(b"unrelated data");
+        for suffix in &[&b""[..], b"a", &[b'x'; 200][..]] {
+            m.clone_from(&prefix);
+            assert!(m == prefix);
+            m.update(suffix);
+            let mut f = prefix.fork();
+            f.update(suffix);
+            assert_eq!(m.digest(), f.digest());
+            let mut data = std::vec![b'p'; 100];
+            data.extend_from_slice(suffix);
+            assert_eq!(m.digest(), Sha1::from(&data).digest());
+        }
+    }
+
+    #[test]
+    fn test_bytes_le_words() {
+        let d = Sha1::from("Hello World!").digest();
+        let le = d.bytes_le_words();
+        let be = d.bytes();
+        assert_eq!(&le[..4], &[0xe6, 0xbd, 0xf7, 0x2e]);
+        for (l, b) in le.chunks(4).zip(be.chunks(4)) {
+            assert_eq!(l, &[b[3], b[2], b[1], b[0]]);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_from_hex_reader() {
+        let d = Sha1::from("Hello World!").digest();
+        let input = b"  2ef7bde6 08ce5404\ne97d5f042f95f89f\r\n1c232871\n";
+        assert_eq!(crate::Digest::from_hex_reader(&input[..]).unwrap(), d);
+        let upper = b"2EF7BDE608CE5404E97D5F042F95F89F1C232871";
+        assert_eq!(crate::Digest::from_hex_reader(&upper[..]).unwrap(), d);
+
+        for bad in &[&b"2ef7bde6"[..], &b"2ef7bde608ce5404e97d5f042f95f89f1c2328710"[..],
+                     &b"2ef7bde608ce5404e97d5f042f95f89f1c23287g"[..]] {
+            let err = crate::Digest::from_hex_reader(*bad).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_export_import_state() {
+        let mut m = Sha1::new();
+        m.update(b"The quick brown fox jumps over the lazy dog. The quick brown fox");
+        m.update(b" jumps over the lazy dog.");
+        let state = m.export_state();
+        let mut resumed = Sha1::import_state(&state[..]).unwrap();
+        assert!(resumed == m);
+        m.update(b" Again.");
+        resumed.update(b" Again.");
+        assert_eq!(resumed.digest(), m.digest());
+        assert!(Sha1::import_state(&state[..20]).is_err());
+    }
+
     #[test]
     #[cfg(feature="std")]
     fn test_parse() {
@@ -789,3 +1342,33 @@ mod serde_tests {
         assert_eq!(y.to_string(), "2ef7bde608ce5404e97d5f042f95f89f1c232871");
     }
 }
+
+#[rustfmt::skip]
+#[cfg(all(test, feature="arbitrary"))]
+mod arbitrary_tests {
+    extern crate std;
+    extern crate arbitrary;
+
+    use self::arbitrary::{Arbitrary, Unstructured};
+
+    use crate::Sha1;
+
+    #[test]
+    fn test_arbitrary_state() {
+        let raw = [0xffu8; 128];
+        let mut u = Unstructured::new(&raw);
+        let mut m = Sha1::arbitrary(&mut u).unwrap();
+        assert!(m.blocks.len < 64);
+        assert_eq!(m.len % 64, 0);
+        let before = m.digest();
+        m.update(b"more data");
+        assert_ne!(m.digest(), before);
+
+        // the largest generated state still leaves room for more data
+        let mut u = Unstructured::new(&raw);
+        let mut m = Sha1::arbitrary(&mut u).unwrap();
+        assert_eq!(m.len, u64::MAX / 8 / 64 / 2 * 64);
+        m.update(&[0; 1 << 16]);
+        m.digest();
+    }
+}
@@ -0,0 +1,220 @@
+//! `urn:sha1:` identifiers as used in magnet links.
+//!
+//! Gnutella and other P2P networks identify files by a URN carrying the
+//! SHA-1 of the contents as 32 characters of RFC 4648 base32.  In magnet
+//! links such a URN is the value of the `xt` (exact topic) parameter.
+//!
+//! ```
+//! use sha1_smol::magnet::Urn;
+//!
+//! let urn = Urn(sha1_smol::Sha1::from("Hello World!").digest());
+//! let mut buf = [0u8; 41];
+//! assert_eq!(urn.encode_into(&mut buf), "urn:sha1:F3333ZQIZZKAJ2L5L4CC7FPYT4OCGKDR");
+//!
+//! let parsed: Urn = "urn:sha1:F3333ZQIZZKAJ2L5L4CC7FPYT4OCGKDR".parse().unwrap();
+//! assert_eq!(parsed, urn);
+//! ```
+
+use core::{fmt, str};
+
+use crate::encoding::{decode_hex_into, BASE32};
+use crate::{Digest, DigestParseError, DIGEST_LENGTH};
+
+/// The prefix of a SHA-1 URN.
+pub const URN_PREFIX: &str = "urn:sha1:";
+
+/// The length of a SHA-1 URN including the prefix.
+pub const URN_LENGTH: usize = URN_PREFIX.len() + 32;
+
+/// A SHA-1 URN.
+///
+/// Formatting produces uppercase base32, parsing accepts either case and a
+/// prefix in any case.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Urn(pub Digest);
+
+impl Urn {
+    /// Writes the URN into a buffer and returns it as string slice.
+    pub fn encode_into<'a>(&self, out: &'a mut [u8; URN_LENGTH]) -> &'a str {
+        out[..URN_PREFIX.len()].copy_from_slice(URN_PREFIX.as_bytes());
+        let bytes = self.0.bytes();
+        for (group, dst) in bytes.chunks(5).zip(out[URN_PREFIX.len()..].chunks_mut(8)) {
+            let value = group.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+            for (idx, c) in dst.iter_mut().enumerate() {
+                *c = BASE32[(value >> (35 - idx * 5)) as usize & 31];
+            }
+        }
+        str::from_utf8(out).unwrap()
+    }
+
+    /// Extracts the SHA-1 URN from the `xt` parameter of a magnet link.
+    ///
+    /// Returns `None` if the link has no SHA-1 exact topic.  Numbered
+    /// parameters such as `xt.1` are considered as well and values are
+    /// percent-decoded before they are matched.
+    ///
+    /// ```
+    /// use sha1_smol::magnet::Urn;
+    ///
+    /// let link = "magnet:?xt=urn:sha1:F3333ZQIZZKAJ2L5L4CC7FPYT4OCGKDR&dn=hello.txt";
+    /// let urn = Urn::from_magnet(link).unwrap();
+    /// assert_eq!(urn.0, sha1_smol::Sha1::from("Hello World!").digest());
+    /// ```
+    pub fn from_magnet(link: &str) -> Option<Urn> {
+        let query = link.strip_prefix("magnet:?")?;
+        query.split('&').find_map(|param| {
+            let (key, value) = param.split_at(param.find('=')?);
+            if key == "xt" || key.starts_with("xt.") {
+                let mut buf = [0u8; URN_LENGTH];
+                percent_decode_into(&value[1..], &mut buf)?.parse().ok()
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Percent-decodes `value` into `out`.
+///
+/// Returns `None` if the value is malformed or too long to be a URN.
+fn percent_decode_into<'a>(value: &str, out: &'a mut [u8; URN_LENGTH]) -> Option<&'a str> {
+    let mut bytes = value.as_bytes();
+    let mut len = 0;
+    while let Some((&c, rest)) = bytes.split_first() {
+        let byte = if c == b'%' {
+            let mut byte = [0u8];
+            decode_hex_into(rest.get(..2)?, &mut byte)?;
+            bytes = &rest[2..];
+            byte[0]
+        } else {
+            bytes = rest;
+            c
+        };
+        *out.get_mut(len)? = byte;
+        len += 1;
+    }
+    str::from_utf8(&out[..len]).ok()
+}
+
+impl From<Digest> for Urn {
+    fn from(digest: Digest) -> Urn {
+        Urn(digest)
+    }
+}
+
+impl From<Urn> for Digest {
+    fn from(urn: Urn) -> Digest {
+        urn.0
+    }
+}
+
+impl fmt::Display for Urn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut buf = [0u8; URN_LENGTH];
+        f.write_str(self.encode_into(&mut buf))
+    }
+}
+
+impl str::FromStr for Urn {
+    type Err = DigestParseError;
+
+    fn from_str(s: &str) -> Result<Urn, DigestParseError> {
+        let s = s.as_bytes();
+        if s.len() != URN_LENGTH
+            || !s[..URN_PREFIX.len()].eq_ignore_ascii_case(URN_PREFIX.as_bytes())
+        {
+            return Err(DigestParseError(()));
+        }
+        let mut bytes = [0u8; DIGEST_LENGTH];
+        for (group, dst) in s[URN_PREFIX.len()..].chunks(8).zip(bytes.chunks_mut(5)) {
+            let mut value = 0u64;
+            for &c in group {
+                let c = c.to_ascii_uppercase();
+                let digit = BASE32
+                    .iter()
+                    .position(|&x| x == c)
+                    .ok_or(DigestParseError(()))?;
+                value = (value << 5) | digit as u64;
+            }
+            for (idx, b) in dst.iter_mut().enumerate() {
+                *b = (value >> (32 - idx * 8)) as u8;
+            }
+        }
+        Ok(Urn(Digest::from_bytes(&bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha1;
+
+    #[test]
+    fn test_encode() {
+        // reference values computed with python's base64.b32encode
+        let mut buf = [0u8; URN_LENGTH];
+        assert_eq!(
+            Urn(Sha1::new().digest()).encode_into(&mut buf),
+            "urn:sha1:3I42H3S6NNFQ2MSVX7XZKYAYSCX5QBYJ"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let expected = Urn(Sha1::from("Hello World!").digest());
+        for s in &[
+            "urn:sha1:F3333ZQIZZKAJ2L5L4CC7FPYT4OCGKDR",
+            "URN:SHA1:f3333zqizzkaj2l5l4cc7fpyt4ocgkdr",
+        ] {
+            assert_eq!(s.parse::<Urn>(), Ok(expected));
+        }
+        for s in &[
+            "urn:sha1:F3333ZQIZZKAJ2L5L4CC7FPYT4OCGKD",
+            "urn:sha1:F3333ZQIZZKAJ2L5L4CC7FPYT4OCGKD1",
+            "urn:btih:F3333ZQIZZKAJ2L5L4CC7FPYT4OCGKDR",
+            "2ef7bde608ce5404e97d5f042f95f89f1c232871",
+        ] {
+            assert!(s.parse::<Urn>().is_err(), "{}", s);
+        }
+
+        let data = [0xa5u8; 20];
+        let urn = Urn(Digest::from_bytes(&data));
+        let mut buf = [0u8; URN_LENGTH];
+        assert_eq!(urn.encode_into(&mut buf).parse(), Ok(urn));
+    }
+
+    #[test]
+    fn test_from_magnet() {
+        let expected = Some(Urn(Sha1::from("Hello World!").digest()));
+        assert_eq!(
+            Urn::from_magnet("magnet:?xt=urn:sha1:F3333ZQIZZKAJ2L5L4CC7FPYT4OCGKDR"),
+            expected
+        );
+        assert_eq!(
+            Urn::from_magnet(
+                "magnet:?dn=x&xt.1=urn:btih:c12fe1c06bba254a9dc9f519b335aa7c1367a88a\
+                 &xt.2=urn:sha1:F3333ZQIZZKAJ2L5L4CC7FPYT4OCGKDR"
+            ),
+            expected
+        );
+        assert_eq!(
+            Urn::from_magnet("magnet:?dn=x&xs=urn:sha1:F3333ZQIZZKAJ2L5L4CC7FPYT4OCGKDR"),
+            None
+        );
+        assert_eq!(
+            Urn::from_magnet("urn:sha1:F3333ZQIZZKAJ2L5L4CC7FPYT4OCGKDR"),
+            None
+        );
+        assert_eq!(
+            Urn::from_magnet(
+                "magnet:?xt.1=urn%3Abtih%3Ac12fe1c06bba254a9dc9f519b335aa7c1367a88a\
+                 &xt.2=urn%3Asha1%3AF3333ZQIZZKAJ2L5L4CC7FPYT4OCGKDR"
+            ),
+            expected
+        );
+        assert_eq!(
+            Urn::from_magnet("magnet:?xt=urn%3asha1%3AF3333ZQIZZKAJ2L5L4CC7FPYT4OCGKD%"),
+            None
+        );
+    }
+}
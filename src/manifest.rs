@@ -0,0 +1,397 @@
+//! `sha1sum` checksum manifests.
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+//!
+//! Parses and writes the lines produced by GNU coreutils' `sha1sum`, both
+//! the default form and the BSD tag form of `sha1sum --tag`:
+//!
+//! ```text
+//! 2ef7bde608ce5404e97d5f042f95f89f1c232871  hello.txt
+//! 2ef7bde608ce5404e97d5f042f95f89f1c232871 *hello.bin
+//! SHA1 (hello.txt) = 2ef7bde608ce5404e97d5f042f95f89f1c232871
+//! ```
+//!
+//! File names containing a backslash, a newline or a carriage return are
+//! escaped and the line is prefixed with a backslash, as coreutils does.
+//! The binary marker (`*`) is preserved but has no effect on hashing.
+//!
+//! ```
+//! use sha1_smol::manifest::{self, Format};
+//!
+//! let entries = manifest::parse("2ef7bde608ce5404e97d5f042f95f89f1c232871  hello.txt\n").unwrap();
+//! assert_eq!(entries[0].path, "hello.txt");
+//! assert_eq!(
+//!     entries[0].to_line(Format::Tag),
+//!     "SHA1 (hello.txt) = 2ef7bde608ce5404e97d5f042f95f89f1c232871"
+//! );
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+use crate::Digest;
+
+/// Indicates that a manifest couldn't be parsed.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum ManifestError {
+    /// The line with the given (one based) number is not a checksum line.
+    MalformedLine(usize),
+    /// The line with the given number is a tag line for another algorithm.
+    UnsupportedAlgorithm(usize),
+}
+
+impl ManifestError {
+    /// Returns the one based number of the offending line.
+    pub fn line(&self) -> usize {
+        match *self {
+            ManifestError::MalformedLine(line) | ManifestError::UnsupportedAlgorithm(line) => line,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match *self {
+            ManifestError::MalformedLine(_) => "malformed checksum line",
+            ManifestError::UnsupportedAlgorithm(_) => "unsupported checksum algorithm",
+        }
+    }
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} on line {}", self.as_str(), self.line())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ManifestError {
+    fn description(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// The line format of a manifest.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum Format {
+    /// `<hex>  <path>` or `<hex> *<path>`, the `sha1sum` default.
+    Default,
+    /// `SHA1 (<path>) = <hex>`, as written by `sha1sum --tag`.
+    Tag,
+}
+
+/// A single entry of a manifest.
+#[derive(Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct Entry {
+    /// The expected digest.
+    pub digest: Digest,
+    /// The path of the file, unescaped.
+    pub path: String,
+    /// Whether the file was marked as binary (`*`).
+    pub binary: bool,
+}
+
+fn needs_escaping(path: &str) -> bool {
+    path.contains(|c| c == '\\' || c == '\n' || c == '\r')
+}
+
+fn escape_into(path: &str, out: &mut String) {
+    for c in path.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn unescape(path: &str) -> Option<String> {
+    let mut rv = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            rv.push(match chars.next()? {
+                '\\' => '\\',
+                'n' => '\n',
+                'r' => '\r',
+                _ => return None,
+            });
+        } else {
+            rv.push(c);
+        }
+    }
+    Some(rv)
+}
+
+impl Entry {
+    /// Formats the entry as a manifest line without line terminator.
+    pub fn to_line(&self, format: Format) -> String {
+        let mut rv = String::new();
+        let mut path = String::new();
+        if needs_escaping(&self.path) {
+            rv.push('\\');
+            escape_into(&self.path, &mut path);
+        } else {
+            path.push_str(&self.path);
+        }
+        match format {
+            Format::Default => {
+                let marker = if self.binary { '*' } else { ' ' };
+                write!(rv, "{} {}{}", self.digest, marker, path).unwrap();
+            }
+            Format::Tag => write!(rv, "SHA1 ({}) = {}", path, self.digest).unwrap(),
+        }
+        rv
+    }
+
+    /// Checks the contents of `reader` against the entry.
+    ///
+    /// (This function is only available if the `std` feature is enabled)
+    #[cfg(feature = "std")]
+    pub fn verify_reader<R: std::io::Read>(&self, reader: R) -> std::io::Result<bool> {
+        Ok(crate::io::hash_reader(reader)? == self.digest)
+    }
+
+    /// Checks the file the entry refers to, relative to `base`.
+    ///
+    /// Absolute paths in the manifest are used as they are.
+    ///
+    /// (This function is only available if the `std` feature is enabled)
+    #[cfg(feature = "std")]
+    pub fn verify_file<P: AsRef<std::path::Path>>(&self, base: P) -> std::io::Result<bool> {
+        Ok(crate::io::hash_file(base.as_ref().join(&self.path))? == self.digest)
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_line(Format::Default))
+    }
+}
+
+/// Parses a single manifest line in either format.
+///
+/// `line` must not include the line terminator.  Errors report line
+/// number 1.
+pub fn parse_line(line: &str) -> Result<Entry, ManifestError> {
+    parse_numbered_line(line, 1)
+}
+
+fn parse_numbered_line(line: &str, number: usize) -> Result<Entry, ManifestError> {
+    let malformed = ManifestError::MalformedLine(number);
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let unescape_path = |path: &str| {
+        if escaped {
+            unescape(path).ok_or(malformed)
+        } else {
+            Ok(String::from(path))
+        }
+    };
+
+    if let Some(rest) = line.strip_prefix("SHA1 (") {
+        let idx = rest.rfind(") = ").ok_or(malformed)?;
+        let digest = rest[idx + 4..].parse().map_err(|_| malformed)?;
+        return Ok(Entry {
+            digest,
+            path: unescape_path(&rest[..idx])?,
+            binary: false,
+        });
+    }
+    if let Some(idx) = line.find(" (") {
+        let algorithm = &line[..idx];
+        if !algorithm.is_empty()
+            && algorithm
+                .bytes()
+                .all(|c| c.is_ascii_alphanumeric() || c == b'-')
+            && line.contains(") = ")
+        {
+            return Err(ManifestError::UnsupportedAlgorithm(number));
+        }
+    }
+
+    if line.len() < 42 || !line.is_char_boundary(40) {
+        return Err(malformed);
+    }
+    let digest = line[..40].parse().map_err(|_| malformed)?;
+    let binary = match &line.as_bytes()[40..42] {
+        b"  " => false,
+        b" *" => true,
+        _ => return Err(malformed),
+    };
+    let path = &line[42..];
+    if path.is_empty() {
+        return Err(malformed);
+    }
+    Ok(Entry {
+        digest,
+        path: unescape_path(path)?,
+        binary,
+    })
+}
+
+/// Parses a manifest.
+///
+/// Empty lines are skipped, both `\n` and `\r\n` terminate lines.
+pub fn parse(manifest: &str) -> Result<Vec<Entry>, ManifestError> {
+    manifest
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(idx, line)| parse_numbered_line(line, idx + 1))
+        .collect()
+}
+
+/// Writes entries as a manifest, one line per entry.
+pub fn write(entries: &[Entry], format: Format) -> String {
+    let mut rv = String::new();
+    for entry in entries {
+        rv.push_str(&entry.to_line(format));
+        rv.push('\n');
+    }
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEX: &str = "2ef7bde608ce5404e97d5f042f95f89f1c232871";
+
+    fn entry(path: &str, binary: bool) -> Entry {
+        Entry {
+            digest: HEX.parse().unwrap(),
+            path: String::from(path),
+            binary,
+        }
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let cases = [
+            (
+                "2ef7bde608ce5404e97d5f042f95f89f1c232871  hello.txt",
+                entry("hello.txt", false),
+            ),
+            (
+                "2EF7BDE608CE5404E97D5F042F95F89F1C232871 *hello.bin",
+                entry("hello.bin", true),
+            ),
+            (
+                "2ef7bde608ce5404e97d5f042f95f89f1c232871   leading space",
+                entry(" leading space", false),
+            ),
+            (
+                "SHA1 (hello.txt) = 2ef7bde608ce5404e97d5f042f95f89f1c232871",
+                entry("hello.txt", false),
+            ),
+            (
+                "SHA1 (a) = (b)) = 2ef7bde608ce5404e97d5f042f95f89f1c232871",
+                entry("a) = (b)", false),
+            ),
+            (
+                "\\2ef7bde608ce5404e97d5f042f95f89f1c232871  a\\\\b\\nc",
+                entry("a\\b\nc", false),
+            ),
+            (
+                "\\SHA1 (a\\rb) = 2ef7bde608ce5404e97d5f042f95f89f1c232871",
+                entry("a\rb", false),
+            ),
+            (
+                "2ef7bde608ce5404e97d5f042f95f89f1c232871  a\\b",
+                entry("a\\b", false),
+            ),
+        ];
+        for (line, expected) in cases.iter() {
+            assert_eq!(parse_line(line).as_ref(), Ok(expected), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn test_parse_line_rejects() {
+        for line in &[
+            "2ef7bde608ce5404e97d5f042f95f89f1c232871",
+            "2ef7bde608ce5404e97d5f042f95f89f1c232871  ",
+            "2ef7bde608ce5404e97d5f042f95f89f1c232871 hello.txt",
+            "2ef7bde608ce5404e97d5f042f95f89f1c23287  hello.txt",
+            "\\2ef7bde608ce5404e97d5f042f95f89f1c232871  a\\x",
+            "SHA1 (hello.txt) = 2ef7bde6",
+            "SHA1 (hello.txt)= 2ef7bde608ce5404e97d5f042f95f89f1c232871",
+            "ä",
+        ] {
+            assert_eq!(
+                parse_line(line),
+                Err(ManifestError::MalformedLine(1)),
+                "{:?}",
+                line
+            );
+        }
+        assert_eq!(
+            parse_line("MD5 (hello.txt) = d41d8cd98f00b204e9800998ecf8427e"),
+            Err(ManifestError::UnsupportedAlgorithm(1))
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let manifest = "2ef7bde608ce5404e97d5f042f95f89f1c232871  a\r\n\nSHA1 (b) = 2ef7bde608ce5404e97d5f042f95f89f1c232871\n";
+        assert_eq!(
+            parse(manifest),
+            Ok(alloc::vec![entry("a", false), entry("b", false)])
+        );
+        assert_eq!(
+            parse("2ef7bde608ce5404e97d5f042f95f89f1c232871  a\n\nbogus\n"),
+            Err(ManifestError::MalformedLine(3))
+        );
+    }
+
+    #[test]
+    fn test_write() {
+        let entries = [
+            entry("a", false),
+            entry("b c", true),
+            entry("x\\y\nz", false),
+        ];
+        let manifest = write(&entries, Format::Default);
+        assert_eq!(
+            manifest,
+            "2ef7bde608ce5404e97d5f042f95f89f1c232871  a\n\
+             2ef7bde608ce5404e97d5f042f95f89f1c232871 *b c\n\
+             \\2ef7bde608ce5404e97d5f042f95f89f1c232871  x\\\\y\\nz\n"
+        );
+        assert_eq!(parse(&manifest).unwrap(), entries);
+
+        let manifest = write(&entries, Format::Tag);
+        assert_eq!(
+            manifest,
+            "SHA1 (a) = 2ef7bde608ce5404e97d5f042f95f89f1c232871\n\
+             SHA1 (b c) = 2ef7bde608ce5404e97d5f042f95f89f1c232871\n\
+             \\SHA1 (x\\\\y\\nz) = 2ef7bde608ce5404e97d5f042f95f89f1c232871\n"
+        );
+        let mut expected = entries.clone();
+        expected[1].binary = false;
+        assert_eq!(parse(&manifest).unwrap(), expected);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_verify() {
+        let entry = entry("hello.txt", false);
+        assert!(entry.verify_reader(&b"Hello World!"[..]).unwrap());
+        assert!(!entry.verify_reader(&b"Hello World"[..]).unwrap());
+
+        let dir =
+            std::env::temp_dir().join(std::format!("sha1-smol-manifest-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("hello.txt"), "Hello World!").unwrap();
+        assert!(entry.verify_file(&dir).unwrap());
+        let missing = Entry {
+            path: String::from("missing.txt"),
+            ..entry
+        };
+        assert!(missing.verify_file(&dir).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+//! Maven `.sha1` checksum files.
+//!
+//! Maven repositories publish the SHA-1 of every artifact in a sidecar file
+//! named after the artifact with `.sha1` appended.  Over the years
+//! different tools wrote different contents into these files, so parsing
+//! accepts all the variants found in the wild:
+//!
+//! * bare hex, with or without a trailing newline,
+//! * hex followed by the file name as written by `sha1sum`,
+//! * the file name followed by the hex (`file.jar: <hex>` or
+//!   `file.jar <hex>`),
+//! * the OpenSSL and BSD tag forms (`SHA1(file.jar)= <hex>`),
+//! * hex split into groups by whitespace.
+//!
+//! Sidecars are always written canonically as bare lowercase hex without a
+//! newline, which is what Maven itself publishes.
+//!
+//! ```
+//! use sha1_smol::maven::parse_sidecar;
+//!
+//! let expected = "2ef7bde608ce5404e97d5f042f95f89f1c232871".parse().ok();
+//! assert_eq!(parse_sidecar("2ef7bde608ce5404e97d5f042f95f89f1c232871\n"), expected);
+//! assert_eq!(
+//!     parse_sidecar("2EF7BDE608CE5404E97D5F042F95F89F1C232871  foo-1.0.jar"),
+//!     expected
+//! );
+//! ```
+
+use crate::encoding::decode_hex_into;
+use crate::{Digest, DIGEST_LENGTH};
+
+/// Decodes `token` if it is exactly one digest of hex.
+fn parse_hex(token: &str) -> Option<Digest> {
+    let mut bytes = [0u8; DIGEST_LENGTH];
+    if token.len() != DIGEST_LENGTH * 2 {
+        return None;
+    }
+    decode_hex_into(token.as_bytes(), &mut bytes)?;
+    Some(Digest::from_bytes(&bytes))
+}
+
+/// Parses the contents of a `.sha1` sidecar file.
+///
+/// Returns `None` if no digest could be found.
+pub fn parse_sidecar(contents: &str) -> Option<Digest> {
+    let contents = contents.trim_start_matches('\u{feff}').trim();
+    if let Some(idx) = contents.rfind('=') {
+        if let Some(digest) = parse_hex(contents[idx + 1..].trim()) {
+            return Some(digest);
+        }
+    }
+    if let Some(digest) = contents.split_whitespace().next().and_then(parse_hex) {
+        return Some(digest);
+    }
+    if let Some(digest) = contents.split_whitespace().last().and_then(parse_hex) {
+        return Some(digest);
+    }
+
+    // hex split into groups
+    let mut hex = [0u8; DIGEST_LENGTH * 2];
+    let mut len = 0;
+    for c in contents.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        *hex.get_mut(len)? = c;
+        len += 1;
+    }
+    parse_hex(core::str::from_utf8(&hex[..len]).ok()?)
+}
+
+/// Returns the path of the sidecar of `artifact`.
+///
+/// (This function is only available if the `std` feature is enabled)
+#[cfg(feature = "std")]
+pub fn sidecar_path<P: AsRef<std::path::Path>>(artifact: P) -> std::path::PathBuf {
+    let mut rv = std::ffi::OsString::from(artifact.as_ref().as_os_str());
+    rv.push(".sha1");
+    rv.into()
+}
+
+/// Hashes `artifact` and writes its canonical sidecar next to it.
+///
+/// Returns the digest of the artifact.
+///
+/// (This function is only available if the `std` feature is enabled)
+#[cfg(feature = "std")]
+pub fn write_sidecar<P: AsRef<std::path::Path>>(artifact: P) -> std::io::Result<Digest> {
+    let artifact = artifact.as_ref();
+    let digest = crate::io::hash_file(artifact)?;
+    std::fs::write(sidecar_path(artifact), std::format!("{}", digest))?;
+    Ok(digest)
+}
+
+/// Verifies `artifact` against the checksum in `sidecar`.
+///
+/// Returns `true` if the artifact matches.  A sidecar without a digest is
+/// reported as an `InvalidData` error.
+///
+/// (This function is only available if the `std` feature is enabled)
+#[cfg(feature = "std")]
+pub fn verify_file<P: AsRef<std::path::Path>, S: AsRef<std::path::Path>>(
+    artifact: P,
+    sidecar: S,
+) -> std::io::Result<bool> {
+    use std::io;
+
+    let contents = std::fs::read(sidecar)?;
+    let expected = core::str::from_utf8(&contents)
+        .ok()
+        .and_then(parse_sidecar)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not a valid sha1 file"))?;
+    Ok(crate::io::hash_file(artifact)? == expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sidecar() {
+        let expected = "2ef7bde608ce5404e97d5f042f95f89f1c232871".parse().ok();
+        for contents in &[
+            "2ef7bde608ce5404e97d5f042f95f89f1c232871",
+            "  2ef7bde608ce5404e97d5f042f95f89f1c232871\r\n",
+            "\u{feff}2ef7bde608ce5404e97d5f042f95f89f1c232871",
+            "2ef7bde608ce5404e97d5f042f95f89f1c232871 *foo-1.0.jar\n",
+            "foo-1.0.jar: 2ef7bde608ce5404e97d5f042f95f89f1c232871",
+            "/home/build/foo-1.0.jar 2ef7bde608ce5404e97d5f042f95f89f1c232871",
+            "SHA1(foo-1.0.jar)= 2ef7bde608ce5404e97d5f042f95f89f1c232871",
+            "SHA1 (foo=1.0.jar) = 2ef7bde608ce5404e97d5f042f95f89f1c232871",
+            "2ef7bde6 08ce5404 e97d5f04 2f95f89f 1c232871",
+            "2e f7 bd e6 08 ce 54 04 e9 7d 5f 04 2f 95 f8 9f\n1c 23 28 71\n",
+        ] {
+            assert_eq!(parse_sidecar(contents), expected, "{:?}", contents);
+        }
+        for contents in &[
+            "",
+            "2ef7bde608ce5404e97d5f042f95f89f1c23287",
+            "2ef7bde608ce5404e97d5f042f95f89f1c23287x",
+            "d41d8cd98f00b204e9800998ecf8427e  foo-1.0.jar",
+            "<html>not found</html>",
+        ] {
+            assert_eq!(parse_sidecar(contents), None, "{:?}", contents);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_files() {
+        let dir = std::env::temp_dir().join(std::format!("sha1-smol-maven-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let artifact = dir.join("foo-1.0.jar");
+        std::fs::write(&artifact, "Hello World!").unwrap();
+
+        let digest = write_sidecar(&artifact).unwrap();
+        let sidecar = dir.join("foo-1.0.jar.sha1");
+        assert_eq!(sidecar_path(&artifact), sidecar);
+        assert_eq!(
+            std::fs::read_to_string(&sidecar).unwrap(),
+            "2ef7bde608ce5404e97d5f042f95f89f1c232871"
+        );
+        assert_eq!(digest, crate::Sha1::from("Hello World!").digest());
+        assert!(verify_file(&artifact, &sidecar).unwrap());
+
+        std::fs::write(
+            &sidecar,
+            "SHA1(foo-1.0.jar)= 0000000000000000000000000000000000000000",
+        )
+        .unwrap();
+        assert!(!verify_file(&artifact, &sidecar).unwrap());
+        std::fs::write(&sidecar, "garbage").unwrap();
+        let err = verify_file(&artifact, &sidecar).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
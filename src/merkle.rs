@@ -0,0 +1,254 @@
+//! Binary Merkle trees over SHA-1.
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+//!
+//! Trees are built the way RFC 6962 (Certificate Transparency) builds them,
+//! with SHA-1 as hash function: leaves are hashed as `SHA1(0x00 || data)`
+//! and inner nodes as `SHA1(0x01 || left || right)`.  The prefixes keep a
+//! leaf from being passed off as an inner node.  The left subtree of a
+//! node always holds the largest power of two of its leaves that is
+//! smaller than their count, so trees only ever grow on the right.
+//!
+//! ```
+//! use sha1_smol::merkle::{self, MerkleTree};
+//!
+//! let tree = MerkleTree::from_leaves(&["a", "b", "c"]);
+//! let proof = tree.proof(2).unwrap();
+//! assert!(proof.verify(&merkle::leaf_hash(b"c"), &tree.root()));
+//! assert!(!proof.verify(&merkle::leaf_hash(b"a"), &tree.root()));
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::{Digest, Sha1};
+
+/// The prefix of hashed leaf data.
+pub const LEAF_PREFIX: u8 = 0x00;
+
+/// The prefix of hashed child nodes.
+pub const NODE_PREFIX: u8 = 0x01;
+
+/// Returns the hash of a leaf with the given data.
+pub fn leaf_hash(data: &[u8]) -> Digest {
+    let mut sha1 = Sha1::from([LEAF_PREFIX]);
+    sha1.update(data);
+    sha1.digest()
+}
+
+/// Returns the hash of an inner node with the given children.
+pub fn node_hash(left: &Digest, right: &Digest) -> Digest {
+    let mut sha1 = Sha1::from([NODE_PREFIX]);
+    sha1.update(&left.bytes());
+    sha1.update(&right.bytes());
+    sha1.digest()
+}
+
+/// A Merkle tree with all its levels kept in memory.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MerkleTree {
+    /// The leaf hashes first, the root last.  A node without a sibling is
+    /// carried up to the next level unchanged.
+    levels: Vec<Vec<Digest>>,
+}
+
+impl MerkleTree {
+    /// Builds a tree from the data of its leaves.
+    pub fn from_leaves<B: AsRef<[u8]>, I: IntoIterator<Item = B>>(leaves: I) -> MerkleTree {
+        MerkleTree::from_leaf_hashes(
+            leaves
+                .into_iter()
+                .map(|leaf| leaf_hash(leaf.as_ref()))
+                .collect(),
+        )
+    }
+
+    /// Builds a tree from precomputed leaf hashes (see [`leaf_hash`]).
+    pub fn from_leaf_hashes(leaves: Vec<Digest>) -> MerkleTree {
+        let mut levels = alloc::vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let level = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| match *pair {
+                    [ref left, ref right] => node_hash(left, right),
+                    _ => pair[0],
+                })
+                .collect();
+            levels.push(level);
+        }
+        MerkleTree { levels }
+    }
+
+    /// Returns the number of leaves.
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Returns `true` if the tree has no leaves.
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+
+    /// Returns the hashes of the leaves.
+    pub fn leaves(&self) -> &[Digest] {
+        &self.levels[0]
+    }
+
+    /// Returns the root hash.
+    ///
+    /// The root of the empty tree is the SHA-1 of the empty string.
+    pub fn root(&self) -> Digest {
+        match self.levels[self.levels.len() - 1].first() {
+            Some(root) => *root,
+            None => Sha1::new().digest(),
+        }
+    }
+
+    /// Returns the inclusion proof of the leaf at `index`.
+    ///
+    /// Returns `None` if `index` is out of bounds.
+    pub fn proof(&self, index: usize) -> Option<Proof> {
+        if index >= self.len() {
+            return None;
+        }
+        let mut path = Vec::new();
+        let mut idx = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(idx ^ 1) {
+                path.push(*sibling);
+            }
+            idx >>= 1;
+        }
+        Some(Proof {
+            index,
+            tree_size: self.len(),
+            path,
+        })
+    }
+}
+
+/// An inclusion proof of a leaf.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Proof {
+    /// The index of the leaf.
+    pub index: usize,
+    /// The number of leaves of the tree.
+    pub tree_size: usize,
+    /// The sibling hashes from the leaf up to the root.
+    pub path: Vec<Digest>,
+}
+
+impl Proof {
+    /// Verifies that the leaf with `leaf_hash` is part of the tree with
+    /// `root`.
+    ///
+    /// The tree size has to come from the same trusted source as `root`,
+    /// such as a signed tree head.  This implements the verification
+    /// algorithm of RFC 9162 section 2.1.3.2.
+    pub fn verify(&self, leaf_hash: &Digest, root: &Digest) -> bool {
+        if self.index >= self.tree_size {
+            return false;
+        }
+        let mut f = self.index;
+        let mut s = self.tree_size - 1;
+        let mut r = *leaf_hash;
+        for p in &self.path {
+            if s == 0 {
+                return false;
+            }
+            if f & 1 == 1 || f == s {
+                r = node_hash(p, &r);
+                while f & 1 == 0 && f != 0 {
+                    f >>= 1;
+                    s >>= 1;
+                }
+            } else {
+                r = node_hash(&r, p);
+            }
+            f >>= 1;
+            s >>= 1;
+        }
+        s == 0 && r == *root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the leaves of the RFC 6962 test suite of certificate-transparency-go,
+    // with the roots computed by a direct python implementation of the
+    // recursive definition in RFC 6962 section 2.1 over hashlib.sha1.
+    const LEAVES: [&[u8]; 8] = [
+        b"",
+        b"\x00",
+        b"\x10",
+        b"\x20\x21",
+        b"\x30\x31",
+        b"\x40\x41\x42\x43",
+        b"\x50\x51\x52\x53\x54\x55\x56\x57",
+        b"\x60\x61\x62\x63\x64\x65\x66\x67\x68\x69\x6a\x6b\x6c\x6d\x6e\x6f",
+    ];
+
+    const ROOTS: [&str; 9] = [
+        "da39a3ee5e6b4b0d3255bfef95601890afd80709",
+        "5ba93c9db0cff93f52b521d7420e43f6eda2784f",
+        "4dc852b78c5e87e6848adf603e3c82cb819cc2c4",
+        "0a1d17488c27ab0f6fbdb3208d5d8196807c9028",
+        "400c414700bec60a80e5fd6ec4889a530c377b6e",
+        "c42e0c9258dc372d468c72c3a36da85a29a8c71c",
+        "170e95a2c03f343aeff4d95e42c9f02e4a5e4a12",
+        "a4751286193b75f4e85c591dd7a735f813dedd55",
+        "5b6e686266068466c89be38fa1e75a865a182057",
+    ];
+
+    #[test]
+    fn test_roots() {
+        for (size, root) in ROOTS.iter().enumerate() {
+            let tree = MerkleTree::from_leaves(&LEAVES[..size]);
+            assert_eq!(tree.len(), size);
+            assert_eq!(tree.root(), root.parse().unwrap(), "size {}", size);
+        }
+        assert!(MerkleTree::from_leaves(&LEAVES[..0]).is_empty());
+    }
+
+    #[test]
+    fn test_proofs() {
+        for size in 1..40u32 {
+            let data: Vec<_> = (0..size).map(|i| i.to_be_bytes()).collect();
+            let tree = MerkleTree::from_leaves(&data);
+            let root = tree.root();
+            assert_eq!(tree.proof(size as usize), None);
+            for (idx, leaf) in tree.leaves().iter().enumerate() {
+                let proof = tree.proof(idx).unwrap();
+                assert!(proof.verify(leaf, &root), "{} of {}", idx, size);
+                assert!(!proof.verify(&leaf_hash(b"other"), &root));
+
+                if idx ^ 1 < tree.len() {
+                    let mut wrong = proof.clone();
+                    wrong.index ^= 1;
+                    assert!(!wrong.verify(leaf, &root), "{} of {}", idx, size);
+                }
+                if !proof.path.is_empty() {
+                    let mut wrong = proof.clone();
+                    wrong.path[0] = leaf_hash(b"other");
+                    assert!(!wrong.verify(leaf, &root));
+                    let mut wrong = proof.clone();
+                    wrong.path.pop();
+                    assert!(!wrong.verify(leaf, &root));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_leaf_is_not_a_node() {
+        let tree = MerkleTree::from_leaves(["a", "b"]);
+        let forged = Proof {
+            index: 0,
+            tree_size: 1,
+            path: Vec::new(),
+        };
+        assert!(!forged.verify(&tree.leaves()[0], &tree.root()));
+        assert_eq!(tree.root(), node_hash(&leaf_hash(b"a"), &leaf_hash(b"b")));
+    }
+}
@@ -0,0 +1,196 @@
+//! Legacy SQL Server password hashes.
+//!
+//! SQL Server 2000 to 2008 R2 store login passwords as a `0x0100` header,
+//! a 4 byte salt and the SHA-1 of the UTF-16LE encoded password followed
+//! by the salt.  SQL Server 2000 appends a second digest of the uppercased
+//! password, which makes that format trivially crackable and is ignored
+//! here: the case sensitive digest alone is enough to verify a password.
+//! SQL Server 2012 and later use SHA-512 with a `0x0200` header.
+//!
+//! ```
+//! use sha1_smol::mssql;
+//!
+//! let hash = mssql::hash("password", [0x0a, 0x1b, 0x2c, 0x3d]);
+//! let mut buf = [0u8; mssql::ENCODED_LENGTH];
+//! assert_eq!(
+//!     mssql::encode_into(&hash, &mut buf),
+//!     "0x01000A1B2C3DD2D8F2670F7A1D944DA9297326EF37740835E611"
+//! );
+//! assert!(mssql::verify(&hash, "password"));
+//! ```
+
+use core::str;
+
+use crate::encoding::{decode_hex_into, encode_hex_into};
+use crate::hmac::ct_eq;
+use crate::{Sha1, DIGEST_LENGTH};
+
+/// The header of SHA-1 password hashes.
+pub const HEADER: [u8; 2] = [0x01, 0x00];
+
+/// The length of the salt.
+pub const SALT_LENGTH: usize = 4;
+
+/// The length of a password hash.
+pub const HASH_LENGTH: usize = HEADER.len() + SALT_LENGTH + DIGEST_LENGTH;
+
+/// The length of a password hash as `0x` prefixed hex.
+pub const ENCODED_LENGTH: usize = 2 + HASH_LENGTH * 2;
+
+/// The length of a SQL Server 2000 hash including the uppercase digest.
+const LEGACY_HASH_LENGTH: usize = HASH_LENGTH + DIGEST_LENGTH;
+
+fn digest(password: &str, salt: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut sha1 = Sha1::new();
+    for unit in password.encode_utf16() {
+        sha1.update(&unit.to_le_bytes());
+    }
+    sha1.update(salt);
+    sha1.digest().bytes()
+}
+
+/// Hashes `password` with `salt`.
+///
+/// The salt has to be generated by the caller from a random source.
+pub fn hash(password: &str, salt: [u8; SALT_LENGTH]) -> [u8; HASH_LENGTH] {
+    let mut rv = [0u8; HASH_LENGTH];
+    rv[..2].copy_from_slice(&HEADER);
+    rv[2..6].copy_from_slice(&salt);
+    rv[6..].copy_from_slice(&digest(password, &salt));
+    rv
+}
+
+/// Extracts the salt from a password hash.
+///
+/// Returns `None` if the hash does not start with the `0x0100` header or is
+/// too short.
+pub fn salt(hash: &[u8]) -> Option<[u8; SALT_LENGTH]> {
+    if hash.len() < HASH_LENGTH || hash[..2] != HEADER {
+        return None;
+    }
+    let mut rv = [0u8; SALT_LENGTH];
+    rv.copy_from_slice(&hash[2..6]);
+    Some(rv)
+}
+
+/// Verifies `password` against a password hash.
+///
+/// Both the 26 byte hashes and the 46 byte hashes of SQL Server 2000 are
+/// accepted.  The digest is compared in constant time.
+pub fn verify(hash: &[u8], password: &str) -> bool {
+    if hash.len() != HASH_LENGTH && hash.len() != LEGACY_HASH_LENGTH {
+        return false;
+    }
+    match salt(hash) {
+        Some(salt) => ct_eq(&digest(password, &salt), &hash[6..HASH_LENGTH]),
+        None => false,
+    }
+}
+
+/// Writes a password hash as `0x` prefixed uppercase hex the way SQL Server
+/// displays `varbinary` values and returns it as string slice.
+pub fn encode_into<'a>(hash: &[u8; HASH_LENGTH], out: &'a mut [u8; ENCODED_LENGTH]) -> &'a str {
+    out[..2].copy_from_slice(b"0x");
+    encode_hex_into(hash, &mut out[2..]);
+    out[2..].make_ascii_uppercase();
+    str::from_utf8(out).unwrap()
+}
+
+/// Parses a password hash from its `0x` prefixed hex form.
+///
+/// Hex of either case is accepted.  SQL Server 2000 hashes are cut down to
+/// the parts [`verify`] looks at.  Returns `None` if `s` is not the hex of a
+/// SHA-1 password hash.
+pub fn parse(s: &str) -> Option<[u8; HASH_LENGTH]> {
+    let hex = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))?
+        .as_bytes();
+    let hex = match hex.len() {
+        len if len == HASH_LENGTH * 2 => hex,
+        len if len == LEGACY_HASH_LENGTH * 2 => {
+            let mut rest = [0u8; DIGEST_LENGTH];
+            decode_hex_into(&hex[HASH_LENGTH * 2..], &mut rest)?;
+            &hex[..HASH_LENGTH * 2]
+        }
+        _ => return None,
+    };
+    let mut rv = [0u8; HASH_LENGTH];
+    decode_hex_into(hex, &mut rv)?;
+    salt(&rv)?;
+    Some(rv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // computed with python hashlib over `password.encode("utf-16le") + salt`
+    const SALT: [u8; SALT_LENGTH] = [0x0a, 0x1b, 0x2c, 0x3d];
+
+    #[test]
+    fn test_hash() {
+        let mut buf = [0u8; ENCODED_LENGTH];
+        let hash = hash("password", SALT);
+        assert_eq!(
+            encode_into(&hash, &mut buf),
+            "0x01000A1B2C3DD2D8F2670F7A1D944DA9297326EF37740835E611"
+        );
+        assert_eq!(salt(&hash), Some(SALT));
+
+        // exercises surrogate pairs
+        let hash = super::hash("Pässwörd😀", SALT);
+        assert_eq!(
+            encode_into(&hash, &mut buf),
+            "0x01000A1B2C3DCDC5AADA6D634A7FC7E8C368B8415435ED9EE216"
+        );
+        assert!(verify(&hash, "Pässwörd😀"));
+    }
+
+    #[test]
+    fn test_verify() {
+        let hash = hash("password", SALT);
+        assert!(verify(&hash, "password"));
+        assert!(!verify(&hash, "Password"));
+        assert!(!verify(&hash[..25], "password"));
+
+        let mut legacy = [0u8; LEGACY_HASH_LENGTH];
+        legacy[..HASH_LENGTH].copy_from_slice(&hash);
+        assert!(verify(&legacy, "password"));
+
+        let mut other = hash;
+        other[0] = 0x02;
+        assert!(!verify(&other, "password"));
+        assert_eq!(salt(&other), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        let expected = Some(hash("password", SALT));
+        assert_eq!(
+            parse("0x01000A1B2C3DD2D8F2670F7A1D944DA9297326EF37740835E611"),
+            expected
+        );
+        assert_eq!(
+            parse("0X01000a1b2c3dd2d8f2670f7a1d944da9297326ef37740835e611"),
+            expected
+        );
+        assert_eq!(
+            parse(
+                "0x01000A1B2C3DD2D8F2670F7A1D944DA9297326EF37740835E611\
+                 0000000000000000000000000000000000000000"
+            ),
+            expected
+        );
+        for s in &[
+            "01000A1B2C3DD2D8F2670F7A1D944DA9297326EF37740835E611",
+            "0x02000A1B2C3DD2D8F2670F7A1D944DA9297326EF37740835E611",
+            "0x01000A1B2C3DD2D8F2670F7A1D944DA9297326EF37740835E6",
+            "0x01000A1B2C3DD2D8F2670F7A1D944DA9297326EF37740835E61x",
+            "0x01000A1B2C3DD2D8F2670F7A1D944DA9297326EF37740835E611\
+             000000000000000000000000000000000000000z",
+        ] {
+            assert_eq!(parse(s), None, "{}", s);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! The multiformats multihash encoding of SHA-1 digests.
+//!
+//! A multihash prefixes the digest with the hash function code and the
+//! digest length, both as unsigned varints.  For SHA-1 the code is `0x11`
+//! and the length 20, so the encoding is always 22 bytes long:
+//!
+//! ```
+//! use sha1_smol::multihash;
+//!
+//! let digest = sha1_smol::Sha1::from("Hello World!").digest();
+//! let encoded = multihash::encode(&digest);
+//! assert_eq!(&encoded[..2], &[0x11, 0x14]);
+//! assert_eq!(multihash::decode(&encoded), Ok(digest));
+//! ```
+
+use core::fmt;
+
+use crate::{Digest, DIGEST_LENGTH};
+
+/// The multihash code of SHA-1.
+pub const CODE: u64 = 0x11;
+
+/// The length of an encoded SHA-1 multihash.
+pub const ENCODED_LENGTH: usize = DIGEST_LENGTH + 2;
+
+/// Indicates that a multihash couldn't be decoded.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum MultihashError {
+    /// The multihash is of another hash function, with the given code.
+    UnsupportedCode(u64),
+    /// The digest is not 20 bytes long.  Truncated SHA-1 multihashes are
+    /// valid multihashes but cannot be represented as a [`Digest`].
+    InvalidLength,
+    /// The input is not a well formed multihash.
+    Malformed,
+}
+
+impl fmt::Display for MultihashError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MultihashError::UnsupportedCode(code) => {
+                write!(f, "unsupported multihash code {:#x}", code)
+            }
+            MultihashError::InvalidLength => f.write_str("invalid sha1 multihash length"),
+            MultihashError::Malformed => f.write_str("malformed multihash"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MultihashError {
+    fn description(&self) -> &str {
+        "invalid multihash"
+    }
+}
+
+/// Encodes a digest as multihash.
+pub fn encode(digest: &Digest) -> [u8; ENCODED_LENGTH] {
+    let mut rv = [0u8; ENCODED_LENGTH];
+    rv[0] = CODE as u8;
+    rv[1] = DIGEST_LENGTH as u8;
+    rv[2..].copy_from_slice(&digest.bytes());
+    rv
+}
+
+/// Reads an unsigned varint off the front of `input`.
+///
+/// Following the multiformats spec, at most nine bytes are accepted and
+/// the encoding has to be minimal.
+fn read_varint(input: &[u8]) -> Result<(u64, &[u8]), MultihashError> {
+    let mut value = 0u64;
+    for (idx, &byte) in input.iter().enumerate().take(9) {
+        value |= ((byte & 0x7f) as u64) << (7 * idx);
+        if byte & 0x80 == 0 {
+            if byte == 0 && idx > 0 {
+                return Err(MultihashError::Malformed);
+            }
+            return Ok((value, &input[idx + 1..]));
+        }
+    }
+    Err(MultihashError::Malformed)
+}
+
+/// Decodes a SHA-1 multihash.
+///
+/// The input has to consist of exactly one multihash.
+pub fn decode(input: &[u8]) -> Result<Digest, MultihashError> {
+    let (code, rest) = read_varint(input)?;
+    let (len, rest) = read_varint(rest)?;
+    if len != rest.len() as u64 {
+        return Err(MultihashError::Malformed);
+    }
+    if code != CODE {
+        return Err(MultihashError::UnsupportedCode(code));
+    }
+    if rest.len() != DIGEST_LENGTH {
+        return Err(MultihashError::InvalidLength);
+    }
+    let mut bytes = [0u8; DIGEST_LENGTH];
+    bytes.copy_from_slice(rest);
+    Ok(Digest::from_bytes(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha1;
+
+    #[test]
+    fn test_encode() {
+        // sha1 of "foo" as printed by python hashlib behind 0x11 0x14
+        let mut expected = [0u8; ENCODED_LENGTH];
+        crate::encoding::decode_hex_into(
+            b"11140beec7b5ea3f0fdbc95d0dd47f3c5bc275da8a33",
+            &mut expected,
+        )
+        .unwrap();
+        let digest = Sha1::from("foo").digest();
+        assert_eq!(encode(&digest), expected);
+        assert_eq!(decode(&expected), Ok(digest));
+    }
+
+    #[test]
+    fn test_decode_rejects() {
+        let encoded = encode(&Sha1::from("foo").digest());
+        assert_eq!(decode(&encoded[..21]), Err(MultihashError::Malformed));
+        assert_eq!(decode(&[]), Err(MultihashError::Malformed));
+
+        let mut truncated = [0u8; 12];
+        truncated[..2].copy_from_slice(&[0x11, 0x0a]);
+        assert_eq!(decode(&truncated), Err(MultihashError::InvalidLength));
+
+        let mut sha256 = [0u8; 34];
+        sha256[..2].copy_from_slice(&[0x12, 0x20]);
+        assert_eq!(decode(&sha256), Err(MultihashError::UnsupportedCode(0x12)));
+
+        // blake2b-256 with a three byte code varint
+        let mut blake2b = [0u8; 36];
+        blake2b[..3].copy_from_slice(&[0xa0, 0xe4, 0x02]);
+        blake2b[3] = 32;
+        assert_eq!(
+            decode(&blake2b[..]),
+            Err(MultihashError::UnsupportedCode(0xb220))
+        );
+
+        // non minimal varint for the code
+        let mut padded = [0u8; 23];
+        padded[..3].copy_from_slice(&[0x91, 0x00, 0x14]);
+        assert_eq!(decode(&padded), Err(MultihashError::Malformed));
+    }
+}
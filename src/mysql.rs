@@ -0,0 +1,157 @@
+//! The MySQL `mysql_native_password` authentication plugin.
+//!
+//! The server stores `SHA1(SHA1(password))`, shown in `mysql.user` as an
+//! asterisk followed by uppercase hex.  During the handshake it sends a 20
+//! byte nonce and the client answers with
+//! `SHA1(password) XOR SHA1(nonce + SHA1(SHA1(password)))`, which lets the
+//! server recover `SHA1(password)` and check it against the stored hash.
+//!
+//! ```
+//! use sha1_smol::mysql;
+//!
+//! let stored = mysql::stored_hash(b"password");
+//! let mut buf = [0u8; mysql::STORED_HASH_LENGTH];
+//! assert_eq!(
+//!     mysql::encode_stored_hash(&stored, &mut buf),
+//!     "*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19"
+//! );
+//!
+//! let nonce = [42u8; 20];
+//! let response = mysql::scramble(b"password", &nonce);
+//! assert!(mysql::verify_scramble(&stored, &nonce, &response));
+//! ```
+//!
+//! Clients send an empty response instead of a scramble if the password
+//! is empty, which [`verify_scramble`] accepts for the stored hash of the
+//! empty password.
+
+use core::str;
+
+use crate::encoding::{decode_hex_into, encode_hex_into};
+use crate::hmac::ct_eq;
+use crate::{Digest, Sha1, DIGEST_LENGTH};
+
+/// The length of a scramble.
+pub const SCRAMBLE_LENGTH: usize = DIGEST_LENGTH;
+
+/// The length of the textual form of a stored hash.
+pub const STORED_HASH_LENGTH: usize = DIGEST_LENGTH * 2 + 1;
+
+/// Returns the hash the server stores for `password`.
+pub fn stored_hash(password: &[u8]) -> Digest {
+    Sha1::from(Sha1::from(password).digest().bytes()).digest()
+}
+
+/// Writes a stored hash in its `*` prefixed uppercase hex form into a
+/// buffer and returns it as string slice.
+pub fn encode_stored_hash<'a>(stored: &Digest, out: &'a mut [u8; STORED_HASH_LENGTH]) -> &'a str {
+    out[0] = b'*';
+    encode_hex_into(&stored.bytes(), &mut out[1..]);
+    out.make_ascii_uppercase();
+    str::from_utf8(out).unwrap()
+}
+
+/// Parses the `*` prefixed hex form of a stored hash.
+///
+/// Hex of either case is accepted.  Returns `None` for anything else,
+/// including the 16 character hashes of the pre 4.1 scheme.
+pub fn parse_stored_hash(s: &str) -> Option<Digest> {
+    let hex = s.strip_prefix('*')?;
+    let mut bytes = [0u8; DIGEST_LENGTH];
+    decode_hex_into(hex.as_bytes(), &mut bytes)?;
+    Some(Digest::from_bytes(&bytes))
+}
+
+/// Returns `SHA1(nonce + stored)`, the mask applied to `SHA1(password)`.
+fn mask(stored: &Digest, nonce: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut sha1 = Sha1::from(nonce);
+    sha1.update(&stored.bytes());
+    sha1.digest().bytes()
+}
+
+/// Computes the client's response to the server `nonce`.
+pub fn scramble(password: &[u8], nonce: &[u8]) -> [u8; SCRAMBLE_LENGTH] {
+    let stage1 = Sha1::from(password).digest().bytes();
+    let stored = Sha1::from(stage1).digest();
+    let mut rv = mask(&stored, nonce);
+    for (r, s) in rv.iter_mut().zip(stage1.iter()) {
+        *r ^= s;
+    }
+    rv
+}
+
+/// Verifies a client's response to `nonce` against the stored hash.
+///
+/// The comparison is done in constant time.  An empty response verifies
+/// if `stored` is the hash of the empty password, other responses that are
+/// not exactly [`SCRAMBLE_LENGTH`] bytes long never verify.
+pub fn verify_scramble(stored: &Digest, nonce: &[u8], response: &[u8]) -> bool {
+    if response.is_empty() {
+        return ct_eq(&stored.bytes(), &stored_hash(b"").bytes());
+    }
+    if response.len() != SCRAMBLE_LENGTH {
+        return false;
+    }
+    let mut stage1 = mask(stored, nonce);
+    for (s, r) in stage1.iter_mut().zip(response.iter()) {
+        *s ^= r;
+    }
+    ct_eq(&Sha1::from(stage1).digest().bytes(), &stored.bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // computed with python hashlib; the stored hash matches what
+    // `SELECT PASSWORD('password')` returns on MySQL 5.7
+
+    #[test]
+    fn test_stored_hash() {
+        let stored = stored_hash(b"password");
+        let mut buf = [0u8; STORED_HASH_LENGTH];
+        let encoded = encode_stored_hash(&stored, &mut buf);
+        assert_eq!(encoded, "*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19");
+        assert_eq!(parse_stored_hash(encoded), Some(stored));
+        assert_eq!(
+            parse_stored_hash("*2470c0c06dee42fd1618bb99005adca2ec9d1e19"),
+            Some(stored)
+        );
+        assert_eq!(
+            parse_stored_hash("2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19"),
+            None
+        );
+        assert_eq!(parse_stored_hash("5d2e19393cc5ef67"), None);
+    }
+
+    #[test]
+    fn test_scramble() {
+        let mut nonce = [0u8; 20];
+        for (idx, b) in nonce.iter_mut().enumerate() {
+            *b = idx as u8 + 1;
+        }
+        let response = scramble(b"password", &nonce);
+        let mut expected = [0u8; SCRAMBLE_LENGTH];
+        decode_hex_into(b"c17d6009a5cb47e59f7483fcf05553bbbf7dd0d6", &mut expected).unwrap();
+        assert_eq!(response, expected);
+
+        let stored = stored_hash(b"password");
+        assert!(verify_scramble(&stored, &nonce, &response));
+        assert!(!verify_scramble(&stored, &[0; 20], &response));
+        assert!(!verify_scramble(&stored, &nonce, &response[..19]));
+        assert!(!verify_scramble(
+            &stored_hash(b"Password"),
+            &nonce,
+            &response
+        ));
+    }
+
+    #[test]
+    fn test_empty_password() {
+        let nonce = [7u8; 20];
+        let stored = stored_hash(b"");
+        assert!(verify_scramble(&stored, &nonce, &[]));
+        assert!(verify_scramble(&stored, &nonce, &scramble(b"", &nonce)));
+        assert!(!verify_scramble(&stored_hash(b"password"), &nonce, &[]));
+    }
+}
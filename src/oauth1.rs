@@ -0,0 +1,246 @@
+//! OAuth 1.0a `HMAC-SHA1` request signatures (RFC 5849).
+//!
+//! The signature is computed over a base string made of the request
+//! method, the base string URI and the normalized request parameters,
+//! which include the parameters from the query string of the URL, the
+//! form encoded body and the `oauth_*` protocol parameters.  The key is made
+//! of the consumer secret and the token secret:
+//!
+//! ```
+//! use sha1_smol::oauth1;
+//!
+//! let params = [
+//!     ("oauth_consumer_key", "dpf43f3p2l4k3l03"),
+//!     ("oauth_token", "nnch734d00sl2jdk"),
+//!     ("oauth_signature_method", "HMAC-SHA1"),
+//!     ("oauth_timestamp", "137131202"),
+//!     ("oauth_nonce", "chapoH"),
+//! ];
+//! let signature = oauth1::sign(
+//!     "GET",
+//!     "http://photos.example.net/photos?file=vacation.jpg&size=original",
+//!     &params,
+//!     "kd94hf93k423kf44",
+//!     "pfkkdhi9sl3r4s00",
+//! )
+//! .unwrap();
+//! assert_eq!(signature, "MdpQcU8iPSUjWoN/UDMsK2sui9I=");
+//! ```
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::query::{canonicalize, percent_decode, percent_encode, sign_base64};
+
+/// Returns the base string URI of `url` (RFC 5849 section 3.4.1.2).
+///
+/// The scheme and host are lowercased, the default port of `http` and
+/// `https` is removed as are the query and the fragment.  Returns `None`
+/// if `url` is not an absolute URL.
+pub fn base_string_uri(url: &str) -> Option<String> {
+    let (scheme, rest) = split_url(url)?;
+    let scheme = scheme.to_ascii_lowercase();
+    let end = rest.find(|c| c == '/' || c == '?' || c == '#');
+    let (authority, rest) = rest.split_at(end.unwrap_or(rest.len()));
+    let path = &rest[..rest.find(|c| c == '?' || c == '#').unwrap_or(rest.len())];
+    let mut host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    if let Some(idx) = host.rfind(':') {
+        let port = &host[idx + 1..];
+        if !host[idx..].contains(']')
+            && ((scheme == "http" && port == "80") || (scheme == "https" && port == "443"))
+        {
+            host = &host[..idx];
+        }
+    }
+    if host.is_empty() {
+        return None;
+    }
+
+    let mut rv = scheme;
+    rv.push_str("://");
+    rv.push_str(&host.to_ascii_lowercase());
+    rv.push_str(if path.is_empty() { "/" } else { path });
+    Some(rv)
+}
+
+fn split_url(url: &str) -> Option<(&str, &str)> {
+    let idx = url.find("://")?;
+    let scheme = &url[..idx];
+    if scheme.is_empty()
+        || !scheme
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'+' || c == b'-' || c == b'.')
+    {
+        return None;
+    }
+    Some((scheme, &url[idx + 3..]))
+}
+
+/// Decodes a form encoded component, where `+` stands for a space.
+fn form_decode(input: &str) -> Option<Vec<u8>> {
+    percent_decode(&input.replace('+', " "))
+}
+
+/// Builds the signature base string (RFC 5849 section 3.4.1).
+///
+/// `params` are the decoded request parameters other than those in the
+/// query string of `url`: the `oauth_*` protocol parameters and the
+/// parameters of a form encoded body.  The query string of `url` is
+/// decoded and added automatically.  An `oauth_signature` parameter is
+/// left out, the `realm` of an `Authorization` header must not be passed.
+///
+/// Returns `None` if `url` is not an absolute URL or its query string is
+/// not validly encoded.
+pub fn signature_base_string<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+    method: &str,
+    url: &str,
+    params: &[(K, V)],
+) -> Option<String> {
+    let uri = base_string_uri(url)?;
+    let mut all: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+    let query = url.split('#').next().unwrap_or("");
+    if let Some(idx) = query.find('?') {
+        for pair in query[idx + 1..].split('&').filter(|pair| !pair.is_empty()) {
+            let (name, value) = pair.split_at(pair.find('=').unwrap_or(pair.len()));
+            let value = value.strip_prefix('=').unwrap_or(value);
+            all.push((form_decode(name)?, form_decode(value)?));
+        }
+    }
+    all.extend(
+        params
+            .iter()
+            .filter(|(name, _)| name.as_ref() != b"oauth_signature")
+            .map(|(name, value)| (name.as_ref().to_vec(), value.as_ref().to_vec())),
+    );
+
+    let mut rv = method.to_ascii_uppercase();
+    rv.push('&');
+    rv.push_str(&percent_encode(uri));
+    rv.push('&');
+    rv.push_str(&percent_encode(canonicalize(&all)));
+    Some(rv)
+}
+
+/// Returns the `HMAC-SHA1` key for a consumer secret and a token secret.
+///
+/// The token secret is empty if the request is not made with a token, for
+/// instance when asking for temporary credentials.
+pub fn signing_key(consumer_secret: &str, token_secret: &str) -> String {
+    let mut rv = percent_encode(consumer_secret);
+    rv.push('&');
+    rv.push_str(&percent_encode(token_secret));
+    rv
+}
+
+/// Computes the base64 encoded `oauth_signature` of a request.
+///
+/// See [`signature_base_string`] for what goes into `params` and when this
+/// returns `None`.
+pub fn sign<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+    method: &str,
+    url: &str,
+    params: &[(K, V)],
+    consumer_secret: &str,
+    token_secret: &str,
+) -> Option<String> {
+    let base_string = signature_base_string(method, url, params)?;
+    let key = signing_key(consumer_secret, token_secret);
+    Some(sign_base64(key.as_bytes(), &base_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_string_uri() {
+        // examples from RFC 5849 section 3.4.1.2
+        assert_eq!(
+            base_string_uri("HTTP://EXAMPLE.COM:80/r%20v/X?id=123").as_deref(),
+            Some("http://example.com/r%20v/X")
+        );
+        assert_eq!(
+            base_string_uri("https://www.example.net:8080/?q=1").as_deref(),
+            Some("https://www.example.net:8080/")
+        );
+        assert_eq!(
+            base_string_uri("https://user@Example.com:443#frag").as_deref(),
+            Some("https://example.com/")
+        );
+        assert_eq!(
+            base_string_uri("http://[::1]/x").as_deref(),
+            Some("http://[::1]/x")
+        );
+        assert_eq!(base_string_uri("/relative/path"), None);
+        assert_eq!(base_string_uri("http:///path"), None);
+    }
+
+    #[test]
+    fn test_signature_base_string() {
+        // example from RFC 5849 section 3.4.1.1
+        let params = [
+            ("oauth_consumer_key", "9djdj82h48djs9d2"),
+            ("oauth_token", "kkk9d7dh3k39sjv7"),
+            ("oauth_signature_method", "HMAC-SHA1"),
+            ("oauth_timestamp", "137131201"),
+            ("oauth_nonce", "7d8f3e4a"),
+            ("oauth_signature", "djosJKDKJSD8743243%2Fjdk33klY%3D"),
+            ("c2", ""),
+            ("a3", "2 q"),
+        ];
+        let base_string = signature_base_string(
+            "post",
+            "http://example.com/request?b5=%3D%253D&a3=a&c%40=&a2=r%20b",
+            &params,
+        );
+        assert_eq!(
+            base_string.as_deref(),
+            Some(
+                "POST&http%3A%2F%2Fexample.com%2Frequest&a2%3Dr%2520b%26a3%3D2%2520q\
+                 %26a3%3Da%26b5%3D%253D%25253D%26c%2540%3D%26c2%3D%26oauth_consumer_\
+                 key%3D9djdj82h48djs9d2%26oauth_nonce%3D7d8f3e4a%26oauth_signature_m\
+                 ethod%3DHMAC-SHA1%26oauth_timestamp%3D137131201%26oauth_token%3Dkkk\
+                 9d7dh3k39sjv7"
+            )
+        );
+        let none: [(&str, &str); 0] = [];
+        assert_eq!(
+            signature_base_string("GET", "http://example.com/?a=%zz", &none),
+            None
+        );
+        assert_eq!(
+            signature_base_string("GET", "http://example.com/?a+b=c+d&e", &none).as_deref(),
+            Some("GET&http%3A%2F%2Fexample.com%2F&a%2520b%3Dc%2520d%26e%3D")
+        );
+    }
+
+    #[test]
+    fn test_sign() {
+        // example from RFC 5849 section 1.2, verified with python
+        let params = [
+            ("file", "vacation.jpg"),
+            ("size", "original"),
+            ("oauth_consumer_key", "dpf43f3p2l4k3l03"),
+            ("oauth_token", "nnch734d00sl2jdk"),
+            ("oauth_signature_method", "HMAC-SHA1"),
+            ("oauth_timestamp", "137131202"),
+            ("oauth_nonce", "chapoH"),
+        ];
+        assert_eq!(
+            sign(
+                "GET",
+                "http://photos.example.net/photos",
+                &params,
+                "kd94hf93k423kf44",
+                "pfkkdhi9sl3r4s00"
+            )
+            .as_deref(),
+            Some("MdpQcU8iPSUjWoN/UDMsK2sui9I=")
+        );
+        assert_eq!(signing_key("a&b", ""), "a%26b&");
+    }
+}
@@ -0,0 +1,177 @@
+//! OpenPGP v4 key fingerprints (RFC 4880 section 12.2).
+//!
+//! The fingerprint of a version 4 key is the SHA-1 of the octet `0x99`, the
+//! two-octet big endian length of the public key packet body and the body
+//! itself.  The key ID is the low 64 bits of the fingerprint.  GnuPG prints
+//! fingerprints as ten groups of four uppercase hex digits with an extra
+//! space in the middle, which is what [`Fingerprint`] formats to:
+//!
+//! ```
+//! use sha1_smol::openpgp::Fingerprint;
+//!
+//! let fpr: Fingerprint = "1ee2a01a153fa0f16bdf4c5fcd54a5aa1ae5f5df".parse().unwrap();
+//! assert_eq!(fpr.to_string(), "1EE2 A01A 153F A0F1 6BDF  4C5F CD54 A5AA 1AE5 F5DF");
+//! assert_eq!(fpr.key_id(), 0xCD54A5AA1AE5F5DF);
+//! ```
+//!
+//! [`fingerprint`] computes the digest from the body of a public key packet.
+
+use core::{fmt, str};
+
+use crate::encoding::{decode_hex_into, encode_hex_into};
+use crate::{Digest, DigestParseError, Sha1, DIGEST_LENGTH};
+
+/// The length of a fingerprint in the GnuPG presentation.
+pub const FINGERPRINT_LENGTH: usize = 50;
+
+/// Computes the fingerprint of a version 4 public key packet body.
+///
+/// `body` is the contents of the public key (or public subkey) packet
+/// without the packet header.  Returns `None` if the body is not of a
+/// version 4 key or too long to be framed with a two-octet length.
+pub fn fingerprint(body: &[u8]) -> Option<Digest> {
+    if body.first() != Some(&4) || body.len() > 0xffff {
+        return None;
+    }
+    let mut m = Sha1::new();
+    m.update(&[0x99]);
+    m.update(&(body.len() as u16).to_be_bytes());
+    m.update(body);
+    Some(m.digest())
+}
+
+/// Returns the 64 bit key ID of a v4 fingerprint.
+pub fn key_id(fingerprint: &Digest) -> u64 {
+    let mut rv = [0u8; 8];
+    rv.copy_from_slice(&fingerprint.bytes()[DIGEST_LENGTH - 8..]);
+    u64::from_be_bytes(rv)
+}
+
+/// A v4 fingerprint in the GnuPG presentation.
+///
+/// Parsing accepts hex of either case with or without whitespace and an
+/// optional `0x` prefix.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Fingerprint(pub Digest);
+
+impl Fingerprint {
+    /// Writes the grouped fingerprint into a buffer and returns it as string
+    /// slice.
+    pub fn encode_into<'a>(&self, out: &'a mut [u8; FINGERPRINT_LENGTH]) -> &'a str {
+        let mut hex = [0u8; DIGEST_LENGTH * 2];
+        encode_hex_into(&self.0.bytes(), &mut hex);
+        let mut pos = 0;
+        for (idx, group) in hex.chunks(4).enumerate() {
+            if idx > 0 {
+                out[pos] = b' ';
+                pos += 1;
+            }
+            if idx == 5 {
+                out[pos] = b' ';
+                pos += 1;
+            }
+            for &c in group {
+                out[pos] = c.to_ascii_uppercase();
+                pos += 1;
+            }
+        }
+        str::from_utf8(out).unwrap()
+    }
+
+    /// Returns the 64 bit key ID.
+    pub fn key_id(&self) -> u64 {
+        key_id(&self.0)
+    }
+}
+
+impl From<Digest> for Fingerprint {
+    fn from(digest: Digest) -> Fingerprint {
+        Fingerprint(digest)
+    }
+}
+
+impl From<Fingerprint> for Digest {
+    fn from(fingerprint: Fingerprint) -> Digest {
+        fingerprint.0
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut buf = [0u8; FINGERPRINT_LENGTH];
+        f.write_str(self.encode_into(&mut buf))
+    }
+}
+
+impl str::FromStr for Fingerprint {
+    type Err = DigestParseError;
+
+    fn from_str(s: &str) -> Result<Fingerprint, DigestParseError> {
+        let s = s.trim();
+        let s = s
+            .strip_prefix("0x")
+            .or_else(|| s.strip_prefix("0X"))
+            .unwrap_or(s);
+        let mut hex = [0u8; DIGEST_LENGTH * 2];
+        let mut len = 0;
+        for c in s.bytes().filter(|c| !c.is_ascii_whitespace()) {
+            *hex.get_mut(len).ok_or(DigestParseError(()))? = c;
+            len += 1;
+        }
+        let mut bytes = [0u8; DIGEST_LENGTH];
+        if len != hex.len() || decode_hex_into(&hex, &mut bytes).is_none() {
+            return Err(DigestParseError(()));
+        }
+        Ok(Fingerprint(Digest::from_bytes(&bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // an ed25519 key generated with GnuPG
+    const BODY: &str = "046acf820016092b06010401da470f0101074094ded1c2928cc5ccbb011d4c2bcdab\
+                        78323886ac7508dcdbdda330498bc8631b";
+
+    // as printed by `gpg --fingerprint`
+    const FINGERPRINT: &str = "1EE2 A01A 153F A0F1 6BDF  4C5F CD54 A5AA 1AE5 F5DF";
+
+    #[test]
+    fn test_fingerprint() {
+        let mut body = [0u8; 51];
+        crate::encoding::decode_hex_into(BODY.as_bytes(), &mut body).unwrap();
+        let fpr = Fingerprint(fingerprint(&body).unwrap());
+        let mut buf = [0u8; FINGERPRINT_LENGTH];
+        assert_eq!(fpr.encode_into(&mut buf), FINGERPRINT);
+        assert_eq!(fpr.key_id(), 0xcd54_a5aa_1ae5_f5df);
+
+        body[0] = 3;
+        assert_eq!(fingerprint(&body), None);
+        assert_eq!(fingerprint(&[]), None);
+        let long = [4u8; 0x10000];
+        assert_eq!(fingerprint(&long), None);
+        assert!(fingerprint(&long[..0xffff]).is_some());
+    }
+
+    #[test]
+    fn test_parse() {
+        let expected = Fingerprint("1ee2a01a153fa0f16bdf4c5fcd54a5aa1ae5f5df".parse().unwrap());
+        for s in &[
+            FINGERPRINT,
+            "1ee2a01a153fa0f16bdf4c5fcd54a5aa1ae5f5df",
+            "0x1EE2A01A153FA0F16BDF4C5FCD54A5AA1AE5F5DF",
+            " 1EE2 A01A 153F A0F1 6BDF\n4C5F CD54 A5AA 1AE5 F5DF ",
+        ] {
+            assert_eq!(s.parse::<Fingerprint>(), Ok(expected), "{:?}", s);
+        }
+        for s in &[
+            "1EE2 A01A 153F A0F1 6BDF  4C5F CD54 A5AA 1AE5 F5D",
+            "1EE2 A01A 153F A0F1 6BDF  4C5F CD54 A5AA 1AE5 F5DF 00",
+            "1EE2 A01A 153F A0F1 6BDF  4C5F CD54 A5AA 1AE5 F5DG",
+            "0xCD54A5AA1AE5F5DF",
+        ] {
+            assert!(s.parse::<Fingerprint>().is_err(), "{:?}", s);
+        }
+    }
+}
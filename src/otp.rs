@@ -0,0 +1,194 @@
+//! One-time passwords with HMAC-SHA1 (RFC 4226, RFC 6238 and Steam Guard).
+//!
+//! Codes are returned as integers.  They have to be zero padded to the
+//! requested number of digits for display:
+//!
+//! ```
+//! let code = sha1_smol::otp::hotp(b"12345678901234567890", 0, 6);
+//! assert_eq!(code, 755224);
+//! assert_eq!(format!("{:06}", code), "755224");
+//! ```
+//!
+//! Time based codes use the number of `step` second intervals since the
+//! Unix epoch as counter.  [`verify`] tolerates clocks that are a few steps
+//! off:
+//!
+//! ```
+//! use sha1_smol::otp;
+//!
+//! let code = otp::totp(b"12345678901234567890", 1_111_111_109, 30, 8);
+//! assert_eq!(code, 7081804);
+//! let offset = otp::verify(b"12345678901234567890", code, 1_111_111_125, 30, 8, 1);
+//! assert_eq!(offset, Some(-1));
+//! ```
+
+use crate::hmac::hmac_sha1;
+
+/// Applies the dynamic truncation of RFC 4226 section 5.3 to an HMAC-SHA1
+/// tag and reduces it to `digits` decimal digits.
+///
+/// This panics if `digits` is not between 1 and 9.
+pub fn truncate(tag: &[u8; 20], digits: u32) -> u32 {
+    assert!((1..=9).contains(&digits), "digits must be between 1 and 9");
+    dynamic_truncate(tag) % 10u32.pow(digits)
+}
+
+/// Extracts the 31 bit value selected by the low nibble of the last byte.
+fn dynamic_truncate(tag: &[u8; 20]) -> u32 {
+    let offset = (tag[19] & 0x0f) as usize;
+    let mut bin = [0u8; 4];
+    bin.copy_from_slice(&tag[offset..offset + 4]);
+    u32::from_be_bytes(bin) & 0x7fff_ffff
+}
+
+/// Computes the HOTP code for `counter`.
+///
+/// This panics if `digits` is not between 1 and 9.
+pub fn hotp(secret: &[u8], counter: u64, digits: u32) -> u32 {
+    truncate(&hmac_sha1(secret, &counter.to_be_bytes()), digits)
+}
+
+/// Computes the TOTP code for the Unix timestamp `time`.
+///
+/// This panics if `step` is zero or `digits` is not between 1 and 9.
+pub fn totp(secret: &[u8], time: u64, step: u64, digits: u32) -> u32 {
+    assert!(step > 0, "step must not be zero");
+    hotp(secret, time / step, digits)
+}
+
+/// Checks a TOTP `code` for the Unix timestamp `time`.
+///
+/// Codes of up to `window` steps before or after `time` are accepted.  The
+/// return value is the offset in steps of the matching code, negative if
+/// the code is from the past, or `None` if no code in the window matches.
+/// All codes in the window are computed and compared, so the time taken
+/// does not depend on which one matched.
+///
+/// This panics if `step` is zero or `digits` is not between 1 and 9.
+pub fn verify(
+    secret: &[u8],
+    code: u32,
+    time: u64,
+    step: u64,
+    digits: u32,
+    window: u32,
+) -> Option<i64> {
+    assert!(step > 0, "step must not be zero");
+    let counter = time / step;
+    let mut rv = None;
+    for offset in -(window as i64)..=(window as i64) {
+        let candidate = if offset < 0 {
+            counter.checked_sub(offset.unsigned_abs())
+        } else {
+            counter.checked_add(offset as u64)
+        };
+        if let Some(candidate) = candidate {
+            let matches = hotp(secret, candidate, digits) == code;
+            if matches && rv.map_or(true, |prev: i64| offset.abs() < prev.abs()) {
+                rv = Some(offset);
+            }
+        }
+    }
+    rv
+}
+
+/// The characters of Steam Guard codes.
+const STEAM_ALPHABET: &[u8; 26] = b"23456789BCDFGHJKMNPQRTVWXY";
+
+/// Computes the Steam Guard code for the Unix timestamp `time`.
+///
+/// Steam uses TOTP with a 30 second step but encodes the truncated value as
+/// five characters of a custom alphabet instead of decimal digits.  The
+/// `secret` is the decoded `shared_secret` of the account.
+///
+/// ```
+/// let code = sha1_smol::otp::steam(b"12345678901234567890", 59);
+/// assert_eq!(&code, b"PV9M4");
+/// ```
+pub fn steam(secret: &[u8], time: u64) -> [u8; 5] {
+    let tag = hmac_sha1(secret, &(time / 30).to_be_bytes());
+    let mut value = dynamic_truncate(&tag);
+    let mut rv = [0u8; 5];
+    for c in rv.iter_mut() {
+        *c = STEAM_ALPHABET[(value % 26) as usize];
+        value /= 26;
+    }
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_hotp() {
+        // RFC 4226 appendix D
+        let expected = [
+            755224, 287082, 359152, 969429, 338314, 254676, 287922, 162583, 399871, 520489,
+        ];
+        for (counter, &code) in expected.iter().enumerate() {
+            assert_eq!(hotp(SECRET, counter as u64, 6), code, "counter {}", counter);
+        }
+    }
+
+    #[test]
+    fn test_truncate() {
+        // RFC 4226 section 5.4
+        let tag = [
+            0x1f, 0x86, 0x98, 0x69, 0x0e, 0x02, 0xca, 0x16, 0x61, 0x85, 0x50, 0xef, 0x7f, 0x19,
+            0xda, 0x8e, 0x94, 0x5b, 0x55, 0x5a,
+        ];
+        assert_eq!(truncate(&tag, 9), 357872921);
+        assert_eq!(truncate(&tag, 6), 872921);
+    }
+
+    #[test]
+    fn test_totp() {
+        // RFC 6238 appendix B
+        for &(time, code) in &[
+            (59, 94287082),
+            (1111111109, 7081804),
+            (1111111111, 14050471),
+            (1234567890, 89005924),
+            (2000000000, 69279037),
+            (20000000000, 65353130),
+        ] {
+            assert_eq!(totp(SECRET, time, 30, 8), code, "time {}", time);
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let code = totp(SECRET, 1234567890, 30, 6);
+        assert_eq!(verify(SECRET, code, 1234567890, 30, 6, 0), Some(0));
+        assert_eq!(verify(SECRET, code, 1234567890 + 30, 30, 6, 0), None);
+        assert_eq!(verify(SECRET, code, 1234567890 + 30, 30, 6, 1), Some(-1));
+        assert_eq!(verify(SECRET, code, 1234567890 - 60, 30, 6, 2), Some(2));
+        assert_eq!(verify(SECRET, code, 1234567890 - 90, 30, 6, 2), None);
+
+        // the window must not wrap around at the epoch
+        let first = totp(SECRET, 0, 30, 6);
+        assert_eq!(verify(SECRET, first, 10, 30, 6, 3), Some(0));
+    }
+
+    #[test]
+    fn test_steam() {
+        // reference values computed with an independent python implementation
+        for &(time, code) in &[
+            (0, b"GG5F5"),
+            (1111111109, b"PY4YB"),
+            (1234567890, b"VHHQY"),
+            (2000000000, b"9N776"),
+        ] {
+            assert_eq!(&steam(SECRET, time), code, "time {}", time);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "digits must be between 1 and 9")]
+    fn test_invalid_digits() {
+        hotp(SECRET, 0, 10);
+    }
+}
@@ -0,0 +1,367 @@
+//! `otpauth://` provisioning URIs.
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+//!
+//! Authenticator apps import HOTP and TOTP secrets from URIs of the form
+//! `otpauth://totp/Issuer:account?secret=BASE32&issuer=Issuer`, usually
+//! shown as a QR code.  [`OtpAuth`] parses and formats these URIs and
+//! computes the codes for the secret they describe:
+//!
+//! ```
+//! use sha1_smol::otpauth::OtpAuth;
+//!
+//! let uri = "otpauth://totp/Example:alice@google.com?secret=JBSWY3DPEHPK3PXP&issuer=Example";
+//! let auth: OtpAuth = uri.parse().unwrap();
+//! assert_eq!(auth.account, "alice@google.com");
+//! assert_eq!(auth.issuer.as_deref(), Some("Example"));
+//! let code = auth.code(1_234_567_890);
+//! # let _ = code;
+//!
+//! let uri = auth.to_string();
+//! assert_eq!(uri.parse::<OtpAuth>().unwrap(), auth);
+//! ```
+//!
+//! Only the SHA-1 algorithm is supported, URIs requesting another one are
+//! rejected.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str;
+
+use crate::encoding::{decode_b32, encode_b32};
+use crate::otp;
+use crate::query::{percent_decode, percent_encode};
+
+/// The default number of digits.
+pub const DEFAULT_DIGITS: u32 = 6;
+
+/// The default TOTP period in seconds.
+pub const DEFAULT_PERIOD: u64 = 30;
+
+/// Indicates that an `otpauth://` URI could not be parsed.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum OtpAuthError {
+    /// The input is not an `otpauth://totp/` or `otpauth://hotp/` URI.
+    InvalidUri,
+    /// The secret is missing or not valid base32.
+    InvalidSecret,
+    /// The URI requests an algorithm other than SHA-1.
+    UnsupportedAlgorithm,
+    /// A parameter has an invalid value or a required one is missing.
+    InvalidParameter,
+}
+
+impl OtpAuthError {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            OtpAuthError::InvalidUri => "invalid otpauth uri",
+            OtpAuthError::InvalidSecret => "invalid otpauth secret",
+            OtpAuthError::UnsupportedAlgorithm => "unsupported otpauth algorithm",
+            OtpAuthError::InvalidParameter => "invalid otpauth parameter",
+        }
+    }
+}
+
+impl fmt::Display for OtpAuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OtpAuthError {
+    fn description(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// The type of one-time password.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum OtpKind {
+    /// Time based codes (RFC 6238) with a period in seconds.
+    Totp {
+        /// The length of a time step in seconds.
+        period: u64,
+    },
+    /// Counter based codes (RFC 4226).
+    Hotp {
+        /// The counter value of the next code.
+        counter: u64,
+    },
+}
+
+/// The contents of an `otpauth://` URI.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OtpAuth {
+    /// The type of the codes.
+    pub kind: OtpKind,
+    /// The account name, usually a user name or email address.
+    pub account: String,
+    /// The provider of the account.
+    pub issuer: Option<String>,
+    /// The raw shared secret.
+    pub secret: Vec<u8>,
+    /// The number of digits of a code.
+    pub digits: u32,
+}
+
+impl OtpAuth {
+    /// Creates a TOTP entry with the default period and digits.
+    pub fn totp(account: &str, secret: &[u8]) -> OtpAuth {
+        OtpAuth::new(
+            OtpKind::Totp {
+                period: DEFAULT_PERIOD,
+            },
+            account,
+            secret,
+        )
+    }
+
+    /// Creates an HOTP entry starting at `counter` with the default digits.
+    pub fn hotp(account: &str, secret: &[u8], counter: u64) -> OtpAuth {
+        OtpAuth::new(OtpKind::Hotp { counter }, account, secret)
+    }
+
+    fn new(kind: OtpKind, account: &str, secret: &[u8]) -> OtpAuth {
+        OtpAuth {
+            kind,
+            account: account.into(),
+            issuer: None,
+            secret: secret.into(),
+            digits: DEFAULT_DIGITS,
+        }
+    }
+
+    /// Computes a code.
+    ///
+    /// For TOTP `value` is the Unix timestamp, for HOTP it is the counter.
+    pub fn code(&self, value: u64) -> u32 {
+        match self.kind {
+            OtpKind::Totp { period } => otp::totp(&self.secret, value, period, self.digits),
+            OtpKind::Hotp { .. } => otp::hotp(&self.secret, value, self.digits),
+        }
+    }
+}
+
+fn decode_str(input: &str) -> Result<String, OtpAuthError> {
+    percent_decode(input)
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or(OtpAuthError::InvalidUri)
+}
+
+fn strip_prefix_ignore_case<'a>(input: &'a str, prefix: &str) -> Option<&'a str> {
+    match input.get(..prefix.len()) {
+        Some(head) if head.eq_ignore_ascii_case(prefix) => Some(&input[prefix.len()..]),
+        _ => None,
+    }
+}
+
+fn parse_number<T: str::FromStr>(value: &str) -> Result<T, OtpAuthError> {
+    value.parse().map_err(|_| OtpAuthError::InvalidParameter)
+}
+
+impl str::FromStr for OtpAuth {
+    type Err = OtpAuthError;
+
+    fn from_str(s: &str) -> Result<OtpAuth, OtpAuthError> {
+        let rest = strip_prefix_ignore_case(s, "otpauth://").ok_or(OtpAuthError::InvalidUri)?;
+        let (totp, rest) = if let Some(rest) = strip_prefix_ignore_case(rest, "totp/") {
+            (true, rest)
+        } else if let Some(rest) = strip_prefix_ignore_case(rest, "hotp/") {
+            (false, rest)
+        } else {
+            return Err(OtpAuthError::InvalidUri);
+        };
+        let mut parts = rest.splitn(2, '?');
+        let raw_label = parts.next().unwrap_or("");
+        let query = parts.next().unwrap_or("");
+
+        // a literal colon separates the issuer prefix, an encoded one is
+        // only treated as separator if there is no literal one
+        let (mut issuer, account) = match raw_label.find(':') {
+            Some(idx) => (
+                Some(decode_str(&raw_label[..idx])?).filter(|issuer| !issuer.is_empty()),
+                decode_str(&raw_label[idx + 1..])?,
+            ),
+            None => {
+                let label = decode_str(raw_label)?;
+                match label.find(':') {
+                    Some(idx) => (Some(label[..idx].into()), label[idx + 1..].into()),
+                    None => (None, label),
+                }
+            }
+        };
+        let account = account.trim_start_matches(' ');
+        let mut secret = None;
+        let mut digits = DEFAULT_DIGITS;
+        let mut period = DEFAULT_PERIOD;
+        let mut counter = None;
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let mut kv = pair.splitn(2, '=');
+            let key = kv.next().unwrap_or("");
+            let value = decode_str(kv.next().ok_or(OtpAuthError::InvalidParameter)?)?;
+            match key {
+                "secret" => {
+                    let raw = decode_b32(value.as_bytes()).ok_or(OtpAuthError::InvalidSecret)?;
+                    secret = Some(raw);
+                }
+                "issuer" => issuer = Some(value),
+                "algorithm" if !value.eq_ignore_ascii_case("SHA1") => {
+                    return Err(OtpAuthError::UnsupportedAlgorithm);
+                }
+                "digits" => digits = parse_number(&value)?,
+                "period" => period = parse_number(&value)?,
+                "counter" => counter = Some(parse_number(&value)?),
+                _ => {}
+            }
+        }
+        if !(1..=9).contains(&digits) || period == 0 {
+            return Err(OtpAuthError::InvalidParameter);
+        }
+        let kind = if totp {
+            OtpKind::Totp { period }
+        } else {
+            OtpKind::Hotp {
+                counter: counter.ok_or(OtpAuthError::InvalidParameter)?,
+            }
+        };
+        Ok(OtpAuth {
+            kind,
+            account: account.into(),
+            issuer,
+            secret: secret.ok_or(OtpAuthError::InvalidSecret)?,
+            digits,
+        })
+    }
+}
+
+impl fmt::Display for OtpAuth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            OtpKind::Totp { .. } => "totp",
+            OtpKind::Hotp { .. } => "hotp",
+        };
+        write!(f, "otpauth://{}/", kind)?;
+        if let Some(ref issuer) = self.issuer {
+            write!(f, "{}:", percent_encode(issuer))?;
+        } else if self.account.contains(':') {
+            // an empty issuer keeps the encoded colon out of the separator
+            f.write_str(":")?;
+        }
+        write!(
+            f,
+            "{}?secret={}",
+            percent_encode(&self.account),
+            encode_b32(&self.secret)
+        )?;
+        if let Some(ref issuer) = self.issuer {
+            write!(f, "&issuer={}", percent_encode(issuer))?;
+        }
+        write!(f, "&algorithm=SHA1&digits={}", self.digits)?;
+        match self.kind {
+            OtpKind::Totp { period } => write!(f, "&period={}", period),
+            OtpKind::Hotp { counter } => write!(f, "&counter={}", counter),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_parse() {
+        let auth: OtpAuth = "otpauth://totp/ACME%20Co:john.doe@email.com?\
+                             secret=HXDMVJECJJWSRB3HWIZR4IFUGFTMXBOZ&issuer=ACME%20Co\
+                             &algorithm=SHA1&digits=6&period=30"
+            .parse()
+            .unwrap();
+        assert_eq!(auth.kind, OtpKind::Totp { period: 30 });
+        assert_eq!(auth.account, "john.doe@email.com");
+        assert_eq!(auth.issuer.as_deref(), Some("ACME Co"));
+        assert_eq!(auth.secret.len(), 20);
+        assert_eq!(auth.digits, 6);
+
+        let auth: OtpAuth =
+            "otpauth://hotp/alice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&counter=4"
+                .parse()
+                .unwrap();
+        assert_eq!(auth.kind, OtpKind::Hotp { counter: 4 });
+        assert_eq!(auth.issuer, None);
+        assert_eq!(auth.secret, b"12345678901234567890");
+        assert_eq!(auth.code(0), 755224);
+
+        let auth: OtpAuth = "otpauth://totp/Example%3A%20alice?secret=MZXQ"
+            .parse()
+            .unwrap();
+        assert_eq!(auth.issuer.as_deref(), Some("Example"));
+        assert_eq!(auth.account, "alice");
+    }
+
+    #[test]
+    fn test_code() {
+        let mut auth = OtpAuth::totp("alice", b"12345678901234567890");
+        auth.digits = 8;
+        assert_eq!(auth.code(59), 94287082);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut auth = OtpAuth::hotp("alice & bob", b"\x00\x01\x02secret", 7);
+        auth.issuer = Some("Example: Inc".into());
+        let uri = auth.to_string();
+        assert_eq!(
+            uri,
+            "otpauth://hotp/Example%3A%20Inc:alice%20%26%20bob?secret=AAAQE43FMNZGK5A\
+             &issuer=Example%3A%20Inc&algorithm=SHA1&digits=6&counter=7"
+        );
+        assert_eq!(uri.parse::<OtpAuth>().unwrap(), auth);
+    }
+
+    #[test]
+    fn test_roundtrip_colon_account() {
+        let auth = OtpAuth::totp("host:alice", b"secret");
+        let uri = auth.to_string();
+        assert!(uri.starts_with("otpauth://totp/:host%3Aalice?"), "{}", uri);
+        assert_eq!(uri.parse::<OtpAuth>().unwrap(), auth);
+    }
+
+    #[test]
+    fn test_roundtrip_trailing_bits() {
+        let auth: OtpAuth = "otpauth://totp/alice?secret=MZXR".parse().unwrap();
+        assert_eq!(auth.secret, b"fo");
+        let uri = auth.to_string();
+        assert!(uri.contains("secret=MZXQ&"), "{}", uri);
+        assert_eq!(uri.parse::<OtpAuth>().unwrap(), auth);
+    }
+
+    #[test]
+    fn test_errors() {
+        for &(uri, err) in &[
+            ("https://example.com", OtpAuthError::InvalidUri),
+            ("otpauth://motp/a?secret=AA", OtpAuthError::InvalidUri),
+            ("otpauth://totp/a", OtpAuthError::InvalidSecret),
+            ("otpauth://totp/a?secret=1", OtpAuthError::InvalidSecret),
+            (
+                "otpauth://totp/a?secret=MZXQ&algorithm=SHA256",
+                OtpAuthError::UnsupportedAlgorithm,
+            ),
+            (
+                "otpauth://totp/a?secret=MZXQ&digits=12",
+                OtpAuthError::InvalidParameter,
+            ),
+            (
+                "otpauth://totp/a?secret=MZXQ&period=0",
+                OtpAuthError::InvalidParameter,
+            ),
+            (
+                "otpauth://hotp/a?secret=MZXQ",
+                OtpAuthError::InvalidParameter,
+            ),
+        ] {
+            assert_eq!(uri.parse::<OtpAuth>(), Err(err), "uri {:?}", uri);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Hashing of file paths in a platform independent form.
+//!
+//! Manifests that identify files by the hash of their path only compare
+//! equal across systems if every system hashes the same bytes.  The
+//! functions in this module normalize a path before hashing it:
+//!
+//! * the path is hashed as UTF-8,
+//! * both `/` and `\` are separators and are hashed as `/`,
+//! * empty components and `.` components are dropped, so repeated and
+//!   trailing separators don't matter,
+//! * a leading separator is kept so absolute and relative paths differ,
+//! * `..` components and the case of the path are kept as they are.
+//!
+//! macOS stores file names in decomposed Unicode form while most other
+//! systems keep whatever form the name was created with.  The `_nfc`
+//! variants additionally convert the path to Unicode normalization form C,
+//! they are only available if the `unicode-normalization` feature is
+//! enabled.
+//!
+//! ```
+//! use sha1_smol::paths::hash_path;
+//!
+//! assert_eq!(hash_path("src\\lib.rs"), hash_path("src/lib.rs"));
+//! assert_eq!(hash_path("./src//lib.rs"), hash_path("src/lib.rs"));
+//! ```
+
+use crate::{Digest, Sha1};
+
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split(|c| c == '/' || c == '\\')
+        .filter(|comp| !comp.is_empty() && *comp != ".")
+}
+
+fn is_absolute(path: &str) -> bool {
+    path.starts_with('/') || path.starts_with('\\')
+}
+
+/// Feeds the normalized form of `path` into `hasher`.
+///
+/// This is useful to include paths in a larger hash, such as the hash of a
+/// whole manifest.
+pub fn update_path(hasher: &mut Sha1, path: &str) {
+    if is_absolute(path) {
+        hasher.update(b"/");
+    }
+    for (idx, comp) in components(path).enumerate() {
+        if idx > 0 {
+            hasher.update(b"/");
+        }
+        hasher.update(comp.as_bytes());
+    }
+}
+
+/// Hashes the normalized form of `path`.
+pub fn hash_path(path: &str) -> Digest {
+    let mut hasher = Sha1::new();
+    update_path(&mut hasher, path);
+    hasher.digest()
+}
+
+/// Feeds the normalized form of `path` converted to NFC into `hasher`.
+///
+/// (This function is only available if the `unicode-normalization` feature
+/// is enabled)
+#[cfg(feature = "unicode-normalization")]
+pub fn update_path_nfc(hasher: &mut Sha1, path: &str) {
+    use unicode_normalization::UnicodeNormalization;
+
+    if is_absolute(path) {
+        hasher.update(b"/");
+    }
+    let mut buf = [0u8; 4];
+    for (idx, comp) in components(path).enumerate() {
+        if idx > 0 {
+            hasher.update(b"/");
+        }
+        for c in comp.nfc() {
+            hasher.update(c.encode_utf8(&mut buf).as_bytes());
+        }
+    }
+}
+
+/// Hashes the normalized form of `path` converted to NFC.
+///
+/// (This function is only available if the `unicode-normalization` feature
+/// is enabled)
+#[cfg(feature = "unicode-normalization")]
+pub fn hash_path_nfc(path: &str) -> Digest {
+    let mut hasher = Sha1::new();
+    update_path_nfc(&mut hasher, path);
+    hasher.digest()
+}
+
+/// Hashes the normalized form of a [`std::path::Path`].
+///
+/// Returns `None` if the path is not valid UTF-8.
+///
+/// (This function is only available if the `std` feature is enabled)
+#[cfg(feature = "std")]
+pub fn hash_std_path(path: &std::path::Path) -> Option<Digest> {
+    path.to_str().map(hash_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalization() {
+        let expected = Sha1::from("src/paths.rs").digest();
+        for path in &[
+            "src/paths.rs",
+            "src\\paths.rs",
+            "./src/paths.rs",
+            "src//paths.rs/",
+            ".\\src\\.\\paths.rs",
+        ] {
+            assert_eq!(hash_path(path), expected, "path {:?}", path);
+        }
+        assert_eq!(
+            hash_path("/src/paths.rs"),
+            Sha1::from("/src/paths.rs").digest()
+        );
+        assert_eq!(hash_path("\\src\\"), Sha1::from("/src").digest());
+        assert_eq!(hash_path("../a"), Sha1::from("../a").digest());
+        assert_eq!(hash_path(""), Sha1::new().digest());
+        assert_ne!(hash_path("Src/paths.rs"), expected);
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-normalization")]
+    fn test_nfc() {
+        let composed = "caf\u{e9}/men\u{fc}.txt";
+        let decomposed = "cafe\u{301}\\menu\u{308}.txt";
+        assert_ne!(hash_path(composed), hash_path(decomposed));
+        assert_eq!(hash_path_nfc(decomposed), hash_path(composed));
+        assert_eq!(hash_path_nfc(composed), hash_path(composed));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_std_path() {
+        let path = std::path::Path::new("src").join("paths.rs");
+        assert_eq!(hash_std_path(&path), Some(hash_path("src/paths.rs")));
+    }
+}
@@ -0,0 +1,58 @@
+//! PBKDF1 with SHA-1 (PKCS #5 v1.5, RFC 8018 section 5.1).
+//!
+//! PBKDF1 is only recommended for compatibility with existing applications
+//! such as legacy encrypted PKCS #8 and PEM files.  New code should use
+//! [`crate::pbkdf2`] instead.
+//!
+//! ```
+//! let mut key = [0u8; 16];
+//! sha1_smol::pbkdf1::derive(b"password", b"salt", 1000, &mut key);
+//! ```
+
+use crate::{Sha1, DIGEST_LENGTH};
+
+/// Derives a key of up to 20 bytes from `password` and `salt` into `out`.
+///
+/// This panics if `iterations` is zero or if `out` is longer than a SHA-1
+/// digest.
+pub fn derive(password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) {
+    assert!(iterations > 0, "iteration count must not be zero");
+    assert!(out.len() <= DIGEST_LENGTH, "derived key too long");
+    let mut m = Sha1::new();
+    m.update(password);
+    m.update(salt);
+    let mut t = m.digest().bytes();
+    for _ in 1..iterations {
+        t = Sha1::from(t).digest().bytes();
+    }
+    out.copy_from_slice(&t[..out.len()]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive() {
+        let salt = [0x78, 0x57, 0x8e, 0x5a, 0x5d, 0x63, 0xcb, 0x06];
+        let mut out = [0u8; 16];
+        derive(b"password", &salt, 1000, &mut out);
+        assert_eq!(
+            out,
+            [
+                0xdc, 0x19, 0x84, 0x7e, 0x05, 0xc6, 0x4d, 0x2f, 0xaf, 0x10, 0xeb, 0xfb, 0x4a, 0x3d,
+                0x2a, 0x20
+            ]
+        );
+
+        let mut one = [0u8; 20];
+        derive(b"password", b"salt", 1, &mut one);
+        assert_eq!(one, Sha1::from("passwordsalt").digest().bytes());
+    }
+
+    #[test]
+    #[should_panic(expected = "derived key too long")]
+    fn test_too_long() {
+        derive(b"password", b"salt", 1, &mut [0u8; 21]);
+    }
+}
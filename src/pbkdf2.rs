@@ -0,0 +1,259 @@
+//! PBKDF2 with HMAC-SHA1 (RFC 2898 / RFC 8018).
+//!
+//! ```
+//! let mut key = [0u8; 16];
+//! sha1_smol::pbkdf2::derive(b"password", b"salt", 4096, &mut key);
+//! ```
+//!
+//! High iteration counts take a while.  [`derive_with_progress`] reports
+//! progress to a callback which can also cancel the derivation:
+//!
+//! ```
+//! use std::ops::ControlFlow;
+//!
+//! let mut key = [0u8; 20];
+//! let rv = sha1_smol::pbkdf2::derive_with_progress(
+//!     b"password", b"salt", 100_000, &mut key, 10_000,
+//!     |done, total| {
+//!         println!("{}/{}", done, total);
+//!         ControlFlow::Continue(())
+//!     },
+//! );
+//! assert!(rv.is_ok());
+//! ```
+
+use core::fmt;
+use core::ops::ControlFlow;
+
+use crate::hmac::HmacKey;
+use crate::DIGEST_LENGTH;
+
+/// Indicates that a derivation was cancelled by the progress callback.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct Cancelled(());
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "key derivation cancelled")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Cancelled {
+    fn description(&self) -> &str {
+        "key derivation cancelled"
+    }
+}
+
+/// Computes the block `T_index` into `out` (at most 20 bytes).
+///
+/// `tick` is invoked after every iteration and can stop the computation.
+fn derive_block<F>(
+    prf: &HmacKey,
+    salt: &[u8],
+    iterations: u32,
+    index: u32,
+    out: &mut [u8],
+    tick: &mut F,
+) -> ControlFlow<()>
+where
+    F: FnMut() -> ControlFlow<()>,
+{
+    let mut m = prf.hmac();
+    m.update(salt);
+    m.update(&index.to_be_bytes());
+    let mut u = m.digest().bytes();
+    let mut t = u;
+    tick()?;
+    for _ in 1..iterations {
+        let mut m = prf.hmac();
+        m.update(&u);
+        u = m.digest().bytes();
+        for (t, u) in t.iter_mut().zip(u.iter()) {
+            *t ^= u;
+        }
+        tick()?;
+    }
+    out.copy_from_slice(&t[..out.len()]);
+    ControlFlow::Continue(())
+}
+
+fn check_params(iterations: u32, out: &[u8]) {
+    assert!(iterations > 0, "iteration count must not be zero");
+    assert!(
+        (out.len() as u64) <= u32::MAX as u64 * DIGEST_LENGTH as u64,
+        "derived key too long"
+    );
+}
+
+/// Derives a key from `password` and `salt` filling all of `out`.
+///
+/// This panics if `iterations` is zero or if `out` is longer than the
+/// `(2^32 - 1) * 20` bytes PBKDF2 can produce.
+pub fn derive(password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) {
+    check_params(iterations, out);
+    let prf = HmacKey::new(password);
+    for (idx, chunk) in out.chunks_mut(DIGEST_LENGTH).enumerate() {
+        let _ = derive_block(&prf, salt, iterations, idx as u32 + 1, chunk, &mut || {
+            ControlFlow::Continue(())
+        });
+    }
+}
+
+/// Like [`derive()`] but computes the output blocks on the `rayon` thread pool.
+///
+/// Every 20 byte block of the output is derived independently, so for
+/// outputs longer than a single SHA-1 digest this spreads the work over
+/// multiple threads.  The result is identical to [`derive()`].
+///
+/// (This function is only available if the `rayon` feature is enabled)
+#[cfg(feature = "rayon")]
+pub fn derive_parallel(password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) {
+    use rayon::prelude::*;
+
+    check_params(iterations, out);
+    let prf = HmacKey::new(password);
+    out.par_chunks_mut(DIGEST_LENGTH)
+        .enumerate()
+        .for_each(|(idx, chunk)| {
+            let _ = derive_block(&prf, salt, iterations, idx as u32 + 1, chunk, &mut || {
+                ControlFlow::Continue(())
+            });
+        });
+}
+
+/// Like [`derive()`] but reports progress and supports cancellation.
+///
+/// `callback` is invoked with the number of completed and total iterations
+/// (over all output blocks) every `every` iterations.  If it returns
+/// `ControlFlow::Break` the derivation stops, `out` is zeroed and
+/// `Cancelled` is returned.
+///
+/// This panics under the same conditions as [`derive()`] or if `every` is
+/// zero.
+pub fn derive_with_progress<F>(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    out: &mut [u8],
+    every: u32,
+    mut callback: F,
+) -> Result<(), Cancelled>
+where
+    F: FnMut(u64, u64) -> ControlFlow<()>,
+{
+    check_params(iterations, out);
+    assert!(every > 0, "progress interval must not be zero");
+    let blocks = (out.len() + DIGEST_LENGTH - 1) / DIGEST_LENGTH;
+    let total = iterations as u64 * blocks as u64;
+    let mut done = 0u64;
+    let mut tick = || {
+        done += 1;
+        if done % every as u64 == 0 {
+            callback(done, total)
+        } else {
+            ControlFlow::Continue(())
+        }
+    };
+    let prf = HmacKey::new(password);
+    for (idx, chunk) in out.chunks_mut(DIGEST_LENGTH).enumerate() {
+        if derive_block(&prf, salt, iterations, idx as u32 + 1, chunk, &mut tick)
+            == ControlFlow::Break(())
+        {
+            for byte in out.iter_mut() {
+                *byte = 0;
+            }
+            return Err(Cancelled(()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::unhex;
+
+    fn check<const N: usize>(password: &[u8], salt: &[u8], iterations: u32, expected: &str) {
+        let mut out = [0u8; N];
+        derive(password, salt, iterations, &mut out);
+        assert_eq!(out, unhex::<N>(expected));
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut out = [0u8; 25];
+        let mut calls = 0;
+        let rv = derive_with_progress(b"password", b"salt", 10, &mut out, 4, |done, total| {
+            calls += 1;
+            assert_eq!(total, 20);
+            assert_eq!(done % 4, 0);
+            ControlFlow::Continue(())
+        });
+        assert_eq!(rv, Ok(()));
+        assert_eq!(calls, 5);
+        let mut expected = [0u8; 25];
+        derive(b"password", b"salt", 10, &mut expected);
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_derive_parallel() {
+        for &len in &[1, 20, 21, 64, 100] {
+            let mut expected = [0u8; 100];
+            let mut out = [0u8; 100];
+            derive(b"password", b"salt", 50, &mut expected[..len]);
+            derive_parallel(b"password", b"salt", 50, &mut out[..len]);
+            assert_eq!(&out[..len], &expected[..len]);
+        }
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut out = [1u8; 20];
+        let rv = derive_with_progress(b"password", b"salt", 1000, &mut out, 10, |done, _| {
+            if done >= 50 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert!(rv.is_err());
+        assert_eq!(out, [0u8; 20]);
+    }
+
+    #[test]
+    fn test_rfc6070_vectors() {
+        check::<20>(
+            b"password",
+            b"salt",
+            1,
+            "0c60c80f961f0e71f3a9b524af6012062fe037a6",
+        );
+        check::<20>(
+            b"password",
+            b"salt",
+            2,
+            "ea6c014dc72d6f8ccd1ed92ace1d41f0d8de8957",
+        );
+        check::<20>(
+            b"password",
+            b"salt",
+            4096,
+            "4b007901b765489abead49d926f721d065a429c1",
+        );
+        check::<25>(
+            b"passwordPASSWORDpassword",
+            b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+            4096,
+            "3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038",
+        );
+        check::<16>(
+            b"pass\0word",
+            b"sa\0lt",
+            4096,
+            "56fa6aa75548099dcc37d7f03425e0c3",
+        );
+    }
+}
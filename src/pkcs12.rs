@@ -0,0 +1,129 @@
+//! The PKCS #12 key derivation function with SHA-1 (RFC 7292 appendix B).
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+//!
+//! Legacy `.p12` / `.pfx` files derive their encryption keys, IVs and MAC
+//! keys from the password with this function.  The purpose is selected by
+//! the diversifier `id`:
+//!
+//! ```
+//! use sha1_smol::pkcs12;
+//!
+//! let salt = [0x0a, 0x58, 0xcf, 0x64, 0x53, 0x0d, 0x82, 0x3f];
+//! let mut key = [0u8; 24];
+//! pkcs12::derive(pkcs12::KEY_MATERIAL, "smeg", &salt, 1, &mut key);
+//! assert_eq!(&key[..4], &[0x8a, 0xaa, 0xe6, 0x29]);
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::{Sha1, DIGEST_LENGTH};
+
+/// The diversifier for deriving encryption keys.
+pub const KEY_MATERIAL: u8 = 1;
+
+/// The diversifier for deriving initialization vectors.
+pub const IV_MATERIAL: u8 = 2;
+
+/// The diversifier for deriving MAC keys.
+pub const MAC_KEY: u8 = 3;
+
+const BLOCK_LENGTH: usize = 64;
+
+/// Appends `data` repeated up to the next multiple of the block length.
+fn extend_repeated(buf: &mut Vec<u8>, data: &[u8]) {
+    if data.is_empty() {
+        return;
+    }
+    let len = (data.len() + BLOCK_LENGTH - 1) / BLOCK_LENGTH * BLOCK_LENGTH;
+    buf.extend(data.iter().cycle().take(len));
+}
+
+/// Derives `out` from `password` and `salt`.
+///
+/// The password is encoded as a NUL terminated big-endian BMPString as the
+/// specification requires.  `id` is one of [`KEY_MATERIAL`],
+/// [`IV_MATERIAL`] or [`MAC_KEY`].
+///
+/// This panics if `iterations` is zero.
+pub fn derive(id: u8, password: &str, salt: &[u8], iterations: u32, out: &mut [u8]) {
+    assert!(iterations > 0, "iteration count must not be zero");
+    let mut bmp = Vec::with_capacity((password.len() + 1) * 2);
+    for unit in password.encode_utf16().chain(Some(0)) {
+        bmp.extend_from_slice(&unit.to_be_bytes());
+    }
+
+    let mut input = Vec::new();
+    extend_repeated(&mut input, salt);
+    extend_repeated(&mut input, &bmp);
+
+    let diversifier = [id; BLOCK_LENGTH];
+    let mut chunks = out.chunks_mut(DIGEST_LENGTH).peekable();
+    while let Some(chunk) = chunks.next() {
+        let mut m = Sha1::new();
+        m.update(&diversifier);
+        m.update(&input);
+        let mut a = m.digest().bytes();
+        for _ in 1..iterations {
+            a = Sha1::from(a).digest().bytes();
+        }
+        chunk.copy_from_slice(&a[..chunk.len()]);
+        if chunks.peek().is_none() {
+            break;
+        }
+
+        // I_j = (I_j + B + 1) mod 2^512 for every block of the input
+        let mut b = [0u8; BLOCK_LENGTH];
+        for (b, a) in b.iter_mut().zip(a.iter().cycle()) {
+            *b = *a;
+        }
+        for block in input.chunks_mut(BLOCK_LENGTH) {
+            let mut carry = 1u16;
+            for (i, b) in block.iter_mut().zip(b.iter()).rev() {
+                let sum = *i as u16 + *b as u16 + carry;
+                *i = sum as u8;
+                carry = sum >> 8;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::unhex;
+
+    fn check<const N: usize>(id: u8, password: &str, salt: &[u8], iterations: u32, expected: &str) {
+        let mut out = [0u8; N];
+        derive(id, password, salt, iterations, &mut out);
+        assert_eq!(out, unhex::<N>(expected));
+    }
+
+    #[test]
+    fn test_derive() {
+        // reference values from OpenSSL's PKCS12KDF
+        check::<24>(
+            KEY_MATERIAL,
+            "smeg",
+            &[0x0a, 0x58, 0xcf, 0x64, 0x53, 0x0d, 0x82, 0x3f],
+            1,
+            "8aaae6297b6cb04642ab5b077851284eb7128f1a2a7fbca3",
+        );
+        check::<20>(
+            MAC_KEY,
+            "queeg",
+            &[0x3d, 0x83, 0xc0, 0xe4, 0x54, 0x6a, 0xc1, 0x40],
+            1000,
+            "17b9e78ea534fc2b6a35512d03799d9ea3c461c0",
+        );
+        check::<70>(
+            KEY_MATERIAL,
+            "password",
+            b"long salt value that is longer than one block of sixty four bytes!!",
+            3,
+            "884469b704d8dcfa672891f7816efde18921379c397dd15c44886666874017\
+             5c1641a8d79e8aa85ef378404bdf41c68a27d5e3dff89bacb9f92f79cf3accd6\
+             f6db30ddc304b3",
+        );
+    }
+}
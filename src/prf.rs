@@ -0,0 +1,89 @@
+//! The FIPS 186-2 pseudo random function used by EAP-SIM and EAP-AKA.
+//!
+//! EAP-SIM (RFC 4186) and EAP-AKA (RFC 4187) derive their session keys
+//! with the general purpose random number generator of FIPS 186-2 change
+//! notice 1 (appendix 3.1, with `b = 160` and no `XSEED`).  Its `G` function
+//! is the bare SHA-1 compression function applied to the input padded with
+//! zeros, without the regular SHA-1 length padding.
+//!
+//! ```
+//! # let xkey = [0u8; 20];
+//! let mut keys = [0u8; 160];
+//! sha1_smol::prf::fips186_2(&xkey, &mut keys);
+//! let (k_encr, rest) = keys.split_at(16);
+//! let (k_aut, rest) = rest.split_at(16);
+//! let (msk, emsk) = rest.split_at(64);
+//! ```
+
+use crate::{DEFAULT_STATE, DIGEST_LENGTH};
+
+/// `G(t, c)`: one compression of `c` padded with zeros from the SHA-1 IV.
+fn g(c: &[u8; DIGEST_LENGTH]) -> [u8; DIGEST_LENGTH] {
+    let mut block = [0u8; 64];
+    block[..DIGEST_LENGTH].copy_from_slice(c);
+    let mut state = DEFAULT_STATE;
+    state.process(&block);
+    let mut rv = [0u8; DIGEST_LENGTH];
+    for (chunk, word) in rv.chunks_mut(4).zip(state.state.iter()) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    rv
+}
+
+/// Computes `xkey = (1 + xkey + w) mod 2^160`.
+fn advance(xkey: &mut [u8; DIGEST_LENGTH], w: &[u8; DIGEST_LENGTH]) {
+    let mut carry = 1u16;
+    for (x, w) in xkey.iter_mut().zip(w.iter()).rev() {
+        let sum = *x as u16 + *w as u16 + carry;
+        *x = sum as u8;
+        carry = sum >> 8;
+    }
+}
+
+/// Fills `out` with the FIPS 186-2 PRF output for the key `xkey`.
+///
+/// Every invocation of `G` produces 20 bytes of output, the last one is
+/// truncated to fit.
+pub fn fips186_2(xkey: &[u8; DIGEST_LENGTH], out: &mut [u8]) {
+    let mut xkey = *xkey;
+    for chunk in out.chunks_mut(DIGEST_LENGTH) {
+        let w = g(&xkey);
+        advance(&mut xkey, &w);
+        chunk.copy_from_slice(&w[..chunk.len()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::unhex;
+
+    fn check<const N: usize>(xkey: &str, expected: &str) {
+        let mut out = [0u8; N];
+        fips186_2(&unhex::<DIGEST_LENGTH>(xkey), &mut out);
+        assert_eq!(out, unhex::<N>(expected));
+    }
+
+    #[test]
+    fn test_fips186_2_cn1_example() {
+        check::<40>(
+            "bd029bbe7f51960bcf9edb2b61f06f0feb5a38b6",
+            "2070b3223dba372fde1c0ffc7b2e3b498b260614\
+             3c6c18bacb0f6c55babb13788e20d737a3275116",
+        );
+    }
+
+    #[test]
+    fn test_rfc4186_keys() {
+        // RFC 4186 appendix A: K_encr, K_aut, MSK and EMSK
+        check::<160>(
+            "e576d5ca332e9930018bf1baee2763c795b3c712",
+            "536e5ebc4465582aa6a8ec9986ebb620\
+             25af1942efcbf4bc72b3943421f2a974\
+             39d45aeaf4e30601983e972b6cfd46d1c363773365690d09cd44976b525f47d3\
+             a60a985e955c53b090b2e4b73719196a402542968fd14a888f46b9a7886e4488\
+             5949eab0fff69d52315c6c634fd14a7f0d52023d56f79698fa6596abeed4f93f\
+             bb48eb534d985414ceed0d9a8ed33c387c9dfdab92ffbdf240fcecf65a2c93b9",
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Canonical query strings signed with HMAC-SHA1.
+//!
+//! OAuth 1.0, AWS signature version 2 and many vendor APIs sign requests by
+//! percent-encoding the parameters, sorting them and computing HMAC-SHA1
+//! over the result (usually together with the method and path).  This
+//! module provides these building blocks individually so custom schemes
+//! can be assembled on top:
+//!
+//! ```
+//! use sha1_smol::query;
+//!
+//! let canonical = query::canonicalize(&[("b", "2 3"), ("a", "1")]);
+//! assert_eq!(canonical, "a=1&b=2%203");
+//! let signature = query::sign_base64(b"secret", &canonical);
+//! # let _ = signature;
+//! ```
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::encoding::{decode_hex_into, encode_b64, Alphabet};
+use crate::hmac::HmacSha1;
+use crate::Digest;
+
+fn is_unreserved(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'-' || c == b'.' || c == b'_' || c == b'~'
+}
+
+/// Percent-encodes `input` as specified by RFC 3986.
+///
+/// Everything but the unreserved characters (`A-Z`, `a-z`, `0-9`, `-`,
+/// `.`, `_`, `~`) is encoded with uppercase hex digits.  Spaces become
+/// `%20`, never `+`.
+pub fn percent_encode<I: AsRef<[u8]>>(input: I) -> String {
+    let mut rv = String::new();
+    percent_encode_to(input.as_ref(), &mut rv);
+    rv
+}
+
+fn percent_encode_to(input: &[u8], out: &mut String) {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    for &c in input {
+        if is_unreserved(c) {
+            out.push(c as char);
+        } else {
+            out.push('%');
+            out.push(HEX[(c >> 4) as usize] as char);
+            out.push(HEX[(c & 0xf) as usize] as char);
+        }
+    }
+}
+
+/// Decodes a percent-encoded string.
+///
+/// `+` is not treated as a space.  Returns `None` if a `%` is not followed
+/// by two hex digits.
+pub fn percent_decode(input: &str) -> Option<Vec<u8>> {
+    let mut rv = Vec::with_capacity(input.len());
+    let mut bytes = input.as_bytes();
+    while let Some((&c, rest)) = bytes.split_first() {
+        if c == b'%' {
+            let mut byte = [0u8];
+            decode_hex_into(rest.get(..2)?, &mut byte)?;
+            rv.push(byte[0]);
+            bytes = &rest[2..];
+        } else {
+            rv.push(c);
+            bytes = rest;
+        }
+    }
+    Some(rv)
+}
+
+/// Builds the canonical query string for `params`.
+///
+/// Names and values are percent-encoded, the pairs are sorted by encoded
+/// name and then by encoded value and joined as `name=value` with `&`.
+pub fn canonicalize<K: AsRef<[u8]>, V: AsRef<[u8]>>(params: &[(K, V)]) -> String {
+    let mut pairs: Vec<(String, String)> = params
+        .iter()
+        .map(|(k, v)| (percent_encode(k), percent_encode(v)))
+        .collect();
+    pairs.sort();
+    let mut rv = String::new();
+    for (idx, (k, v)) in pairs.iter().enumerate() {
+        if idx > 0 {
+            rv.push('&');
+        }
+        rv.push_str(k);
+        rv.push('=');
+        rv.push_str(v);
+    }
+    rv
+}
+
+/// Computes the HMAC-SHA1 of `string_to_sign` with `key`.
+pub fn sign(key: &[u8], string_to_sign: &str) -> Digest {
+    let mut mac = HmacSha1::new(key);
+    mac.update(string_to_sign.as_bytes());
+    mac.digest()
+}
+
+/// Computes the HMAC-SHA1 of `string_to_sign` as standard base64.
+pub fn sign_base64(key: &[u8], string_to_sign: &str) -> String {
+    encode_b64(&sign(key, string_to_sign).bytes(), Alphabet::Standard, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(
+            percent_encode("Ladies + Gentlemen"),
+            "Ladies%20%2B%20Gentlemen"
+        );
+        assert_eq!(percent_encode("a-b.c_d~e"), "a-b.c_d~e");
+        assert_eq!(percent_encode("☃"), "%E2%98%83");
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(
+            percent_decode("Ladies%20%2b%20Gentlemen+").unwrap(),
+            b"Ladies + Gentlemen+"
+        );
+        assert_eq!(percent_decode("%E2%98%83").unwrap(), "☃".as_bytes());
+        assert_eq!(percent_decode("%2"), None);
+        assert_eq!(percent_decode("%zz"), None);
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let params = [("c", "3"), ("a", "2"), ("a", "1"), ("b c", "")];
+        assert_eq!(canonicalize(&params), "a=1&a=2&b%20c=&c=3");
+    }
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(
+            sign_base64(b"Jefe", "what do ya want for nothing?"),
+            "7/zfauXrL6LSdBbV8YTfnCWafHk="
+        );
+    }
+}
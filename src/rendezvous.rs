@@ -0,0 +1,183 @@
+//! Rendezvous (highest random weight) hashing based on SHA-1.
+//!
+//! Every node gets a score for a key and the key is assigned to the nodes
+//! with the highest scores.  Adding or removing a node only moves the keys
+//! that node wins or loses.
+//!
+//! The score of a node is the first 8 bytes (big endian) of
+//! `SHA1(len(key) || key || node)` where `len(key)` is the length of the key
+//! as big endian `u32`.
+//!
+//! Weighted placement uses the logarithmic method: the score is mapped into
+//! the open interval `(0, 1)` as `h` and the weighted score is
+//! `-weight / ln(h)`.  A node with twice the weight receives twice as many
+//! keys on average.
+
+use crate::Sha1;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Returns the score of `node` for `key`.
+pub fn score<K: AsRef<[u8]>, N: AsRef<[u8]>>(key: K, node: N) -> u64 {
+    let key = key.as_ref();
+    let mut m = Sha1::new();
+    m.update(&(key.len() as u32).to_be_bytes());
+    m.update(key);
+    m.update(node.as_ref());
+    let bytes = m.digest().bytes();
+    let mut rv = [0u8; 8];
+    rv.copy_from_slice(&bytes[..8]);
+    u64::from_be_bytes(rv)
+}
+
+/// Returns the index of the node with the highest score for `key`.
+///
+/// Ties go to the node that comes first.  Returns `None` if `nodes` is
+/// empty.
+///
+/// ```
+/// use sha1_smol::rendezvous;
+///
+/// let nodes = ["node-a", "node-b", "node-c"];
+/// let idx = rendezvous::winner("user:42", &nodes).unwrap();
+/// let best = rendezvous::score("user:42", nodes[idx]);
+/// assert!(nodes.iter().all(|node| rendezvous::score("user:42", node) <= best));
+/// ```
+pub fn winner<K: AsRef<[u8]>, N: AsRef<[u8]>>(key: K, nodes: &[N]) -> Option<usize> {
+    let key = key.as_ref();
+    let mut best: Option<(usize, u64)> = None;
+    for (idx, node) in nodes.iter().enumerate() {
+        let score = score(key, node);
+        if best.map_or(true, |(_, best)| score > best) {
+            best = Some((idx, score));
+        }
+    }
+    best.map(|(idx, _)| idx)
+}
+
+/// Returns the indexes of the `k` nodes with the highest scores for `key`,
+/// best node first.
+///
+/// Fewer indexes are returned if there are fewer than `k` nodes.  Ties are
+/// ordered by index.
+///
+/// (The function is only available if the `alloc` feature is enabled)
+#[cfg(feature = "alloc")]
+pub fn top_k<K: AsRef<[u8]>, N: AsRef<[u8]>>(key: K, nodes: &[N], k: usize) -> Vec<usize> {
+    let key = key.as_ref();
+    let mut scored: Vec<_> = nodes
+        .iter()
+        .enumerate()
+        .map(|(idx, node)| (core::cmp::Reverse(score(key, node)), idx))
+        .collect();
+    scored.sort_unstable();
+    scored.into_iter().take(k).map(|(_, idx)| idx).collect()
+}
+
+/// Returns the weighted score of `node` for `key`.
+///
+/// Nodes with a weight that is not positive always score `0.0`.
+///
+/// (The function is only available if the `std` feature is enabled)
+#[cfg(feature = "std")]
+pub fn weighted_score<K: AsRef<[u8]>, N: AsRef<[u8]>>(key: K, node: N, weight: f64) -> f64 {
+    if weight.is_nan() || weight <= 0.0 {
+        return 0.0;
+    }
+    // use the top 53 bits so the value is exactly representable and the
+    // half step keeps it away from both 0 and 1.
+    let h = ((score(key, node) >> 11) as f64 + 0.5) / 9007199254740992.0;
+    -weight / h.ln()
+}
+
+/// Ranks all weighted nodes for `key`, best node first.
+///
+/// Returns the index into `nodes` together with the weighted score.  Nodes
+/// with equal scores keep their relative order.
+///
+/// (The function is only available if the `std` feature is enabled)
+#[cfg(feature = "std")]
+pub fn rank_weighted<K, N>(key: K, nodes: &[(N, f64)]) -> Vec<(usize, f64)>
+where
+    K: AsRef<[u8]>,
+    N: AsRef<[u8]>,
+{
+    let key = key.as_ref();
+    let mut rv: Vec<_> = nodes
+        .iter()
+        .enumerate()
+        .map(|(idx, &(ref node, weight))| (idx, weighted_score(key, node, weight)))
+        .collect();
+    rv.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(core::cmp::Ordering::Equal));
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        assert_eq!(score("key", "node-a"), score("key", "node-a"));
+        assert_ne!(score("key", "node-a"), score("key", "node-b"));
+        // the key length framing keeps these apart
+        assert_ne!(score("ab", "c"), score("a", "bc"));
+    }
+
+    #[test]
+    fn test_winner() {
+        let nodes = ["a", "b", "c", "d"];
+        assert_eq!(winner("key", &[] as &[&str]), None);
+        assert_eq!(winner("key", &nodes[..1]), Some(0));
+        let mut wins = [0; 4];
+        for i in 0..4000u32 {
+            let idx = winner(i.to_be_bytes(), &nodes).unwrap();
+            // removing a losing node never changes the winner
+            let loser = (idx + 1) % 4;
+            let mut rest = [""; 3];
+            let mut pos = 0;
+            for (j, node) in nodes.iter().enumerate() {
+                if j != loser {
+                    rest[pos] = node;
+                    pos += 1;
+                }
+            }
+            let rest_idx = winner(i.to_be_bytes(), &rest).unwrap();
+            assert_eq!(rest[rest_idx], nodes[idx]);
+            wins[idx] += 1;
+        }
+        assert!(wins.iter().all(|&w| w > 850 && w < 1150), "{:?}", wins);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_top_k() {
+        let nodes = ["a", "b", "c", "d"];
+        for i in 0..100u32 {
+            let key = i.to_be_bytes();
+            let top = top_k(key, &nodes, 3);
+            assert_eq!(top.len(), 3);
+            assert_eq!(top[0], winner(key, &nodes).unwrap());
+            assert!(score(key, nodes[top[0]]) >= score(key, nodes[top[1]]));
+            assert!(score(key, nodes[top[1]]) >= score(key, nodes[top[2]]));
+        }
+        assert_eq!(top_k("key", &nodes, 10).len(), 4);
+        assert!(top_k("key", &nodes, 0).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_rank_weighted() {
+        let nodes = [("a", 1.0), ("b", 2.0), ("c", 0.0)];
+        let mut wins = [0; 3];
+        for i in 0..3000u32 {
+            let ranking = rank_weighted(i.to_be_bytes(), &nodes);
+            assert_eq!(ranking.len(), 3);
+            assert_eq!(ranking[2], (2, 0.0));
+            wins[ranking[0].0] += 1;
+        }
+        assert_eq!(wins[2], 0);
+        assert!(wins[1] > 1800 && wins[1] < 2200);
+    }
+}
@@ -0,0 +1,227 @@
+//! Deterministic DSA / ECDSA nonces with HMAC-SHA1 (RFC 6979).
+//!
+//! All integers are unsigned big endian byte strings.  `q` is the group
+//! order, `x` the private key and `h1` the SHA-1 hash of the message.  The
+//! nonce is written into a buffer of `rlen` bytes, the length of `q`
+//! without leading zero bytes:
+//!
+//! ```
+//! use sha1_smol::{rfc6979, Sha1};
+//!
+//! # let q = [0x99, 0x6f, 0x96, 0x7f, 0x6c, 0x8e, 0x38, 0x8d, 0x9e, 0x28,
+//! #          0xd0, 0x1e, 0x20, 0x5f, 0xba, 0x95, 0x7a, 0x56, 0x98, 0xb1];
+//! # let x = [0x41, 0x16, 0x02, 0xcb, 0x19, 0xa6, 0xcc, 0xc3, 0x44, 0x94,
+//! #          0xd7, 0x9d, 0x98, 0xef, 0x1e, 0x7e, 0xd5, 0xaf, 0x25, 0xf7];
+//! let h1 = Sha1::from("sample").digest().bytes();
+//! let mut k = [0u8; 20];
+//! rfc6979::generate_k(&x, &h1, &q, &mut k);
+//! assert_eq!(&k[..4], &[0x7b, 0xdb, 0x6b, 0x0f]);
+//! ```
+
+use core::cmp::Ordering;
+
+use crate::hmac::HmacSha1;
+use crate::DIGEST_LENGTH;
+
+fn strip_zeros(mut v: &[u8]) -> &[u8] {
+    while let Some((&0, rest)) = v.split_first() {
+        v = rest;
+    }
+    v
+}
+
+/// Returns the bit length of a big endian integer.
+fn bit_len(v: &[u8]) -> usize {
+    let v = strip_zeros(v);
+    match v.first() {
+        Some(&first) => v.len() * 8 - first.leading_zeros() as usize,
+        None => 0,
+    }
+}
+
+/// Compares two big endian integers of the same length.
+fn cmp(a: &[u8], b: &[u8]) -> Ordering {
+    a.iter().cmp(b.iter())
+}
+
+/// Subtracts `b` from `a` in place, `a` must not be smaller than `b`.
+fn sub_assign(a: &mut [u8], b: &[u8]) {
+    let mut borrow = 0i16;
+    for (a, &b) in a.iter_mut().zip(b.iter()).rev() {
+        let diff = *a as i16 - b as i16 - borrow;
+        borrow = (diff < 0) as i16;
+        *a = diff as u8;
+    }
+}
+
+/// Shifts a big endian integer right by less than eight bits.
+fn shift_right(v: &mut [u8], shift: usize) {
+    if shift == 0 {
+        return;
+    }
+    let mut carry = 0u8;
+    for byte in v.iter_mut() {
+        let next = *byte << (8 - shift);
+        *byte = (*byte >> shift) | carry;
+        carry = next;
+    }
+}
+
+/// Converts the leftmost `qlen` bits of `input` into an integer (`bits2int`).
+///
+/// The result is written into `out` which must be `ceil(qlen / 8)` bytes
+/// long.
+pub fn bits2int(input: &[u8], qlen: usize, out: &mut [u8]) {
+    let rlen = (qlen + 7) / 8;
+    assert_eq!(out.len(), rlen, "output must be ceil(qlen / 8) bytes");
+    if input.len() * 8 <= qlen {
+        let pad = rlen - input.len();
+        out[..pad].iter_mut().for_each(|b| *b = 0);
+        out[pad..].copy_from_slice(input);
+        return;
+    }
+    out.copy_from_slice(&input[..rlen]);
+    shift_right(out, rlen * 8 - qlen);
+}
+
+/// Encodes the integer `x` in exactly `out.len()` bytes (`int2octets`).
+///
+/// This panics if `x` does not fit.
+pub fn int2octets(x: &[u8], out: &mut [u8]) {
+    let x = strip_zeros(x);
+    assert!(x.len() <= out.len(), "integer too large");
+    let pad = out.len() - x.len();
+    out[..pad].iter_mut().for_each(|b| *b = 0);
+    out[pad..].copy_from_slice(x);
+}
+
+/// Converts `input` into an integer modulo `q` of `rlen` bytes
+/// (`bits2octets`).
+pub fn bits2octets(input: &[u8], q: &[u8], out: &mut [u8]) {
+    let q = strip_zeros(q);
+    bits2int(input, bit_len(q), out);
+    // bits2int is below 2^qlen < 2q so one subtraction is enough
+    if cmp(out, q) != Ordering::Less {
+        sub_assign(out, q);
+    }
+}
+
+/// Generates the deterministic nonce `k` for private key `x` and message
+/// hash `h1` in the group of order `q` using HMAC-SHA1.
+///
+/// `k` must be as long as `q` without leading zeros.  This panics if `q` is
+/// smaller than two or `x` is longer than `q`.
+pub fn generate_k(x: &[u8], h1: &[u8], q: &[u8], k: &mut [u8]) {
+    let q = strip_zeros(q);
+    let qlen = bit_len(q);
+    assert!(qlen >= 2, "group order too small");
+    assert_eq!(k.len(), q.len(), "k must be as long as q");
+
+    // int2octets(x) is fed through the hmac in two parts to avoid a buffer
+    let x = strip_zeros(x);
+    assert!(x.len() <= q.len(), "integer too large");
+    let x_pad = q.len() - x.len();
+
+    // bits2octets(h1) is kept in `k` until the nonce loop overwrites it
+    bits2octets(h1, q, k);
+
+    let mut key = [0u8; DIGEST_LENGTH];
+    let mut v = [1u8; DIGEST_LENGTH];
+    for &round in &[0x00u8, 0x01] {
+        let mut m = HmacSha1::new(&key);
+        m.update(&v);
+        m.update(&[round]);
+        for _ in 0..x_pad {
+            m.update(&[0]);
+        }
+        m.update(x);
+        m.update(k);
+        key = m.digest().bytes();
+        v = hmac(&key, &[&v]);
+    }
+
+    loop {
+        let mut filled = 0;
+        while filled < k.len() {
+            v = hmac(&key, &[&v]);
+            let n = core::cmp::min(DIGEST_LENGTH, k.len() - filled);
+            k[filled..filled + n].copy_from_slice(&v[..n]);
+            filled += n;
+        }
+        // this is bits2int(T) as only the leftmost qlen bits are used
+        shift_right(k, k.len() * 8 - qlen);
+        if k.iter().any(|&b| b != 0) && cmp(k, q) == Ordering::Less {
+            return;
+        }
+        key = hmac(&key, &[&v, &[0]]);
+        v = hmac(&key, &[&v]);
+    }
+}
+
+fn hmac(key: &[u8], parts: &[&[u8]]) -> [u8; DIGEST_LENGTH] {
+    let mut m = HmacSha1::new(key);
+    for part in parts {
+        m.update(part);
+    }
+    m.digest().bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::unhex;
+    use crate::Sha1;
+
+    fn check<const N: usize>(q: &str, x: &str, msg: &str, expected: &str) {
+        let q: [u8; N] = unhex(q);
+        let x: [u8; N] = unhex(x);
+        let mut k = [0u8; N];
+        generate_k(&x, &Sha1::from(msg).digest().bytes(), &q, &mut k);
+        assert_eq!(k, unhex::<N>(expected), "message {:?}", msg);
+    }
+
+    #[test]
+    fn test_dsa_1024() {
+        // RFC 6979 appendix A.2.1
+        check::<20>(
+            "996f967f6c8e388d9e28d01e205fba957a5698b1",
+            "411602cb19a6ccc34494d79d98ef1e7ed5af25f7",
+            "sample",
+            "7bdb6b0ff756e1bb5d53583ef979082f9ad5bd5b",
+        );
+    }
+
+    #[test]
+    fn test_ecdsa_p192() {
+        // RFC 6979 appendix A.2.3
+        check::<24>(
+            "ffffffffffffffffffffffff99def836146bc9b1b4d22831",
+            "6fab034934e4c0fc9ae67f5b5659a9d7d1fefd187ee09fd4",
+            "sample",
+            "37d7ca00d2c7b0e5e412ac03bd44ba837fdd5b28cd3b0021",
+        );
+    }
+
+    #[test]
+    fn test_ecdsa_k163() {
+        // RFC 6979 appendix A.2.8, qlen is not a multiple of 8 here
+        let q = "04000000000000000000020108a2e0cc0d99f8a5ef";
+        let x = "009a4d6792295a7f730fc3f2b49cbc0f62e862272f";
+        check::<21>(q, x, "sample", "009744429fa741d12de2be8316e35e84db9e5df1cd");
+        check::<21>(q, x, "test", "014cab9192f39c8a0ea8e81b4b87574228c99cd681");
+    }
+
+    #[test]
+    fn test_helpers() {
+        let mut out = [0u8; 3];
+        bits2int(&[0xff, 0xff, 0xff, 0xff], 20, &mut out);
+        assert_eq!(out, [0x0f, 0xff, 0xff]);
+        bits2int(&[0xab], 20, &mut out);
+        assert_eq!(out, [0x00, 0x00, 0xab]);
+        int2octets(&[0, 0, 0, 1, 2], &mut out);
+        assert_eq!(out, [0, 1, 2]);
+        let mut out = [0u8; 2];
+        bits2octets(&[0xff, 0xff], &[0x80, 0x01], &mut out);
+        assert_eq!(out, [0x7f, 0xfe]);
+    }
+}
@@ -0,0 +1,198 @@
+//! Deterministic sampling based on SHA-1.
+//!
+//! The decision only depends on the key and the rate, so every service (and
+//! every language) that follows the same rule samples the same keys:
+//!
+//! 1. hash the key with SHA-1,
+//! 2. interpret the first 8 bytes of the digest as a big endian `u64`,
+//! 3. sample the key if that number is smaller than `floor(rate * 2^64)`.
+//!
+//! A rate of `1.0` or more samples every key, a rate of `0.0` or less (or
+//! NaN) samples none.  Keys sampled at a rate are also sampled at every
+//! higher rate which makes gradual rollouts stable.
+//!
+//! For reproducible shuffles and partition assignments [`shuffle`] (and
+//! `permute` with the `alloc` feature) derive a Fisher-Yates shuffle from a
+//! seed.  The random numbers come from SHA-1 in counter mode rather than
+//! from the [`drbg`](crate::drbg) module: they are the first 8 bytes (big
+//! endian) of `SHA1(seed || u64be(counter))` for counter values starting at
+//! zero, so the stream is easy to reproduce in other languages.  For
+//! every `i` from `n - 1` down to `1` an index `j` in `0..=i` is drawn by
+//! rejecting numbers below `2^64 mod (i + 1)` and reducing the first
+//! accepted one modulo `i + 1`; then the elements `i` and `j` are swapped.
+
+use crate::Sha1;
+
+/// Decides whether `key` is part of a sample of the given `rate`.
+///
+/// ```
+/// let enabled = sha1_smol::sampling::sample("user:42", 0.25);
+/// assert_eq!(enabled, sha1_smol::sampling::sample("user:42", 0.25));
+/// ```
+pub fn sample<K: AsRef<[u8]>>(key: K, rate: f64) -> bool {
+    if rate >= 1.0 {
+        return true;
+    }
+    if rate.is_nan() || rate <= 0.0 {
+        return false;
+    }
+    let threshold = (rate * 18446744073709551616.0) as u64;
+    sample_point(key.as_ref()) < threshold
+}
+
+/// Returns the position of `key` in the sampling space.
+fn sample_point(key: &[u8]) -> u64 {
+    let bytes = Sha1::from(key).digest().bytes();
+    let mut point = [0u8; 8];
+    point.copy_from_slice(&bytes[..8]);
+    u64::from_be_bytes(point)
+}
+
+/// The random number stream driving [`shuffle`], SHA-1 of the seed and a
+/// big endian counter.
+struct Stream {
+    seed: Sha1,
+    counter: u64,
+}
+
+impl Stream {
+    fn new(seed: &[u8]) -> Stream {
+        Stream {
+            seed: Sha1::from(seed),
+            counter: 0,
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut m = self.seed.fork();
+        m.update(&self.counter.to_be_bytes());
+        self.counter += 1;
+        let mut rv = [0u8; 8];
+        rv.copy_from_slice(&m.digest().bytes()[..8]);
+        u64::from_be_bytes(rv)
+    }
+
+    /// Returns an unbiased number in `0..bound`.
+    fn below(&mut self, bound: u64) -> u64 {
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let x = self.next_u64();
+            if x >= threshold {
+                return x % bound;
+            }
+        }
+    }
+}
+
+/// Shuffles `items` deterministically based on `seed`.
+///
+/// The same seed and length always produce the same order, see the module
+/// documentation for the exact procedure.
+///
+/// ```
+/// let mut a = [1, 2, 3, 4, 5];
+/// let mut b = a;
+/// sha1_smol::sampling::shuffle(b"seed", &mut a);
+/// sha1_smol::sampling::shuffle(b"seed", &mut b);
+/// assert_eq!(a, b);
+/// ```
+pub fn shuffle<T>(seed: &[u8], items: &mut [T]) {
+    let mut stream = Stream::new(seed);
+    for i in (1..items.len()).rev() {
+        let j = stream.below(i as u64 + 1) as usize;
+        items.swap(i, j);
+    }
+}
+
+/// Returns a deterministic permutation of `0..n` derived from `seed`.
+///
+/// This is the result of [`shuffle`] applied to `0..n`, so the first `k`
+/// elements also form a reproducible sample of `k` out of `n` items.
+///
+/// (This function is only available if the `alloc` feature is enabled)
+#[cfg(feature = "alloc")]
+pub fn permute(seed: &[u8], n: usize) -> alloc::vec::Vec<usize> {
+    let mut rv: alloc::vec::Vec<usize> = (0..n).collect();
+    shuffle(seed, &mut rv);
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_bounds() {
+        assert!(sample("anything", 1.0));
+        assert!(!sample("anything", 0.0));
+        assert!(!sample("anything", -1.0));
+        assert!(!sample("anything", f64::NAN));
+    }
+
+    #[test]
+    fn test_sample_point() {
+        // sha1("Hello World!") = 2ef7bde608ce5404...
+        assert_eq!(sample_point(b"Hello World!"), 0x2ef7bde608ce5404);
+        assert!(sample("Hello World!", 0.19));
+        assert!(!sample("Hello World!", 0.18));
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut stream = Stream::new(b"seed");
+        for counter in 0..3u64 {
+            let mut m = Sha1::from(b"seed");
+            m.update(&counter.to_be_bytes());
+            let mut expected = [0u8; 8];
+            expected.copy_from_slice(&m.digest().bytes()[..8]);
+            assert_eq!(stream.next_u64(), u64::from_be_bytes(expected));
+        }
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let mut items = [0u32; 50];
+        for (i, item) in items.iter_mut().enumerate() {
+            *item = i as u32;
+        }
+        let mut a = items;
+        shuffle(b"seed", &mut a);
+        let mut b = items;
+        shuffle(b"seed", &mut b);
+        let mut c = items;
+        shuffle(b"other seed", &mut c);
+        assert_eq!(a[..], b[..]);
+        assert_ne!(a[..], c[..]);
+        assert_ne!(a[..], items[..]);
+        a.sort_unstable();
+        assert_eq!(a[..], items[..]);
+
+        shuffle::<u8>(b"seed", &mut []);
+        let mut one = [7];
+        shuffle(b"seed", &mut one);
+        assert_eq!(one, [7]);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_permute() {
+        // cross checked against an independent python implementation
+        let p = permute(b"partitions", 8);
+        assert_eq!(p, [7, 2, 1, 6, 3, 0, 5, 4]);
+        let mut sorted = p.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..8).collect::<alloc::vec::Vec<_>>());
+        assert!(permute(b"partitions", 0).is_empty());
+    }
+
+    #[test]
+    fn test_sample_rate() {
+        let mut hits = 0;
+        for i in 0..10000u32 {
+            if sample(i.to_be_bytes(), 0.3) {
+                hits += 1;
+            }
+        }
+        assert!(hits > 2700 && hits < 3300);
+    }
+}
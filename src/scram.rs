@@ -0,0 +1,632 @@
+//! The SCRAM-SHA-1 SASL mechanism (RFC 5802).
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+//!
+//! [`ScramClient`] implements the client side of the exchange without
+//! channel binding.  The client nonce has to be generated by the caller
+//! from a cryptographically secure source, and the password has to be
+//! normalized with SASLprep beforehand if it is not plain ASCII:
+//!
+//! ```
+//! use sha1_smol::scram::ScramClient;
+//!
+//! let client = ScramClient::new("user", "pencil", "fyko+d2lbbFgONRv9qkxdawL").unwrap();
+//! assert_eq!(client.client_first(), "n,,n=user,r=fyko+d2lbbFgONRv9qkxdawL");
+//!
+//! let server_first = "r=fyko+d2lbbFgONRv9qkxdawL3rfcNHYJY1ZVvWVs7j,s=QSXCR+Q6sek8bf92,i=4096";
+//! let client_final = client.handle_server_first(server_first).unwrap();
+//! assert_eq!(
+//!     client_final.message(),
+//!     "c=biws,r=fyko+d2lbbFgONRv9qkxdawL3rfcNHYJY1ZVvWVs7j,p=v0X8v3Bz2T0CJGbJQyF0X+HI4Ts="
+//! );
+//! client_final.handle_server_final("v=rmF9pqV8S7suAoZWja4dJRkFsKQ=").unwrap();
+//! ```
+//!
+//! On the server side a [`Verifier`] is stored per user instead of the
+//! password.  [`ScramServer`] parses the client's first message so the
+//! verifier can be looked up, and validates the client proof:
+//!
+//! ```
+//! use sha1_smol::scram::{ScramServer, Verifier};
+//!
+//! let verifier = Verifier::new(b"pencil", b"salt", 4096);
+//! let server = ScramServer::handle_client_first("n,,n=user,r=abc").unwrap();
+//! assert_eq!(server.username(), "user");
+//! let server = server.respond(&verifier, "def").unwrap();
+//! assert_eq!(server.message(), "r=abcdef,s=c2FsdA==,i=4096");
+//! # let _ = server;
+//! ```
+//!
+//! The individual key derivation steps are exposed as functions as well.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::encoding::{decode_b64, encode_b64, Alphabet};
+use crate::hmac::{ct_eq, hmac_sha1};
+use crate::{pbkdf2, Sha1, DIGEST_LENGTH};
+
+/// The GS2 header for a client without channel binding support.
+const GS2_HEADER: &str = "n,,";
+
+/// Indicates that a SCRAM exchange failed.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum ScramError {
+    /// The nonce is empty or contains characters that are not allowed.
+    InvalidNonce,
+    /// A message from the peer is malformed or uses an unsupported
+    /// mandatory extension.
+    InvalidMessage,
+    /// The server nonce does not start with the client nonce.
+    NonceMismatch,
+    /// The server aborted the exchange with an `e=` attribute.
+    ServerError,
+    /// The proof or signature of the peer is wrong.
+    AuthenticationFailed,
+}
+
+impl ScramError {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            ScramError::InvalidNonce => "invalid scram nonce",
+            ScramError::InvalidMessage => "invalid scram message",
+            ScramError::NonceMismatch => "scram nonce mismatch",
+            ScramError::ServerError => "scram server error",
+            ScramError::AuthenticationFailed => "scram authentication failed",
+        }
+    }
+}
+
+impl fmt::Display for ScramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ScramError {
+    fn description(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// Computes `SaltedPassword := Hi(password, salt, i)`.
+pub fn salted_password(password: &[u8], salt: &[u8], iterations: u32) -> [u8; DIGEST_LENGTH] {
+    let mut rv = [0u8; DIGEST_LENGTH];
+    pbkdf2::derive(password, salt, iterations, &mut rv);
+    rv
+}
+
+/// Computes `ClientKey := HMAC(SaltedPassword, "Client Key")`.
+pub fn client_key(salted_password: &[u8; DIGEST_LENGTH]) -> [u8; DIGEST_LENGTH] {
+    hmac_sha1(salted_password, b"Client Key")
+}
+
+/// Computes `StoredKey := H(ClientKey)`.
+pub fn stored_key(client_key: &[u8; DIGEST_LENGTH]) -> [u8; DIGEST_LENGTH] {
+    Sha1::from(&client_key[..]).digest().bytes()
+}
+
+/// Computes `ServerKey := HMAC(SaltedPassword, "Server Key")`.
+pub fn server_key(salted_password: &[u8; DIGEST_LENGTH]) -> [u8; DIGEST_LENGTH] {
+    hmac_sha1(salted_password, b"Server Key")
+}
+
+/// Computes `ClientProof := ClientKey XOR HMAC(StoredKey, AuthMessage)`.
+pub fn client_proof(client_key: &[u8; DIGEST_LENGTH], auth_message: &str) -> [u8; DIGEST_LENGTH] {
+    let mut rv = hmac_sha1(&stored_key(client_key), auth_message.as_bytes());
+    for (a, b) in rv.iter_mut().zip(client_key.iter()) {
+        *a ^= b;
+    }
+    rv
+}
+
+/// Computes `ServerSignature := HMAC(ServerKey, AuthMessage)`.
+pub fn server_signature(
+    server_key: &[u8; DIGEST_LENGTH],
+    auth_message: &str,
+) -> [u8; DIGEST_LENGTH] {
+    hmac_sha1(server_key, auth_message.as_bytes())
+}
+
+/// Checks that a nonce only consists of printable ASCII other than `,`.
+fn validate_nonce(nonce: &str) -> Result<(), ScramError> {
+    if nonce.is_empty()
+        || !nonce
+            .bytes()
+            .all(|c| (0x21..=0x7e).contains(&c) && c != b',')
+    {
+        return Err(ScramError::InvalidNonce);
+    }
+    Ok(())
+}
+
+/// Encodes a user name as `saslname`.
+fn escape_username(username: &str) -> String {
+    let mut rv = String::with_capacity(username.len());
+    for c in username.chars() {
+        match c {
+            ',' => rv.push_str("=2C"),
+            '=' => rv.push_str("=3D"),
+            c => rv.push(c),
+        }
+    }
+    rv
+}
+
+/// Returns the value of the attribute `name` at the start of `input` and
+/// the remaining input.
+fn attribute(input: &str, name: char) -> Result<(&str, &str), ScramError> {
+    let mut parts = input.splitn(2, ',');
+    let attr = parts.next().unwrap_or("");
+    let mut chars = attr.chars();
+    if chars.next() != Some(name) || chars.next() != Some('=') {
+        return Err(ScramError::InvalidMessage);
+    }
+    Ok((&attr[2..], parts.next().unwrap_or("")))
+}
+
+/// The client side of a SCRAM-SHA-1 exchange before the server's first
+/// message arrived.
+#[derive(Clone, Debug)]
+pub struct ScramClient {
+    password: String,
+    nonce: String,
+    client_first_bare: String,
+}
+
+impl ScramClient {
+    /// Starts an exchange for `username` with the client `nonce`.
+    pub fn new(username: &str, password: &str, nonce: &str) -> Result<ScramClient, ScramError> {
+        validate_nonce(nonce)?;
+        let mut client_first_bare = String::from("n=");
+        client_first_bare.push_str(&escape_username(username));
+        client_first_bare.push_str(",r=");
+        client_first_bare.push_str(nonce);
+        Ok(ScramClient {
+            password: password.into(),
+            nonce: nonce.into(),
+            client_first_bare,
+        })
+    }
+
+    /// Returns the `client-first-message` to send to the server.
+    pub fn client_first(&self) -> String {
+        let mut rv = String::from(GS2_HEADER);
+        rv.push_str(&self.client_first_bare);
+        rv
+    }
+
+    /// Processes the `server-first-message` and computes the client proof.
+    pub fn handle_server_first(self, server_first: &str) -> Result<ClientFinal, ScramError> {
+        let (nonce, rest) = attribute(server_first, 'r')?;
+        let (salt, rest) = attribute(rest, 's')?;
+        let (iterations, _extensions) = attribute(rest, 'i')?;
+        validate_nonce(nonce)?;
+        if !nonce.starts_with(&self.nonce) || nonce.len() == self.nonce.len() {
+            return Err(ScramError::NonceMismatch);
+        }
+        let salt =
+            decode_b64(salt.as_bytes(), Alphabet::Standard).ok_or(ScramError::InvalidMessage)?;
+        let iterations: u32 = iterations.parse().map_err(|_| ScramError::InvalidMessage)?;
+        if iterations == 0 {
+            return Err(ScramError::InvalidMessage);
+        }
+
+        let mut message = String::from("c=");
+        message.push_str(&encode_b64(GS2_HEADER.as_bytes(), Alphabet::Standard, true));
+        message.push_str(",r=");
+        message.push_str(nonce);
+        let mut auth_message = self.client_first_bare;
+        auth_message.push(',');
+        auth_message.push_str(server_first);
+        auth_message.push(',');
+        auth_message.push_str(&message);
+
+        let salted = salted_password(self.password.as_bytes(), &salt, iterations);
+        let proof = client_proof(&client_key(&salted), &auth_message);
+        message.push_str(",p=");
+        message.push_str(&encode_b64(&proof, Alphabet::Standard, true));
+        Ok(ClientFinal {
+            message,
+            server_signature: server_signature(&server_key(&salted), &auth_message),
+        })
+    }
+}
+
+/// The client side of a SCRAM-SHA-1 exchange waiting for the server's
+/// final message.
+#[derive(Clone, Debug)]
+pub struct ClientFinal {
+    message: String,
+    server_signature: [u8; DIGEST_LENGTH],
+}
+
+impl ClientFinal {
+    /// Returns the `client-final-message` to send to the server.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Verifies the server signature in the `server-final-message`.
+    pub fn handle_server_final(&self, server_final: &str) -> Result<(), ScramError> {
+        if server_final.starts_with("e=") {
+            return Err(ScramError::ServerError);
+        }
+        let (signature, _extensions) = attribute(server_final, 'v')?;
+        let signature = decode_b64(signature.as_bytes(), Alphabet::Standard)
+            .ok_or(ScramError::InvalidMessage)?;
+        if !ct_eq(&signature, &self.server_signature) {
+            return Err(ScramError::AuthenticationFailed);
+        }
+        Ok(())
+    }
+}
+
+/// Checks `ClientProof` against `StoredKey` for the given `AuthMessage`.
+///
+/// The client key is recovered from the proof and its hash compared to the
+/// stored key in constant time.
+pub fn verify_client_proof(
+    stored_key: &[u8; DIGEST_LENGTH],
+    auth_message: &str,
+    proof: &[u8],
+) -> bool {
+    if proof.len() != DIGEST_LENGTH {
+        return false;
+    }
+    let mut client_key = hmac_sha1(stored_key, auth_message.as_bytes());
+    for (a, b) in client_key.iter_mut().zip(proof.iter()) {
+        *a ^= b;
+    }
+    ct_eq(&self::stored_key(&client_key), stored_key)
+}
+
+/// Decodes a `saslname` back into the user name.
+fn unescape_username(name: &str) -> Result<String, ScramError> {
+    let mut rv = String::with_capacity(name.len());
+    let mut rest = name;
+    while let Some(idx) = rest.find('=') {
+        rv.push_str(&rest[..idx]);
+        match rest.get(idx..idx + 3) {
+            Some("=2C") => rv.push(','),
+            Some("=3D") => rv.push('='),
+            _ => return Err(ScramError::InvalidMessage),
+        }
+        rest = &rest[idx + 3..];
+    }
+    rv.push_str(rest);
+    Ok(rv)
+}
+
+/// The credentials a server stores for a user.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Verifier {
+    /// The salt for the password.
+    pub salt: Vec<u8>,
+    /// The PBKDF2 iteration count.
+    pub iterations: u32,
+    /// `StoredKey`, used to validate client proofs.
+    pub stored_key: [u8; DIGEST_LENGTH],
+    /// `ServerKey`, used to compute the server signature.
+    pub server_key: [u8; DIGEST_LENGTH],
+}
+
+impl Verifier {
+    /// Computes the verifier for `password`.
+    pub fn new(password: &[u8], salt: &[u8], iterations: u32) -> Verifier {
+        let salted = salted_password(password, salt, iterations);
+        Verifier {
+            salt: salt.into(),
+            iterations,
+            stored_key: stored_key(&client_key(&salted)),
+            server_key: server_key(&salted),
+        }
+    }
+}
+
+/// The server side of a SCRAM-SHA-1 exchange after the client's first
+/// message arrived.
+#[derive(Clone, Debug)]
+pub struct ScramServer {
+    gs2_header: String,
+    username: String,
+    nonce: String,
+    client_first_bare: String,
+}
+
+impl ScramServer {
+    /// Parses the `client-first-message`.
+    ///
+    /// Clients that require channel binding are rejected, an authorization
+    /// identity is accepted and can be retrieved with [`authzid`].
+    ///
+    /// [`authzid`]: ScramServer::authzid
+    pub fn handle_client_first(client_first: &str) -> Result<ScramServer, ScramError> {
+        let mut parts = client_first.splitn(3, ',');
+        let cbind = parts.next().unwrap_or("");
+        let authzid = parts.next().ok_or(ScramError::InvalidMessage)?;
+        let bare = parts.next().ok_or(ScramError::InvalidMessage)?;
+        if (cbind != "n" && cbind != "y") || !(authzid.is_empty() || authzid.starts_with("a=")) {
+            return Err(ScramError::InvalidMessage);
+        }
+        let (username, rest) = attribute(bare, 'n')?;
+        let (nonce, _extensions) = attribute(rest, 'r')?;
+        validate_nonce(nonce)?;
+        Ok(ScramServer {
+            gs2_header: String::from(&client_first[..client_first.len() - bare.len()]),
+            username: unescape_username(username)?,
+            nonce: nonce.into(),
+            client_first_bare: bare.into(),
+        })
+    }
+
+    /// Returns the user name the client authenticates as.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Returns the authorization identity if the client sent one.
+    pub fn authzid(&self) -> Option<&str> {
+        let authzid = &self.gs2_header[2..self.gs2_header.len() - 1];
+        if authzid.is_empty() {
+            None
+        } else {
+            Some(&authzid[2..])
+        }
+    }
+
+    /// Builds the `server-first-message` with the verifier of the user.
+    ///
+    /// `nonce` is the server's part of the nonce and has to be generated
+    /// from a cryptographically secure source.
+    pub fn respond(self, verifier: &Verifier, nonce: &str) -> Result<ServerFirst, ScramError> {
+        validate_nonce(nonce)?;
+        let mut message = String::from("r=");
+        message.push_str(&self.nonce);
+        message.push_str(nonce);
+        message.push_str(",s=");
+        message.push_str(&encode_b64(&verifier.salt, Alphabet::Standard, true));
+        message.push_str(",i=");
+        message.push_str(&alloc::format!("{}", verifier.iterations));
+        Ok(ServerFirst {
+            gs2_header: self.gs2_header,
+            nonce: String::from(&message[2..2 + self.nonce.len() + nonce.len()]),
+            client_first_bare: self.client_first_bare,
+            message,
+            stored_key: verifier.stored_key,
+            server_key: verifier.server_key,
+        })
+    }
+}
+
+/// The server side of a SCRAM-SHA-1 exchange waiting for the client's
+/// final message.
+#[derive(Clone, Debug)]
+pub struct ServerFirst {
+    gs2_header: String,
+    nonce: String,
+    client_first_bare: String,
+    message: String,
+    stored_key: [u8; DIGEST_LENGTH],
+    server_key: [u8; DIGEST_LENGTH],
+}
+
+impl ServerFirst {
+    /// Returns the `server-first-message` to send to the client.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Validates the `client-final-message` and returns the
+    /// `server-final-message` to send on success.
+    ///
+    /// If the proof is wrong this fails with
+    /// [`ScramError::AuthenticationFailed`] and the server should answer
+    /// with `e=invalid-proof`.
+    pub fn handle_client_final(&self, client_final: &str) -> Result<String, ScramError> {
+        let proof_idx = client_final
+            .rfind(",p=")
+            .ok_or(ScramError::InvalidMessage)?;
+        let without_proof = &client_final[..proof_idx];
+        let proof = &client_final[proof_idx + 3..];
+        let (cbind, rest) = attribute(without_proof, 'c')?;
+        let (nonce, _extensions) = attribute(rest, 'r')?;
+        let cbind =
+            decode_b64(cbind.as_bytes(), Alphabet::Standard).ok_or(ScramError::InvalidMessage)?;
+        if cbind != self.gs2_header.as_bytes() {
+            return Err(ScramError::InvalidMessage);
+        }
+        if nonce != self.nonce {
+            return Err(ScramError::NonceMismatch);
+        }
+        let proof =
+            decode_b64(proof.as_bytes(), Alphabet::Standard).ok_or(ScramError::InvalidMessage)?;
+
+        let mut auth_message = self.client_first_bare.clone();
+        auth_message.push(',');
+        auth_message.push_str(&self.message);
+        auth_message.push(',');
+        auth_message.push_str(without_proof);
+        if !verify_client_proof(&self.stored_key, &auth_message, &proof) {
+            return Err(ScramError::AuthenticationFailed);
+        }
+        let mut rv = String::from("v=");
+        rv.push_str(&encode_b64(
+            &server_signature(&self.server_key, &auth_message),
+            Alphabet::Standard,
+            true,
+        ));
+        Ok(rv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 5802 section 5
+    const NONCE: &str = "fyko+d2lbbFgONRv9qkxdawL";
+    const SERVER_FIRST: &str =
+        "r=fyko+d2lbbFgONRv9qkxdawL3rfcNHYJY1ZVvWVs7j,s=QSXCR+Q6sek8bf92,i=4096";
+
+    #[test]
+    fn test_exchange() {
+        let client = ScramClient::new("user", "pencil", NONCE).unwrap();
+        assert_eq!(
+            client.client_first(),
+            "n,,n=user,r=fyko+d2lbbFgONRv9qkxdawL"
+        );
+        let client_final = client.handle_server_first(SERVER_FIRST).unwrap();
+        assert_eq!(
+            client_final.message(),
+            "c=biws,r=fyko+d2lbbFgONRv9qkxdawL3rfcNHYJY1ZVvWVs7j,p=v0X8v3Bz2T0CJGbJQyF0X+HI4Ts="
+        );
+        assert_eq!(
+            client_final.handle_server_final("v=rmF9pqV8S7suAoZWja4dJRkFsKQ="),
+            Ok(())
+        );
+        assert_eq!(
+            client_final.handle_server_final("v=rmF9pqV8S7suAoZWja4dJRkFsKA="),
+            Err(ScramError::AuthenticationFailed)
+        );
+        assert_eq!(
+            client_final.handle_server_final("e=invalid-proof"),
+            Err(ScramError::ServerError)
+        );
+    }
+
+    #[test]
+    fn test_username_escaping() {
+        let client = ScramClient::new("a,b=c", "pw", "abc").unwrap();
+        assert_eq!(client.client_first(), "n,,n=a=2Cb=3Dc,r=abc");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            ScramClient::new("user", "pencil", "a,b").unwrap_err(),
+            ScramError::InvalidNonce
+        );
+        assert_eq!(
+            ScramClient::new("user", "pencil", "").unwrap_err(),
+            ScramError::InvalidNonce
+        );
+        let client = ScramClient::new("user", "pencil", NONCE).unwrap();
+        for &(server_first, err) in &[
+            (
+                "r=other,s=QSXCR+Q6sek8bf92,i=4096",
+                ScramError::NonceMismatch,
+            ),
+            (
+                "r=fyko+d2lbbFgONRv9qkxdawL,s=QSXCR+Q6sek8bf92,i=4096",
+                ScramError::NonceMismatch,
+            ),
+            (
+                "m=ext,r=fyko+d2lbbFgONRv9qkxdawLxx,s=QSXCR+Q6sek8bf92,i=4096",
+                ScramError::InvalidMessage,
+            ),
+            (
+                "r=fyko+d2lbbFgONRv9qkxdawLxx,s=!!,i=4096",
+                ScramError::InvalidMessage,
+            ),
+            (
+                "r=fyko+d2lbbFgONRv9qkxdawLxx,s=QSXCR+Q6sek8bf92,i=0",
+                ScramError::InvalidMessage,
+            ),
+            (
+                "r=fyko+d2lbbFgONRv9qkxdawLxx,s=QSXCR+Q6sek8bf92",
+                ScramError::InvalidMessage,
+            ),
+        ] {
+            assert_eq!(
+                client
+                    .clone()
+                    .handle_server_first(server_first)
+                    .unwrap_err(),
+                err,
+                "{:?}",
+                server_first
+            );
+        }
+    }
+
+    fn rfc_verifier() -> Verifier {
+        let salt = decode_b64(b"QSXCR+Q6sek8bf92", Alphabet::Standard).unwrap();
+        Verifier::new(b"pencil", &salt, 4096)
+    }
+
+    #[test]
+    fn test_server_exchange() {
+        let server =
+            ScramServer::handle_client_first("n,,n=user,r=fyko+d2lbbFgONRv9qkxdawL").unwrap();
+        assert_eq!(server.username(), "user");
+        assert_eq!(server.authzid(), None);
+        let server = server
+            .respond(&rfc_verifier(), "3rfcNHYJY1ZVvWVs7j")
+            .unwrap();
+        assert_eq!(server.message(), SERVER_FIRST);
+        assert_eq!(
+            server
+                .handle_client_final(
+                    "c=biws,r=fyko+d2lbbFgONRv9qkxdawL3rfcNHYJY1ZVvWVs7j,\
+                     p=v0X8v3Bz2T0CJGbJQyF0X+HI4Ts="
+                )
+                .unwrap(),
+            "v=rmF9pqV8S7suAoZWja4dJRkFsKQ="
+        );
+        assert_eq!(
+            server.handle_client_final(
+                "c=biws,r=fyko+d2lbbFgONRv9qkxdawL3rfcNHYJY1ZVvWVs7j,\
+                 p=v0X8v3Bz2T0CJGbJQyF0X+HI4TA="
+            ),
+            Err(ScramError::AuthenticationFailed)
+        );
+        assert_eq!(
+            server.handle_client_final("c=biws,r=fyko+d2lbbFgONRv9qkxdawL,p=AAAA"),
+            Err(ScramError::NonceMismatch)
+        );
+    }
+
+    #[test]
+    fn test_client_and_server() {
+        let verifier = Verifier::new(b"hunter2", b"NaCl", 1000);
+        let client = ScramClient::new("a,b=c", "hunter2", "client-nonce").unwrap();
+        let server = ScramServer::handle_client_first(&client.client_first()).unwrap();
+        assert_eq!(server.username(), "a,b=c");
+        let server = server.respond(&verifier, "server-nonce").unwrap();
+        let client = client.handle_server_first(server.message()).unwrap();
+        let server_final = server.handle_client_final(client.message()).unwrap();
+        assert_eq!(client.handle_server_final(&server_final), Ok(()));
+
+        let wrong = ScramClient::new("a,b=c", "hunter3", "client-nonce")
+            .unwrap()
+            .handle_server_first(server.message())
+            .unwrap();
+        assert_eq!(
+            server.handle_client_final(wrong.message()),
+            Err(ScramError::AuthenticationFailed)
+        );
+    }
+
+    #[test]
+    fn test_server_client_first() {
+        let server = ScramServer::handle_client_first("y,a=admin,n=user,r=abc").unwrap();
+        assert_eq!(server.authzid(), Some("admin"));
+        for &msg in &[
+            "p=tls-unique,,n=user,r=abc",
+            "n,,n=user",
+            "n,,r=abc",
+            "n,,n=us=er,r=abc",
+            "n,x,n=user,r=abc",
+        ] {
+            assert_eq!(
+                ScramServer::handle_client_first(msg).unwrap_err(),
+                ScramError::InvalidMessage,
+                "{:?}",
+                msg
+            );
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Per-segment and whole-object digests in a single pass.
+//!
+//! Multipart uploads to object stores validate every part with its own
+//! SHA-1 and frequently also want the SHA-1 of the complete object (for
+//! instance Backblaze B2's `large_file_sha1`).  [`SegmentedHasher`] computes
+//! both while the data streams through once:
+//!
+//! ```
+//! use sha1_smol::segmented::SegmentedHasher;
+//!
+//! let mut parts = Vec::new();
+//! let mut hasher = SegmentedHasher::new(5);
+//! hasher.update(b"Hello World!", |segment| parts.push(segment));
+//! let whole = hasher.finish(|segment| parts.push(segment));
+//!
+//! assert_eq!(parts.len(), 3);
+//! assert_eq!(parts[2].len, 2);
+//! assert_eq!(whole, sha1_smol::Sha1::from("Hello World!").digest());
+//! ```
+//!
+//! The hasher is cheap to clone.  To retry the upload of a part without
+//! hashing the object again from the start, clone the hasher at the segment
+//! boundary and restore the clone if the part has to be sent again.
+//!
+//! This is the same segmenting `TranscriptHasher` records transcripts
+//! with, and the types are re-exported from the [`transcript`] module.
+//!
+//! [`transcript`]: crate::transcript
+
+pub use crate::transcript::{Segment, SegmentedHasher};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha1;
+
+    fn data() -> [u8; 1000] {
+        let mut rv = [0u8; 1000];
+        for (idx, byte) in rv.iter_mut().enumerate() {
+            *byte = (idx % 251) as u8;
+        }
+        rv
+    }
+
+    #[test]
+    fn test_segments() {
+        let data = data();
+        for &(size, pieces) in &[(1u64, 7usize), (64, 1), (100, 33), (999, 1000), (1000, 3)] {
+            let mut hasher = SegmentedHasher::new(size);
+            let mut count = 0;
+            let mut check = |segment: Segment| {
+                let start = segment.offset as usize;
+                let end = start + segment.len as usize;
+                assert_eq!(start as u64, count * size);
+                assert_eq!(segment.digest, Sha1::from(&data[start..end]).digest());
+                count += 1;
+            };
+            for chunk in data.chunks(pieces) {
+                hasher.update(chunk, &mut check);
+            }
+            assert_eq!(hasher.len(), 1000);
+            assert_eq!(hasher.finish(&mut check), Sha1::from(&data[..]).digest());
+            assert_eq!(count, (1000 + size - 1) / size, "segment size {}", size);
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut segments = 0;
+        let hasher = SegmentedHasher::new(10);
+        assert!(hasher.is_empty());
+        let digest = hasher.finish(|segment| {
+            assert_eq!(segment.len, 0);
+            assert_eq!(segment.digest, Sha1::new().digest());
+            segments += 1;
+        });
+        assert_eq!(segments, 1);
+        assert_eq!(digest, Sha1::new().digest());
+    }
+
+    #[test]
+    fn test_retry_from_checkpoint() {
+        let data = data();
+        let mut hasher = SegmentedHasher::new(300);
+        hasher.update(&data[..300], |_| {});
+        let checkpoint = hasher.clone();
+        hasher.update(&data[300..450], |_| panic!("no segment expected"));
+
+        // the part upload failed, rewind and send it again
+        let mut hasher = checkpoint;
+        let mut second = None;
+        hasher.update(&data[300..600], |segment| second = Some(segment));
+        let second = second.unwrap();
+        assert_eq!((second.offset, second.len), (300, 300));
+        assert_eq!(second.digest, Sha1::from(&data[300..600]).digest());
+        hasher.update(&data[600..], |_| {});
+        assert_eq!(hasher.finish(|_| {}), Sha1::from(&data[..]).digest());
+    }
+}
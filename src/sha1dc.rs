@@ -0,0 +1,381 @@
+//! SHA-1 with detection of collision attacks.
+//!
+//! (This module is only available if the `collision-detection` feature is
+//! enabled)
+//!
+//! [`Sha1Dc`] computes the regular SHA-1 of its input and additionally
+//! reports whether the input contains a block crafted with one of the known
+//! collision attacks, such as the SHAttered and SHAmbles collisions.  This
+//! is the counter-cryptanalysis of Marc Stevens and Dan Shumow that git
+//! ships as sha1collisiondetection.
+//!
+//! ```
+//! use sha1_smol::sha1dc::Sha1Dc;
+//! use sha1_smol::Sha1;
+//!
+//! let result = Sha1Dc::from("Hello World!").result();
+//! assert_eq!(result.digest, Sha1::from("Hello World!").digest());
+//! assert!(!result.collision);
+//! ```
+//!
+//! All attacks build a near collision block along one of a few disturbance
+//! vectors.  Every compressed block is matched against the unavoidable bit
+//! conditions of these vectors, and for the rare blocks satisfying them the
+//! block of the other message is reconstructed from the vector's message
+//! difference.  If both blocks end up at the same chaining value the input
+//! is half of a collision.  False positives are expected with a probability
+//! below `2^-90` and hashing is a couple of times slower than with
+//! [`Sha1`](crate::Sha1).
+//!
+//! A hasher created with [`Sha1Dc::hardened`] additionally rewrites the
+//! digest of such inputs like the "safe hash" mode of sha1collisiondetection
+//! does, which is the default of the copy git bundles: every detected block
+//! is compressed two more times, so the two messages of a collision no
+//! longer share a digest.  Benign inputs keep their regular SHA-1.
+//!
+//! ```
+//! use sha1_smol::sha1dc::Sha1Dc;
+//! use sha1_smol::Sha1;
+//!
+//! let mut m = Sha1Dc::hardened();
+//! m.update(b"Hello World!");
+//! assert_eq!(m.digest(), Sha1::from("Hello World!").digest());
+//! ```
+
+use crate::ubc::{ubc_check, DVS};
+use crate::{Blocks, Digest, Sha1State, DEFAULT_STATE, K0, K1, K2, K3};
+
+/// The result of hashing with collision detection.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Detection {
+    /// The SHA-1 of the input.
+    ///
+    /// For a hardened hasher this is not the SHA-1 if a collision was
+    /// detected.
+    pub digest: Digest,
+    /// `true` if the input contains a block of a collision attack.
+    pub collision: bool,
+}
+
+/// A SHA-1 hasher detecting collision attacks.
+#[derive(Clone)]
+pub struct Sha1Dc {
+    state: Sha1State,
+    blocks: Blocks,
+    len: u64,
+    collision: bool,
+    hardened: bool,
+}
+
+impl Default for Sha1Dc {
+    fn default() -> Sha1Dc {
+        Sha1Dc::new()
+    }
+}
+
+impl Sha1Dc {
+    /// Creates a fresh hasher.
+    pub fn new() -> Sha1Dc {
+        Sha1Dc {
+            state: DEFAULT_STATE,
+            blocks: Blocks {
+                len: 0,
+                block: [0; 64],
+            },
+            len: 0,
+            collision: false,
+            hardened: false,
+        }
+    }
+
+    /// Creates a fresh hasher that rewrites the digest of detected
+    /// collisions.
+    pub fn hardened() -> Sha1Dc {
+        Sha1Dc {
+            hardened: true,
+            ..Sha1Dc::new()
+        }
+    }
+
+    /// Returns `true` if the hasher rewrites the digest of detected
+    /// collisions.
+    pub fn is_hardened(&self) -> bool {
+        self.hardened
+    }
+
+    /// Shortcut to create a hasher from some bytes.
+    pub fn from<D: AsRef<[u8]>>(data: D) -> Sha1Dc {
+        let mut rv = Sha1Dc::new();
+        rv.update(data.as_ref());
+        rv
+    }
+
+    /// Resets the hasher to its initial state.
+    ///
+    /// A hardened hasher stays hardened.
+    pub fn reset(&mut self) {
+        self.state = DEFAULT_STATE;
+        self.blocks.len = 0;
+        self.len = 0;
+        self.collision = false;
+    }
+
+    /// Update hash with input data.
+    pub fn update(&mut self, data: &[u8]) {
+        let len = &mut self.len;
+        let state = &mut self.state;
+        let collision = &mut self.collision;
+        let hardened = self.hardened;
+        self.blocks.input(data, |block| {
+            *len += block.len() as u64;
+            *collision |= process(state, block, hardened);
+        })
+    }
+
+    /// Returns the digest together with whether a collision attack was
+    /// detected.
+    pub fn result(&self) -> Detection {
+        let mut state = self.state;
+        let mut collision = self.collision;
+        self.blocks.finish(self.len, |block| {
+            collision |= process(&mut state, block, self.hardened)
+        });
+        Detection {
+            digest: Digest { data: state },
+            collision,
+        }
+    }
+
+    /// Retrieve digest result.
+    ///
+    /// Unless the hasher is hardened and a collision was detected this is
+    /// the same digest [`Sha1`](crate::Sha1) computes, use
+    /// [`result`](Self::result) to learn about detected collisions.
+    pub fn digest(&self) -> Digest {
+        self.result().digest
+    }
+}
+
+#[inline(always)]
+fn f(t: usize, b: u32, c: u32, d: u32) -> u32 {
+    match t / 20 {
+        0 => (d ^ (b & (c ^ d))).wrapping_add(K0),
+        1 => (b ^ c ^ d).wrapping_add(K1),
+        2 => ((b & c) | (b & d) | (c & d)).wrapping_add(K2),
+        _ => (b ^ c ^ d).wrapping_add(K3),
+    }
+}
+
+/// Runs step `t` of the compression function forwards.
+#[inline(always)]
+fn step([a, b, c, d, e]: [u32; 5], t: usize, w: u32) -> [u32; 5] {
+    let temp = a
+        .rotate_left(5)
+        .wrapping_add(f(t, b, c, d))
+        .wrapping_add(e)
+        .wrapping_add(w);
+    [temp, a, b.rotate_left(30), c, d]
+}
+
+/// Runs step `t` of the compression function backwards.
+#[inline(always)]
+fn unstep([a, b, c, d, e]: [u32; 5], t: usize, w: u32) -> [u32; 5] {
+    let (a, b, c, d, temp) = (b, c.rotate_right(30), d, e, a);
+    let e = temp
+        .wrapping_sub(a.rotate_left(5))
+        .wrapping_sub(f(t, b, c, d))
+        .wrapping_sub(w);
+    [a, b, c, d, e]
+}
+
+fn add(a: [u32; 5], b: [u32; 5]) -> [u32; 5] {
+    let mut rv = a;
+    for (x, y) in rv.iter_mut().zip(b.iter()) {
+        *x = x.wrapping_add(*y);
+    }
+    rv
+}
+
+/// Compresses `block` into `state` and returns `true` if the block is one
+/// half of a near collision.
+///
+/// If `hardened` is set a detected block is compressed twice more.
+fn process(state: &mut Sha1State, block: &[u8; 64], hardened: bool) -> bool {
+    let mut w = [0u32; 80];
+    for (word, chunk) in w.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    for t in 16..80 {
+        w[t] = (w[t - 3] ^ w[t - 8] ^ w[t - 14] ^ w[t - 16]).rotate_left(1);
+    }
+
+    // the disturbance vectors are tested from the working variables
+    // before step 58 or 65
+    let ihv = state.state;
+    let mut s = ihv;
+    let (mut s58, mut s65) = (s, s);
+    for (t, &wt) in w.iter().enumerate() {
+        match t {
+            58 => s58 = s,
+            65 => s65 = s,
+            _ => {}
+        }
+        s = step(s, t, wt);
+    }
+    state.state = add(ihv, s);
+
+    let mask = ubc_check(&w);
+    let collision = DVS.iter().filter(|dv| mask & dv.bit != 0).any(|dv| {
+        let mut w2 = w;
+        for (x, y) in w2.iter_mut().zip(dv.dm.iter()) {
+            *x ^= *y;
+        }
+        let start = if dv.testt == 58 { s58 } else { s65 };
+        let mut ihv2 = start;
+        for (t, &wt) in w2[..dv.testt].iter().enumerate().rev() {
+            ihv2 = unstep(ihv2, t, wt);
+        }
+        let mut s2 = start;
+        for (t, &wt) in w2.iter().enumerate().skip(dv.testt) {
+            s2 = step(s2, t, wt);
+        }
+        add(ihv2, s2) == state.state
+    });
+    if collision && hardened {
+        state.process(block);
+        state.process(block);
+    }
+    collision
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha1;
+
+    // the first 320 bytes of the two PDFs from shattered.io and the two
+    // messages from sha-mbles.github.io, as shipped with the test suite of
+    // sha1collisiondetection.
+    const SHATTERED: [&[u8]; 2] = [
+        include_bytes!("testdata/shattered-1.bin"),
+        include_bytes!("testdata/shattered-2.bin"),
+    ];
+    const SHAMBLES: [&[u8]; 2] = [
+        include_bytes!("testdata/sha-mbles-1.bin"),
+        include_bytes!("testdata/sha-mbles-2.bin"),
+    ];
+
+    #[test]
+    fn test_benign() {
+        let mut data = [0u8; 300];
+        for (idx, b) in data.iter_mut().enumerate() {
+            *b = (idx * 7) as u8;
+        }
+        for len in 0..data.len() {
+            let result = Sha1Dc::from(&data[..len]).result();
+            assert_eq!(result.digest, Sha1::from(&data[..len]).digest());
+            assert!(!result.collision, "{}", len);
+        }
+
+        let mut m = Sha1Dc::new();
+        for chunk in data.chunks(13) {
+            m.update(chunk);
+        }
+        assert_eq!(m.digest(), Sha1::from(&data[..]).digest());
+    }
+
+    #[test]
+    fn test_shattered() {
+        for input in SHATTERED.iter() {
+            let result = Sha1Dc::from(*input).result();
+            assert_eq!(
+                result.digest,
+                "f92d74e3874587aaf443d1db961d4e26dde13e9c".parse().unwrap()
+            );
+            assert!(result.collision);
+
+            // a common suffix keeps the collision and its detection
+            let mut m = Sha1Dc::from(*input);
+            m.update(b"suffix");
+            let mut plain = Sha1::from(*input);
+            plain.update(b"suffix");
+            assert_eq!(m.result().digest, plain.digest());
+            assert!(m.result().collision);
+        }
+        assert_ne!(SHATTERED[0], SHATTERED[1]);
+    }
+
+    #[test]
+    fn test_shambles() {
+        for input in SHAMBLES.iter() {
+            let result = Sha1Dc::from(*input).result();
+            assert_eq!(
+                result.digest,
+                "8ac60ba76f1999a1ab70223f225aefdc78d4ddc0".parse().unwrap()
+            );
+            assert!(result.collision);
+        }
+        assert_ne!(SHAMBLES[0], SHAMBLES[1]);
+    }
+
+    fn hardened(data: &[u8]) -> Detection {
+        let mut m = Sha1Dc::hardened();
+        m.update(data);
+        m.result()
+    }
+
+    #[test]
+    fn test_hardened() {
+        // the safe hash digests of the SHAmbles messages as listed by the
+        // test suite of sha1collisiondetection
+        let expected = [
+            "4f3d9be4a472c4dae83c6314aa6c36a064c1fd14",
+            "9ed5d77a4f48be1dbf3e9e15650733eb850897f2",
+        ];
+        for (input, digest) in SHAMBLES.iter().zip(expected.iter()) {
+            let result = hardened(input);
+            assert_eq!(result.digest, digest.parse().unwrap());
+            assert!(result.collision);
+        }
+
+        let a = hardened(SHATTERED[0]);
+        let b = hardened(SHATTERED[1]);
+        assert!(a.collision && b.collision);
+        assert_ne!(a.digest, b.digest);
+        assert_ne!(a.digest, Sha1::from(SHATTERED[0]).digest());
+    }
+
+    #[test]
+    fn test_hardened_benign() {
+        let data = [0x5au8; 1000];
+        for len in (0..data.len()).step_by(37) {
+            let mut m = Sha1Dc::hardened();
+            assert!(m.is_hardened());
+            m.update(&data[..len]);
+            let result = m.result();
+            assert_eq!(result.digest, Sha1::from(&data[..len]).digest());
+            assert!(!result.collision);
+        }
+        assert!(!Sha1Dc::new().is_hardened());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut m = Sha1Dc::from(SHATTERED[0]);
+        assert!(m.result().collision);
+        m.reset();
+        m.update(b"abc");
+        assert_eq!(m.result().digest, Sha1::from("abc").digest());
+        assert!(!m.result().collision);
+
+        let mut m = Sha1Dc::hardened();
+        m.update(SHAMBLES[0]);
+        m.reset();
+        assert!(m.is_hardened());
+        m.update(SHAMBLES[0]);
+        assert_eq!(
+            m.digest(),
+            "4f3d9be4a472c4dae83c6314aa6c36a064c1fd14".parse().unwrap()
+        );
+    }
+}
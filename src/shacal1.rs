@@ -0,0 +1,137 @@
+//! The SHACAL-1 block cipher.
+//!
+//! (This module is only available if the `shacal1` feature is enabled)
+//!
+//! SHACAL-1 is the SHA-1 compression function without the final addition
+//! of the chaining value: the 160 bit block takes the place of the state
+//! and the 512 bit key the place of the message block.
+//!
+//! ```
+//! use sha1_smol::shacal1;
+//!
+//! let key = [7u8; shacal1::KEY_LENGTH];
+//! let block = *b"twenty byte block!!!";
+//! let encrypted = shacal1::encrypt_block(&key, &block);
+//! assert_eq!(shacal1::decrypt_block(&key, &encrypted), block);
+//! ```
+//!
+//! Like SHA-1 itself this is provided for compatibility with existing
+//! formats and research, not for new designs.
+
+use crate::{Sha1State, DIGEST_LENGTH, K0, K1, K2, K3};
+
+/// The length of a key in bytes.
+pub const KEY_LENGTH: usize = 64;
+
+/// The length of a block in bytes.
+pub const BLOCK_LENGTH: usize = DIGEST_LENGTH;
+
+fn load(block: &[u8; BLOCK_LENGTH]) -> [u32; 5] {
+    let mut rv = [0u32; 5];
+    for (word, chunk) in rv.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    rv
+}
+
+fn store(words: [u32; 5]) -> [u8; BLOCK_LENGTH] {
+    let mut rv = [0u8; BLOCK_LENGTH];
+    for (chunk, word) in rv.chunks_mut(4).zip(words.iter()) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    rv
+}
+
+/// Encrypts a block.
+pub fn encrypt_block(key: &[u8; KEY_LENGTH], block: &[u8; BLOCK_LENGTH]) -> [u8; BLOCK_LENGTH] {
+    store(Sha1State { state: load(block) }.rounds(key))
+}
+
+/// Decrypts a block by running the rounds of [`encrypt_block`] backwards.
+pub fn decrypt_block(key: &[u8; KEY_LENGTH], block: &[u8; BLOCK_LENGTH]) -> [u8; BLOCK_LENGTH] {
+    let mut w = [0u32; 80];
+    for (word, chunk) in w.iter_mut().zip(key.chunks(4)) {
+        *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    for t in 16..80 {
+        w[t] = (w[t - 3] ^ w[t - 8] ^ w[t - 14] ^ w[t - 16]).rotate_left(1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e] = load(block);
+    for t in (0..80).rev() {
+        // undo `e, d, c, b, a = d, c, b <<< 30, a, temp`
+        let temp = a;
+        a = b;
+        b = c.rotate_right(30);
+        c = d;
+        d = e;
+        let (f, k) = match t / 20 {
+            0 => (d ^ (b & (c ^ d)), K0),
+            1 => (b ^ c ^ d, K1),
+            2 => ((b & c) | (b & d) | (c & d), K2),
+            _ => (b ^ c ^ d, K3),
+        };
+        e = temp
+            .wrapping_sub(a.rotate_left(5))
+            .wrapping_sub(f)
+            .wrapping_sub(k)
+            .wrapping_sub(w[t]);
+    }
+    store([a, b, c, d, e])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sha1;
+
+    #[test]
+    fn test_matches_compression() {
+        // the padded block of "abc" encrypts the IV to the SHA-1 of "abc"
+        // minus the feed forward
+        let mut key = [0u8; KEY_LENGTH];
+        key[..3].copy_from_slice(b"abc");
+        key[3] = 0x80;
+        key[63] = 24;
+        let iv = store(crate::DEFAULT_STATE.state);
+        let encrypted = load(&encrypt_block(&key, &iv));
+        let mut digest = [0u32; 5];
+        for ((out, x), y) in digest
+            .iter_mut()
+            .zip(encrypted.iter())
+            .zip(load(&iv).iter())
+        {
+            *out = x.wrapping_add(*y);
+        }
+        assert_eq!(store(digest), Sha1::from("abc").digest().bytes());
+        assert_eq!(decrypt_block(&key, &store(encrypted)), iv);
+    }
+
+    #[test]
+    fn test_nessie() {
+        // NESSIE SHACAL-1 test vectors, set 1, vector #0
+        let mut key = [0u8; KEY_LENGTH];
+        key[0] = 0x80;
+        let plain = [0u8; BLOCK_LENGTH];
+        let cipher = [
+            0x0f, 0xfd, 0x8d, 0x43, 0xb4, 0xe3, 0x3c, 0x7c, 0x53, 0x46, 0x1b, 0xd1, 0x0f, 0x27,
+            0xa5, 0x46, 0x10, 0x50, 0xd9, 0x0d,
+        ];
+        assert_eq!(encrypt_block(&key, &plain), cipher);
+        assert_eq!(decrypt_block(&key, &cipher), plain);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut key = [0u8; KEY_LENGTH];
+        let mut block = [0u8; BLOCK_LENGTH];
+        for i in 0..32u8 {
+            for (idx, b) in key.iter_mut().enumerate() {
+                *b = (idx as u8).wrapping_mul(31).wrapping_add(i);
+            }
+            block = encrypt_block(&key, &block);
+            assert_eq!(decrypt_block(&key, &encrypt_block(&key, &block)), block);
+        }
+        assert_ne!(encrypt_block(&key, &block), block);
+    }
+}
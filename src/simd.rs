@@ -139,7 +139,7 @@ impl Shr<u32x4> for u32x4 {
 }
 
 #[derive(Clone, Copy)]
-#[allow(non_camel_case_types)]
+#[allow(non_camel_case_types, dead_code)]
 pub struct u64x2(pub u64, pub u64);
 
 impl Add for u64x2 {
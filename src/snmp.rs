@@ -0,0 +1,130 @@
+//! SNMPv3 user based security model authentication (`usmHMACSHAAuthProtocol`).
+//!
+//! RFC 3414 derives a key from the user's password by hashing one megabyte
+//! of the repeated password, localizes it to an SNMP engine by hashing it
+//! together with the engine ID and authenticates messages with the first 12
+//! bytes of HMAC-SHA1 (HMAC-SHA-1-96).  The tag is computed over the whole
+//! message with the `msgAuthenticationParameters` field set to zeros:
+//!
+//! ```
+//! use sha1_smol::snmp;
+//!
+//! let key = snmp::password_to_key(b"maplesyrup");
+//! let key = snmp::localize_key(&key, b"\x80\x00\x1f\x88\x04engine");
+//!
+//! // `offset` is the position of the 12 bytes of `msgAuthenticationParameters`
+//! let mut message = [0u8; 64];
+//! let offset = 20;
+//! let tag = snmp::auth_params(&key, &message, offset);
+//! message[offset..offset + 12].copy_from_slice(&tag);
+//! assert!(snmp::verify_auth_params(&key, &message, offset));
+//! ```
+
+use crate::hmac::{ct_eq, HmacSha1};
+use crate::{Sha1, DIGEST_LENGTH};
+
+/// The length of the `msgAuthenticationParameters` of HMAC-SHA-1-96.
+pub const AUTH_PARAMS_LENGTH: usize = 12;
+
+/// The number of bytes of the repeated password that are hashed.
+const EXPANDED_PASSWORD_LENGTH: usize = 1024 * 1024;
+
+/// Derives the user key from a password (RFC 3414 section A.2.2).
+///
+/// This panics if `password` is empty.  RFC 3414 requires passwords of at
+/// least eight characters but that is left to the caller to enforce.
+pub fn password_to_key(password: &[u8]) -> [u8; DIGEST_LENGTH] {
+    assert!(!password.is_empty(), "password must not be empty");
+    let mut m = Sha1::new();
+    let mut buf = [0u8; 64];
+    let mut idx = 0;
+    for _ in 0..EXPANDED_PASSWORD_LENGTH / buf.len() {
+        for byte in buf.iter_mut() {
+            *byte = password[idx % password.len()];
+            idx += 1;
+        }
+        m.update(&buf);
+    }
+    m.digest().bytes()
+}
+
+/// Localizes a user key to the SNMP engine with the given ID.
+pub fn localize_key(key: &[u8; DIGEST_LENGTH], engine_id: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut m = Sha1::from(&key[..]);
+    m.update(engine_id);
+    m.update(key);
+    m.digest().bytes()
+}
+
+/// Computes the `msgAuthenticationParameters` of a message.
+///
+/// `offset` is the position of the parameters within `message`, the
+/// current contents of those 12 bytes are ignored and hashed as zeros.  This
+/// panics if the parameters do not fit into `message`.
+pub fn auth_params(
+    localized_key: &[u8; DIGEST_LENGTH],
+    message: &[u8],
+    offset: usize,
+) -> [u8; AUTH_PARAMS_LENGTH] {
+    let end = offset
+        .checked_add(AUTH_PARAMS_LENGTH)
+        .filter(|&end| end <= message.len())
+        .expect("authentication parameters out of bounds");
+    let mut mac = HmacSha1::new(localized_key);
+    mac.update(&message[..offset]);
+    mac.update(&[0; AUTH_PARAMS_LENGTH]);
+    mac.update(&message[end..]);
+    let mut rv = [0u8; AUTH_PARAMS_LENGTH];
+    rv.copy_from_slice(&mac.digest().bytes()[..AUTH_PARAMS_LENGTH]);
+    rv
+}
+
+/// Verifies the `msgAuthenticationParameters` found at `offset` in a
+/// received message.
+///
+/// The comparison is constant time.  This panics if the parameters do not
+/// fit into `message`.
+pub fn verify_auth_params(
+    localized_key: &[u8; DIGEST_LENGTH],
+    message: &[u8],
+    offset: usize,
+) -> bool {
+    let expected = auth_params(localized_key, message, offset);
+    ct_eq(&expected, &message[offset..offset + AUTH_PARAMS_LENGTH])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::unhex;
+
+    #[test]
+    fn test_keys() {
+        // example from RFC 3414 section A.3.2
+        let key = password_to_key(b"maplesyrup");
+        assert_eq!(key, unhex("9fb5cc0381497b3793528939ff788d5d79145211"));
+        let key = localize_key(&key, &unhex::<12>("000000000000000000000002"));
+        assert_eq!(key, unhex("6695febc9288e36282235fc7151f128497b38f3f"));
+    }
+
+    #[test]
+    fn test_auth_params() {
+        let key = unhex("6695febc9288e36282235fc7151f128497b38f3f");
+        let mut message = *b"\x30\x20header\x04\x0cxxxxxxxxxxxxpayload";
+        // reference value computed with python's hmac module
+        let expected = unhex("0faf62593b7f0b5545884f3e");
+        assert_eq!(auth_params(&key, &message, 10), expected);
+        assert!(!verify_auth_params(&key, &message, 10));
+
+        message[10..22].copy_from_slice(&expected);
+        assert!(verify_auth_params(&key, &message, 10));
+        message[28] ^= 1;
+        assert!(!verify_auth_params(&key, &message, 10));
+    }
+
+    #[test]
+    #[should_panic(expected = "authentication parameters out of bounds")]
+    fn test_auth_params_out_of_bounds() {
+        auth_params(&[0; 20], &[0; 20], 9);
+    }
+}
@@ -0,0 +1,195 @@
+//! Subresource Integrity strings.
+//!
+//! Subresource Integrity (SRI) describes the expected digest of a resource
+//! as the algorithm name followed by a dash and the base64 encoded digest.
+//! Browsers no longer accept SHA-1, but legacy asset pipelines and lock files
+//! still carry `sha1-` integrity strings.
+//!
+//! ```
+//! use sha1_smol::sri::{verify_integrity, Integrity};
+//!
+//! let integrity = Integrity(sha1_smol::Sha1::from("Hello World!").digest());
+//! assert_eq!(integrity.to_string(), "sha1-Lve95gjOVATpfV8EL5X4nxwjKHE=");
+//! assert!(verify_integrity(b"Hello World!", "sha1-Lve95gjOVATpfV8EL5X4nxwjKHE="));
+//! ```
+
+use core::{fmt, str};
+
+use crate::encoding::{decode_b64_into, encode_b64_into, Alphabet};
+use crate::hmac::ct_eq;
+use crate::{Digest, DigestParseError, Sha1, DIGEST_LENGTH};
+
+/// The algorithm prefix of a SHA-1 integrity string.
+pub const PREFIX: &str = "sha1-";
+
+/// The length of a SHA-1 integrity string.
+pub const INTEGRITY_LENGTH: usize = PREFIX.len() + 28;
+
+/// A SHA-1 integrity string.
+///
+/// Formatting produces padded standard base64.  Parsing additionally
+/// accepts the algorithm name in any case, the URL safe alphabet, missing
+/// padding and trailing `?` options, which are ignored.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Integrity(pub Digest);
+
+impl Integrity {
+    /// Writes the integrity string into a buffer and returns it as string
+    /// slice.
+    pub fn encode_into<'a>(&self, out: &'a mut [u8; INTEGRITY_LENGTH]) -> &'a str {
+        out[..PREFIX.len()].copy_from_slice(PREFIX.as_bytes());
+        encode_b64_into(
+            &self.0.bytes(),
+            Alphabet::Standard,
+            true,
+            &mut out[PREFIX.len()..],
+        );
+        str::from_utf8(out).unwrap()
+    }
+}
+
+impl From<Digest> for Integrity {
+    fn from(digest: Digest) -> Integrity {
+        Integrity(digest)
+    }
+}
+
+impl From<Integrity> for Digest {
+    fn from(integrity: Integrity) -> Digest {
+        integrity.0
+    }
+}
+
+impl fmt::Display for Integrity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut buf = [0u8; INTEGRITY_LENGTH];
+        f.write_str(self.encode_into(&mut buf))
+    }
+}
+
+impl str::FromStr for Integrity {
+    type Err = DigestParseError;
+
+    fn from_str(s: &str) -> Result<Integrity, DigestParseError> {
+        let s = s.as_bytes();
+        if s.len() < PREFIX.len() || !s[..PREFIX.len()].eq_ignore_ascii_case(PREFIX.as_bytes()) {
+            return Err(DigestParseError(()));
+        }
+        let value = &s[PREFIX.len()..];
+        let value = match value.iter().position(|&c| c == b'?') {
+            Some(idx) => &value[..idx],
+            None => value,
+        };
+        let mut bytes = [0u8; DIGEST_LENGTH];
+        let len = decode_b64_into(value, Alphabet::Standard, &mut bytes)
+            .or_else(|| decode_b64_into(value, Alphabet::UrlSafe, &mut bytes))
+            .ok_or(DigestParseError(()))?;
+        if len != DIGEST_LENGTH {
+            return Err(DigestParseError(()));
+        }
+        Ok(Integrity(Digest::from_bytes(&bytes)))
+    }
+}
+
+/// Verifies `data` against integrity metadata.
+///
+/// `integrity` may be a whitespace separated list as found in the
+/// `integrity` attribute of HTML elements.  Like browsers only the
+/// strongest algorithm present is checked, so if the list contains a
+/// `sha256`, `sha384` or `sha512` entry this returns `false` as those can't
+/// be verified here, rather than falling back to SHA-1.  Otherwise entries
+/// of unknown algorithms and malformed entries are skipped and the data
+/// matches if any SHA-1 entry matches.  Digests are compared in constant
+/// time.
+pub fn verify_integrity(data: &[u8], integrity: &str) -> bool {
+    let stronger = integrity.split_ascii_whitespace().any(|token| {
+        STRONGER_PREFIXES.iter().any(|prefix| {
+            token
+                .get(..prefix.len())
+                .map_or(false, |head| head.eq_ignore_ascii_case(prefix))
+        })
+    });
+    if stronger {
+        return false;
+    }
+    let actual = Sha1::from(data).digest().bytes();
+    let mut matched = false;
+    for expected in integrity
+        .split_ascii_whitespace()
+        .filter_map(|token| token.parse::<Integrity>().ok())
+    {
+        matched |= ct_eq(&actual, &expected.0.bytes());
+    }
+    matched
+}
+
+/// The prefixes of the algorithms SRI prefers over SHA-1.
+const STRONGER_PREFIXES: [&str; 3] = ["sha256-", "sha384-", "sha512-"];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        // base64 of the sha1 of "Hello World!" from python hashlib
+        let integrity = Integrity(Sha1::from("Hello World!").digest());
+        let mut buf = [0u8; INTEGRITY_LENGTH];
+        assert_eq!(
+            integrity.encode_into(&mut buf),
+            "sha1-Lve95gjOVATpfV8EL5X4nxwjKHE="
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let expected = Integrity(Sha1::from("Hello World!").digest());
+        for s in &[
+            "sha1-Lve95gjOVATpfV8EL5X4nxwjKHE=",
+            "SHA1-Lve95gjOVATpfV8EL5X4nxwjKHE",
+            "sha1-Lve95gjOVATpfV8EL5X4nxwjKHE=?ct=application/javascript",
+        ] {
+            assert_eq!(s.parse::<Integrity>(), Ok(expected), "{}", s);
+        }
+
+        // the empty string which contains both `+` and `/`
+        let empty = Integrity(Sha1::new().digest());
+        assert_eq!("sha1-2jmj7l5rSw0yVb/vlWAYkK/YBwk=".parse(), Ok(empty));
+        assert_eq!("sha1-2jmj7l5rSw0yVb_vlWAYkK_YBwk=".parse(), Ok(empty));
+
+        for s in &[
+            "",
+            "sha1-",
+            "sha256-Lve95gjOVATpfV8EL5X4nxwjKHE=",
+            "sha1-Lve95gjOVATpfV8EL5X4nxwjKH",
+            "sha1-Lve95gjOVATpfV8EL5X4nxwjKHE=x",
+            "sha1-Lve95gjOVATpfV8EL5X4nxwjKHEA",
+        ] {
+            assert!(s.parse::<Integrity>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_verify_integrity() {
+        let data = b"Hello World!";
+        assert!(verify_integrity(data, "sha1-Lve95gjOVATpfV8EL5X4nxwjKHE="));
+        assert!(verify_integrity(
+            data,
+            "md5-7Qdih1MuhjZehB6Sv8UNjA== \
+             sha1-2jmj7l5rSw0yVb/vlWAYkK/YBwk=\tsha1-Lve95gjOVATpfV8EL5X4nxwjKHE="
+        ));
+        assert!(!verify_integrity(data, "sha1-2jmj7l5rSw0yVb/vlWAYkK/YBwk="));
+        assert!(!verify_integrity(data, "sha1-garbage"));
+        assert!(!verify_integrity(data, ""));
+
+        // a stronger algorithm wins even if the sha1 entry matches
+        for list in &[
+            "sha384-oqVuAfXRKap7fdgcCY5uykM6+R9GqQ8K/uxy9rx7HNQlGYl1kPzQho1wx4JwY8wC \
+             sha1-Lve95gjOVATpfV8EL5X4nxwjKHE=",
+            "sha1-Lve95gjOVATpfV8EL5X4nxwjKHE= SHA256-garbage",
+            "sha1-Lve95gjOVATpfV8EL5X4nxwjKHE= sha512-x",
+        ] {
+            assert!(!verify_integrity(data, list), "{}", list);
+        }
+    }
+}
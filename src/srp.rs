@@ -0,0 +1,180 @@
+//! The SHA-1 constructions of SRP (RFC 2945).
+//!
+//! These are the hashing steps of the Secure Remote Password protocol.  The
+//! modular arithmetic is left to the caller, all integers are passed as
+//! unsigned big endian byte strings:
+//!
+//! ```
+//! use sha1_smol::srp;
+//!
+//! # let (salt, n, g, a, b, s) = (b"salt", b"N", b"g", b"A", b"B", b"S");
+//! let x = srp::private_key(salt, b"alice", b"password123");
+//! // ... compute the premaster secret S from x ...
+//! let key = srp::interleave(s);
+//! let m = srp::client_proof(n, g, b"alice", salt, a, b, &key);
+//! let expected = srp::server_proof(a, &m, &key);
+//! ```
+
+use crate::{Sha1, DIGEST_LENGTH};
+
+/// The length of the session key produced by [`interleave`].
+pub const SESSION_KEY_LENGTH: usize = 2 * DIGEST_LENGTH;
+
+/// Computes the private key `x = SHA1(s | SHA1(U | ":" | p))`.
+pub fn private_key(salt: &[u8], username: &[u8], password: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut inner = Sha1::new();
+    inner.update(username);
+    inner.update(b":");
+    inner.update(password);
+    let mut outer = Sha1::from(salt);
+    outer.update(&inner.digest().bytes());
+    outer.digest().bytes()
+}
+
+/// Computes the hashed identity `SHA1(U)`.
+pub fn hash_identity(username: &[u8]) -> [u8; DIGEST_LENGTH] {
+    Sha1::from(username).digest().bytes()
+}
+
+/// Computes `SHA1(N) xor SHA1(g)`.
+pub fn hash_n_xor_g(n: &[u8], g: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut rv = Sha1::from(n).digest().bytes();
+    let g = Sha1::from(g).digest().bytes();
+    for (a, b) in rv.iter_mut().zip(g.iter()) {
+        *a ^= b;
+    }
+    rv
+}
+
+/// Derives the session key `K` from the premaster secret `S` with the
+/// interleaved hash `SHA_Interleave`.
+///
+/// Leading zero bytes of `S` are removed, and if an odd number of bytes is
+/// left the first one is dropped as well.  The even and odd bytes are hashed
+/// separately and the two digests are interleaved.
+pub fn interleave(secret: &[u8]) -> [u8; SESSION_KEY_LENGTH] {
+    let mut secret = secret;
+    while let Some((&0, rest)) = secret.split_first() {
+        secret = rest;
+    }
+    if secret.len() % 2 == 1 {
+        secret = &secret[1..];
+    }
+    let mut even = Sha1::new();
+    let mut odd = Sha1::new();
+    for pair in secret.chunks(2) {
+        even.update(&pair[..1]);
+        odd.update(&pair[1..]);
+    }
+    let even = even.digest().bytes();
+    let odd = odd.digest().bytes();
+    let mut rv = [0u8; SESSION_KEY_LENGTH];
+    for (idx, pair) in rv.chunks_mut(2).enumerate() {
+        pair[0] = even[idx];
+        pair[1] = odd[idx];
+    }
+    rv
+}
+
+/// Computes the client's proof of the session key.
+///
+/// `M = SHA1(SHA1(N) xor SHA1(g) | SHA1(U) | s | A | B | K)`
+pub fn client_proof(
+    n: &[u8],
+    g: &[u8],
+    username: &[u8],
+    salt: &[u8],
+    a: &[u8],
+    b: &[u8],
+    key: &[u8],
+) -> [u8; DIGEST_LENGTH] {
+    let mut m = Sha1::from(&hash_n_xor_g(n, g)[..]);
+    m.update(&hash_identity(username));
+    m.update(salt);
+    m.update(a);
+    m.update(b);
+    m.update(key);
+    m.digest().bytes()
+}
+
+/// Computes the server's proof of the session key `SHA1(A | M | K)`.
+pub fn server_proof(
+    a: &[u8],
+    client_proof: &[u8; DIGEST_LENGTH],
+    key: &[u8],
+) -> [u8; DIGEST_LENGTH] {
+    let mut m = Sha1::from(a);
+    m.update(client_proof);
+    m.update(key);
+    m.digest().bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::unhex;
+
+    // the 1024 bit group of RFC 5054 appendix A
+    const N_1024: &str = "eeaf0ab9adb38dd69c33f80afa8fc5e86072618775ff3c0b9ea2314c9c256576\
+                          d674df7496ea81d3383b4813d692c6e0e0d5d8e250b98be48e495c1d6089dad1\
+                          5dc7d7b46154d6b6ce8ef4ad69b15d4982559b297bcf1885c529f566660e57ec\
+                          68edbc3c05726cc02fd4cbf4976eaa9afd5138fe8376435b9fc61d2fc0eb06e3";
+
+    #[test]
+    fn test_private_key() {
+        // RFC 5054 appendix B
+        let salt = unhex::<16>("beb25379d1a8581eb5a727673a2441ee");
+        assert_eq!(
+            private_key(&salt, b"alice", b"password123"),
+            unhex("94b7555aabe9127cc58ccf4993db6cf84d16c124")
+        );
+    }
+
+    // the remaining values were computed with Python's hashlib following
+    // RFC 2945
+
+    fn premaster() -> [u8; 71] {
+        let mut rv = [0u8; 71];
+        for (idx, byte) in rv.iter_mut().enumerate().skip(2) {
+            *byte = idx as u8 - 1;
+        }
+        rv
+    }
+
+    #[test]
+    fn test_interleave() {
+        // two leading zeros and an odd number of remaining bytes
+        assert_eq!(
+            interleave(&premaster())[..],
+            unhex::<40>(
+                "b497fd72f192fbc63e142fd1cf6356120a37835a\
+                 c34ed5b7ce6a3744e213e6a1d41939f6adf79dfa"
+            )[..]
+        );
+        assert_eq!(interleave(&premaster()[3..]), interleave(&premaster()));
+    }
+
+    #[test]
+    fn test_proofs() {
+        let n = unhex::<128>(N_1024);
+        assert_eq!(
+            hash_n_xor_g(&n, &[2]),
+            unhex("ab2c16200a9951bd19cdb2b1bf8a69ed5ca39ea9")
+        );
+
+        let salt = unhex::<16>("beb25379d1a8581eb5a727673a2441ee");
+        let mut a = [0u8; 64];
+        let mut b = [0u8; 64];
+        for idx in 0..64 {
+            a[idx] = idx as u8 + 1;
+            b[idx] = idx as u8 + 65;
+        }
+        let key = interleave(&premaster());
+        let m = client_proof(&n, &[2], b"alice", &salt, &a, &b, &key);
+        assert_eq!(m, unhex("33365f4056a95d2c98b6da7a8c343cf9dc666f8e"));
+        assert_eq!(
+            server_proof(&a, &m, &key),
+            unhex("1fd0059d3873b2801b84c3a1705b07a51cb353e3")
+        );
+    }
+}
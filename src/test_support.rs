@@ -0,0 +1,167 @@
+//! A reusable test harness for SHA-1 implementations.
+//!
+//! [`check_impl`] runs the vector suite this crate tests itself against on
+//! any [`Sha1Engine`], which lets integrators of alternative backends (for
+//! instance hardware engines) validate them with a single call from their
+//! own test suite:
+//!
+//! ```
+//! sha1_smol::test_support::check_impl(sha1_smol::Sha1::new);
+//! ```
+
+use crate::backend::Sha1Engine;
+use crate::{Digest, Sha1};
+
+/// Known answer vectors as `(input, hex digest)`.
+static VECTORS: &[(&str, &str)] = &[
+    ("", "da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+    ("abc", "a9993e364706816aba3e25717850c26c9cd0d89d"),
+    (
+        "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+        "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+    ),
+    (
+        "The quick brown fox jumps over the lazy dog",
+        "2fd4e1c67a2d28fced849ee1bb76e7391b93eb12",
+    ),
+    (
+        "The quick brown fox jumps over the lazy cog",
+        "de9f2c7fd25e1b3afad3e85a0bd17d9b100db4b3",
+    ),
+    ("testing\n", "9801739daae44ec5293d4e1f53d3f4d2d426d91c"),
+    (
+        "xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx",
+        "cef734ba81a024479e09eb5a75b6ddae62e6abf1",
+    ),
+    (
+        "xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx",
+        "025ecbd5d70f8fb3c5457cd96bab13fda305dc59",
+    ),
+];
+
+fn parse(hex: &str) -> Digest {
+    hex.parse().expect("invalid vector")
+}
+
+/// Runs the vector suite against engines created by `factory`.
+///
+/// This covers the known answer vectors, every input length across the
+/// first two block boundaries (compared with the portable implementation),
+/// updates split at every position, `reset` and one million `a`s fed in
+/// uneven pieces.  It also requires [`Sha1Engine::try_finalize`] to agree
+/// with `digest`.  It panics with a description of the failing case on the
+/// first mismatch.
+pub fn check_impl<E, F>(mut factory: F)
+where
+    E: Sha1Engine,
+    F: FnMut() -> E,
+{
+    for &(input, expected) in VECTORS {
+        let mut engine = factory();
+        engine.update(input.as_bytes());
+        assert_eq!(engine.digest(), parse(expected), "vector {:?}", input);
+        assert_eq!(
+            engine.try_finalize(),
+            Ok(parse(expected)),
+            "try_finalize of vector {:?}",
+            input
+        );
+    }
+
+    let mut data = [0u8; 130];
+    for (idx, byte) in data.iter_mut().enumerate() {
+        *byte = (idx as u8).wrapping_mul(167).wrapping_add(13);
+    }
+    for len in 0..=data.len() {
+        let mut engine = factory();
+        engine.update(&data[..len]);
+        let expected = Sha1::from(&data[..len]).digest();
+        assert_eq!(engine.digest(), expected, "input of {} bytes", len);
+    }
+
+    let expected = Sha1::from(&data[..]).digest();
+    for split in 0..=data.len() {
+        let mut engine = factory();
+        engine.update(&data[..split]);
+        engine.update(&[]);
+        engine.update(&data[split..]);
+        assert_eq!(engine.digest(), expected, "update split at {}", split);
+    }
+
+    let mut engine = factory();
+    engine.update(b"garbage");
+    let _ = engine.digest();
+    engine.reset();
+    engine.update(b"abc");
+    assert_eq!(
+        engine.digest(),
+        parse("a9993e364706816aba3e25717850c26c9cd0d89d"),
+        "after reset"
+    );
+
+    let mut engine = factory();
+    let chunk = [b'a'; 997];
+    let mut remaining = 1_000_000;
+    while remaining > 0 {
+        let n = core::cmp::min(remaining, chunk.len());
+        engine.update(&chunk[..n]);
+        remaining -= n;
+    }
+    assert_eq!(
+        engine.digest(),
+        parse("34aa973cd4c4daa4f61eeb2bdbad27316534016f"),
+        "one million a"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Std(Sha1);
+
+    impl Sha1Engine for Std {
+        fn update(&mut self, data: &[u8]) {
+            self.0.update(data);
+        }
+
+        fn digest(&self) -> Digest {
+            self.0.digest()
+        }
+
+        fn reset(&mut self) {
+            self.0.reset();
+        }
+    }
+
+    struct Broken(Sha1);
+
+    impl Sha1Engine for Broken {
+        fn update(&mut self, data: &[u8]) {
+            // drops the last byte of every update
+            if !data.is_empty() {
+                self.0.update(&data[..data.len() - 1]);
+            }
+        }
+
+        fn digest(&self) -> Digest {
+            self.0.digest()
+        }
+
+        fn reset(&mut self) {
+            self.0.reset();
+        }
+    }
+
+    #[test]
+    fn test_check_impl() {
+        check_impl(Sha1::new);
+        check_impl(|| Std(Sha1::new()));
+    }
+
+    #[test]
+    #[should_panic(expected = "vector \"abc\"")]
+    fn test_check_impl_detects_bugs() {
+        check_impl(|| Broken(Sha1::new()));
+    }
+}
@@ -0,0 +1,197 @@
+//! The SHA-1 half of the TLS 1.0 / 1.1 pseudo random function.
+//!
+//! TLS 1.0 (RFC 2246) and 1.1 (RFC 4346) define their PRF as the XOR of
+//! `P_MD5` and `P_SHA1` over the two halves of the secret.  This module
+//! implements the `P_SHA1` expansion with the label already prepended to
+//! the seed:
+//!
+//! ```
+//! let mut key_block = [0u8; 104];
+//! sha1_smol::tls::p_sha1(b"secret", b"key expansion", b"seed", &mut key_block);
+//! ```
+//!
+//! For output that is consumed incrementally [`PSha1`] produces the same
+//! stream in pieces.
+//!
+//! [`TranscriptSha1`] keeps the running SHA-1 handshake hash that TLS 1.1
+//! and earlier combine with an MD5 hash for signatures and `Finished`
+//! messages.
+
+use crate::hmac::HmacKey;
+use crate::{Digest, Sha1, DIGEST_LENGTH};
+
+/// The length of a combined `MD5 || SHA-1` handshake hash.
+pub const COMBINED_LENGTH: usize = 16 + DIGEST_LENGTH;
+
+/// Fills `out` with `P_SHA1(secret, label || seed)`.
+pub fn p_sha1(secret: &[u8], label: &[u8], seed: &[u8], out: &mut [u8]) {
+    PSha1::new(secret, label, seed).fill(out);
+}
+
+/// A streaming `P_SHA1` expansion.
+///
+/// Calling [`fill`](PSha1::fill) repeatedly continues the output stream, so
+/// splitting a request for `n` bytes over multiple calls yields the same
+/// bytes as a single call.
+#[derive(Clone)]
+pub struct PSha1<'a> {
+    key: HmacKey,
+    label: &'a [u8],
+    seed: &'a [u8],
+    a: [u8; DIGEST_LENGTH],
+    block: [u8; DIGEST_LENGTH],
+    pos: usize,
+}
+
+impl<'a> PSha1<'a> {
+    /// Starts the expansion of `secret` over `label || seed`.
+    pub fn new(secret: &[u8], label: &'a [u8], seed: &'a [u8]) -> PSha1<'a> {
+        let key = HmacKey::new(secret);
+        let mut m = key.hmac();
+        m.update(label);
+        m.update(seed);
+        let a = m.digest().bytes();
+        PSha1 {
+            key,
+            label,
+            seed,
+            a,
+            block: [0; DIGEST_LENGTH],
+            pos: DIGEST_LENGTH,
+        }
+    }
+
+    /// Writes the next `out.len()` bytes of the stream into `out`.
+    pub fn fill(&mut self, out: &mut [u8]) {
+        let mut out = out;
+        while !out.is_empty() {
+            if self.pos == DIGEST_LENGTH {
+                self.next_block();
+            }
+            let n = core::cmp::min(out.len(), DIGEST_LENGTH - self.pos);
+            out[..n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+            self.pos += n;
+            out = &mut out[n..];
+        }
+    }
+
+    fn next_block(&mut self) {
+        // block(i) = HMAC(secret, A(i) || label || seed)
+        let mut m = self.key.hmac();
+        m.update(&self.a);
+        m.update(self.label);
+        m.update(self.seed);
+        self.block = m.digest().bytes();
+        // A(i + 1) = HMAC(secret, A(i))
+        let mut m = self.key.hmac();
+        m.update(&self.a);
+        self.a = m.digest().bytes();
+        self.pos = 0;
+    }
+}
+
+/// The running SHA-1 hash over the handshake messages.
+///
+/// The transcript can be forked cheaply whenever an intermediate hash is
+/// needed (for instance for `CertificateVerify` before the client
+/// `Finished` message) while the handshake continues on the original.
+/// This crate does not implement MD5; the caller maintains that half of
+/// the transcript and joins it with [`combine`](TranscriptSha1::combine):
+///
+/// ```
+/// use sha1_smol::tls::TranscriptSha1;
+///
+/// # fn md5(_: &[u8]) -> [u8; 16] { [0; 16] }
+/// let mut transcript = TranscriptSha1::new();
+/// transcript.update(b"client hello");
+/// transcript.update(b"server hello");
+/// let md5_digest = md5(b"client helloserver hello");
+/// let signed = transcript.combine(&md5_digest);
+/// assert_eq!(&signed[16..], &transcript.digest().bytes()[..]);
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct TranscriptSha1 {
+    hasher: Sha1,
+}
+
+impl TranscriptSha1 {
+    /// Creates an empty transcript.
+    pub fn new() -> TranscriptSha1 {
+        TranscriptSha1::default()
+    }
+
+    /// Appends a handshake message to the transcript.
+    pub fn update(&mut self, message: &[u8]) {
+        self.hasher.update(message);
+    }
+
+    /// Returns an independent copy of the transcript at this point.
+    pub fn fork(&self) -> TranscriptSha1 {
+        self.clone()
+    }
+
+    /// Returns the SHA-1 hash of the transcript so far.
+    pub fn digest(&self) -> Digest {
+        self.hasher.digest()
+    }
+
+    /// Joins a caller computed MD5 transcript hash with the SHA-1 hash.
+    ///
+    /// Returns the 36 byte `MD5 || SHA-1` value used by TLS 1.0 and 1.1.
+    pub fn combine(&self, md5_digest: &[u8; 16]) -> [u8; COMBINED_LENGTH] {
+        let mut rv = [0u8; COMBINED_LENGTH];
+        rv[..16].copy_from_slice(md5_digest);
+        rv[16..].copy_from_slice(&self.digest().bytes());
+        rv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::unhex;
+
+    #[test]
+    fn test_p_sha1() {
+        // reference value from OpenSSL's TLS1-PRF with a SHA1 digest
+        let want: [u8; 50] = unhex(
+            "f98e5f14ad0fa4f23c494dd1b0b88bef977191520249f40f7d06ce7d144f504d\
+             725759b9ba743dbe3c2daefbd3232981d827",
+        );
+        let mut out = [0u8; 50];
+        p_sha1(b"secret", b"test label", b"seed", &mut out);
+        assert_eq!(out, want);
+
+        let mut stream = PSha1::new(b"secret", b"test label", b"seed");
+        let mut out = [0u8; 50];
+        let (head, tail) = out.split_at_mut(7);
+        stream.fill(head);
+        let (mid, tail) = tail.split_at_mut(20);
+        stream.fill(mid);
+        stream.fill(&mut []);
+        stream.fill(tail);
+        assert_eq!(out, want);
+    }
+
+    #[test]
+    fn test_transcript_combine() {
+        use openssl::hash::{hash, MessageDigest};
+
+        let mut transcript = TranscriptSha1::new();
+        transcript.update(b"client hello");
+        let fork = transcript.fork();
+        transcript.update(b"server hello");
+        assert_eq!(fork.digest(), Sha1::from("client hello").digest());
+
+        let messages = b"client helloserver hello";
+        let md5 = hash(MessageDigest::md5(), messages).unwrap();
+        let mut md5_digest = [0u8; 16];
+        md5_digest.copy_from_slice(&md5);
+        let combined = transcript.combine(&md5_digest);
+        assert_eq!(combined[..16], md5[..]);
+        assert_eq!(
+            combined[16..],
+            hash(MessageDigest::sha1(), messages).unwrap()[..]
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Signed tokens using HMAC-SHA1.
+//!
+//! A token is the URL safe base64 (without padding) encoded payload and
+//! the encoded HMAC-SHA1 tag, separated by a dot:
+//!
+//! ```text
+//! base64(payload) "." base64(hmac(key, base64(payload)))
+//! ```
+//!
+//! Tokens can optionally carry an expiration time (seconds since the UNIX
+//! epoch) which is covered by the signature:
+//!
+//! ```text
+//! base64(payload) "." expires "." base64(hmac(key, base64(payload) "." expires))
+//! ```
+//!
+//! (This module is only available if the `alloc` feature is enabled)
+//!
+//! ```
+//! use sha1_smol::token;
+//!
+//! let t = token::sign(b"user=42", b"secret");
+//! assert_eq!(token::verify(&t, b"secret", 0).unwrap(), b"user=42");
+//! assert!(token::verify(&t, b"other secret", 0).is_err());
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::encoding::{decode_b64, encode_b64, Alphabet};
+use crate::hmac::{ct_eq, HmacSha1};
+
+/// Indicates that a token couldn't be verified.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum TokenError {
+    /// The token is not in the expected format.
+    Malformed,
+    /// The signature does not match.
+    BadSignature,
+    /// The signature is valid but the token expired.
+    Expired,
+}
+
+impl TokenError {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            TokenError::Malformed => "malformed token",
+            TokenError::BadSignature => "bad token signature",
+            TokenError::Expired => "token expired",
+        }
+    }
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TokenError {
+    fn description(&self) -> &str {
+        self.as_str()
+    }
+}
+
+fn finish(mut signed: String, key: &[u8]) -> String {
+    let mut mac = HmacSha1::new(key);
+    mac.update(signed.as_bytes());
+    signed.push('.');
+    signed.push_str(&encode_b64(&mac.digest().bytes(), Alphabet::UrlSafe, false));
+    signed
+}
+
+/// Signs `payload` with `key`.
+pub fn sign(payload: &[u8], key: &[u8]) -> String {
+    finish(encode_b64(payload, Alphabet::UrlSafe, false), key)
+}
+
+/// Signs `payload` with `key` and an expiration time.
+///
+/// `expires_at` is in seconds since the UNIX epoch.
+pub fn sign_with_expiry(payload: &[u8], key: &[u8], expires_at: u64) -> String {
+    use core::fmt::Write;
+    let mut signed = encode_b64(payload, Alphabet::UrlSafe, false);
+    write!(signed, ".{}", expires_at).unwrap();
+    finish(signed, key)
+}
+
+/// Verifies a token and returns its payload.
+///
+/// `now` is the current time in seconds since the UNIX epoch.  It's only
+/// used for tokens that carry an expiration time; such a token is rejected
+/// once `now` is past it.  The signature is always compared in constant
+/// time and checked before the expiration time.
+pub fn verify(token: &str, key: &[u8], now: u64) -> Result<Vec<u8>, TokenError> {
+    let dot = token.rfind('.').ok_or(TokenError::Malformed)?;
+    let (signed, tag) = (&token[..dot], &token[dot + 1..]);
+    let tag = decode_b64(tag.as_bytes(), Alphabet::UrlSafe).ok_or(TokenError::Malformed)?;
+
+    let mut parts = signed.splitn(2, '.');
+    let payload = parts.next().unwrap_or("");
+    let expires_at = match parts.next() {
+        Some(expires) => {
+            if expires.is_empty() || !expires.bytes().all(|c| c.is_ascii_digit()) {
+                return Err(TokenError::Malformed);
+            }
+            Some(expires.parse::<u64>().map_err(|_| TokenError::Malformed)?)
+        }
+        None => None,
+    };
+    let payload = decode_b64(payload.as_bytes(), Alphabet::UrlSafe).ok_or(TokenError::Malformed)?;
+
+    let mut mac = HmacSha1::new(key);
+    mac.update(signed.as_bytes());
+    if !ct_eq(&mac.digest().bytes(), &tag) {
+        return Err(TokenError::BadSignature);
+    }
+    match expires_at {
+        Some(expires_at) if now > expires_at => Err(TokenError::Expired),
+        _ => Ok(payload),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_verify() {
+        let t = sign(b"hello", b"key");
+        assert!(t.starts_with("aGVsbG8."));
+        assert_eq!(verify(&t, b"key", 0).unwrap(), b"hello");
+        assert_eq!(verify(&t, b"nope", 0), Err(TokenError::BadSignature));
+        assert_eq!(verify("aGVsbG8", b"key", 0), Err(TokenError::Malformed));
+        assert_eq!(
+            verify(&t.replace("aGVsbG8", "aGVsbG9"), b"key", 0),
+            Err(TokenError::Malformed)
+        );
+    }
+
+    #[test]
+    fn test_expiry() {
+        let t = sign_with_expiry(b"hello", b"key", 1000);
+        assert_eq!(verify(&t, b"key", 1000).unwrap(), b"hello");
+        assert_eq!(verify(&t, b"key", 1001), Err(TokenError::Expired));
+        let tampered = t.replace(".1000.", ".9999.");
+        assert_eq!(
+            verify(&tampered, b"key", 1001),
+            Err(TokenError::BadSignature)
+        );
+    }
+}
@@ -0,0 +1,437 @@
+//! BitTorrent v1 piece hashes and infohashes.
+//!
+//! A v1 torrent splits its contents into pieces of a fixed length (only the
+//! last one may be shorter) and stores the concatenated 20-byte SHA-1 of
+//! every piece in the `pieces` key of the info dictionary.  The torrent
+//! itself is identified by the SHA-1 of the bencoded info dictionary, the
+//! infohash.  It has to be computed over the exact bytes found in the
+//! metainfo file rather than over a re-encoded dictionary:
+//!
+//! ```
+//! use sha1_smol::torrent::{info_dict, infohash};
+//!
+//! let metainfo = b"d8:announce3:url4:infod4:name1:a6:lengthi0eee";
+//! let info = info_dict(metainfo).unwrap();
+//! assert_eq!(info, b"d4:name1:a6:lengthi0ee");
+//! assert_eq!(infohash(info), sha1_smol::Sha1::from(&info[..]).digest());
+//! ```
+//!
+//! Downloaded data can be checked as it arrives with a [`PieceVerifier`].
+
+use core::{cmp, fmt};
+
+use crate::{Digest, Sha1, DIGEST_LENGTH};
+
+/// Computes the infohash of a bencoded info dictionary.
+pub fn infohash(info: &[u8]) -> Digest {
+    Sha1::from(info).digest()
+}
+
+/// Returns the end of the bencoded value starting at `pos`.
+fn skip_value(data: &[u8], mut pos: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    loop {
+        match *data.get(pos)? {
+            b'i' => pos += data[pos..].iter().position(|&c| c == b'e')? + 1,
+            b'l' | b'd' => {
+                depth += 1;
+                pos += 1;
+                continue;
+            }
+            b'e' if depth > 0 => {
+                depth -= 1;
+                pos += 1;
+            }
+            b'0'..=b'9' => {
+                let colon = pos + data[pos..].iter().position(|&c| c == b':')?;
+                let mut len = 0usize;
+                for &c in &data[pos..colon] {
+                    if !c.is_ascii_digit() {
+                        return None;
+                    }
+                    len = len.checked_mul(10)?.checked_add((c - b'0') as usize)?;
+                }
+                pos = (colon + 1).checked_add(len)?;
+                if pos > data.len() {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+        if depth == 0 {
+            return Some(pos);
+        }
+    }
+}
+
+/// Locates the bencoded info dictionary in the contents of a `.torrent`
+/// file.
+///
+/// Returns `None` if the metainfo is not a well formed dictionary or has no
+/// `info` key.  The info dictionary itself is only checked to be a well
+/// formed dictionary.
+pub fn info_dict(metainfo: &[u8]) -> Option<&[u8]> {
+    if metainfo.first() != Some(&b'd') {
+        return None;
+    }
+    let mut pos = 1;
+    let mut rv = None;
+    while *metainfo.get(pos)? != b'e' {
+        if !metainfo[pos].is_ascii_digit() {
+            return None;
+        }
+        let key_end = skip_value(metainfo, pos)?;
+        let key = &metainfo[pos..key_end];
+        let value_end = skip_value(metainfo, key_end)?;
+        if key == b"4:info" {
+            if metainfo[key_end] != b'd' {
+                return None;
+            }
+            rv = Some(&metainfo[key_end..value_end]);
+        }
+        pos = value_end;
+    }
+    rv
+}
+
+/// Returns the SHA-1 of every piece of `data`.
+///
+/// This panics if `piece_length` is zero.
+pub fn piece_hashes(data: &[u8], piece_length: usize) -> impl Iterator<Item = Digest> + '_ {
+    assert!(piece_length > 0, "piece length must not be zero");
+    data.chunks(piece_length)
+        .map(|piece| Sha1::from(piece).digest())
+}
+
+/// Reads everything from `reader` and returns the value of the `pieces`
+/// key, the concatenated SHA-1 of every piece.
+///
+/// Empty input has no pieces.  This panics if `piece_length` is zero.
+///
+/// (This function is only available if the `std` feature is enabled)
+#[cfg(feature = "std")]
+pub fn pieces<R: std::io::Read>(
+    mut reader: R,
+    piece_length: usize,
+) -> std::io::Result<std::vec::Vec<u8>> {
+    use std::io::ErrorKind;
+
+    assert!(piece_length > 0, "piece length must not be zero");
+    let mut rv = std::vec::Vec::new();
+    let mut buf = [0u8; 8192];
+    let mut m = Sha1::new();
+    let mut piece_len = 0;
+    loop {
+        let want = std::cmp::min(buf.len(), piece_length - piece_len);
+        let n = match reader.read(&mut buf[..want]) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        m.update(&buf[..n]);
+        piece_len += n;
+        if piece_len == piece_length {
+            rv.extend_from_slice(&m.digest().bytes());
+            m.reset();
+            piece_len = 0;
+        }
+    }
+    if piece_len > 0 {
+        rv.extend_from_slice(&m.digest().bytes());
+    }
+    Ok(rv)
+}
+
+/// Errors reported by a [`PieceVerifier`].
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum PieceError {
+    /// The piece with the given index does not match its hash.
+    Corrupt(usize),
+    /// More data was fed than the pieces cover.
+    TooLong,
+    /// The data ended before the last piece.
+    Truncated,
+}
+
+impl fmt::Display for PieceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PieceError::Corrupt(index) => write!(f, "piece {} is corrupt", index),
+            PieceError::TooLong => f.write_str("data exceeds the last piece"),
+            PieceError::Truncated => f.write_str("data ends before the last piece"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PieceError {
+    fn description(&self) -> &str {
+        "torrent piece verification failed"
+    }
+}
+
+/// Verifies data against the `pieces` of a torrent while it is fed in.
+///
+/// Every piece is checked as soon as its last byte arrives, the data itself
+/// is never buffered.  Verification carries on after a corrupt piece so
+/// that the remaining pieces can still be checked, the index of the first
+/// corrupt piece is available from [`first_corrupt`](Self::first_corrupt).
+///
+/// ```
+/// use sha1_smol::torrent::{PieceError, PieceVerifier};
+///
+/// let pieces = sha1_smol::Sha1::from("hello").digest().bytes();
+/// let mut verifier = PieceVerifier::new(&pieces, 16384);
+/// verifier.update(b"hel").unwrap();
+/// verifier.update(b"lo").unwrap();
+/// assert_eq!(verifier.finish(), Ok(()));
+/// ```
+#[derive(Clone)]
+pub struct PieceVerifier<'a> {
+    pieces: &'a [u8],
+    piece_length: usize,
+    hasher: Sha1,
+    piece_len: usize,
+    index: usize,
+    first_corrupt: Option<usize>,
+}
+
+impl<'a> PieceVerifier<'a> {
+    /// Creates a verifier for the concatenated piece hashes `pieces`.
+    ///
+    /// This panics if `piece_length` is zero or the length of `pieces` is
+    /// not a multiple of 20.
+    pub fn new(pieces: &'a [u8], piece_length: usize) -> PieceVerifier<'a> {
+        assert!(piece_length > 0, "piece length must not be zero");
+        assert!(
+            pieces.len() % DIGEST_LENGTH == 0,
+            "pieces must be a multiple of 20 bytes"
+        );
+        PieceVerifier {
+            pieces,
+            piece_length,
+            hasher: Sha1::new(),
+            piece_len: 0,
+            index: 0,
+            first_corrupt: None,
+        }
+    }
+
+    /// Returns the number of pieces.
+    pub fn piece_count(&self) -> usize {
+        self.pieces.len() / DIGEST_LENGTH
+    }
+
+    /// Returns the number of pieces that were completed so far, corrupt or
+    /// not.
+    pub fn completed(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the index of the first piece that did not match its hash.
+    pub fn first_corrupt(&self) -> Option<usize> {
+        self.first_corrupt
+    }
+
+    /// Feeds downloaded data into the verifier.
+    ///
+    /// Fails with the first corrupt piece completed by this call, all of
+    /// `data` is consumed regardless.  Data past the last piece is rejected
+    /// with [`PieceError::TooLong`] and ignored.
+    pub fn update(&mut self, mut data: &[u8]) -> Result<(), PieceError> {
+        let mut rv = Ok(());
+        while !data.is_empty() {
+            if self.index == self.piece_count() {
+                return rv.and(Err(PieceError::TooLong));
+            }
+            let n = cmp::min(data.len(), self.piece_length - self.piece_len);
+            self.hasher.update(&data[..n]);
+            self.piece_len += n;
+            data = &data[n..];
+            if self.piece_len == self.piece_length {
+                if let Err(err) = self.complete_piece() {
+                    rv = rv.and(Err(err));
+                }
+            }
+        }
+        rv
+    }
+
+    /// Verifies the final, possibly shorter piece and checks that no piece
+    /// is missing.
+    ///
+    /// Fails with the first corrupt piece if there was any.
+    pub fn finish(mut self) -> Result<(), PieceError> {
+        // only the last piece may be short, a partial earlier piece means
+        // the data was cut off
+        if self.piece_len > 0 && self.index + 1 == self.piece_count() {
+            self.complete_piece().ok();
+        }
+        if let Some(index) = self.first_corrupt {
+            return Err(PieceError::Corrupt(index));
+        }
+        if self.index < self.piece_count() {
+            return Err(PieceError::Truncated);
+        }
+        Ok(())
+    }
+
+    fn complete_piece(&mut self) -> Result<(), PieceError> {
+        let index = self.index;
+        let expected = &self.pieces[index * DIGEST_LENGTH..(index + 1) * DIGEST_LENGTH];
+        let ok = self.hasher.digest().bytes()[..] == *expected;
+        self.hasher.reset();
+        self.piece_len = 0;
+        self.index += 1;
+        if ok {
+            Ok(())
+        } else {
+            self.first_corrupt.get_or_insert(index);
+            Err(PieceError::Corrupt(index))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INFO: &[u8] =
+        b"d6:lengthi12e4:name5:a.txt12:piece lengthi16384e6:pieces20:xxxxxxxxxxxxxxxxxxxxe";
+
+    #[test]
+    fn test_infohash() {
+        // reference value computed with python's hashlib
+        assert_eq!(
+            infohash(INFO),
+            "d2e7fbfdd8894cd2e4d45e7a6faa836088c9a602".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_info_dict() {
+        let mut metainfo = [0u8; 256];
+        let prefix = b"d8:announce14:http://tracker7:comment3:l:e4:info";
+        metainfo[..prefix.len()].copy_from_slice(prefix);
+        metainfo[prefix.len()..prefix.len() + INFO.len()].copy_from_slice(INFO);
+        let suffix = b"5:otherli1ei-2e3:fooee";
+        let end = prefix.len() + INFO.len();
+        metainfo[end..end + suffix.len()].copy_from_slice(suffix);
+        let metainfo = &metainfo[..end + suffix.len()];
+
+        assert_eq!(info_dict(metainfo), Some(INFO));
+        assert_eq!(info_dict(&metainfo[..metainfo.len() - 1]), None);
+        assert_eq!(info_dict(b"d4:infoi1ee"), None);
+        assert_eq!(info_dict(b"d4:name4:infoe"), None);
+        assert_eq!(info_dict(b"d4:info99:de"), None);
+        assert_eq!(info_dict(b"l4:infodee"), None);
+        assert_eq!(info_dict(b"d4:infodee"), Some(&b"de"[..]));
+    }
+
+    #[test]
+    fn test_piece_hashes() {
+        let mut data = [0u8; 1024];
+        for (idx, byte) in data.iter_mut().enumerate() {
+            *byte = idx as u8;
+        }
+        let mut hashes = piece_hashes(&data, 300);
+        // reference values computed with python's hashlib
+        assert_eq!(
+            hashes.next(),
+            Some("bf77ecf143ceb21f1676c34b8d89c8bb3c43cc4e".parse().unwrap())
+        );
+        assert_eq!(
+            hashes.nth(2),
+            Some("eea4a060cef06b62fad4eca5ce9b9f978656a7b2".parse().unwrap())
+        );
+        assert_eq!(hashes.next(), None);
+        assert_eq!(piece_hashes(&[], 300).count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_pieces() {
+        let data: std::vec::Vec<u8> = (0..20000u32).map(|x| (x % 251) as u8).collect();
+        for &piece_length in &[1, 100, 8192, 16384, 20000, 32768] {
+            let expected: std::vec::Vec<u8> = piece_hashes(&data, piece_length)
+                .flat_map(|digest| digest.bytes().to_vec())
+                .collect();
+            assert_eq!(pieces(&data[..], piece_length).unwrap(), expected);
+        }
+        assert!(pieces(&b""[..], 10).unwrap().is_empty());
+    }
+
+    fn pieces_of(data: &[u8; 1024]) -> [u8; 80] {
+        let mut rv = [0u8; 80];
+        for (dst, digest) in rv.chunks_mut(20).zip(piece_hashes(data, 300)) {
+            dst.copy_from_slice(&digest.bytes());
+        }
+        rv
+    }
+
+    fn test_data() -> [u8; 1024] {
+        let mut data = [0u8; 1024];
+        for (idx, byte) in data.iter_mut().enumerate() {
+            *byte = (idx % 251) as u8;
+        }
+        data
+    }
+
+    #[test]
+    fn test_piece_verifier() {
+        let data = test_data();
+        let pieces = pieces_of(&data);
+        for &chunk in &[1, 7, 300, 1024] {
+            let mut verifier = PieceVerifier::new(&pieces, 300);
+            assert_eq!(verifier.piece_count(), 4);
+            for part in data.chunks(chunk) {
+                verifier.update(part).unwrap();
+            }
+            assert_eq!(verifier.completed(), 3);
+            assert_eq!(verifier.finish(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_piece_verifier_corrupt() {
+        let mut data = test_data();
+        let pieces = pieces_of(&data);
+        data[650] ^= 1;
+        data[1000] ^= 1;
+
+        let mut verifier = PieceVerifier::new(&pieces, 300);
+        verifier.update(&data[..600]).unwrap();
+        assert_eq!(verifier.update(&data[600..]), Err(PieceError::Corrupt(2)));
+        assert_eq!(verifier.first_corrupt(), Some(2));
+        assert_eq!(verifier.finish(), Err(PieceError::Corrupt(2)));
+
+        let mut verifier = PieceVerifier::new(&pieces, 300);
+        verifier.update(&data[..900]).ok();
+        assert_eq!(verifier.update(&data[900..]), Ok(()));
+        assert_eq!(verifier.finish(), Err(PieceError::Corrupt(2)));
+    }
+
+    #[test]
+    fn test_piece_verifier_length() {
+        let data = test_data();
+        let pieces = pieces_of(&data);
+
+        let mut verifier = PieceVerifier::new(&pieces, 300);
+        verifier.update(&data[..900]).unwrap();
+        assert_eq!(verifier.finish(), Err(PieceError::Truncated));
+
+        // cut off in the middle of the third of four pieces
+        let mut verifier = PieceVerifier::new(&pieces, 300);
+        verifier.update(&data[..650]).unwrap();
+        assert_eq!(verifier.finish(), Err(PieceError::Truncated));
+
+        let mut verifier = PieceVerifier::new(&pieces[..60], 300);
+        assert_eq!(verifier.update(&data), Err(PieceError::TooLong));
+        assert_eq!(verifier.finish(), Ok(()));
+
+        let mut verifier = PieceVerifier::new(&[], 300);
+        assert_eq!(verifier.clone().finish(), Ok(()));
+        assert_eq!(verifier.update(b"x"), Err(PieceError::TooLong));
+    }
+}
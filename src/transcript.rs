@@ -0,0 +1,332 @@
+//! Segment transcripts for long hashes.
+//!
+//! When hashing huge inputs a mismatching digest only says that something
+//! somewhere is wrong.  A `TranscriptHasher` additionally records the digest
+//! of every fixed size segment of the input (for instance every 64 MB) so
+//! that an integrity failure can be narrowed down to the affected regions.
+//!
+//! ```
+//! # #[cfg(feature = "alloc")] {
+//! use sha1_smol::transcript::TranscriptHasher;
+//!
+//! let mut m = TranscriptHasher::new(4);
+//! m.update(b"Hello World!");
+//! let (digest, transcript) = m.finish();
+//! assert_eq!(digest.to_string(), "2ef7bde608ce5404e97d5f042f95f89f1c232871");
+//! assert_eq!(transcript.segments().len(), 3);
+//! # }
+//! ```
+//!
+//! With the `std` feature `verify_with_transcript` checks a reader
+//! against a transcript and reports the segments that differ, so only
+//! those need to be repaired or downloaded again.
+//!
+//! Without the `alloc` feature the segments can still be observed as they
+//! are completed with a [`SegmentedHasher`], which the transcript hasher
+//! is built on.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::cmp;
+#[cfg(feature = "alloc")]
+use core::fmt;
+
+use crate::{Digest, Sha1};
+
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+
+/// A segment of the input recorded in a transcript.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Segment {
+    /// The offset of the segment in the input.
+    pub offset: u64,
+    /// The length of the segment in bytes.
+    pub len: u64,
+    /// The SHA-1 of the segment contents.
+    pub digest: Digest,
+}
+
+/// The recorded segments of a hash.
+///
+/// (This type is only available if the `alloc` feature is enabled)
+///
+/// Formatting a transcript with `Display` produces one line per segment
+/// with the offset, length and hex digest separated by spaces.
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Transcript {
+    segment_size: u64,
+    segments: Vec<Segment>,
+}
+
+#[cfg(feature = "alloc")]
+impl Transcript {
+    /// Returns the segment size the transcript was recorded with.
+    pub fn segment_size(&self) -> u64 {
+        self.segment_size
+    }
+
+    /// Returns the total length of the hashed input.
+    pub fn len(&self) -> u64 {
+        self.segments.last().map_or(0, |s| s.offset + s.len)
+    }
+
+    /// Returns `true` if the transcript covers no input.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns the recorded segments in input order.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for segment in &self.segments {
+            writeln!(f, "{} {} {}", segment.offset, segment.len, segment.digest)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hashes an input and each of its fixed size segments.
+///
+/// Unlike `TranscriptHasher` this does not keep the segments but hands
+/// them to a callback as they are completed, so it works without the
+/// `alloc` feature.  The hasher is cheap to clone, which allows resuming
+/// from a segment boundary.
+#[derive(Clone)]
+pub struct SegmentedHasher {
+    segment_size: u64,
+    whole: Sha1,
+    segment: Sha1,
+    segment_len: u64,
+    offset: u64,
+}
+
+impl SegmentedHasher {
+    /// Creates a hasher with segments of `segment_size` bytes.
+    ///
+    /// This panics if `segment_size` is zero.
+    pub fn new(segment_size: u64) -> SegmentedHasher {
+        assert!(segment_size > 0, "segment size must not be zero");
+        SegmentedHasher {
+            segment_size,
+            whole: Sha1::new(),
+            segment: Sha1::new(),
+            segment_len: 0,
+            offset: 0,
+        }
+    }
+
+    /// Returns the configured segment size.
+    pub fn segment_size(&self) -> u64 {
+        self.segment_size
+    }
+
+    /// Returns the number of bytes hashed so far.
+    pub fn len(&self) -> u64 {
+        self.offset + self.segment_len
+    }
+
+    /// Returns `true` if no data was hashed yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Feeds `data` into the hasher.
+    ///
+    /// `on_segment` is invoked for every segment that is completed by this
+    /// call, in order.
+    pub fn update<F: FnMut(Segment)>(&mut self, mut data: &[u8], mut on_segment: F) {
+        self.whole.update(data);
+        while !data.is_empty() {
+            let room = self.segment_size - self.segment_len;
+            let amt = cmp::min(room, data.len() as u64) as usize;
+            self.segment.update(&data[..amt]);
+            self.segment_len += amt as u64;
+            data = &data[amt..];
+            if self.segment_len == self.segment_size {
+                on_segment(self.close_segment());
+            }
+        }
+    }
+
+    /// Completes the final, possibly shorter segment and returns the digest
+    /// of the whole input.
+    ///
+    /// An empty input is reported as a single empty segment, as uploads
+    /// split into parts consist of at least one part.
+    pub fn finish<F: FnMut(Segment)>(mut self, mut on_segment: F) -> Digest {
+        if self.segment_len > 0 || self.offset == 0 {
+            on_segment(self.close_segment());
+        }
+        self.whole.digest()
+    }
+
+    fn close_segment(&mut self) -> Segment {
+        let rv = Segment {
+            offset: self.offset,
+            len: self.segment_len,
+            digest: self.segment.digest(),
+        };
+        self.offset += self.segment_len;
+        self.segment_len = 0;
+        self.segment.reset();
+        rv
+    }
+}
+
+/// A hasher that records a transcript of segment digests.
+///
+/// (This type is only available if the `alloc` feature is enabled)
+#[cfg(feature = "alloc")]
+#[derive(Clone)]
+pub struct TranscriptHasher {
+    inner: SegmentedHasher,
+    segments: Vec<Segment>,
+}
+
+#[cfg(feature = "alloc")]
+impl TranscriptHasher {
+    /// Creates a hasher that records a digest every `segment_size` bytes.
+    ///
+    /// This panics if `segment_size` is zero.
+    pub fn new(segment_size: u64) -> TranscriptHasher {
+        TranscriptHasher {
+            inner: SegmentedHasher::new(segment_size),
+            segments: Vec::new(),
+        }
+    }
+
+    /// Update hash with input data.
+    pub fn update(&mut self, data: &[u8]) {
+        let segments = &mut self.segments;
+        self.inner.update(data, |segment| segments.push(segment));
+    }
+
+    /// Returns the digest of the whole input and the transcript.
+    ///
+    /// The transcript of an empty input has no segments.
+    pub fn finish(mut self) -> (Digest, Transcript) {
+        if self.inner.segment_len > 0 {
+            self.segments.push(self.inner.close_segment());
+        }
+        let transcript = Transcript {
+            segment_size: self.inner.segment_size,
+            segments: self.segments,
+        };
+        (self.inner.whole.digest(), transcript)
+    }
+}
+
+/// The result of verifying data against a transcript.
+///
+/// (This type is only available if the `std` feature is enabled)
+#[cfg(feature = "std")]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TranscriptVerification {
+    /// The indexes of the segments that did not match.
+    ///
+    /// Segments that are cut short because the input ended early count as
+    /// mismatching.
+    pub mismatched: Vec<usize>,
+    /// The number of bytes the input has beyond the end of the transcript.
+    pub trailing_bytes: u64,
+}
+
+#[cfg(feature = "std")]
+impl TranscriptVerification {
+    /// Returns `true` if the input matched the transcript exactly.
+    pub fn is_ok(&self) -> bool {
+        self.mismatched.is_empty() && self.trailing_bytes == 0
+    }
+}
+
+/// Verifies everything read from `reader` against `transcript`.
+///
+/// (The function is only available if the `std` feature is enabled)
+#[cfg(feature = "std")]
+pub fn verify_with_transcript<R: Read>(
+    mut reader: R,
+    transcript: &Transcript,
+) -> io::Result<TranscriptVerification> {
+    let mut mismatched = Vec::new();
+    for (idx, segment) in transcript.segments().iter().enumerate() {
+        let mut m = Sha1::new();
+        let read = crate::io::update_reader(&mut m, (&mut reader).take(segment.len))?;
+        if read != segment.len || m.digest() != segment.digest {
+            mismatched.push(idx);
+        }
+    }
+    let trailing_bytes = io::copy(&mut reader, &mut io::sink())?;
+    Ok(TranscriptVerification {
+        mismatched,
+        trailing_bytes,
+    })
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    extern crate std;
+
+    use self::std::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn test_transcript_segments() {
+        let data = [b'a'; 10];
+        let mut m = TranscriptHasher::new(4);
+        m.update(&data[..3]);
+        m.update(&data[3..]);
+        let (digest, transcript) = m.finish();
+        assert_eq!(digest, Sha1::from(&data[..]).digest());
+        assert_eq!(transcript.len(), 10);
+        let lens: Vec<_> = transcript
+            .segments()
+            .iter()
+            .map(|s| (s.offset, s.len))
+            .collect();
+        assert_eq!(lens, [(0, 4), (4, 4), (8, 2)]);
+        assert_eq!(
+            transcript.segments()[2].digest,
+            Sha1::from(&data[8..]).digest()
+        );
+        assert!(transcript
+            .to_string()
+            .starts_with("0 4 70c881d4a26984ddce795f6f71817c9cf4480e79\n"));
+    }
+
+    #[test]
+    fn test_transcript_empty() {
+        let (digest, transcript) = TranscriptHasher::new(4).finish();
+        assert_eq!(digest, Sha1::new().digest());
+        assert!(transcript.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_verify_with_transcript() {
+        let mut data = [b'a'; 10];
+        let mut m = TranscriptHasher::new(4);
+        m.update(&data);
+        let (_, transcript) = m.finish();
+        assert!(verify_with_transcript(&data[..], &transcript)
+            .unwrap()
+            .is_ok());
+
+        data[5] = b'b';
+        let rv = verify_with_transcript(&data[..], &transcript).unwrap();
+        assert_eq!(rv.mismatched, [1]);
+        let rv = verify_with_transcript(&data[..9], &transcript).unwrap();
+        assert_eq!(rv.mismatched, [1, 2]);
+        let longer = [b'a'; 12];
+        let rv = verify_with_transcript(&longer[..], &transcript).unwrap();
+        assert_eq!((rv.mismatched.len(), rv.trailing_bytes), (0, 2));
+    }
+}
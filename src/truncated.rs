@@ -0,0 +1,79 @@
+//! SHA-1 truncated to a fixed number of bytes.
+//!
+//! Some protocols identify data by a truncated SHA-1, for instance the first
+//! 80 or 96 bits.  `TruncatedSha1` makes the output length part of the
+//! type so a truncated identifier can't be confused with a full digest.
+//!
+//! ```
+//! use sha1_smol::truncated::TruncatedSha1;
+//!
+//! let mut m = TruncatedSha1::<10>::new();
+//! m.update(b"Hello World!");
+//! assert_eq!(m.digest(), [0x2e, 0xf7, 0xbd, 0xe6, 0x08, 0xce, 0x54, 0x04, 0xe9, 0x7d]);
+//! ```
+
+use crate::{Sha1, DIGEST_LENGTH};
+
+/// A SHA-1 hash object producing the first `N` bytes of the digest.
+#[derive(Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub struct TruncatedSha1<const N: usize> {
+    inner: Sha1,
+}
+
+impl<const N: usize> Default for TruncatedSha1<N> {
+    fn default() -> TruncatedSha1<N> {
+        TruncatedSha1::new()
+    }
+}
+
+impl<const N: usize> TruncatedSha1<N> {
+    /// Creates a fresh hash object.
+    ///
+    /// This panics if `N` is larger than the 20 byte SHA-1 digest.
+    pub fn new() -> TruncatedSha1<N> {
+        assert!(N <= DIGEST_LENGTH, "truncated digest longer than sha1");
+        TruncatedSha1 { inner: Sha1::new() }
+    }
+
+    /// Resets the hash object to its initial state.
+    pub fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    /// Update hash with input data.
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    /// Retrieve the truncated digest.
+    pub fn digest(&self) -> [u8; N] {
+        let mut rv = [0u8; N];
+        rv.copy_from_slice(&self.inner.digest().bytes()[..N]);
+        rv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncated() {
+        let mut m = TruncatedSha1::<12>::new();
+        m.update(b"The quick brown fox jumps over the lazy dog");
+        let full = Sha1::from("The quick brown fox jumps over the lazy dog").digest();
+        assert_eq!(m.digest()[..], full.bytes()[..12]);
+        m.reset();
+        assert_eq!(m.digest()[..], Sha1::new().digest().bytes()[..12]);
+        assert_eq!(
+            TruncatedSha1::<20>::new().digest(),
+            Sha1::new().digest().bytes()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_too_long() {
+        TruncatedSha1::<21>::new();
+    }
+}
@@ -0,0 +1,1034 @@
+//! Disturbance vectors and unavoidable bit conditions of SHA-1DC.
+//!
+//! The tables and checks in this file are taken from the
+//! sha1collisiondetection project by Marc Stevens and Dan Shumow:
+//!
+//! > Copyright 2017 Marc Stevens <marc@marc-stevens.nl>, Dan Shumow
+//! > <danshu@microsoft.com>.  Distributed under the MIT Software License,
+//! > see <https://opensource.org/licenses/MIT>.
+//!
+//! Every known attack on SHA-1 picks one of these 32 disturbance vectors
+//! `I(K,B)` or `II(K,B)`.  A message block following one leaves traces in
+//! the expanded message that `ubc_check` tests for, so that only the few
+//! vectors a block could belong to have to be recompressed.
+
+/// A disturbance vector.
+pub(crate) struct Dv {
+    /// The step after which both blocks of a near collision share their
+    /// working variables.
+    pub testt: usize,
+    /// The bit of the vector in the mask returned by [`ubc_check`].
+    pub bit: u32,
+    /// The difference of the expanded messages.
+    pub dm: [u32; 80],
+}
+
+const DV_I_43_0: u32 = 1 << 0;
+const DV_I_44_0: u32 = 1 << 1;
+const DV_I_45_0: u32 = 1 << 2;
+const DV_I_46_0: u32 = 1 << 3;
+const DV_I_46_2: u32 = 1 << 4;
+const DV_I_47_0: u32 = 1 << 5;
+const DV_I_47_2: u32 = 1 << 6;
+const DV_I_48_0: u32 = 1 << 7;
+const DV_I_48_2: u32 = 1 << 8;
+const DV_I_49_0: u32 = 1 << 9;
+const DV_I_49_2: u32 = 1 << 10;
+const DV_I_50_0: u32 = 1 << 11;
+const DV_I_50_2: u32 = 1 << 12;
+const DV_I_51_0: u32 = 1 << 13;
+const DV_I_51_2: u32 = 1 << 14;
+const DV_I_52_0: u32 = 1 << 15;
+const DV_II_45_0: u32 = 1 << 16;
+const DV_II_46_0: u32 = 1 << 17;
+const DV_II_46_2: u32 = 1 << 18;
+const DV_II_47_0: u32 = 1 << 19;
+const DV_II_48_0: u32 = 1 << 20;
+const DV_II_49_0: u32 = 1 << 21;
+const DV_II_49_2: u32 = 1 << 22;
+const DV_II_50_0: u32 = 1 << 23;
+const DV_II_50_2: u32 = 1 << 24;
+const DV_II_51_0: u32 = 1 << 25;
+const DV_II_51_2: u32 = 1 << 26;
+const DV_II_52_0: u32 = 1 << 27;
+const DV_II_53_0: u32 = 1 << 28;
+const DV_II_54_0: u32 = 1 << 29;
+const DV_II_55_0: u32 = 1 << 30;
+const DV_II_56_0: u32 = 1 << 31;
+
+/// The disturbance vectors checked for.
+pub(crate) const DVS: [Dv; 32] = [
+    // I(43,0)
+    Dv {
+        testt: 58,
+        bit: DV_I_43_0,
+        dm: [
+            0x08000000, 0x9800000c, 0xd8000010, 0x08000010, 0xb8000010, 0x98000000, 0x60000000,
+            0x00000008, 0xc0000000, 0x90000014, 0x10000010, 0xb8000014, 0x28000000, 0x20000010,
+            0x48000000, 0x08000018, 0x60000000, 0x90000010, 0xf0000010, 0x90000008, 0xc0000000,
+            0x90000010, 0xf0000010, 0xb0000008, 0x40000000, 0x90000000, 0xf0000010, 0x90000018,
+            0x60000000, 0x90000010, 0x90000010, 0x90000000, 0x80000000, 0x00000010, 0xa0000000,
+            0x20000000, 0xa0000000, 0x20000010, 0x00000000, 0x20000010, 0x20000000, 0x00000010,
+            0x20000000, 0x00000010, 0xa0000000, 0x00000000, 0x20000000, 0x20000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000001, 0x00000020, 0x00000001, 0x40000002, 0x40000040,
+            0x40000002, 0x80000004, 0x80000080, 0x80000006, 0x00000049, 0x00000103, 0x80000009,
+            0x80000012, 0x80000202, 0x00000018, 0x00000164, 0x00000408, 0x800000e6, 0x8000004c,
+            0x00000803, 0x80000161, 0x80000599,
+        ],
+    },
+    // I(44,0)
+    Dv {
+        testt: 58,
+        bit: DV_I_44_0,
+        dm: [
+            0xb4000008, 0x08000000, 0x9800000c, 0xd8000010, 0x08000010, 0xb8000010, 0x98000000,
+            0x60000000, 0x00000008, 0xc0000000, 0x90000014, 0x10000010, 0xb8000014, 0x28000000,
+            0x20000010, 0x48000000, 0x08000018, 0x60000000, 0x90000010, 0xf0000010, 0x90000008,
+            0xc0000000, 0x90000010, 0xf0000010, 0xb0000008, 0x40000000, 0x90000000, 0xf0000010,
+            0x90000018, 0x60000000, 0x90000010, 0x90000010, 0x90000000, 0x80000000, 0x00000010,
+            0xa0000000, 0x20000000, 0xa0000000, 0x20000010, 0x00000000, 0x20000010, 0x20000000,
+            0x00000010, 0x20000000, 0x00000010, 0xa0000000, 0x00000000, 0x20000000, 0x20000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000001, 0x00000020, 0x00000001, 0x40000002,
+            0x40000040, 0x40000002, 0x80000004, 0x80000080, 0x80000006, 0x00000049, 0x00000103,
+            0x80000009, 0x80000012, 0x80000202, 0x00000018, 0x00000164, 0x00000408, 0x800000e6,
+            0x8000004c, 0x00000803, 0x80000161,
+        ],
+    },
+    // I(45,0)
+    Dv {
+        testt: 58,
+        bit: DV_I_45_0,
+        dm: [
+            0xf4000014, 0xb4000008, 0x08000000, 0x9800000c, 0xd8000010, 0x08000010, 0xb8000010,
+            0x98000000, 0x60000000, 0x00000008, 0xc0000000, 0x90000014, 0x10000010, 0xb8000014,
+            0x28000000, 0x20000010, 0x48000000, 0x08000018, 0x60000000, 0x90000010, 0xf0000010,
+            0x90000008, 0xc0000000, 0x90000010, 0xf0000010, 0xb0000008, 0x40000000, 0x90000000,
+            0xf0000010, 0x90000018, 0x60000000, 0x90000010, 0x90000010, 0x90000000, 0x80000000,
+            0x00000010, 0xa0000000, 0x20000000, 0xa0000000, 0x20000010, 0x00000000, 0x20000010,
+            0x20000000, 0x00000010, 0x20000000, 0x00000010, 0xa0000000, 0x00000000, 0x20000000,
+            0x20000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000001, 0x00000020, 0x00000001,
+            0x40000002, 0x40000040, 0x40000002, 0x80000004, 0x80000080, 0x80000006, 0x00000049,
+            0x00000103, 0x80000009, 0x80000012, 0x80000202, 0x00000018, 0x00000164, 0x00000408,
+            0x800000e6, 0x8000004c, 0x00000803,
+        ],
+    },
+    // I(46,0)
+    Dv {
+        testt: 58,
+        bit: DV_I_46_0,
+        dm: [
+            0x2c000010, 0xf4000014, 0xb4000008, 0x08000000, 0x9800000c, 0xd8000010, 0x08000010,
+            0xb8000010, 0x98000000, 0x60000000, 0x00000008, 0xc0000000, 0x90000014, 0x10000010,
+            0xb8000014, 0x28000000, 0x20000010, 0x48000000, 0x08000018, 0x60000000, 0x90000010,
+            0xf0000010, 0x90000008, 0xc0000000, 0x90000010, 0xf0000010, 0xb0000008, 0x40000000,
+            0x90000000, 0xf0000010, 0x90000018, 0x60000000, 0x90000010, 0x90000010, 0x90000000,
+            0x80000000, 0x00000010, 0xa0000000, 0x20000000, 0xa0000000, 0x20000010, 0x00000000,
+            0x20000010, 0x20000000, 0x00000010, 0x20000000, 0x00000010, 0xa0000000, 0x00000000,
+            0x20000000, 0x20000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000001, 0x00000020,
+            0x00000001, 0x40000002, 0x40000040, 0x40000002, 0x80000004, 0x80000080, 0x80000006,
+            0x00000049, 0x00000103, 0x80000009, 0x80000012, 0x80000202, 0x00000018, 0x00000164,
+            0x00000408, 0x800000e6, 0x8000004c,
+        ],
+    },
+    // I(46,2)
+    Dv {
+        testt: 58,
+        bit: DV_I_46_2,
+        dm: [
+            0xb0000040, 0xd0000053, 0xd0000022, 0x20000000, 0x60000032, 0x60000043, 0x20000040,
+            0xe0000042, 0x60000002, 0x80000001, 0x00000020, 0x00000003, 0x40000052, 0x40000040,
+            0xe0000052, 0xa0000000, 0x80000040, 0x20000001, 0x20000060, 0x80000001, 0x40000042,
+            0xc0000043, 0x40000022, 0x00000003, 0x40000042, 0xc0000043, 0xc0000022, 0x00000001,
+            0x40000002, 0xc0000043, 0x40000062, 0x80000001, 0x40000042, 0x40000042, 0x40000002,
+            0x00000002, 0x00000040, 0x80000002, 0x80000000, 0x80000002, 0x80000040, 0x00000000,
+            0x80000040, 0x80000000, 0x00000040, 0x80000000, 0x00000040, 0x80000002, 0x00000000,
+            0x80000000, 0x80000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000004, 0x00000080,
+            0x00000004, 0x00000009, 0x00000101, 0x00000009, 0x00000012, 0x00000202, 0x0000001a,
+            0x00000124, 0x0000040c, 0x00000026, 0x0000004a, 0x0000080a, 0x00000060, 0x00000590,
+            0x00001020, 0x0000039a, 0x00000132,
+        ],
+    },
+    // I(47,0)
+    Dv {
+        testt: 58,
+        bit: DV_I_47_0,
+        dm: [
+            0xc8000010, 0x2c000010, 0xf4000014, 0xb4000008, 0x08000000, 0x9800000c, 0xd8000010,
+            0x08000010, 0xb8000010, 0x98000000, 0x60000000, 0x00000008, 0xc0000000, 0x90000014,
+            0x10000010, 0xb8000014, 0x28000000, 0x20000010, 0x48000000, 0x08000018, 0x60000000,
+            0x90000010, 0xf0000010, 0x90000008, 0xc0000000, 0x90000010, 0xf0000010, 0xb0000008,
+            0x40000000, 0x90000000, 0xf0000010, 0x90000018, 0x60000000, 0x90000010, 0x90000010,
+            0x90000000, 0x80000000, 0x00000010, 0xa0000000, 0x20000000, 0xa0000000, 0x20000010,
+            0x00000000, 0x20000010, 0x20000000, 0x00000010, 0x20000000, 0x00000010, 0xa0000000,
+            0x00000000, 0x20000000, 0x20000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000001,
+            0x00000020, 0x00000001, 0x40000002, 0x40000040, 0x40000002, 0x80000004, 0x80000080,
+            0x80000006, 0x00000049, 0x00000103, 0x80000009, 0x80000012, 0x80000202, 0x00000018,
+            0x00000164, 0x00000408, 0x800000e6,
+        ],
+    },
+    // I(47,2)
+    Dv {
+        testt: 58,
+        bit: DV_I_47_2,
+        dm: [
+            0x20000043, 0xb0000040, 0xd0000053, 0xd0000022, 0x20000000, 0x60000032, 0x60000043,
+            0x20000040, 0xe0000042, 0x60000002, 0x80000001, 0x00000020, 0x00000003, 0x40000052,
+            0x40000040, 0xe0000052, 0xa0000000, 0x80000040, 0x20000001, 0x20000060, 0x80000001,
+            0x40000042, 0xc0000043, 0x40000022, 0x00000003, 0x40000042, 0xc0000043, 0xc0000022,
+            0x00000001, 0x40000002, 0xc0000043, 0x40000062, 0x80000001, 0x40000042, 0x40000042,
+            0x40000002, 0x00000002, 0x00000040, 0x80000002, 0x80000000, 0x80000002, 0x80000040,
+            0x00000000, 0x80000040, 0x80000000, 0x00000040, 0x80000000, 0x00000040, 0x80000002,
+            0x00000000, 0x80000000, 0x80000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000004,
+            0x00000080, 0x00000004, 0x00000009, 0x00000101, 0x00000009, 0x00000012, 0x00000202,
+            0x0000001a, 0x00000124, 0x0000040c, 0x00000026, 0x0000004a, 0x0000080a, 0x00000060,
+            0x00000590, 0x00001020, 0x0000039a,
+        ],
+    },
+    // I(48,0)
+    Dv {
+        testt: 58,
+        bit: DV_I_48_0,
+        dm: [
+            0xb800000a, 0xc8000010, 0x2c000010, 0xf4000014, 0xb4000008, 0x08000000, 0x9800000c,
+            0xd8000010, 0x08000010, 0xb8000010, 0x98000000, 0x60000000, 0x00000008, 0xc0000000,
+            0x90000014, 0x10000010, 0xb8000014, 0x28000000, 0x20000010, 0x48000000, 0x08000018,
+            0x60000000, 0x90000010, 0xf0000010, 0x90000008, 0xc0000000, 0x90000010, 0xf0000010,
+            0xb0000008, 0x40000000, 0x90000000, 0xf0000010, 0x90000018, 0x60000000, 0x90000010,
+            0x90000010, 0x90000000, 0x80000000, 0x00000010, 0xa0000000, 0x20000000, 0xa0000000,
+            0x20000010, 0x00000000, 0x20000010, 0x20000000, 0x00000010, 0x20000000, 0x00000010,
+            0xa0000000, 0x00000000, 0x20000000, 0x20000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000001, 0x00000020, 0x00000001, 0x40000002, 0x40000040, 0x40000002, 0x80000004,
+            0x80000080, 0x80000006, 0x00000049, 0x00000103, 0x80000009, 0x80000012, 0x80000202,
+            0x00000018, 0x00000164, 0x00000408,
+        ],
+    },
+    // I(48,2)
+    Dv {
+        testt: 58,
+        bit: DV_I_48_2,
+        dm: [
+            0xe000002a, 0x20000043, 0xb0000040, 0xd0000053, 0xd0000022, 0x20000000, 0x60000032,
+            0x60000043, 0x20000040, 0xe0000042, 0x60000002, 0x80000001, 0x00000020, 0x00000003,
+            0x40000052, 0x40000040, 0xe0000052, 0xa0000000, 0x80000040, 0x20000001, 0x20000060,
+            0x80000001, 0x40000042, 0xc0000043, 0x40000022, 0x00000003, 0x40000042, 0xc0000043,
+            0xc0000022, 0x00000001, 0x40000002, 0xc0000043, 0x40000062, 0x80000001, 0x40000042,
+            0x40000042, 0x40000002, 0x00000002, 0x00000040, 0x80000002, 0x80000000, 0x80000002,
+            0x80000040, 0x00000000, 0x80000040, 0x80000000, 0x00000040, 0x80000000, 0x00000040,
+            0x80000002, 0x00000000, 0x80000000, 0x80000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000004, 0x00000080, 0x00000004, 0x00000009, 0x00000101, 0x00000009, 0x00000012,
+            0x00000202, 0x0000001a, 0x00000124, 0x0000040c, 0x00000026, 0x0000004a, 0x0000080a,
+            0x00000060, 0x00000590, 0x00001020,
+        ],
+    },
+    // I(49,0)
+    Dv {
+        testt: 58,
+        bit: DV_I_49_0,
+        dm: [
+            0x18000000, 0xb800000a, 0xc8000010, 0x2c000010, 0xf4000014, 0xb4000008, 0x08000000,
+            0x9800000c, 0xd8000010, 0x08000010, 0xb8000010, 0x98000000, 0x60000000, 0x00000008,
+            0xc0000000, 0x90000014, 0x10000010, 0xb8000014, 0x28000000, 0x20000010, 0x48000000,
+            0x08000018, 0x60000000, 0x90000010, 0xf0000010, 0x90000008, 0xc0000000, 0x90000010,
+            0xf0000010, 0xb0000008, 0x40000000, 0x90000000, 0xf0000010, 0x90000018, 0x60000000,
+            0x90000010, 0x90000010, 0x90000000, 0x80000000, 0x00000010, 0xa0000000, 0x20000000,
+            0xa0000000, 0x20000010, 0x00000000, 0x20000010, 0x20000000, 0x00000010, 0x20000000,
+            0x00000010, 0xa0000000, 0x00000000, 0x20000000, 0x20000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000001, 0x00000020, 0x00000001, 0x40000002, 0x40000040, 0x40000002,
+            0x80000004, 0x80000080, 0x80000006, 0x00000049, 0x00000103, 0x80000009, 0x80000012,
+            0x80000202, 0x00000018, 0x00000164,
+        ],
+    },
+    // I(49,2)
+    Dv {
+        testt: 58,
+        bit: DV_I_49_2,
+        dm: [
+            0x60000000, 0xe000002a, 0x20000043, 0xb0000040, 0xd0000053, 0xd0000022, 0x20000000,
+            0x60000032, 0x60000043, 0x20000040, 0xe0000042, 0x60000002, 0x80000001, 0x00000020,
+            0x00000003, 0x40000052, 0x40000040, 0xe0000052, 0xa0000000, 0x80000040, 0x20000001,
+            0x20000060, 0x80000001, 0x40000042, 0xc0000043, 0x40000022, 0x00000003, 0x40000042,
+            0xc0000043, 0xc0000022, 0x00000001, 0x40000002, 0xc0000043, 0x40000062, 0x80000001,
+            0x40000042, 0x40000042, 0x40000002, 0x00000002, 0x00000040, 0x80000002, 0x80000000,
+            0x80000002, 0x80000040, 0x00000000, 0x80000040, 0x80000000, 0x00000040, 0x80000000,
+            0x00000040, 0x80000002, 0x00000000, 0x80000000, 0x80000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000004, 0x00000080, 0x00000004, 0x00000009, 0x00000101, 0x00000009,
+            0x00000012, 0x00000202, 0x0000001a, 0x00000124, 0x0000040c, 0x00000026, 0x0000004a,
+            0x0000080a, 0x00000060, 0x00000590,
+        ],
+    },
+    // I(50,0)
+    Dv {
+        testt: 65,
+        bit: DV_I_50_0,
+        dm: [
+            0x0800000c, 0x18000000, 0xb800000a, 0xc8000010, 0x2c000010, 0xf4000014, 0xb4000008,
+            0x08000000, 0x9800000c, 0xd8000010, 0x08000010, 0xb8000010, 0x98000000, 0x60000000,
+            0x00000008, 0xc0000000, 0x90000014, 0x10000010, 0xb8000014, 0x28000000, 0x20000010,
+            0x48000000, 0x08000018, 0x60000000, 0x90000010, 0xf0000010, 0x90000008, 0xc0000000,
+            0x90000010, 0xf0000010, 0xb0000008, 0x40000000, 0x90000000, 0xf0000010, 0x90000018,
+            0x60000000, 0x90000010, 0x90000010, 0x90000000, 0x80000000, 0x00000010, 0xa0000000,
+            0x20000000, 0xa0000000, 0x20000010, 0x00000000, 0x20000010, 0x20000000, 0x00000010,
+            0x20000000, 0x00000010, 0xa0000000, 0x00000000, 0x20000000, 0x20000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000001, 0x00000020, 0x00000001, 0x40000002, 0x40000040,
+            0x40000002, 0x80000004, 0x80000080, 0x80000006, 0x00000049, 0x00000103, 0x80000009,
+            0x80000012, 0x80000202, 0x00000018,
+        ],
+    },
+    // I(50,2)
+    Dv {
+        testt: 65,
+        bit: DV_I_50_2,
+        dm: [
+            0x20000030, 0x60000000, 0xe000002a, 0x20000043, 0xb0000040, 0xd0000053, 0xd0000022,
+            0x20000000, 0x60000032, 0x60000043, 0x20000040, 0xe0000042, 0x60000002, 0x80000001,
+            0x00000020, 0x00000003, 0x40000052, 0x40000040, 0xe0000052, 0xa0000000, 0x80000040,
+            0x20000001, 0x20000060, 0x80000001, 0x40000042, 0xc0000043, 0x40000022, 0x00000003,
+            0x40000042, 0xc0000043, 0xc0000022, 0x00000001, 0x40000002, 0xc0000043, 0x40000062,
+            0x80000001, 0x40000042, 0x40000042, 0x40000002, 0x00000002, 0x00000040, 0x80000002,
+            0x80000000, 0x80000002, 0x80000040, 0x00000000, 0x80000040, 0x80000000, 0x00000040,
+            0x80000000, 0x00000040, 0x80000002, 0x00000000, 0x80000000, 0x80000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000004, 0x00000080, 0x00000004, 0x00000009, 0x00000101,
+            0x00000009, 0x00000012, 0x00000202, 0x0000001a, 0x00000124, 0x0000040c, 0x00000026,
+            0x0000004a, 0x0000080a, 0x00000060,
+        ],
+    },
+    // I(51,0)
+    Dv {
+        testt: 65,
+        bit: DV_I_51_0,
+        dm: [
+            0xe8000000, 0x0800000c, 0x18000000, 0xb800000a, 0xc8000010, 0x2c000010, 0xf4000014,
+            0xb4000008, 0x08000000, 0x9800000c, 0xd8000010, 0x08000010, 0xb8000010, 0x98000000,
+            0x60000000, 0x00000008, 0xc0000000, 0x90000014, 0x10000010, 0xb8000014, 0x28000000,
+            0x20000010, 0x48000000, 0x08000018, 0x60000000, 0x90000010, 0xf0000010, 0x90000008,
+            0xc0000000, 0x90000010, 0xf0000010, 0xb0000008, 0x40000000, 0x90000000, 0xf0000010,
+            0x90000018, 0x60000000, 0x90000010, 0x90000010, 0x90000000, 0x80000000, 0x00000010,
+            0xa0000000, 0x20000000, 0xa0000000, 0x20000010, 0x00000000, 0x20000010, 0x20000000,
+            0x00000010, 0x20000000, 0x00000010, 0xa0000000, 0x00000000, 0x20000000, 0x20000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000001, 0x00000020, 0x00000001, 0x40000002,
+            0x40000040, 0x40000002, 0x80000004, 0x80000080, 0x80000006, 0x00000049, 0x00000103,
+            0x80000009, 0x80000012, 0x80000202,
+        ],
+    },
+    // I(51,2)
+    Dv {
+        testt: 65,
+        bit: DV_I_51_2,
+        dm: [
+            0xa0000003, 0x20000030, 0x60000000, 0xe000002a, 0x20000043, 0xb0000040, 0xd0000053,
+            0xd0000022, 0x20000000, 0x60000032, 0x60000043, 0x20000040, 0xe0000042, 0x60000002,
+            0x80000001, 0x00000020, 0x00000003, 0x40000052, 0x40000040, 0xe0000052, 0xa0000000,
+            0x80000040, 0x20000001, 0x20000060, 0x80000001, 0x40000042, 0xc0000043, 0x40000022,
+            0x00000003, 0x40000042, 0xc0000043, 0xc0000022, 0x00000001, 0x40000002, 0xc0000043,
+            0x40000062, 0x80000001, 0x40000042, 0x40000042, 0x40000002, 0x00000002, 0x00000040,
+            0x80000002, 0x80000000, 0x80000002, 0x80000040, 0x00000000, 0x80000040, 0x80000000,
+            0x00000040, 0x80000000, 0x00000040, 0x80000002, 0x00000000, 0x80000000, 0x80000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000004, 0x00000080, 0x00000004, 0x00000009,
+            0x00000101, 0x00000009, 0x00000012, 0x00000202, 0x0000001a, 0x00000124, 0x0000040c,
+            0x00000026, 0x0000004a, 0x0000080a,
+        ],
+    },
+    // I(52,0)
+    Dv {
+        testt: 65,
+        bit: DV_I_52_0,
+        dm: [
+            0x04000010, 0xe8000000, 0x0800000c, 0x18000000, 0xb800000a, 0xc8000010, 0x2c000010,
+            0xf4000014, 0xb4000008, 0x08000000, 0x9800000c, 0xd8000010, 0x08000010, 0xb8000010,
+            0x98000000, 0x60000000, 0x00000008, 0xc0000000, 0x90000014, 0x10000010, 0xb8000014,
+            0x28000000, 0x20000010, 0x48000000, 0x08000018, 0x60000000, 0x90000010, 0xf0000010,
+            0x90000008, 0xc0000000, 0x90000010, 0xf0000010, 0xb0000008, 0x40000000, 0x90000000,
+            0xf0000010, 0x90000018, 0x60000000, 0x90000010, 0x90000010, 0x90000000, 0x80000000,
+            0x00000010, 0xa0000000, 0x20000000, 0xa0000000, 0x20000010, 0x00000000, 0x20000010,
+            0x20000000, 0x00000010, 0x20000000, 0x00000010, 0xa0000000, 0x00000000, 0x20000000,
+            0x20000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000001, 0x00000020, 0x00000001,
+            0x40000002, 0x40000040, 0x40000002, 0x80000004, 0x80000080, 0x80000006, 0x00000049,
+            0x00000103, 0x80000009, 0x80000012,
+        ],
+    },
+    // II(45,0)
+    Dv {
+        testt: 58,
+        bit: DV_II_45_0,
+        dm: [
+            0xec000014, 0x0c000002, 0xc0000010, 0xb400001c, 0x2c000004, 0xbc000018, 0xb0000010,
+            0x0000000c, 0xb8000010, 0x08000018, 0x78000010, 0x08000014, 0x70000010, 0xb800001c,
+            0xe8000000, 0xb0000004, 0x58000010, 0xb000000c, 0x48000000, 0xb0000000, 0xb8000010,
+            0x98000010, 0xa0000000, 0x00000000, 0x00000000, 0x20000000, 0x80000000, 0x00000010,
+            0x00000000, 0x20000010, 0x20000000, 0x00000010, 0x60000000, 0x00000018, 0xe0000000,
+            0x90000000, 0x30000010, 0xb0000000, 0x20000000, 0x20000000, 0xa0000000, 0x00000010,
+            0x80000000, 0x20000000, 0x20000000, 0x20000000, 0x80000000, 0x00000010, 0x00000000,
+            0x20000010, 0xa0000000, 0x00000000, 0x20000000, 0x20000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000001, 0x00000020, 0x00000001,
+            0x40000002, 0x40000041, 0x40000022, 0x80000005, 0xc0000082, 0xc0000046, 0x4000004b,
+            0x80000107, 0x00000089, 0x00000014, 0x8000024b, 0x0000011b, 0x8000016d, 0x8000041a,
+            0x000002e4, 0x80000054, 0x00000967,
+        ],
+    },
+    // II(46,0)
+    Dv {
+        testt: 58,
+        bit: DV_II_46_0,
+        dm: [
+            0x2400001c, 0xec000014, 0x0c000002, 0xc0000010, 0xb400001c, 0x2c000004, 0xbc000018,
+            0xb0000010, 0x0000000c, 0xb8000010, 0x08000018, 0x78000010, 0x08000014, 0x70000010,
+            0xb800001c, 0xe8000000, 0xb0000004, 0x58000010, 0xb000000c, 0x48000000, 0xb0000000,
+            0xb8000010, 0x98000010, 0xa0000000, 0x00000000, 0x00000000, 0x20000000, 0x80000000,
+            0x00000010, 0x00000000, 0x20000010, 0x20000000, 0x00000010, 0x60000000, 0x00000018,
+            0xe0000000, 0x90000000, 0x30000010, 0xb0000000, 0x20000000, 0x20000000, 0xa0000000,
+            0x00000010, 0x80000000, 0x20000000, 0x20000000, 0x20000000, 0x80000000, 0x00000010,
+            0x00000000, 0x20000010, 0xa0000000, 0x00000000, 0x20000000, 0x20000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000001, 0x00000020,
+            0x00000001, 0x40000002, 0x40000041, 0x40000022, 0x80000005, 0xc0000082, 0xc0000046,
+            0x4000004b, 0x80000107, 0x00000089, 0x00000014, 0x8000024b, 0x0000011b, 0x8000016d,
+            0x8000041a, 0x000002e4, 0x80000054,
+        ],
+    },
+    // II(46,2)
+    Dv {
+        testt: 58,
+        bit: DV_II_46_2,
+        dm: [
+            0x90000070, 0xb0000053, 0x30000008, 0x00000043, 0xd0000072, 0xb0000010, 0xf0000062,
+            0xc0000042, 0x00000030, 0xe0000042, 0x20000060, 0xe0000041, 0x20000050, 0xc0000041,
+            0xe0000072, 0xa0000003, 0xc0000012, 0x60000041, 0xc0000032, 0x20000001, 0xc0000002,
+            0xe0000042, 0x60000042, 0x80000002, 0x00000000, 0x00000000, 0x80000000, 0x00000002,
+            0x00000040, 0x00000000, 0x80000040, 0x80000000, 0x00000040, 0x80000001, 0x00000060,
+            0x80000003, 0x40000002, 0xc0000040, 0xc0000002, 0x80000000, 0x80000000, 0x80000002,
+            0x00000040, 0x00000002, 0x80000000, 0x80000000, 0x80000000, 0x00000002, 0x00000040,
+            0x00000000, 0x80000040, 0x80000002, 0x00000000, 0x80000000, 0x80000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000004, 0x00000080,
+            0x00000004, 0x00000009, 0x00000105, 0x00000089, 0x00000016, 0x0000020b, 0x0000011b,
+            0x0000012d, 0x0000041e, 0x00000224, 0x00000050, 0x0000092e, 0x0000046c, 0x000005b6,
+            0x0000106a, 0x00000b90, 0x00000152,
+        ],
+    },
+    // II(47,0)
+    Dv {
+        testt: 58,
+        bit: DV_II_47_0,
+        dm: [
+            0x20000010, 0x2400001c, 0xec000014, 0x0c000002, 0xc0000010, 0xb400001c, 0x2c000004,
+            0xbc000018, 0xb0000010, 0x0000000c, 0xb8000010, 0x08000018, 0x78000010, 0x08000014,
+            0x70000010, 0xb800001c, 0xe8000000, 0xb0000004, 0x58000010, 0xb000000c, 0x48000000,
+            0xb0000000, 0xb8000010, 0x98000010, 0xa0000000, 0x00000000, 0x00000000, 0x20000000,
+            0x80000000, 0x00000010, 0x00000000, 0x20000010, 0x20000000, 0x00000010, 0x60000000,
+            0x00000018, 0xe0000000, 0x90000000, 0x30000010, 0xb0000000, 0x20000000, 0x20000000,
+            0xa0000000, 0x00000010, 0x80000000, 0x20000000, 0x20000000, 0x20000000, 0x80000000,
+            0x00000010, 0x00000000, 0x20000010, 0xa0000000, 0x00000000, 0x20000000, 0x20000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000001,
+            0x00000020, 0x00000001, 0x40000002, 0x40000041, 0x40000022, 0x80000005, 0xc0000082,
+            0xc0000046, 0x4000004b, 0x80000107, 0x00000089, 0x00000014, 0x8000024b, 0x0000011b,
+            0x8000016d, 0x8000041a, 0x000002e4,
+        ],
+    },
+    // II(48,0)
+    Dv {
+        testt: 58,
+        bit: DV_II_48_0,
+        dm: [
+            0xbc00001a, 0x20000010, 0x2400001c, 0xec000014, 0x0c000002, 0xc0000010, 0xb400001c,
+            0x2c000004, 0xbc000018, 0xb0000010, 0x0000000c, 0xb8000010, 0x08000018, 0x78000010,
+            0x08000014, 0x70000010, 0xb800001c, 0xe8000000, 0xb0000004, 0x58000010, 0xb000000c,
+            0x48000000, 0xb0000000, 0xb8000010, 0x98000010, 0xa0000000, 0x00000000, 0x00000000,
+            0x20000000, 0x80000000, 0x00000010, 0x00000000, 0x20000010, 0x20000000, 0x00000010,
+            0x60000000, 0x00000018, 0xe0000000, 0x90000000, 0x30000010, 0xb0000000, 0x20000000,
+            0x20000000, 0xa0000000, 0x00000010, 0x80000000, 0x20000000, 0x20000000, 0x20000000,
+            0x80000000, 0x00000010, 0x00000000, 0x20000010, 0xa0000000, 0x00000000, 0x20000000,
+            0x20000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000001, 0x00000020, 0x00000001, 0x40000002, 0x40000041, 0x40000022, 0x80000005,
+            0xc0000082, 0xc0000046, 0x4000004b, 0x80000107, 0x00000089, 0x00000014, 0x8000024b,
+            0x0000011b, 0x8000016d, 0x8000041a,
+        ],
+    },
+    // II(49,0)
+    Dv {
+        testt: 58,
+        bit: DV_II_49_0,
+        dm: [
+            0x3c000004, 0xbc00001a, 0x20000010, 0x2400001c, 0xec000014, 0x0c000002, 0xc0000010,
+            0xb400001c, 0x2c000004, 0xbc000018, 0xb0000010, 0x0000000c, 0xb8000010, 0x08000018,
+            0x78000010, 0x08000014, 0x70000010, 0xb800001c, 0xe8000000, 0xb0000004, 0x58000010,
+            0xb000000c, 0x48000000, 0xb0000000, 0xb8000010, 0x98000010, 0xa0000000, 0x00000000,
+            0x00000000, 0x20000000, 0x80000000, 0x00000010, 0x00000000, 0x20000010, 0x20000000,
+            0x00000010, 0x60000000, 0x00000018, 0xe0000000, 0x90000000, 0x30000010, 0xb0000000,
+            0x20000000, 0x20000000, 0xa0000000, 0x00000010, 0x80000000, 0x20000000, 0x20000000,
+            0x20000000, 0x80000000, 0x00000010, 0x00000000, 0x20000010, 0xa0000000, 0x00000000,
+            0x20000000, 0x20000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000001, 0x00000020, 0x00000001, 0x40000002, 0x40000041, 0x40000022,
+            0x80000005, 0xc0000082, 0xc0000046, 0x4000004b, 0x80000107, 0x00000089, 0x00000014,
+            0x8000024b, 0x0000011b, 0x8000016d,
+        ],
+    },
+    // II(49,2)
+    Dv {
+        testt: 58,
+        bit: DV_II_49_2,
+        dm: [
+            0xf0000010, 0xf000006a, 0x80000040, 0x90000070, 0xb0000053, 0x30000008, 0x00000043,
+            0xd0000072, 0xb0000010, 0xf0000062, 0xc0000042, 0x00000030, 0xe0000042, 0x20000060,
+            0xe0000041, 0x20000050, 0xc0000041, 0xe0000072, 0xa0000003, 0xc0000012, 0x60000041,
+            0xc0000032, 0x20000001, 0xc0000002, 0xe0000042, 0x60000042, 0x80000002, 0x00000000,
+            0x00000000, 0x80000000, 0x00000002, 0x00000040, 0x00000000, 0x80000040, 0x80000000,
+            0x00000040, 0x80000001, 0x00000060, 0x80000003, 0x40000002, 0xc0000040, 0xc0000002,
+            0x80000000, 0x80000000, 0x80000002, 0x00000040, 0x00000002, 0x80000000, 0x80000000,
+            0x80000000, 0x00000002, 0x00000040, 0x00000000, 0x80000040, 0x80000002, 0x00000000,
+            0x80000000, 0x80000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000004, 0x00000080, 0x00000004, 0x00000009, 0x00000105, 0x00000089,
+            0x00000016, 0x0000020b, 0x0000011b, 0x0000012d, 0x0000041e, 0x00000224, 0x00000050,
+            0x0000092e, 0x0000046c, 0x000005b6,
+        ],
+    },
+    // II(50,0)
+    Dv {
+        testt: 65,
+        bit: DV_II_50_0,
+        dm: [
+            0xb400001c, 0x3c000004, 0xbc00001a, 0x20000010, 0x2400001c, 0xec000014, 0x0c000002,
+            0xc0000010, 0xb400001c, 0x2c000004, 0xbc000018, 0xb0000010, 0x0000000c, 0xb8000010,
+            0x08000018, 0x78000010, 0x08000014, 0x70000010, 0xb800001c, 0xe8000000, 0xb0000004,
+            0x58000010, 0xb000000c, 0x48000000, 0xb0000000, 0xb8000010, 0x98000010, 0xa0000000,
+            0x00000000, 0x00000000, 0x20000000, 0x80000000, 0x00000010, 0x00000000, 0x20000010,
+            0x20000000, 0x00000010, 0x60000000, 0x00000018, 0xe0000000, 0x90000000, 0x30000010,
+            0xb0000000, 0x20000000, 0x20000000, 0xa0000000, 0x00000010, 0x80000000, 0x20000000,
+            0x20000000, 0x20000000, 0x80000000, 0x00000010, 0x00000000, 0x20000010, 0xa0000000,
+            0x00000000, 0x20000000, 0x20000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000001, 0x00000020, 0x00000001, 0x40000002, 0x40000041,
+            0x40000022, 0x80000005, 0xc0000082, 0xc0000046, 0x4000004b, 0x80000107, 0x00000089,
+            0x00000014, 0x8000024b, 0x0000011b,
+        ],
+    },
+    // II(50,2)
+    Dv {
+        testt: 65,
+        bit: DV_II_50_2,
+        dm: [
+            0xd0000072, 0xf0000010, 0xf000006a, 0x80000040, 0x90000070, 0xb0000053, 0x30000008,
+            0x00000043, 0xd0000072, 0xb0000010, 0xf0000062, 0xc0000042, 0x00000030, 0xe0000042,
+            0x20000060, 0xe0000041, 0x20000050, 0xc0000041, 0xe0000072, 0xa0000003, 0xc0000012,
+            0x60000041, 0xc0000032, 0x20000001, 0xc0000002, 0xe0000042, 0x60000042, 0x80000002,
+            0x00000000, 0x00000000, 0x80000000, 0x00000002, 0x00000040, 0x00000000, 0x80000040,
+            0x80000000, 0x00000040, 0x80000001, 0x00000060, 0x80000003, 0x40000002, 0xc0000040,
+            0xc0000002, 0x80000000, 0x80000000, 0x80000002, 0x00000040, 0x00000002, 0x80000000,
+            0x80000000, 0x80000000, 0x00000002, 0x00000040, 0x00000000, 0x80000040, 0x80000002,
+            0x00000000, 0x80000000, 0x80000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000004, 0x00000080, 0x00000004, 0x00000009, 0x00000105,
+            0x00000089, 0x00000016, 0x0000020b, 0x0000011b, 0x0000012d, 0x0000041e, 0x00000224,
+            0x00000050, 0x0000092e, 0x0000046c,
+        ],
+    },
+    // II(51,0)
+    Dv {
+        testt: 65,
+        bit: DV_II_51_0,
+        dm: [
+            0xc0000010, 0xb400001c, 0x3c000004, 0xbc00001a, 0x20000010, 0x2400001c, 0xec000014,
+            0x0c000002, 0xc0000010, 0xb400001c, 0x2c000004, 0xbc000018, 0xb0000010, 0x0000000c,
+            0xb8000010, 0x08000018, 0x78000010, 0x08000014, 0x70000010, 0xb800001c, 0xe8000000,
+            0xb0000004, 0x58000010, 0xb000000c, 0x48000000, 0xb0000000, 0xb8000010, 0x98000010,
+            0xa0000000, 0x00000000, 0x00000000, 0x20000000, 0x80000000, 0x00000010, 0x00000000,
+            0x20000010, 0x20000000, 0x00000010, 0x60000000, 0x00000018, 0xe0000000, 0x90000000,
+            0x30000010, 0xb0000000, 0x20000000, 0x20000000, 0xa0000000, 0x00000010, 0x80000000,
+            0x20000000, 0x20000000, 0x20000000, 0x80000000, 0x00000010, 0x00000000, 0x20000010,
+            0xa0000000, 0x00000000, 0x20000000, 0x20000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000001, 0x00000020, 0x00000001, 0x40000002,
+            0x40000041, 0x40000022, 0x80000005, 0xc0000082, 0xc0000046, 0x4000004b, 0x80000107,
+            0x00000089, 0x00000014, 0x8000024b,
+        ],
+    },
+    // II(51,2)
+    Dv {
+        testt: 65,
+        bit: DV_II_51_2,
+        dm: [
+            0x00000043, 0xd0000072, 0xf0000010, 0xf000006a, 0x80000040, 0x90000070, 0xb0000053,
+            0x30000008, 0x00000043, 0xd0000072, 0xb0000010, 0xf0000062, 0xc0000042, 0x00000030,
+            0xe0000042, 0x20000060, 0xe0000041, 0x20000050, 0xc0000041, 0xe0000072, 0xa0000003,
+            0xc0000012, 0x60000041, 0xc0000032, 0x20000001, 0xc0000002, 0xe0000042, 0x60000042,
+            0x80000002, 0x00000000, 0x00000000, 0x80000000, 0x00000002, 0x00000040, 0x00000000,
+            0x80000040, 0x80000000, 0x00000040, 0x80000001, 0x00000060, 0x80000003, 0x40000002,
+            0xc0000040, 0xc0000002, 0x80000000, 0x80000000, 0x80000002, 0x00000040, 0x00000002,
+            0x80000000, 0x80000000, 0x80000000, 0x00000002, 0x00000040, 0x00000000, 0x80000040,
+            0x80000002, 0x00000000, 0x80000000, 0x80000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000004, 0x00000080, 0x00000004, 0x00000009,
+            0x00000105, 0x00000089, 0x00000016, 0x0000020b, 0x0000011b, 0x0000012d, 0x0000041e,
+            0x00000224, 0x00000050, 0x0000092e,
+        ],
+    },
+    // II(52,0)
+    Dv {
+        testt: 65,
+        bit: DV_II_52_0,
+        dm: [
+            0x0c000002, 0xc0000010, 0xb400001c, 0x3c000004, 0xbc00001a, 0x20000010, 0x2400001c,
+            0xec000014, 0x0c000002, 0xc0000010, 0xb400001c, 0x2c000004, 0xbc000018, 0xb0000010,
+            0x0000000c, 0xb8000010, 0x08000018, 0x78000010, 0x08000014, 0x70000010, 0xb800001c,
+            0xe8000000, 0xb0000004, 0x58000010, 0xb000000c, 0x48000000, 0xb0000000, 0xb8000010,
+            0x98000010, 0xa0000000, 0x00000000, 0x00000000, 0x20000000, 0x80000000, 0x00000010,
+            0x00000000, 0x20000010, 0x20000000, 0x00000010, 0x60000000, 0x00000018, 0xe0000000,
+            0x90000000, 0x30000010, 0xb0000000, 0x20000000, 0x20000000, 0xa0000000, 0x00000010,
+            0x80000000, 0x20000000, 0x20000000, 0x20000000, 0x80000000, 0x00000010, 0x00000000,
+            0x20000010, 0xa0000000, 0x00000000, 0x20000000, 0x20000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000001, 0x00000020, 0x00000001,
+            0x40000002, 0x40000041, 0x40000022, 0x80000005, 0xc0000082, 0xc0000046, 0x4000004b,
+            0x80000107, 0x00000089, 0x00000014,
+        ],
+    },
+    // II(53,0)
+    Dv {
+        testt: 65,
+        bit: DV_II_53_0,
+        dm: [
+            0xcc000014, 0x0c000002, 0xc0000010, 0xb400001c, 0x3c000004, 0xbc00001a, 0x20000010,
+            0x2400001c, 0xec000014, 0x0c000002, 0xc0000010, 0xb400001c, 0x2c000004, 0xbc000018,
+            0xb0000010, 0x0000000c, 0xb8000010, 0x08000018, 0x78000010, 0x08000014, 0x70000010,
+            0xb800001c, 0xe8000000, 0xb0000004, 0x58000010, 0xb000000c, 0x48000000, 0xb0000000,
+            0xb8000010, 0x98000010, 0xa0000000, 0x00000000, 0x00000000, 0x20000000, 0x80000000,
+            0x00000010, 0x00000000, 0x20000010, 0x20000000, 0x00000010, 0x60000000, 0x00000018,
+            0xe0000000, 0x90000000, 0x30000010, 0xb0000000, 0x20000000, 0x20000000, 0xa0000000,
+            0x00000010, 0x80000000, 0x20000000, 0x20000000, 0x20000000, 0x80000000, 0x00000010,
+            0x00000000, 0x20000010, 0xa0000000, 0x00000000, 0x20000000, 0x20000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000001, 0x00000020,
+            0x00000001, 0x40000002, 0x40000041, 0x40000022, 0x80000005, 0xc0000082, 0xc0000046,
+            0x4000004b, 0x80000107, 0x00000089,
+        ],
+    },
+    // II(54,0)
+    Dv {
+        testt: 65,
+        bit: DV_II_54_0,
+        dm: [
+            0x0400001c, 0xcc000014, 0x0c000002, 0xc0000010, 0xb400001c, 0x3c000004, 0xbc00001a,
+            0x20000010, 0x2400001c, 0xec000014, 0x0c000002, 0xc0000010, 0xb400001c, 0x2c000004,
+            0xbc000018, 0xb0000010, 0x0000000c, 0xb8000010, 0x08000018, 0x78000010, 0x08000014,
+            0x70000010, 0xb800001c, 0xe8000000, 0xb0000004, 0x58000010, 0xb000000c, 0x48000000,
+            0xb0000000, 0xb8000010, 0x98000010, 0xa0000000, 0x00000000, 0x00000000, 0x20000000,
+            0x80000000, 0x00000010, 0x00000000, 0x20000010, 0x20000000, 0x00000010, 0x60000000,
+            0x00000018, 0xe0000000, 0x90000000, 0x30000010, 0xb0000000, 0x20000000, 0x20000000,
+            0xa0000000, 0x00000010, 0x80000000, 0x20000000, 0x20000000, 0x20000000, 0x80000000,
+            0x00000010, 0x00000000, 0x20000010, 0xa0000000, 0x00000000, 0x20000000, 0x20000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000001,
+            0x00000020, 0x00000001, 0x40000002, 0x40000041, 0x40000022, 0x80000005, 0xc0000082,
+            0xc0000046, 0x4000004b, 0x80000107,
+        ],
+    },
+    // II(55,0)
+    Dv {
+        testt: 65,
+        bit: DV_II_55_0,
+        dm: [
+            0x00000010, 0x0400001c, 0xcc000014, 0x0c000002, 0xc0000010, 0xb400001c, 0x3c000004,
+            0xbc00001a, 0x20000010, 0x2400001c, 0xec000014, 0x0c000002, 0xc0000010, 0xb400001c,
+            0x2c000004, 0xbc000018, 0xb0000010, 0x0000000c, 0xb8000010, 0x08000018, 0x78000010,
+            0x08000014, 0x70000010, 0xb800001c, 0xe8000000, 0xb0000004, 0x58000010, 0xb000000c,
+            0x48000000, 0xb0000000, 0xb8000010, 0x98000010, 0xa0000000, 0x00000000, 0x00000000,
+            0x20000000, 0x80000000, 0x00000010, 0x00000000, 0x20000010, 0x20000000, 0x00000010,
+            0x60000000, 0x00000018, 0xe0000000, 0x90000000, 0x30000010, 0xb0000000, 0x20000000,
+            0x20000000, 0xa0000000, 0x00000010, 0x80000000, 0x20000000, 0x20000000, 0x20000000,
+            0x80000000, 0x00000010, 0x00000000, 0x20000010, 0xa0000000, 0x00000000, 0x20000000,
+            0x20000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000001, 0x00000020, 0x00000001, 0x40000002, 0x40000041, 0x40000022, 0x80000005,
+            0xc0000082, 0xc0000046, 0x4000004b,
+        ],
+    },
+    // II(56,0)
+    Dv {
+        testt: 65,
+        bit: DV_II_56_0,
+        dm: [
+            0x2600001a, 0x00000010, 0x0400001c, 0xcc000014, 0x0c000002, 0xc0000010, 0xb400001c,
+            0x3c000004, 0xbc00001a, 0x20000010, 0x2400001c, 0xec000014, 0x0c000002, 0xc0000010,
+            0xb400001c, 0x2c000004, 0xbc000018, 0xb0000010, 0x0000000c, 0xb8000010, 0x08000018,
+            0x78000010, 0x08000014, 0x70000010, 0xb800001c, 0xe8000000, 0xb0000004, 0x58000010,
+            0xb000000c, 0x48000000, 0xb0000000, 0xb8000010, 0x98000010, 0xa0000000, 0x00000000,
+            0x00000000, 0x20000000, 0x80000000, 0x00000010, 0x00000000, 0x20000010, 0x20000000,
+            0x00000010, 0x60000000, 0x00000018, 0xe0000000, 0x90000000, 0x30000010, 0xb0000000,
+            0x20000000, 0x20000000, 0xa0000000, 0x00000010, 0x80000000, 0x20000000, 0x20000000,
+            0x20000000, 0x80000000, 0x00000010, 0x00000000, 0x20000010, 0xa0000000, 0x00000000,
+            0x20000000, 0x20000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000001, 0x00000020, 0x00000001, 0x40000002, 0x40000041, 0x40000022,
+            0x80000005, 0xc0000082, 0xc0000046,
+        ],
+    },
+];
+
+/// Returns the mask of the disturbance vectors whose unavoidable bit
+/// conditions the expanded message `w` satisfies.
+pub(crate) fn ubc_check(w: &[u32; 80]) -> u32 {
+    let mut mask = !0u32;
+    mask &= ((w[44] ^ w[45]) >> 29 & 1).wrapping_sub(1)
+        | !(DV_I_48_0 | DV_I_51_0 | DV_I_52_0 | DV_II_45_0 | DV_II_46_0 | DV_II_50_0 | DV_II_51_0);
+    mask &= ((w[49] ^ w[50]) >> 29 & 1).wrapping_sub(1)
+        | !(DV_I_46_0 | DV_II_45_0 | DV_II_50_0 | DV_II_51_0 | DV_II_55_0 | DV_II_56_0);
+    mask &= ((w[48] ^ w[49]) >> 29 & 1).wrapping_sub(1)
+        | !(DV_I_45_0 | DV_I_52_0 | DV_II_49_0 | DV_II_50_0 | DV_II_54_0 | DV_II_55_0);
+    mask &= ((w[47] ^ w[50] >> 25) & (1 << 4)).wrapping_sub(1 << 4)
+        | !(DV_I_47_0 | DV_I_49_0 | DV_I_51_0 | DV_II_45_0 | DV_II_51_0 | DV_II_56_0);
+    mask &= ((w[47] ^ w[48]) >> 29 & 1).wrapping_sub(1)
+        | !(DV_I_44_0 | DV_I_51_0 | DV_II_48_0 | DV_II_49_0 | DV_II_53_0 | DV_II_54_0);
+    mask &= ((w[46] >> 4 ^ w[49] >> 29) & 1).wrapping_sub(1)
+        | !(DV_I_46_0 | DV_I_48_0 | DV_I_50_0 | DV_I_52_0 | DV_II_50_0 | DV_II_55_0);
+    mask &= ((w[46] ^ w[47]) >> 29 & 1).wrapping_sub(1)
+        | !(DV_I_43_0 | DV_I_50_0 | DV_II_47_0 | DV_II_48_0 | DV_II_52_0 | DV_II_53_0);
+    mask &= ((w[45] >> 4 ^ w[48] >> 29) & 1).wrapping_sub(1)
+        | !(DV_I_45_0 | DV_I_47_0 | DV_I_49_0 | DV_I_51_0 | DV_II_49_0 | DV_II_54_0);
+    mask &= ((w[45] ^ w[46]) >> 29 & 1).wrapping_sub(1)
+        | !(DV_I_49_0 | DV_I_52_0 | DV_II_46_0 | DV_II_47_0 | DV_II_51_0 | DV_II_52_0);
+    mask &= ((w[44] >> 4 ^ w[47] >> 29) & 1).wrapping_sub(1)
+        | !(DV_I_44_0 | DV_I_46_0 | DV_I_48_0 | DV_I_50_0 | DV_II_48_0 | DV_II_53_0);
+    mask &= ((w[43] >> 4 ^ w[46] >> 29) & 1).wrapping_sub(1)
+        | !(DV_I_43_0 | DV_I_45_0 | DV_I_47_0 | DV_I_49_0 | DV_II_47_0 | DV_II_52_0);
+    mask &= ((w[43] ^ w[44]) >> 29 & 1).wrapping_sub(1)
+        | !(DV_I_47_0 | DV_I_50_0 | DV_I_51_0 | DV_II_45_0 | DV_II_49_0 | DV_II_50_0);
+    mask &= ((w[42] >> 4 ^ w[45] >> 29) & 1).wrapping_sub(1)
+        | !(DV_I_44_0 | DV_I_46_0 | DV_I_48_0 | DV_I_52_0 | DV_II_46_0 | DV_II_51_0);
+    mask &= ((w[41] >> 4 ^ w[44] >> 29) & 1).wrapping_sub(1)
+        | !(DV_I_43_0 | DV_I_45_0 | DV_I_47_0 | DV_I_51_0 | DV_II_45_0 | DV_II_50_0);
+    mask &= ((w[40] ^ w[41]) >> 29 & 1).wrapping_sub(1)
+        | !(DV_I_44_0 | DV_I_47_0 | DV_I_48_0 | DV_II_46_0 | DV_II_47_0 | DV_II_56_0);
+    mask &= ((w[54] ^ w[55]) >> 29 & 1).wrapping_sub(1)
+        | !(DV_I_51_0 | DV_II_47_0 | DV_II_50_0 | DV_II_55_0 | DV_II_56_0);
+    mask &= ((w[53] ^ w[54]) >> 29 & 1).wrapping_sub(1)
+        | !(DV_I_50_0 | DV_II_46_0 | DV_II_49_0 | DV_II_54_0 | DV_II_55_0);
+    mask &= ((w[52] ^ w[53]) >> 29 & 1).wrapping_sub(1)
+        | !(DV_I_49_0 | DV_II_45_0 | DV_II_48_0 | DV_II_53_0 | DV_II_54_0);
+    mask &= ((w[50] ^ w[53] >> 25) & (1 << 4)).wrapping_sub(1 << 4)
+        | !(DV_I_50_0 | DV_I_52_0 | DV_II_46_0 | DV_II_48_0 | DV_II_54_0);
+    mask &= ((w[50] ^ w[51]) >> 29 & 1).wrapping_sub(1)
+        | !(DV_I_47_0 | DV_II_46_0 | DV_II_51_0 | DV_II_52_0 | DV_II_56_0);
+    mask &= ((w[49] ^ w[52] >> 25) & (1 << 4)).wrapping_sub(1 << 4)
+        | !(DV_I_49_0 | DV_I_51_0 | DV_II_45_0 | DV_II_47_0 | DV_II_53_0);
+    mask &= ((w[48] ^ w[51] >> 25) & (1 << 4)).wrapping_sub(1 << 4)
+        | !(DV_I_48_0 | DV_I_50_0 | DV_I_52_0 | DV_II_46_0 | DV_II_52_0);
+    mask &= ((w[42] ^ w[43]) >> 29 & 1).wrapping_sub(1)
+        | !(DV_I_46_0 | DV_I_49_0 | DV_I_50_0 | DV_II_48_0 | DV_II_49_0);
+    mask &= ((w[41] ^ w[42]) >> 29 & 1).wrapping_sub(1)
+        | !(DV_I_45_0 | DV_I_48_0 | DV_I_49_0 | DV_II_47_0 | DV_II_48_0);
+    mask &= ((w[40] >> 4 ^ w[43] >> 29) & 1).wrapping_sub(1)
+        | !(DV_I_44_0 | DV_I_46_0 | DV_I_50_0 | DV_II_49_0 | DV_II_56_0);
+    mask &= ((w[39] >> 4 ^ w[42] >> 29) & 1).wrapping_sub(1)
+        | !(DV_I_43_0 | DV_I_45_0 | DV_I_49_0 | DV_II_48_0 | DV_II_55_0);
+    if mask & (DV_I_44_0 | DV_I_48_0 | DV_II_47_0 | DV_II_54_0 | DV_II_56_0) != 0 {
+        mask &= ((w[38] >> 4 ^ w[41] >> 29) & 1).wrapping_sub(1)
+            | !(DV_I_44_0 | DV_I_48_0 | DV_II_47_0 | DV_II_54_0 | DV_II_56_0)
+    }
+    mask &= ((w[37] >> 4 ^ w[40] >> 29) & 1).wrapping_sub(1)
+        | !(DV_I_43_0 | DV_I_47_0 | DV_II_46_0 | DV_II_53_0 | DV_II_55_0);
+    if mask & (DV_I_52_0 | DV_II_48_0 | DV_II_51_0 | DV_II_56_0) != 0 {
+        mask &= ((w[55] ^ w[56]) >> 29 & 1).wrapping_sub(1)
+            | !(DV_I_52_0 | DV_II_48_0 | DV_II_51_0 | DV_II_56_0)
+    }
+    if mask & (DV_I_52_0 | DV_II_48_0 | DV_II_50_0 | DV_II_56_0) != 0 {
+        mask &= ((w[52] ^ w[55] >> 25) & (1 << 4)).wrapping_sub(1 << 4)
+            | !(DV_I_52_0 | DV_II_48_0 | DV_II_50_0 | DV_II_56_0)
+    }
+    if mask & (DV_I_51_0 | DV_II_47_0 | DV_II_49_0 | DV_II_55_0) != 0 {
+        mask &= ((w[51] ^ w[54] >> 25) & (1 << 4)).wrapping_sub(1 << 4)
+            | !(DV_I_51_0 | DV_II_47_0 | DV_II_49_0 | DV_II_55_0)
+    }
+    if mask & (DV_I_48_0 | DV_II_47_0 | DV_II_52_0 | DV_II_53_0) != 0 {
+        mask &= ((w[51] ^ w[52]) >> 29 & 1).wrapping_sub(1)
+            | !(DV_I_48_0 | DV_II_47_0 | DV_II_52_0 | DV_II_53_0)
+    }
+    if mask & (DV_I_46_0 | DV_I_49_0 | DV_II_45_0 | DV_II_48_0) != 0 {
+        mask &= ((w[36] >> 4 ^ w[40] >> 29) & 1).wrapping_sub(1)
+            | !(DV_I_46_0 | DV_I_49_0 | DV_II_45_0 | DV_II_48_0)
+    }
+    if mask & (DV_I_52_0 | DV_II_48_0 | DV_II_49_0) != 0 {
+        mask &=
+            0u32.wrapping_sub((w[53] ^ w[56]) >> 29 & 1) | !(DV_I_52_0 | DV_II_48_0 | DV_II_49_0)
+    }
+    if mask & (DV_I_50_0 | DV_II_46_0 | DV_II_47_0) != 0 {
+        mask &=
+            0u32.wrapping_sub((w[51] ^ w[54]) >> 29 & 1) | !(DV_I_50_0 | DV_II_46_0 | DV_II_47_0)
+    }
+    if mask & (DV_I_49_0 | DV_I_51_0 | DV_II_45_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[50] ^ w[52]) >> 29 & 1) | !(DV_I_49_0 | DV_I_51_0 | DV_II_45_0)
+    }
+    if mask & (DV_I_48_0 | DV_I_50_0 | DV_I_52_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[49] ^ w[51]) >> 29 & 1) | !(DV_I_48_0 | DV_I_50_0 | DV_I_52_0)
+    }
+    if mask & (DV_I_47_0 | DV_I_49_0 | DV_I_51_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[48] ^ w[50]) >> 29 & 1) | !(DV_I_47_0 | DV_I_49_0 | DV_I_51_0)
+    }
+    if mask & (DV_I_46_0 | DV_I_48_0 | DV_I_50_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[47] ^ w[49]) >> 29 & 1) | !(DV_I_46_0 | DV_I_48_0 | DV_I_50_0)
+    }
+    if mask & (DV_I_45_0 | DV_I_47_0 | DV_I_49_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[46] ^ w[48]) >> 29 & 1) | !(DV_I_45_0 | DV_I_47_0 | DV_I_49_0)
+    }
+    mask &=
+        ((w[45] ^ w[47]) & (1 << 6)).wrapping_sub(1 << 6) | !(DV_I_47_2 | DV_I_49_2 | DV_I_51_2);
+    if mask & (DV_I_44_0 | DV_I_46_0 | DV_I_48_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[45] ^ w[47]) >> 29 & 1) | !(DV_I_44_0 | DV_I_46_0 | DV_I_48_0)
+    }
+    mask &= ((w[44] ^ w[46]) >> 6 & 1).wrapping_sub(1) | !(DV_I_46_2 | DV_I_48_2 | DV_I_50_2);
+    if mask & (DV_I_43_0 | DV_I_45_0 | DV_I_47_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[44] ^ w[46]) >> 29 & 1) | !(DV_I_43_0 | DV_I_45_0 | DV_I_47_0)
+    }
+    mask &=
+        0u32.wrapping_sub((w[41] ^ w[42] >> 5) & (1 << 1)) | !(DV_I_48_2 | DV_II_46_2 | DV_II_51_2);
+    mask &=
+        0u32.wrapping_sub((w[40] ^ w[41] >> 5) & (1 << 1)) | !(DV_I_47_2 | DV_I_51_2 | DV_II_50_2);
+    if mask & (DV_I_44_0 | DV_I_46_0 | DV_II_56_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[40] ^ w[42]) >> 4 & 1) | !(DV_I_44_0 | DV_I_46_0 | DV_II_56_0)
+    }
+    mask &=
+        0u32.wrapping_sub((w[39] ^ w[40] >> 5) & (1 << 1)) | !(DV_I_46_2 | DV_I_50_2 | DV_II_49_2);
+    if mask & (DV_I_43_0 | DV_I_45_0 | DV_II_55_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[39] ^ w[41]) >> 4 & 1) | !(DV_I_43_0 | DV_I_45_0 | DV_II_55_0)
+    }
+    if mask & (DV_I_44_0 | DV_II_54_0 | DV_II_56_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[38] ^ w[40]) >> 4 & 1) | !(DV_I_44_0 | DV_II_54_0 | DV_II_56_0)
+    }
+    if mask & (DV_I_43_0 | DV_II_53_0 | DV_II_55_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[37] ^ w[39]) >> 4 & 1) | !(DV_I_43_0 | DV_II_53_0 | DV_II_55_0)
+    }
+    mask &=
+        0u32.wrapping_sub((w[36] ^ w[37] >> 5) & (1 << 1)) | !(DV_I_47_2 | DV_I_50_2 | DV_II_46_2);
+    if mask & (DV_I_45_0 | DV_I_48_0 | DV_II_47_0) != 0 {
+        mask &=
+            ((w[35] >> 4 ^ w[39] >> 29) & 1).wrapping_sub(1) | !(DV_I_45_0 | DV_I_48_0 | DV_II_47_0)
+    }
+    if mask & (DV_I_48_0 | DV_II_48_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[63] ^ w[64] >> 5) & (1 << 0)) | !(DV_I_48_0 | DV_II_48_0)
+    }
+    if mask & (DV_I_45_0 | DV_II_45_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[63] ^ w[64] >> 5) & (1 << 1)) | !(DV_I_45_0 | DV_II_45_0)
+    }
+    if mask & (DV_I_47_0 | DV_II_47_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[62] ^ w[63] >> 5) & (1 << 0)) | !(DV_I_47_0 | DV_II_47_0)
+    }
+    if mask & (DV_I_46_0 | DV_II_46_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[61] ^ w[62] >> 5) & (1 << 0)) | !(DV_I_46_0 | DV_II_46_0)
+    }
+    mask &= 0u32.wrapping_sub((w[61] ^ w[62] >> 5) & (1 << 2)) | !(DV_I_46_2 | DV_II_46_2);
+    if mask & (DV_I_45_0 | DV_II_45_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[60] ^ w[61] >> 5) & (1 << 0)) | !(DV_I_45_0 | DV_II_45_0)
+    }
+    if mask & (DV_II_51_0 | DV_II_54_0) != 0 {
+        mask &= ((w[58] ^ w[59]) >> 29 & 1).wrapping_sub(1) | !(DV_II_51_0 | DV_II_54_0)
+    }
+    if mask & (DV_II_50_0 | DV_II_53_0) != 0 {
+        mask &= ((w[57] ^ w[58]) >> 29 & 1).wrapping_sub(1) | !(DV_II_50_0 | DV_II_53_0)
+    }
+    if mask & (DV_II_52_0 | DV_II_54_0) != 0 {
+        mask &= ((w[56] ^ w[59] >> 25) & (1 << 4)).wrapping_sub(1 << 4) | !(DV_II_52_0 | DV_II_54_0)
+    }
+    if mask & (DV_II_51_0 | DV_II_52_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[56] ^ w[59]) >> 29 & 1) | !(DV_II_51_0 | DV_II_52_0)
+    }
+    if mask & (DV_II_49_0 | DV_II_52_0) != 0 {
+        mask &= ((w[56] ^ w[57]) >> 29 & 1).wrapping_sub(1) | !(DV_II_49_0 | DV_II_52_0)
+    }
+    if mask & (DV_II_51_0 | DV_II_53_0) != 0 {
+        mask &= ((w[55] ^ w[58] >> 25) & (1 << 4)).wrapping_sub(1 << 4) | !(DV_II_51_0 | DV_II_53_0)
+    }
+    if mask & (DV_II_50_0 | DV_II_52_0) != 0 {
+        mask &= ((w[54] ^ w[57] >> 25) & (1 << 4)).wrapping_sub(1 << 4) | !(DV_II_50_0 | DV_II_52_0)
+    }
+    if mask & (DV_II_49_0 | DV_II_51_0) != 0 {
+        mask &= ((w[53] ^ w[56] >> 25) & (1 << 4)).wrapping_sub(1 << 4) | !(DV_II_49_0 | DV_II_51_0)
+    }
+    mask &= ((w[51] ^ w[50] >> 5) & (1 << 1)).wrapping_sub(1 << 1) | !(DV_I_50_2 | DV_II_46_2);
+    mask &= ((w[48] ^ w[50]) & (1 << 6)).wrapping_sub(1 << 6) | !(DV_I_50_2 | DV_II_46_2);
+    if mask & (DV_I_51_0 | DV_I_52_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[48] ^ w[55]) >> 29 & 1) | !(DV_I_51_0 | DV_I_52_0)
+    }
+    mask &= ((w[47] ^ w[49]) & (1 << 6)).wrapping_sub(1 << 6) | !(DV_I_49_2 | DV_I_51_2);
+    mask &= ((w[48] ^ w[47] >> 5) & (1 << 1)).wrapping_sub(1 << 1) | !(DV_I_47_2 | DV_II_51_2);
+    mask &= ((w[46] ^ w[48]) & (1 << 6)).wrapping_sub(1 << 6) | !(DV_I_48_2 | DV_I_50_2);
+    mask &= ((w[47] ^ w[46] >> 5) & (1 << 1)).wrapping_sub(1 << 1) | !(DV_I_46_2 | DV_II_50_2);
+    mask &= 0u32.wrapping_sub((w[44] ^ w[45] >> 5) & (1 << 1)) | !(DV_I_51_2 | DV_II_49_2);
+    mask &= ((w[43] ^ w[45]) & (1 << 6)).wrapping_sub(1 << 6) | !(DV_I_47_2 | DV_I_49_2);
+    mask &= ((w[42] ^ w[44]) >> 6 & 1).wrapping_sub(1) | !(DV_I_46_2 | DV_I_48_2);
+    mask &= ((w[43] ^ w[42] >> 5) & (1 << 1)).wrapping_sub(1 << 1) | !(DV_II_46_2 | DV_II_51_2);
+    mask &= ((w[42] ^ w[41] >> 5) & (1 << 1)).wrapping_sub(1 << 1) | !(DV_I_51_2 | DV_II_50_2);
+    mask &= ((w[41] ^ w[40] >> 5) & (1 << 1)).wrapping_sub(1 << 1) | !(DV_I_50_2 | DV_II_49_2);
+    if mask & (DV_I_52_0 | DV_II_51_0) != 0 {
+        mask &= ((w[39] ^ w[43] >> 25) & (1 << 4)).wrapping_sub(1 << 4) | !(DV_I_52_0 | DV_II_51_0)
+    }
+    if mask & (DV_I_51_0 | DV_II_50_0) != 0 {
+        mask &= ((w[38] ^ w[42] >> 25) & (1 << 4)).wrapping_sub(1 << 4) | !(DV_I_51_0 | DV_II_50_0)
+    }
+    if mask & (DV_I_48_2 | DV_I_51_2) != 0 {
+        mask &= 0u32.wrapping_sub((w[37] ^ w[38] >> 5) & (1 << 1)) | !(DV_I_48_2 | DV_I_51_2)
+    }
+    if mask & (DV_I_50_0 | DV_II_49_0) != 0 {
+        mask &= ((w[37] ^ w[41] >> 25) & (1 << 4)).wrapping_sub(1 << 4) | !(DV_I_50_0 | DV_II_49_0)
+    }
+    if mask & (DV_II_52_0 | DV_II_54_0) != 0 {
+        mask &= 0u32.wrapping_sub((w[36] ^ w[38]) & (1 << 4)) | !(DV_II_52_0 | DV_II_54_0)
+    }
+    mask &= 0u32.wrapping_sub((w[35] ^ w[36] >> 5) & (1 << 1)) | !(DV_I_46_2 | DV_I_49_2);
+    if mask & (DV_I_51_0 | DV_II_47_0) != 0 {
+        mask &= ((w[35] ^ w[39] >> 25) & (1 << 3)).wrapping_sub(1 << 3) | !(DV_I_51_0 | DV_II_47_0)
+    }
+    if mask != 0 {
+        if mask & DV_I_43_0 != 0
+            && ((w[61] ^ w[62] >> 5) & (1 << 1) == 0
+                || (w[59] ^ w[63] >> 25) & (1 << 5) != 0
+                || (w[58] ^ w[63] >> 30) & (1 << 0) == 0)
+        {
+            mask &= !DV_I_43_0
+        }
+        if mask & DV_I_44_0 != 0
+            && ((w[62] ^ w[63] >> 5) & (1 << 1) == 0
+                || (w[60] ^ w[64] >> 25) & (1 << 5) != 0
+                || (w[59] ^ w[64] >> 30) & (1 << 0) == 0)
+        {
+            mask &= !DV_I_44_0
+        }
+        if mask & DV_I_46_2 != 0 {
+            mask &= !((w[40] ^ w[42]) >> 2) | !DV_I_46_2
+        }
+        if mask & DV_I_47_2 != 0
+            && ((w[62] ^ w[63] >> 5) & (1 << 2) == 0 || (w[41] ^ w[43]) & (1 << 6) != 0)
+        {
+            mask &= !DV_I_47_2
+        }
+        if mask & DV_I_48_2 != 0
+            && ((w[63] ^ w[64] >> 5) & (1 << 2) == 0 || (w[48] ^ w[49] << 5) & (1 << 6) != 0)
+        {
+            mask &= !DV_I_48_2
+        }
+        if mask & DV_I_49_2 != 0
+            && ((w[49] ^ w[50] << 5) & (1 << 6) != 0
+                || (w[42] ^ w[50]) & (1 << 1) == 0
+                || (w[39] ^ w[40] << 5) & (1 << 6) != 0
+                || (w[38] ^ w[40]) & (1 << 1) == 0)
+        {
+            mask &= !DV_I_49_2
+        }
+        if mask & DV_I_50_0 != 0 {
+            mask &= (w[36] ^ w[37]) << 7 | !DV_I_50_0
+        }
+        if mask & DV_I_50_2 != 0 {
+            mask &= (w[43] ^ w[51]) << 11 | !DV_I_50_2
+        }
+        if mask & DV_I_51_0 != 0 {
+            mask &= (w[37] ^ w[38]) << 9 | !DV_I_51_0
+        }
+        if mask & DV_I_51_2 != 0
+            && ((w[51] ^ w[52] << 5) & (1 << 6) != 0
+                || (w[49] ^ w[51]) & (1 << 6) != 0
+                || (w[37] ^ w[37] >> 5) & (1 << 1) != 0
+                || (w[35] ^ w[39] >> 25) & (1 << 5) != 0)
+        {
+            mask &= !DV_I_51_2
+        }
+        if mask & DV_I_52_0 != 0 {
+            mask &= (w[38] ^ w[39]) << 11 | !DV_I_52_0
+        }
+        if mask & DV_II_46_2 != 0 {
+            mask &= (w[47] ^ w[51]) << 17 | !DV_II_46_2
+        }
+        if mask & DV_II_48_0 != 0
+            && ((w[36] ^ w[40] >> 25) & (1 << 3) != 0 || (w[35] ^ w[40] << 2) & (1 << 30) == 0)
+        {
+            mask &= !DV_II_48_0
+        }
+        if mask & DV_II_49_0 != 0
+            && ((w[37] ^ w[41] >> 25) & (1 << 3) != 0 || (w[36] ^ w[41] << 2) & (1 << 30) == 0)
+        {
+            mask &= !DV_II_49_0
+        }
+        if mask & DV_II_49_2 != 0
+            && ((w[53] ^ w[54] << 5) & (1 << 6) != 0
+                || (w[51] ^ w[53]) & (1 << 6) != 0
+                || (w[50] ^ w[54]) & (1 << 1) == 0
+                || (w[45] ^ w[46] << 5) & (1 << 6) != 0
+                || (w[37] ^ w[41] >> 25) & (1 << 5) != 0
+                || (w[36] ^ w[41] >> 30) & (1 << 0) == 0)
+        {
+            mask &= !DV_II_49_2
+        }
+        if mask & DV_II_50_0 != 0
+            && ((w[55] ^ w[58]) & (1 << 29) == 0
+                || (w[38] ^ w[42] >> 25) & (1 << 3) != 0
+                || (w[37] ^ w[42] << 2) & (1 << 30) == 0)
+        {
+            mask &= !DV_II_50_0
+        }
+        if mask & DV_II_50_2 != 0
+            && ((w[54] ^ w[55] << 5) & (1 << 6) != 0
+                || (w[52] ^ w[54]) & (1 << 6) != 0
+                || (w[51] ^ w[55]) & (1 << 1) == 0
+                || (w[45] ^ w[47]) & (1 << 1) == 0
+                || (w[38] ^ w[42] >> 25) & (1 << 5) != 0
+                || (w[37] ^ w[42] >> 30) & (1 << 0) == 0)
+        {
+            mask &= !DV_II_50_2
+        }
+        if mask & DV_II_51_0 != 0
+            && ((w[39] ^ w[43] >> 25) & (1 << 3) != 0 || (w[38] ^ w[43] << 2) & (1 << 30) == 0)
+        {
+            mask &= !DV_II_51_0
+        }
+        if mask & DV_II_51_2 != 0
+            && ((w[55] ^ w[56] << 5) & (1 << 6) != 0
+                || (w[53] ^ w[55]) & (1 << 6) != 0
+                || (w[52] ^ w[56]) & (1 << 1) == 0
+                || (w[46] ^ w[48]) & (1 << 1) == 0
+                || (w[39] ^ w[43] >> 25) & (1 << 5) != 0
+                || (w[38] ^ w[43] >> 30) & (1 << 0) == 0)
+        {
+            mask &= !DV_II_51_2
+        }
+        if mask & DV_II_52_0 != 0
+            && ((w[59] ^ w[60]) & (1 << 29) != 0
+                || (w[40] ^ w[44] >> 25) & (1 << 3) != 0
+                || (w[40] ^ w[44] >> 25) & (1 << 4) != 0
+                || (w[39] ^ w[44] << 2) & (1 << 30) == 0)
+        {
+            mask &= !DV_II_52_0
+        }
+        if mask & DV_II_53_0 != 0
+            && ((w[58] ^ w[61]) & (1 << 29) == 0
+                || (w[57] ^ w[61] >> 25) & (1 << 4) != 0
+                || (w[41] ^ w[45] >> 25) & (1 << 3) != 0
+                || (w[41] ^ w[45] >> 25) & (1 << 4) != 0)
+        {
+            mask &= !DV_II_53_0
+        }
+        if mask & DV_II_54_0 != 0
+            && ((w[58] ^ w[62] >> 25) & (1 << 4) != 0
+                || (w[42] ^ w[46] >> 25) & (1 << 3) != 0
+                || (w[42] ^ w[46] >> 25) & (1 << 4) != 0)
+        {
+            mask &= !DV_II_54_0
+        }
+        if mask & DV_II_55_0 != 0
+            && ((w[59] ^ w[63] >> 25) & (1 << 4) != 0
+                || (w[57] ^ w[59] >> 25) & (1 << 4) != 0
+                || (w[43] ^ w[47] >> 25) & (1 << 3) != 0
+                || (w[43] ^ w[47] >> 25) & (1 << 4) != 0)
+        {
+            mask &= !DV_II_55_0
+        }
+        if mask & DV_II_56_0 != 0
+            && ((w[60] ^ w[64] >> 25) & (1 << 4) != 0
+                || (w[44] ^ w[48] >> 25) & (1 << 3) != 0
+                || (w[44] ^ w[48] >> 25) & (1 << 4) != 0)
+        {
+            mask &= !DV_II_56_0
+        }
+    }
+    mask
+}
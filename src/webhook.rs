@@ -0,0 +1,222 @@
+//! Webhook signature verification.
+//!
+//! GitHub, GitLab and many other services sign webhook deliveries with
+//! HMAC-SHA1 over the raw request body and send the tag in a header like
+//! `X-Hub-Signature: sha1=<hex>`.  The helpers here parse such headers and
+//! compare the tag in constant time.
+//!
+//! Other (mostly older) services send bare hex or base64 tags and sign a
+//! timestamp together with the body to prevent replays.  These can be
+//! handled with [`verify_signature`] and [`check_timestamp`]:
+//!
+//! ```
+//! use sha1_smol::webhook::{self, SignatureFormat};
+//!
+//! # let (header, timestamp, now, body) = ("", 0, 0, b"");
+//! # let _ = (|| -> Result<(), webhook::WebhookError> {
+//! webhook::check_timestamp(timestamp, now, 300)?;
+//! let ts = timestamp.to_string();
+//! webhook::verify_signature(
+//!     header,
+//!     SignatureFormat::Base64,
+//!     b"secret",
+//!     &[ts.as_bytes(), b".", body],
+//! )?;
+//! # Ok(()) })();
+//! ```
+//!
+//! ```
+//! use sha1_smol::webhook;
+//!
+//! let header = "sha1=f9a0a1e0d6b3f6cd3a6d56cbaad5bd64a5cf5ac1";
+//! let rv = webhook::verify_x_hub_signature(header, b"secret", b"{}");
+//! assert_eq!(rv, Err(webhook::WebhookError::BadSignature));
+//! ```
+
+use core::fmt;
+
+use crate::encoding::{decode_b64_into, decode_hex_into, Alphabet};
+use crate::hmac::{ct_eq, HmacSha1};
+
+/// Indicates that a webhook signature couldn't be verified.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum WebhookError {
+    /// The signature header is not in the expected format.
+    MalformedHeader,
+    /// The signature does not match the body.
+    BadSignature,
+    /// The timestamp is outside of the accepted tolerance.
+    StaleTimestamp,
+}
+
+impl WebhookError {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            WebhookError::MalformedHeader => "malformed signature header",
+            WebhookError::BadSignature => "bad webhook signature",
+            WebhookError::StaleTimestamp => "stale webhook timestamp",
+        }
+    }
+}
+
+impl fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WebhookError {
+    fn description(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// The encoding of the tag in a signature header.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum SignatureFormat {
+    /// Hex encoded tag following a fixed prefix such as `"sha1="`.
+    PrefixedHex(&'static str),
+    /// Bare hex encoded tag (either case).
+    Hex,
+    /// Standard base64 encoded tag.
+    Base64,
+}
+
+impl SignatureFormat {
+    fn decode(&self, value: &str) -> Option<[u8; 20]> {
+        let mut tag = [0u8; 20];
+        let value = value.trim();
+        match *self {
+            SignatureFormat::PrefixedHex(prefix) => {
+                decode_hex_into(value.strip_prefix(prefix)?.as_bytes(), &mut tag)?
+            }
+            SignatureFormat::Hex => decode_hex_into(value.as_bytes(), &mut tag)?,
+            SignatureFormat::Base64 => {
+                if decode_b64_into(value.as_bytes(), Alphabet::Standard, &mut tag)? != tag.len() {
+                    return None;
+                }
+            }
+        }
+        Some(tag)
+    }
+}
+
+/// Verifies a signature header for a message.
+///
+/// The message is given in parts which are signed as if they were
+/// concatenated, so schemes that sign a timestamp along with the body do
+/// not need to allocate.
+pub fn verify_signature(
+    header_value: &str,
+    format: SignatureFormat,
+    secret: &[u8],
+    message: &[&[u8]],
+) -> Result<(), WebhookError> {
+    let tag = format
+        .decode(header_value)
+        .ok_or(WebhookError::MalformedHeader)?;
+    let mut mac = HmacSha1::new(secret);
+    for part in message {
+        mac.update(part);
+    }
+    if ct_eq(&mac.digest().bytes(), &tag) {
+        Ok(())
+    } else {
+        Err(WebhookError::BadSignature)
+    }
+}
+
+/// Checks that `timestamp` is at most `tolerance` seconds away from `now`.
+///
+/// Both are in seconds since the UNIX epoch.  Timestamps in the future are
+/// subject to the same tolerance to allow for clock skew.
+pub fn check_timestamp(timestamp: u64, now: u64, tolerance: u64) -> Result<(), WebhookError> {
+    let delta = if now > timestamp {
+        now - timestamp
+    } else {
+        timestamp - now
+    };
+    if delta <= tolerance {
+        Ok(())
+    } else {
+        Err(WebhookError::StaleTimestamp)
+    }
+}
+
+/// Verifies an `X-Hub-Signature` header value (`sha1=<hex>`) for `body`.
+pub fn verify_x_hub_signature(
+    header_value: &str,
+    secret: &[u8],
+    body: &[u8],
+) -> Result<(), WebhookError> {
+    verify_signature(
+        header_value,
+        SignatureFormat::PrefixedHex("sha1="),
+        secret,
+        &[body],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_x_hub_signature() {
+        let header = "sha1=effcdf6ae5eb2fa2d27416d5f184df9c259a7c79";
+        let body = b"what do ya want for nothing?";
+        assert_eq!(verify_x_hub_signature(header, b"Jefe", body), Ok(()));
+        assert_eq!(
+            verify_x_hub_signature(&header.to_ascii_uppercase()[..], b"Jefe", body),
+            Err(WebhookError::MalformedHeader)
+        );
+        assert_eq!(
+            verify_x_hub_signature(header, b"Jeff", body),
+            Err(WebhookError::BadSignature)
+        );
+        assert_eq!(
+            verify_x_hub_signature("sha1=effc", b"Jefe", body),
+            Err(WebhookError::MalformedHeader)
+        );
+    }
+
+    #[test]
+    fn test_signature_formats() {
+        let body: &[&[u8]] = &[b"what do ya ", b"want for nothing?"];
+        for header in [
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79",
+            "EFFCDF6AE5EB2FA2D27416D5F184DF9C259A7C79",
+        ]
+        .iter()
+        {
+            assert_eq!(
+                verify_signature(header, SignatureFormat::Hex, b"Jefe", body),
+                Ok(())
+            );
+        }
+        assert_eq!(
+            verify_signature(
+                "7/zfauXrL6LSdBbV8YTfnCWafHk=",
+                SignatureFormat::Base64,
+                b"Jefe",
+                body
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            verify_signature("7/zfauXrL6LSdBbV", SignatureFormat::Base64, b"Jefe", body),
+            Err(WebhookError::MalformedHeader)
+        );
+    }
+
+    #[test]
+    fn test_check_timestamp() {
+        assert_eq!(check_timestamp(1000, 1300, 300), Ok(()));
+        assert_eq!(check_timestamp(1300, 1000, 300), Ok(()));
+        assert_eq!(
+            check_timestamp(1000, 1301, 300),
+            Err(WebhookError::StaleTimestamp)
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! The WebSocket opening handshake.
+//!
+//! A server accepts a WebSocket upgrade (RFC 6455 section 4.2.2) by
+//! answering the `Sec-WebSocket-Key` request header with a
+//! `Sec-WebSocket-Accept` header holding the base64 encoded SHA-1 of the key
+//! concatenated with a fixed GUID:
+//!
+//! ```
+//! use sha1_smol::websocket::websocket_accept;
+//!
+//! let accept = websocket_accept("dGhlIHNhbXBsZSBub25jZQ==");
+//! assert_eq!(&accept, b"s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+//! ```
+
+use crate::encoding::{encode_b64_into, Alphabet};
+use crate::hmac::ct_eq;
+use crate::Sha1;
+
+/// The GUID appended to the key before hashing.
+pub const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The length of a `Sec-WebSocket-Accept` value.
+pub const ACCEPT_LENGTH: usize = 28;
+
+/// Computes the `Sec-WebSocket-Accept` value for a `Sec-WebSocket-Key`.
+///
+/// The key is used as sent by the client, surrounding whitespace has to be
+/// removed beforehand.  The result is ASCII and can be turned into a string
+/// with [`core::str::from_utf8`].
+pub fn websocket_accept(key: &str) -> [u8; ACCEPT_LENGTH] {
+    let mut m = Sha1::from(key);
+    m.update(GUID.as_bytes());
+    let mut rv = [0u8; ACCEPT_LENGTH];
+    encode_b64_into(&m.digest().bytes(), Alphabet::Standard, true, &mut rv);
+    rv
+}
+
+/// Checks the `Sec-WebSocket-Accept` header a server answered `key` with.
+///
+/// This is the check a client performs before considering the connection
+/// established.
+pub fn verify_accept(key: &str, accept: &str) -> bool {
+    ct_eq(&websocket_accept(key), accept.trim().as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_websocket_accept() {
+        // example from RFC 6455 section 1.3
+        assert_eq!(
+            &websocket_accept("dGhlIHNhbXBsZSBub25jZQ=="),
+            b"s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+        // example from RFC 6455 section 4.2.2
+        assert_eq!(
+            &websocket_accept("x3JJHMbDL1EzLkh9GBhXDw=="),
+            b"HSmrc0sMlYUkAGmm5OPpG2HaGWk="
+        );
+    }
+
+    #[test]
+    fn test_verify_accept() {
+        assert!(verify_accept(
+            "dGhlIHNhbXBsZSBub25jZQ==",
+            " s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        ));
+        assert!(!verify_accept(
+            "dGhlIHNhbXBsZSBub25jZQ==",
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo"
+        ));
+        assert!(!verify_accept(
+            "x3JJHMbDL1EzLkh9GBhXDw==",
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        ));
+    }
+}
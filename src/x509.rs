@@ -0,0 +1,201 @@
+//! SHA-1 thumbprints of X.509 certificates.
+//!
+//! The thumbprint (or fingerprint) of a certificate is the SHA-1 of its DER
+//! encoding.  Windows, browsers and `openssl x509 -fingerprint` show it as
+//! uppercase hex with the bytes separated by colons, which is what
+//! [`Thumbprint`] formats to:
+//!
+//! ```
+//! use sha1_smol::x509::{thumbprint, Thumbprint};
+//!
+//! # let der = b"";
+//! let tp = Thumbprint(thumbprint(der));
+//! assert_eq!(
+//!     tp.to_string(),
+//!     "DA:39:A3:EE:5E:6B:4B:0D:32:55:BF:EF:95:60:18:90:AF:D8:07:09"
+//! );
+//! ```
+//!
+//! No ASN.1 parsing takes place, the input is hashed as it is.
+
+use core::{fmt, str};
+
+use crate::encoding::{decode_hex_into, encode_hex_into};
+use crate::{Digest, DigestParseError, Sha1, DIGEST_LENGTH};
+
+/// The length of a colon separated thumbprint.
+pub const THUMBPRINT_LENGTH: usize = DIGEST_LENGTH * 3 - 1;
+
+/// Computes the thumbprint of a DER encoded certificate.
+pub fn thumbprint(der: &[u8]) -> Digest {
+    Sha1::from(der).digest()
+}
+
+/// Computes the thumbprint of the first certificate in a PEM document.
+///
+/// Returns `None` if there is no `CERTIFICATE` block or its contents are
+/// not valid base64.
+///
+/// (This function is only available if the `alloc` feature is enabled)
+#[cfg(feature = "alloc")]
+pub fn thumbprint_pem(pem: &str) -> Option<Digest> {
+    use crate::encoding::{decode_b64, Alphabet};
+
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+    let start = pem.find(BEGIN)? + BEGIN.len();
+    let end = start + pem[start..].find(END)?;
+    let body: alloc::vec::Vec<u8> = pem[start..end]
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+    Some(thumbprint(&decode_b64(&body, Alphabet::Standard)?))
+}
+
+/// A thumbprint in its usual presentation.
+///
+/// Formatting produces uppercase hex separated by colons.  Parsing accepts
+/// hex of either case separated by colons, by spaces or not at all, as
+/// copied from the Windows certificate dialog for instance.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Thumbprint(pub Digest);
+
+impl Thumbprint {
+    /// Writes the colon separated thumbprint into a buffer and returns it as
+    /// string slice.
+    pub fn encode_into<'a>(&self, out: &'a mut [u8; THUMBPRINT_LENGTH]) -> &'a str {
+        let mut hex = [0u8; DIGEST_LENGTH * 2];
+        encode_hex_into(&self.0.bytes(), &mut hex);
+        for (idx, pair) in hex.chunks(2).enumerate() {
+            out[idx * 3] = pair[0].to_ascii_uppercase();
+            out[idx * 3 + 1] = pair[1].to_ascii_uppercase();
+            if idx * 3 + 2 < THUMBPRINT_LENGTH {
+                out[idx * 3 + 2] = b':';
+            }
+        }
+        str::from_utf8(out).unwrap()
+    }
+}
+
+impl From<Digest> for Thumbprint {
+    fn from(digest: Digest) -> Thumbprint {
+        Thumbprint(digest)
+    }
+}
+
+impl From<Thumbprint> for Digest {
+    fn from(thumbprint: Thumbprint) -> Digest {
+        thumbprint.0
+    }
+}
+
+impl fmt::Display for Thumbprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut buf = [0u8; THUMBPRINT_LENGTH];
+        f.write_str(self.encode_into(&mut buf))
+    }
+}
+
+impl str::FromStr for Thumbprint {
+    type Err = DigestParseError;
+
+    fn from_str(s: &str) -> Result<Thumbprint, DigestParseError> {
+        let s = s.trim().as_bytes();
+        let sep = match s.get(2) {
+            Some(&c) if c == b':' || c == b' ' => Some(c),
+            _ => None,
+        };
+        let mut hex = [0u8; DIGEST_LENGTH * 2];
+        match sep {
+            Some(sep) if s.len() == THUMBPRINT_LENGTH => {
+                for (idx, chunk) in s.chunks(3).enumerate() {
+                    if chunk.len() == 3 && chunk[2] != sep {
+                        return Err(DigestParseError(()));
+                    }
+                    hex[idx * 2..idx * 2 + 2].copy_from_slice(&chunk[..2]);
+                }
+            }
+            None if s.len() == hex.len() => hex.copy_from_slice(s),
+            _ => return Err(DigestParseError(())),
+        }
+        let mut bytes = [0u8; DIGEST_LENGTH];
+        decode_hex_into(&hex, &mut bytes).ok_or(DigestParseError(()))?;
+        Ok(Thumbprint(Digest::from_bytes(&bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::{decode_b64_into, Alphabet};
+
+    // self-signed certificate generated with openssl
+    const CERT: &str = "\
+-----BEGIN CERTIFICATE-----
+MIIBhzCCAS2gAwIBAgIUZemEmCGs8Bhh3dFTaMYLp8ksFk4wCgYIKoZIzj0EAwIw
+GTEXMBUGA1UEAwwOc2hhMS1zbW9sIHRlc3QwHhcNMjYxMDE0MTMyMDMxWhcNMzYx
+MDExMTMyMDMxWjAZMRcwFQYDVQQDDA5zaGExLXNtb2wgdGVzdDBZMBMGByqGSM49
+AgEGCCqGSM49AwEHA0IABFKyu9AxScNTpXqQGerYVQ7X0OebjEkPZ5Ca6Vt/N5AM
+rRhJvkBIJV/TGon60tuXUZ+rkeb2iZMIQ0s2yYB8qZejUzBRMB0GA1UdDgQWBBRH
+JuI/kX6GQ5TX+YEpxcX5fjZKGDAfBgNVHSMEGDAWgBRHJuI/kX6GQ5TX+YEpxcX5
+fjZKGDAPBgNVHRMBAf8EBTADAQH/MAoGCCqGSM49BAMCA0gAMEUCIHpNAk/TibR6
+5kApmc68IW+94nOV6HL/i763eNkt9bAgAiEA+pwKDo/mq+2LBtu+uvmbycRgGLCo
+/houYI8wAfCO8Rw=
+-----END CERTIFICATE-----
+";
+
+    // as printed by `openssl x509 -noout -fingerprint -sha1`
+    const FINGERPRINT: &str = "9A:B8:3A:D8:D9:2D:F2:36:B9:10:EA:3F:DF:44:61:0D:91:DB:BE:37";
+
+    #[test]
+    fn test_thumbprint() {
+        let mut body = [0u8; 600];
+        let mut len = 0;
+        for line in CERT.lines().filter(|line| !line.starts_with("-----")) {
+            body[len..len + line.len()].copy_from_slice(line.as_bytes());
+            len += line.len();
+        }
+        let mut der = [0u8; 400];
+        let der_len = decode_b64_into(&body[..len], Alphabet::Standard, &mut der).unwrap();
+
+        let tp = Thumbprint(thumbprint(&der[..der_len]));
+        let mut buf = [0u8; THUMBPRINT_LENGTH];
+        assert_eq!(tp.encode_into(&mut buf), FINGERPRINT);
+        assert_eq!(
+            tp.0,
+            "9ab83ad8d92df236b910ea3fdf44610d91dbbe37".parse().unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_thumbprint_pem() {
+        let expected: Thumbprint = FINGERPRINT.parse().unwrap();
+        assert_eq!(thumbprint_pem(CERT), Some(expected.0));
+        let bundle = alloc::format!("subject=CN = test\n{}{}", CERT, CERT);
+        assert_eq!(thumbprint_pem(&bundle), Some(expected.0));
+        assert_eq!(thumbprint_pem(&CERT[..CERT.len() - 10]), None);
+        assert_eq!(thumbprint_pem("no certificate"), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        let expected = Thumbprint("9ab83ad8d92df236b910ea3fdf44610d91dbbe37".parse().unwrap());
+        for s in &[
+            FINGERPRINT,
+            "9a:b8:3a:d8:d9:2d:f2:36:b9:10:ea:3f:df:44:61:0d:91:db:be:37",
+            "9a b8 3a d8 d9 2d f2 36 b9 10 ea 3f df 44 61 0d 91 db be 37",
+            " 9AB83AD8D92DF236B910EA3FDF44610D91DBBE37\n",
+        ] {
+            assert_eq!(s.parse::<Thumbprint>(), Ok(expected), "{:?}", s);
+        }
+        for s in &[
+            "9A:B8:3A:D8:D9:2D:F2:36:B9:10:EA:3F:DF:44:61:0D:91:DB:BE",
+            "9A:B8:3A:D8:D9:2D:F2:36:B9:10:EA:3F:DF:44:61:0D:91:DB:BE:3Z",
+            "9A:B8 3A:D8:D9:2D:F2:36:B9:10:EA:3F:DF:44:61:0D:91:DB:BE:37",
+            "9A-B8-3A-D8-D9-2D-F2-36-B9-10-EA-3F-DF-44-61-0D-91-DB-BE-37",
+        ] {
+            assert!(s.parse::<Thumbprint>().is_err(), "{:?}", s);
+        }
+    }
+}